@@ -1,17 +1,260 @@
 use std::path::PathBuf;
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+
+use flipper_ir_dumps::signal::Packet;
+
+use crate::filter::Pattern;
 
 /// Clap based CLI argument parsing.
-///
-/// Currently supports only reading from a file.
 #[derive(Debug, Parser)]
 #[command(version, author)]
 pub struct Cli {
-    /// The file to read the IR signals from.
+    /// The files to read the IR signals from, or directories to read all `.ir` files under.
+    /// `-` reads a dump from stdin.
+    ///
+    /// With several dumps, each row starts with the file it comes from.
+    #[clap(short, long, required = true)]
+    pub file: Vec<PathBuf>,
+    /// The output file, stdout if omitted.
     #[clap(short, long)]
-    pub file: PathBuf,
-    /// The output csv file.
-    #[clap(short, long)]
-    pub output_file: PathBuf,
+    pub output_file: Option<PathBuf>,
+    /// Only export signals whose whole name matches one of these shell globs, such as `vol_*`:
+    /// `*` matches any characters, `?` one, and `[0-9]` or `[!0-9]` one in or out of a class.
+    /// Regular expressions aren't supported.
+    #[clap(long)]
+    pub include: Vec<Pattern>,
+    /// Leave out signals whose whole name matches one of these shell globs, as `--include`
+    /// takes.
+    #[clap(long)]
+    pub exclude: Vec<Pattern>,
+    /// Separator of CSV fields, in output and in `--to-ir` input.
+    #[clap(long, default_value_t = ',', value_parser = ascii_char)]
+    pub delimiter: char,
+    /// Separate CSV fields with tabs, same as `--delimiter` with a tab.
+    #[clap(long, conflicts_with = "delimiter")]
+    pub tsv: bool,
+    /// Compress the output, by default as the extension of the output file tells: gzip for
+    /// `.gz`, none otherwise.
+    #[clap(long, value_enum)]
+    pub compress: Option<Compression>,
+    /// Format of the output file.
+    #[clap(long, value_enum, default_value_t = Format::Csv)]
+    pub format: Format,
+    /// How to write packets in CSV output.
+    #[clap(long, value_enum, default_value_t = PacketFormat::Bits)]
+    pub packets: PacketFormat,
+    /// Follow signal names in CSV output with their frequency, duty cycle, packet count,
+    /// bit length and duration in µs.
+    #[clap(long)]
+    pub metadata: bool,
+    /// Write a row per packet in CSV output, under a header, instead of a row per signal.
+    #[clap(long)]
+    pub per_packet: bool,
+    /// Write the timings of signals in CSV output instead of their packets: a row per signal,
+    /// or a row per duration with `--raw long`.
+    #[clap(long, value_enum, num_args = 0..=1, default_missing_value = "wide")]
+    pub raw: Option<RawLayout>,
+    /// Write these columns of CSV output, in this order, under a header, instead of the
+    /// default layout.
+    #[clap(long, value_enum, value_delimiter = ',')]
+    pub columns: Vec<Column>,
+    /// Read CSV files of signals instead, and write them as a `.ir` dump.
+    ///
+    /// Files start with a header of their columns: `name`, then either `protocol`, `address`
+    /// and `command` in hexadecimal, or `timings` in µs, and optionally `frequency` and
+    /// `duty_cycle`.
+    #[clap(long)]
+    pub to_ir: bool,
+    /// Export signals that fail to parse with an `error` column, and dumps that fail to
+    /// decode not at all, instead of stopping at the first failure. Failures are summed up
+    /// and the exit code is non-zero if there are any.
+    #[clap(long)]
+    pub keep_going: bool,
+    /// Merge consecutive packets differing in at most this many bits into repeats.
+    #[clap(long, default_value_t = 0)]
+    pub repeat_tolerance: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// A row per signal: its name, then its packets as bits.
+    Csv,
+    /// An array with an object per signal, its packets as bits and hex.
+    Json,
+    /// A Parquet file with a row per signal and typed columns, all of them unless `--columns`
+    /// picks some.
+    Parquet,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Compression {
+    /// No compression, whatever the extension.
+    None,
+    /// Gzip, as read by `zcat`, pandas and polars.
+    Gzip,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PacketFormat {
+    /// Bits, as received: `11100000...`.
+    Bits,
+    /// Hexadecimal, as IR code databases list them: `0xE0E040BF`.
+    Hex,
+    /// Bits, then hexadecimal in parentheses.
+    Both,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum RawLayout {
+    /// A row per signal: its name, then its durations in µs.
+    Wide,
+    /// A row per duration, under a header: signal, index, level and duration in µs.
+    Long,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[value(rename_all = "snake_case")]
+pub enum Column {
+    /// The dump the signal comes from, empty with a single dump.
+    SourceFile,
+    /// The name of the signal.
+    Name,
+    /// Carrier frequency, in Hz.
+    Frequency,
+    /// Carrier duty cycle, between 0 and 1.
+    DutyCycle,
+    /// Number of packets, repeats aside.
+    PacketCount,
+    /// Number of bits of all packets.
+    BitLength,
+    /// Duration of the signal, in µs.
+    Duration,
+    /// The protocol the signal was decoded with, if any.
+    Protocol,
+    /// Address of the decoded frame, in hexadecimal.
+    Address,
+    /// Command of the decoded frame, in hexadecimal.
+    Command,
+    /// Whether the decoded frame passed the integrity checks of its protocol.
+    Valid,
+    /// Packets as bits, separated by spaces.
+    Bits,
+    /// Packets as hexadecimal, separated by spaces.
+    Hex,
+    /// Why the signal couldn't be parsed, if it couldn't.
+    Error,
+}
+
+impl PacketFormat {
+    /// `packet` written this way.
+    pub fn write(self, packet: &Packet) -> String {
+        match self {
+            PacketFormat::Bits => packet.to_string(),
+            PacketFormat::Hex => format!("0x{}", packet.to_hex()),
+            PacketFormat::Both => format!("{} (0x{})", packet, packet.to_hex()),
+        }
+    }
+}
+
+impl Cli {
+    /// Separator of CSV fields.
+    pub fn delimiter(&self) -> u8 {
+        if self.tsv {
+            b'\t'
+        } else {
+            // checked to be ASCII by `ascii_char`
+            self.delimiter as u8
+        }
+    }
+
+    /// Whether the signal named `name` passes the `--include` and `--exclude` filters.
+    pub fn exports(&self, name: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|pattern| pattern.is_match(name)))
+            && !self.exclude.iter().any(|pattern| pattern.is_match(name))
+    }
+}
+
+/// Parse a single ASCII character, as CSV delimiters are bytes.
+fn ascii_char(text: &str) -> Result<char, String> {
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii() => Ok(c),
+        _ => Err("expected a single ASCII character".to_owned()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::tests::{nec, signal};
+
+    fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+        Cli::try_parse_from(["flipper-ir-dumps-csv", "-f", "-"].iter().chain(args))
+    }
+
+    #[test]
+    fn test_exports() {
+        let cli = parse(&[
+            "--include",
+            "vol_*",
+            "--include",
+            "mute",
+            "--exclude",
+            "*_dn",
+        ])
+        .unwrap();
+        let exported =
+            ["vol_up", "vol_dn", "mute", "mute_2", "power"].map(|name| cli.exports(name));
+        assert_eq!(exported, [true, false, true, false, false]);
+        assert!(parse(&["--include", "temp_[0-9"]).is_err());
+    }
+
+    #[test]
+    fn test_columns() {
+        let cli = parse(&["--columns", "hex,name", "--columns", "source_file"]).unwrap();
+        assert_eq!(cli.columns, [Column::Hex, Column::Name, Column::SourceFile]);
+        assert!(parse(&["--columns", "hex,nope"]).is_err());
+    }
+
+    #[test]
+    fn test_delimiter() {
+        assert_eq!(parse(&[]).unwrap().delimiter(), b',');
+        assert_eq!(parse(&["--delimiter", ";"]).unwrap().delimiter(), b';');
+        assert_eq!(parse(&["--tsv"]).unwrap().delimiter(), b'\t');
+        assert!(parse(&["--tsv", "--delimiter", ";"]).is_err());
+        assert!(parse(&["--delimiter", "é"]).is_err());
+    }
+
+    #[test]
+    fn test_ascii_char() {
+        assert_eq!(ascii_char("|"), Ok('|'));
+        assert_eq!(ascii_char("\t"), Ok('\t'));
+        for text in ["", ";;", "é"] {
+            assert_eq!(
+                ascii_char(text),
+                Err("expected a single ASCII character".to_owned())
+            );
+        }
+    }
+
+    #[test]
+    fn test_packet_format() {
+        let power = signal("Power", nec(0x04, 0x08));
+        let packet = &power.packets()[0];
+        assert_eq!(
+            PacketFormat::Bits.write(packet),
+            "11110111000010001111101100000100"
+        );
+        assert_eq!(PacketFormat::Hex.write(packet), "0xF708FB04");
+        assert_eq!(
+            PacketFormat::Both.write(packet),
+            "11110111000010001111101100000100 (0xF708FB04)"
+        );
+
+        let short = signal("Short", vec![500, 500, 500, 1500, 500]);
+        let packet = &short.packets()[0];
+        assert_eq!(PacketFormat::Bits.write(packet), "10");
+        assert_eq!(PacketFormat::Hex.write(packet), "0x2");
+    }
 }
@@ -0,0 +1,169 @@
+use std::io::Write;
+
+use clap::ValueEnum;
+use color_eyre::{eyre::WrapErr, Result};
+use csv::Writer;
+
+use crate::{
+    cli::{Column, PacketFormat},
+    input::Signal,
+};
+
+impl Column {
+    /// Title of the column, as given on the command line.
+    pub fn title(self) -> String {
+        self.to_possible_value()
+            .expect("no column is skipped")
+            .get_name()
+            .to_owned()
+    }
+
+    /// The field of `signal` in this column.
+    pub fn field(self, signal: &Signal) -> String {
+        let packets = |format: PacketFormat| {
+            let packets: Vec<String> = signal
+                .packets()
+                .iter()
+                .map(|packet| format.write(packet))
+                .collect();
+            packets.join(" ")
+        };
+
+        match self {
+            Column::SourceFile => signal.source_file.clone().unwrap_or_default(),
+            Column::Name => signal.name().to_owned(),
+            Column::Frequency => signal.raw.frequency().hz().to_string(),
+            Column::DutyCycle => signal.raw.duty_cycle().get().to_string(),
+            Column::PacketCount => signal.packets().len().to_string(),
+            Column::BitLength => {
+                let bit_len: usize = signal.packets().iter().map(|packet| packet.bit_len()).sum();
+                bit_len.to_string()
+            }
+            Column::Duration => signal.raw.duration().to_string(),
+            Column::Protocol => signal
+                .protocol()
+                .map(|decoded| decoded.protocol().to_owned())
+                .unwrap_or_default(),
+            Column::Address => hex(signal
+                .protocol()
+                .and_then(|decoded| decoded.frame().address)),
+            Column::Command => hex(signal
+                .protocol()
+                .and_then(|decoded| decoded.frame().command)),
+            Column::Valid => signal
+                .protocol()
+                .map(|_| signal.is_valid().to_string())
+                .unwrap_or_default(),
+            Column::Bits => packets(PacketFormat::Bits),
+            Column::Hex => packets(PacketFormat::Hex),
+            Column::Error => signal.error().unwrap_or_default().to_owned(),
+        }
+    }
+}
+
+/// A field of a decoded frame in hexadecimal, empty if the protocol hasn't it.
+pub fn hex(field: Option<u32>) -> String {
+    field
+        .map(|value| format!("0x{:02X}", value))
+        .unwrap_or_default()
+}
+
+/// Write a header of the titles of `columns`, then a row per signal with its
+/// fields in these columns.
+pub fn write(
+    writer: &mut Writer<impl Write>,
+    signals: &[Signal],
+    columns: &[Column],
+) -> Result<()> {
+    writer
+        .write_record(columns.iter().map(|column| column.title()))
+        .wrap_err("Failed to write header")?;
+    for signal in signals {
+        writer
+            .write_record(columns.iter().map(|column| column.field(signal)))
+            .wrap_err("Failed to write record")?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::tests::{csv, nec, signal};
+
+    #[test]
+    fn test_title() {
+        assert_eq!(Column::Name.title(), "name");
+        assert_eq!(Column::SourceFile.title(), "source_file");
+        assert_eq!(Column::PacketCount.title(), "packet_count");
+        assert_eq!(Column::DutyCycle.title(), "duty_cycle");
+    }
+
+    #[test]
+    fn test_field() {
+        let data = vec![
+            500, 500, 500, 1500, 500, 40000, 500, 500, 500, 500, 500, 1500, 500,
+        ];
+        let signal = Signal {
+            source_file: Some("tv.ir".to_owned()),
+            ..signal("Blips", data)
+        };
+
+        let fields = [
+            Column::SourceFile,
+            Column::Name,
+            Column::Frequency,
+            Column::DutyCycle,
+            Column::PacketCount,
+            Column::BitLength,
+            Column::Duration,
+            Column::Bits,
+            Column::Hex,
+            Column::Error,
+        ]
+        .map(|column| column.field(&signal));
+        assert_eq!(
+            fields,
+            ["tv.ir", "Blips", "38000", "0.33", "2", "5", "48000", "10 100", "0x2 0x4", ""]
+        );
+    }
+
+    #[test]
+    fn test_protocol_fields() {
+        let protocol = [
+            Column::Protocol,
+            Column::Address,
+            Column::Command,
+            Column::Valid,
+        ];
+        let power = signal("Power", nec(0x04, 0x08));
+        assert_eq!(
+            protocol.map(|column| column.field(&power)),
+            ["NEC", "0x04", "0x08", "true"]
+        );
+        // Fields of frames no protocol decoded are empty, not invalid.
+        let short = signal("Short", vec![500, 500, 500, 1500, 500]);
+        assert_eq!(protocol.map(|column| column.field(&short)), [""; 4]);
+    }
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(hex(None), "");
+        assert_eq!(hex(Some(0x4)), "0x04");
+        assert_eq!(hex(Some(0xE0E0)), "0xE0E0");
+    }
+
+    #[test]
+    fn test_write() {
+        let signals = [
+            signal("Power", nec(0x04, 0x08)),
+            signal("Short", vec![500, 500, 500, 1500, 500]),
+        ];
+        assert_eq!(
+            csv(|writer| write(writer, &signals, &[Column::Hex, Column::Name])),
+            "hex,name\n0xF708FB04,Power\n0x2,Short\n"
+        );
+        assert_eq!(csv(|writer| write(writer, &[], &[Column::Name])), "name\n");
+    }
+}
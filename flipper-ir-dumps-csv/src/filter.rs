@@ -0,0 +1,197 @@
+use std::{iter::Peekable, str::Chars, str::FromStr};
+
+/// A shell glob on signal names, matching whole names.
+///
+/// - `*` matches any run of characters, empty included, and `?` any single one;
+/// - classes such as `[0-9]` or `[a-z_]` match one of their characters, and
+///   `[!0-9]` or `[^0-9]` one not among them; a `]` right after the opening
+///   bracket is part of the class;
+/// - a backslash makes the next character literal, e.g. `\*`.
+///
+/// Matching is case-sensitive, as names are.
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    tokens: Vec<Token>,
+}
+
+#[derive(Debug, Clone)]
+enum Token {
+    Char(char),
+    Any,
+    Star,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+impl Token {
+    /// Whether the token matches `c`, for tokens matching one character.
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Token::Char(expected) => c == *expected,
+            Token::Any => true,
+            Token::Star => false,
+            Token::Class { negated, ranges } => {
+                ranges.iter().any(|&(low, high)| (low..=high).contains(&c)) != *negated
+            }
+        }
+    }
+}
+
+impl Pattern {
+    /// Whether the pattern matches the whole of `name`.
+    pub fn is_match(&self, name: &str) -> bool {
+        let chars: Vec<char> = name.chars().collect();
+        let (mut t, mut c) = (0, 0);
+        // the token after the last star, and the character it was tried at
+        let mut backtrack = None;
+
+        while c < chars.len() {
+            match self.tokens.get(t) {
+                Some(Token::Star) => {
+                    t += 1;
+                    backtrack = Some((t, c));
+                }
+                Some(token) if token.matches(chars[c]) => {
+                    t += 1;
+                    c += 1;
+                }
+                // let the last star match one more character
+                _ => match backtrack {
+                    Some((star_t, star_c)) => {
+                        t = star_t;
+                        c = star_c + 1;
+                        backtrack = Some((star_t, c));
+                    }
+                    None => return false,
+                },
+            }
+        }
+
+        self.tokens[t..]
+            .iter()
+            .all(|token| matches!(token, Token::Star))
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = String;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        let mut chars = pattern.chars().peekable();
+        let mut tokens = Vec::new();
+
+        while let Some(c) = chars.next() {
+            tokens.push(match c {
+                '*' => Token::Star,
+                '?' => Token::Any,
+                '[' => class(&mut chars)?,
+                '\\' => Token::Char(
+                    chars
+                        .next()
+                        .ok_or_else(|| "trailing backslash".to_owned())?,
+                ),
+                c => Token::Char(c),
+            });
+        }
+
+        Ok(Pattern { tokens })
+    }
+}
+
+/// The class following an opening bracket.
+fn class(rest: &mut Peekable<Chars>) -> Result<Token, String> {
+    let unclosed = || "unclosed character class".to_owned();
+
+    let negated = matches!(rest.peek(), Some('!' | '^'));
+    if negated {
+        rest.next();
+    }
+
+    let mut ranges = Vec::new();
+    let mut first = true;
+    loop {
+        let low = match rest.next().ok_or_else(unclosed)? {
+            ']' if !first => break,
+            '\\' => rest.next().ok_or_else(unclosed)?,
+            c => c,
+        };
+        first = false;
+
+        let ahead = rest.clone().nth(1);
+        let high = match (rest.peek(), ahead) {
+            (Some('-'), Some(high)) if high != ']' => {
+                rest.next();
+                match rest.next().ok_or_else(unclosed)? {
+                    '\\' => rest.next().ok_or_else(unclosed)?,
+                    high => high,
+                }
+            }
+            _ => low,
+        };
+        if high < low {
+            return Err(format!("invalid range {}-{}", low, high));
+        }
+        ranges.push((low, high));
+    }
+
+    Ok(Token::Class { negated, ranges })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(glob: &str, names: &[&str]) -> Vec<bool> {
+        let pattern: Pattern = glob.parse().unwrap();
+        names.iter().map(|name| pattern.is_match(name)).collect()
+    }
+
+    #[test]
+    fn test_literals() {
+        assert_eq!(
+            matches("vol_up", &["vol_up", "vol_up2", "a_vol_up", "Vol_up"]),
+            [true, false, false, false]
+        );
+        assert_eq!(matches("", &["", "a"]), [true, false]);
+        assert_eq!(matches(r"\*\?\[", &["*?[", "a?["]), [true, false]);
+    }
+
+    #[test]
+    fn test_wildcards() {
+        let names = ["vol_up", "vol_dn", "vol_", "volume", "mute"];
+        assert_eq!(matches("vol_*", &names), [true, true, true, false, false]);
+        assert_eq!(matches("vol_??", &names), [true, true, false, false, false]);
+        assert_eq!(matches("*u*", &names), [true, false, false, true, true]);
+        assert_eq!(matches("*", &names), [true; 5]);
+        assert_eq!(matches("v*_*p", &names), [true, false, false, false, false]);
+        assert_eq!(matches("**e", &names), [false, false, false, true, true]);
+    }
+
+    #[test]
+    fn test_classes() {
+        let names = ["temp_16", "temp_a", "temp_-", "temp_]"];
+        assert_eq!(matches("temp_[0-9]*", &names), [true, false, false, false]);
+        assert_eq!(matches("temp_[!0-9]", &names), [false, true, true, true]);
+        assert_eq!(matches("temp_[^0-9]", &names), [false, true, true, true]);
+        assert_eq!(matches("temp_[a-]", &names), [false, true, true, false]);
+        assert_eq!(matches("temp_[]a]", &names), [false, true, false, true]);
+        assert_eq!(matches(r"temp_[\]]", &names), [false, false, false, true]);
+    }
+
+    #[test]
+    fn test_errors() {
+        for glob in ["temp_[0-9", "[", "[]", "a\\", "[9-0]"] {
+            assert!(glob.parse::<Pattern>().is_err(), "{}", glob);
+        }
+    }
+
+    #[test]
+    fn test_long_names() {
+        // backtracking stays polynomial on stars that can't match
+        let name = "a".repeat(10_000);
+        assert!(!matches("*a*a*a*a*b", &[&name])[0]);
+        assert!(matches("*a*a*a*a*", &[&name])[0]);
+    }
+}
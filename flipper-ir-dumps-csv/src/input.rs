@@ -0,0 +1,269 @@
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::{eyre::WrapErr, Result};
+
+use flipper_ir_dumps::{
+    dump::DumpFile,
+    protocol::Decoded,
+    signal::{Packet, ParsedSignal, RawSignal},
+};
+
+/// The path standing for stdin.
+const STDIN: &str = "-";
+
+/// A signal to export, parsed into packets.
+pub struct Signal {
+    /// The dump the signal comes from, when exporting several.
+    pub source_file: Option<String>,
+    pub raw: RawSignal,
+    /// The signal parsed into packets, or why it couldn't be.
+    pub parsed: Result<ParsedSignal, String>,
+}
+
+impl Signal {
+    pub fn name(&self) -> &str {
+        self.raw.name()
+    }
+
+    /// The packets of the signal, none if it couldn't be parsed.
+    pub fn packets(&self) -> &[Packet] {
+        self.parsed.as_ref().map_or(&[], ParsedSignal::packets)
+    }
+
+    /// The signal as decoded by a protocol, if any.
+    pub fn protocol(&self) -> Option<&Decoded> {
+        self.parsed.as_ref().ok()?.protocol()
+    }
+
+    /// Whether all packets passed the integrity checks of their protocol.
+    pub fn is_valid(&self) -> bool {
+        self.packets().iter().all(Packet::is_valid)
+    }
+
+    /// Why the signal couldn't be parsed, if it couldn't.
+    pub fn error(&self) -> Option<&str> {
+        self.parsed.as_ref().err().map(String::as_str)
+    }
+}
+
+/// The dumps to read: files as given, and `.ir` files under directories,
+/// hidden ones aside.
+pub fn dump_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            files.extend(ir_files(path)?);
+        } else {
+            files.push(path.clone());
+        }
+    }
+    Ok(files)
+}
+
+fn ir_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        let entries = std::fs::read_dir(&dir)
+            .wrap_err_with(|| format!("Failed to list {}", dir.display()))?;
+        for entry in entries {
+            let path = entry.wrap_err("Failed to list directory")?.path();
+            let hidden = path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+            if hidden {
+                continue;
+            }
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path.extension().is_some_and(|extension| extension == "ir") {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Contents of the file at `path`, or of stdin if it's `-`.
+pub fn read(path: &Path) -> Result<Vec<u8>> {
+    read_from(path, std::io::stdin())
+}
+
+/// Contents of the file at `path`, or of `stdin` if it's `-`.
+fn read_from(path: &Path, mut stdin: impl Read) -> Result<Vec<u8>> {
+    if path == Path::new(STDIN) {
+        let mut contents = Vec::new();
+        stdin
+            .read_to_end(&mut contents)
+            .wrap_err("Failed to read stdin")?;
+        Ok(contents)
+    } else {
+        std::fs::read(path).wrap_err_with(|| format!("Failed to read {}", path.display()))
+    }
+}
+
+/// Read the dump at `path`, or stdin if it's `-`, reporting its warnings.
+pub fn read_dump(path: &Path) -> Result<DumpFile> {
+    let contents = read(path)?;
+    let dump = DumpFile::from_bytes(&contents)
+        .wrap_err_with(|| format!("Failed decoding dump {}", path.display()))?;
+
+    for warning in dump.warnings() {
+        eprintln!("warning: {}: {}", path.display(), warning);
+    }
+    for issue in dump.validate() {
+        eprintln!("warning: {}: {}", path.display(), issue);
+    }
+
+    Ok(dump)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use flipper_ir_dumps::signal::{DutyCycle, Polarity, TimingSequence};
+
+    use super::*;
+
+    /// Timings of an NEC frame of `address` and `command`.
+    pub fn nec(address: u8, command: u8) -> Vec<u32> {
+        let mut data = vec![9024, 4512];
+        for byte in [address, !address, command, !command] {
+            for bit in 0..8 {
+                data.push(564);
+                data.push(if byte >> bit & 1 == 1 { 1692 } else { 564 });
+            }
+        }
+        data.push(564);
+        data
+    }
+
+    /// A signal of `data` at 38kHz, parsed as for export.
+    pub fn signal(name: &str, data: Vec<u32>) -> Signal {
+        let timings = TimingSequence::new(Polarity::StartsWithPulse, data);
+        let raw = RawSignal::new(name, 38_000, DutyCycle::default(), timings).unwrap();
+        let parsed = ParsedSignal::try_from(&raw).map_err(|err| err.to_string());
+        Signal {
+            source_file: None,
+            raw,
+            parsed,
+        }
+    }
+
+    /// A signal of a held button, sending the same NEC frame twice.
+    pub fn held(name: &str) -> Signal {
+        let mut data = nec(0x04, 0x08);
+        data.push(40000);
+        data.extend(nec(0x04, 0x08));
+        let mut signal = signal(name, data);
+        if let Ok(parsed) = &mut signal.parsed {
+            parsed.merge_repeats(0);
+        }
+        signal
+    }
+
+    /// A signal that couldn't be parsed.
+    pub fn failed(name: &str) -> Signal {
+        Signal {
+            parsed: Err("no packets".to_owned()),
+            ..signal(name, vec![500])
+        }
+    }
+
+    /// The CSV that `write` writes.
+    pub fn csv(write: impl FnOnce(&mut csv::Writer<Vec<u8>>) -> Result<()>) -> String {
+        let mut writer = csv::WriterBuilder::new()
+            .flexible(true)
+            .from_writer(Vec::new());
+        write(&mut writer).unwrap();
+        String::from_utf8(writer.into_inner().unwrap()).unwrap()
+    }
+
+    /// An empty directory of the system's temporary one, for `test`.
+    pub fn temp_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "flipper-ir-dumps-csv-{}-{}",
+            test,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_dump_files() {
+        let dir = temp_dir("dump-files");
+        for file in [
+            "b.ir",
+            "a.ir",
+            "notes.txt",
+            ".hidden.ir",
+            "nested/c.ir",
+            ".git/d.ir",
+        ] {
+            let path = dir.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+
+        let files = dump_files(&[dir.join("notes.txt"), dir.clone()]).unwrap();
+        assert_eq!(
+            files,
+            ["notes.txt", "a.ir", "b.ir", "nested/c.ir"].map(|file| dir.join(file))
+        );
+        assert_eq!(
+            dump_files(&[PathBuf::from(STDIN)]).unwrap(),
+            [PathBuf::from("-")]
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_read() {
+        let dir = temp_dir("read");
+        let path = dir.join("Power.ir");
+        std::fs::write(&path, "from a file").unwrap();
+
+        let stdin = &b"from stdin"[..];
+        assert_eq!(read_from(&path, stdin).unwrap(), b"from a file");
+        assert_eq!(read_from(Path::new("-"), stdin).unwrap(), b"from stdin");
+        // Only a lone dash is stdin.
+        let err = read_from(&dir.join("-"), stdin).unwrap_err();
+        assert!(err.to_string().starts_with("Failed to read"));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_read_dump() {
+        let dump = read_dump(Path::new("../data/Dump_temp_16.ir")).unwrap();
+        assert!(!dump.signals().is_empty());
+
+        let err = read_dump(Path::new("../data/missing.ir")).unwrap_err();
+        assert_eq!(err.to_string(), "Failed to read ../data/missing.ir");
+        let err = read_dump(Path::new("Cargo.toml")).unwrap_err();
+        assert_eq!(err.to_string(), "Failed decoding dump Cargo.toml");
+    }
+
+    #[test]
+    fn test_signal() {
+        let power = signal("Power", nec(0x04, 0x08));
+        assert_eq!(power.name(), "Power");
+        assert_eq!(power.packets().len(), 1);
+        assert_eq!(power.protocol().map(Decoded::protocol), Some("NEC"));
+        assert!(power.is_valid());
+        assert_eq!(power.error(), None);
+
+        let broken = failed("Broken");
+        assert!(broken.packets().is_empty());
+        assert!(broken.protocol().is_none());
+        assert_eq!(broken.error(), Some("no packets"));
+    }
+}
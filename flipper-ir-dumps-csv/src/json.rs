@@ -0,0 +1,113 @@
+use std::io::{self, Write};
+
+use crate::input::Signal;
+
+/// Write `signals` as a JSON array of objects such as:
+///
+/// ```json
+/// {"name": "Power", "frequency": 38000, "duty_cycle": 0.33, "packets": [
+///   {"bits": "00100000110111110001000011101111", "hex": "20DF10EF", "repeats": 2}
+/// ]}
+/// ```
+///
+/// Objects start with a `source_file` member when signals have one, and
+/// signals that couldn't be parsed have an `error` member and no packets.
+pub fn write(mut writer: impl Write, signals: &[Signal]) -> io::Result<()> {
+    writeln!(writer, "[")?;
+    for (i, signal) in signals.iter().enumerate() {
+        let packets: Vec<String> = signal
+            .packets()
+            .iter()
+            .map(|packet| {
+                format!(
+                    "    {{\"bits\": \"{}\", \"hex\": \"{}\", \"repeats\": {}}}",
+                    packet,
+                    packet.to_hex(),
+                    packet.repeats()
+                )
+            })
+            .collect();
+        write!(writer, "  {{")?;
+        if let Some(source_file) = &signal.source_file {
+            write!(writer, "\"source_file\": {}, ", quote(source_file))?;
+        }
+        write!(
+            writer,
+            "\"name\": {}, \"frequency\": {}, \"duty_cycle\": {}, ",
+            quote(signal.name()),
+            signal.raw.frequency().hz(),
+            signal.raw.duty_cycle().get()
+        )?;
+        if let Some(error) = signal.error() {
+            write!(writer, "\"error\": {}, ", quote(error))?;
+        }
+        write!(writer, "\"packets\": [")?;
+        if !packets.is_empty() {
+            write!(writer, "\n{}\n  ", packets.join(",\n"))?;
+        }
+        write!(writer, "]}}")?;
+        writeln!(writer, "{}", if i + 1 < signals.len() { "," } else { "" })?;
+    }
+    writeln!(writer, "]")?;
+    writer.flush()
+}
+
+/// A JSON string literal.
+fn quote(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::tests::{failed, nec, signal};
+
+    fn json(signals: &[Signal]) -> String {
+        let mut output = Vec::new();
+        write(&mut output, signals).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_write() {
+        assert_eq!(json(&[]), "[\n]\n");
+        assert_eq!(
+            json(&[signal("Power", nec(0x04, 0x08)), failed("Broken")]),
+            r#"[
+  {"name": "Power", "frequency": 38000, "duty_cycle": 0.33, "packets": [
+    {"bits": "11110111000010001111101100000100", "hex": "F708FB04", "repeats": 0}
+  ]},
+  {"name": "Broken", "frequency": 38000, "duty_cycle": 0.33, "error": "no packets", "packets": []}
+]
+"#
+        );
+    }
+
+    #[test]
+    fn test_source_file() {
+        let power = Signal {
+            source_file: Some("tv/Power.ir".to_owned()),
+            ..signal("Power", nec(0x04, 0x08))
+        };
+        assert!(json(&[power])
+            .starts_with("[\n  {\"source_file\": \"tv/Power.ir\", \"name\": \"Power\","));
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("Power"), "\"Power\"");
+        assert_eq!(quote("\"On\\Off\""), r#""\"On\\Off\"""#);
+        assert_eq!(quote("a\nb\tc\u{1}"), r#""a\u000ab\u0009c\u0001""#);
+        assert_eq!(quote("Vol ±"), "\"Vol ±\"");
+    }
+}
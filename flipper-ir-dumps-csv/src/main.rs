@@ -1,41 +1,161 @@
-use clap::Parser;
-use color_eyre::eyre::WrapErr;
-use csv::WriterBuilder;
+use std::io::Write;
 
-use flipper_ir_dumps::{dump::DumpFile, signal::ParsedSignal};
+use clap::{Parser, ValueEnum};
+use color_eyre::eyre::{eyre, WrapErr};
+use csv::{Writer, WriterBuilder};
+
+use flipper_ir_dumps::signal::ParsedSignal;
 
 mod cli;
-use cli::Cli;
+use cli::{Cli, Column, Format};
+
+mod columns;
+mod filter;
+
+mod input;
+use input::Signal;
+
+mod json;
+mod output;
+mod packets;
+use output::Output;
+
+mod parquet;
+mod raw;
+mod to_ir;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     color_eyre::install()?;
 
     let cli = Cli::parse();
-    let contents = std::fs::read_to_string(cli.file).wrap_err("Failed to read file")?;
-    let dump = DumpFile::try_from(contents.as_str());
-
-    let dump = match dump {
-        Ok(dump) => dump,
-        Err(err) => {
-            eprintln!("Failed decoding dump: {:?}", err);
-            return Ok(());
+    if cli.to_ir {
+        let mut output = Output::open(cli.output_file.as_deref(), cli.compress)?;
+        to_ir::convert(&cli.file, cli.delimiter(), &mut output)?;
+        output.finish().wrap_err("Failed to write dump")?;
+        return Ok(());
+    }
+
+    let paths = input::dump_files(&cli.file)?;
+    // the source of each signal is worth a column only with several dumps
+    let several = paths.len() > 1 || cli.file.iter().any(|path| path.is_dir());
+
+    let mut signals = Vec::new();
+    let mut failed_dumps = 0;
+    for path in &paths {
+        let dump = match input::read_dump(path) {
+            Ok(dump) => dump,
+            Err(err) if cli.keep_going => {
+                eprintln!("{:#}", err);
+                failed_dumps += 1;
+                continue;
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        for signal in dump
+            .signals()
+            .iter()
+            .filter(|signal| cli.exports(signal.name()))
+        {
+            let parsed = match ParsedSignal::try_from(signal) {
+                Ok(mut parsed) => {
+                    parsed.merge_repeats(cli.repeat_tolerance);
+                    Ok(parsed)
+                }
+                Err(err) if cli.keep_going => {
+                    eprintln!("Failed to parse signal {}: {}", signal.name(), err);
+                    Err(err.to_string())
+                }
+                Err(err) => {
+                    return Err(err)
+                        .wrap_err_with(|| format!("Failed to parse signal {}", signal.name()))?
+                }
+            };
+            signals.push(Signal {
+                source_file: several.then(|| path.display().to_string()),
+                raw: signal.clone(),
+                parsed,
+            });
+        }
+    }
+
+    let mut output = Output::open(cli.output_file.as_deref(), cli.compress)?;
+    match cli.format {
+        Format::Csv => {
+            let mut writer = WriterBuilder::new()
+                .flexible(true)
+                .delimiter(cli.delimiter())
+                .from_writer(&mut output);
+            match cli.raw {
+                Some(layout) => raw::write(&mut writer, &signals, layout)?,
+                None if !cli.columns.is_empty() => {
+                    columns::write(&mut writer, &signals, &cli.columns)?
+                }
+                None if cli.per_packet => packets::write(&mut writer, &signals, cli.keep_going)?,
+                None => write_csv(&mut writer, &signals, &cli)?,
+            }
+            writer.flush().wrap_err("Failed to write CSV")?;
+        }
+        Format::Json => json::write(&mut output, &signals).wrap_err("Failed to write JSON")?,
+        Format::Parquet => {
+            let columns = if cli.columns.is_empty() {
+                Column::value_variants()
+                    .iter()
+                    .copied()
+                    .filter(|&column| column != Column::SourceFile || several)
+                    .filter(|&column| column != Column::Error || cli.keep_going)
+                    .collect()
+            } else {
+                cli.columns.clone()
+            };
+            parquet::write(&mut output, &signals, &columns).wrap_err("Failed to write Parquet")?
         }
-    };
+    }
+    output.finish().wrap_err("Failed to write output")?;
 
-    let mut writer = WriterBuilder::new()
-        .flexible(true)
-        .from_path(cli.output_file)
-        .wrap_err("Failed to create CSV writer")?;
+    let failed_signals = signals
+        .iter()
+        .filter(|signal| signal.error().is_some())
+        .count();
+    if failed_dumps + failed_signals > 0 {
+        return Err(eyre!(
+            "{} of {} dumps failed to decode, {} of {} signals failed to parse",
+            failed_dumps,
+            paths.len(),
+            failed_signals,
+            signals.len()
+        )
+        .into());
+    }
 
-    for signal in dump.signals() {
-        let parsed_signal = ParsedSignal::try_from(signal).wrap_err("Failed to parse signal")?;
+    Ok(())
+}
 
-        let mut record = vec![parsed_signal.name().to_owned()];
+/// Write a row per signal: its source file if any, its name, its error with
+/// `--keep-going`, its metadata with `--metadata`, then its packets.
+fn write_csv(
+    writer: &mut Writer<impl Write>,
+    signals: &[Signal],
+    cli: &Cli,
+) -> color_eyre::Result<()> {
+    let packets = cli.packets;
+    for signal in signals {
+        let mut record: Vec<String> = signal.source_file.iter().cloned().collect();
+        record.push(signal.name().to_owned());
+        if cli.keep_going {
+            record.push(Column::Error.field(signal));
+        }
+        if cli.metadata {
+            record.extend(metadata_fields(signal));
+        }
         record.extend(
-            parsed_signal
+            signal
                 .packets()
                 .iter()
-                .map(|packet| packet.to_string()),
+                .map(|packet| match packet.repeats() {
+                    0 => packets.write(packet),
+                    repeats => format!("{} (+{} repeats)", packets.write(packet), repeats),
+                }),
         );
 
         writer
@@ -45,3 +165,92 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+/// Frequency, duty cycle, packet count, bit length and duration of `signal`.
+fn metadata_fields(signal: &Signal) -> [String; 5] {
+    [
+        Column::Frequency,
+        Column::DutyCycle,
+        Column::PacketCount,
+        Column::BitLength,
+        Column::Duration,
+    ]
+    .map(|column| column.field(signal))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::tests::{failed, held, signal};
+
+    /// The CSV of `signals` as exported with `args`.
+    fn csv(signals: &[Signal], args: &[&str]) -> String {
+        let cli =
+            Cli::try_parse_from(["flipper-ir-dumps-csv", "-f", "-"].iter().chain(args)).unwrap();
+        let mut writer = WriterBuilder::new()
+            .flexible(true)
+            .delimiter(cli.delimiter())
+            .from_writer(Vec::new());
+        write_csv(&mut writer, signals, &cli).unwrap();
+        String::from_utf8(writer.into_inner().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let signals = [
+            held("Power"),
+            signal("Short", vec![500, 500, 500, 1500, 500]),
+        ];
+        assert_eq!(
+            csv(&signals, &[]),
+            "Power,11110111000010001111101100000100 (+1 repeats)\nShort,10\n"
+        );
+        assert_eq!(
+            csv(&signals, &["--packets", "hex"]),
+            "Power,0xF708FB04 (+1 repeats)\nShort,0x2\n"
+        );
+    }
+
+    #[test]
+    fn test_delimiter() {
+        let signals = [signal("Short, or not", vec![500, 500, 500, 1500, 500])];
+        assert_eq!(csv(&signals, &[]), "\"Short, or not\",10\n");
+        assert_eq!(csv(&signals, &["--tsv"]), "Short, or not\t10\n");
+        assert_eq!(csv(&signals, &["--delimiter", ";"]), "Short, or not;10\n");
+    }
+
+    #[test]
+    fn test_keep_going() {
+        let signals = [
+            signal("Short", vec![500, 500, 500, 1500, 500]),
+            failed("Broken"),
+        ];
+        assert_eq!(
+            csv(&signals, &["--keep-going"]),
+            "Short,,10\nBroken,no packets\n"
+        );
+        assert_eq!(
+            csv(&signals, &["--keep-going", "--metadata"]),
+            "Short,,38000,0.33,1,2,3500,10\nBroken,no packets,38000,0.33,0,0,500\n"
+        );
+    }
+
+    #[test]
+    fn test_metadata() {
+        let signals = [
+            held("Power"),
+            signal("Short", vec![500, 500, 500, 1500, 500]),
+        ];
+        assert_eq!(
+            metadata_fields(&signals[1]),
+            ["38000", "0.33", "1", "2", "3500"]
+        );
+        assert_eq!(
+            csv(&signals, &["--metadata"]),
+            concat!(
+                "Power,38000,0.33,1,32,176488,11110111000010001111101100000100 (+1 repeats)\n",
+                "Short,38000,0.33,1,2,3500,10\n"
+            )
+        );
+    }
+}
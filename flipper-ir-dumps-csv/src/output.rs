@@ -0,0 +1,124 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use color_eyre::{eyre::WrapErr, Result};
+use flate2::{write::GzEncoder, Compression as Level};
+
+use crate::cli::Compression;
+
+/// Where the export goes, compressed or not.
+pub enum Output {
+    Plain(Box<dyn Write>),
+    Gzip(GzEncoder<Box<dyn Write>>),
+}
+
+impl Output {
+    /// The output file at `path`, or stdout if there's none, compressed as
+    /// asked, or else as the extension of the file tells.
+    pub fn open(path: Option<&Path>, compression: Option<Compression>) -> Result<Self> {
+        let extension = path
+            .and_then(Path::extension)
+            .map(|extension| extension.to_string_lossy().to_lowercase());
+        let compression = match (compression, extension.as_deref()) {
+            (Some(compression), _) => compression,
+            (None, Some("gz")) => Compression::Gzip,
+            (None, _) => Compression::None,
+        };
+
+        let writer: Box<dyn Write> = match path {
+            Some(path) => {
+                let file = File::create(path).wrap_err("Failed to create output file")?;
+                Box::new(BufWriter::new(file))
+            }
+            None => Box::new(io::stdout().lock()),
+        };
+        Ok(match compression {
+            Compression::None => Output::Plain(writer),
+            Compression::Gzip => Output::Gzip(GzEncoder::new(writer, Level::default())),
+        })
+    }
+
+    /// Write what's left, such as the end of the compressed stream.
+    pub fn finish(self) -> io::Result<()> {
+        match self {
+            Output::Plain(mut writer) => writer.flush(),
+            Output::Gzip(encoder) => encoder.finish()?.flush(),
+        }
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Output::Plain(writer) => writer.write(buf),
+            Output::Gzip(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::Plain(writer) => writer.flush(),
+            Output::Gzip(encoder) => encoder.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::Read, path::PathBuf};
+
+    use flate2::read::GzDecoder;
+
+    use super::*;
+    use crate::input::tests::temp_dir;
+
+    const TEXT: &[u8] = b"name,packet\nPower,0110\n";
+
+    /// What's in the file at `path` after writing [`TEXT`] to it.
+    fn written(path: &Path, compression: Option<Compression>) -> Vec<u8> {
+        let mut output = Output::open(Some(path), compression).unwrap();
+        output.write_all(TEXT).unwrap();
+        output.finish().unwrap();
+        fs::read(path).unwrap()
+    }
+
+    fn gunzip(data: &[u8]) -> Vec<u8> {
+        let mut text = Vec::new();
+        GzDecoder::new(data).read_to_end(&mut text).unwrap();
+        text
+    }
+
+    #[test]
+    fn test_extension() {
+        let dir = temp_dir("output-extension");
+        let path = |name: &str| -> PathBuf { dir.join(name) };
+
+        assert_eq!(written(&path("plain.csv"), None), TEXT);
+        assert_eq!(written(&path("no-extension"), None), TEXT);
+        assert_eq!(gunzip(&written(&path("small.csv.gz"), None)), TEXT);
+        assert_eq!(gunzip(&written(&path("LOUD.CSV.GZ"), None)), TEXT);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_compression_overrides_extension() {
+        let dir = temp_dir("output-override");
+
+        let gzip = written(&dir.join("data.csv"), Some(Compression::Gzip));
+        assert_eq!(gunzip(&gzip), TEXT);
+        let plain = written(&dir.join("data.csv.gz"), Some(Compression::None));
+        assert_eq!(plain, TEXT);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_stdout() {
+        // Without a path, output goes to stdout, plain unless asked.
+        assert!(matches!(Output::open(None, None), Ok(Output::Plain(_))));
+    }
+}
@@ -0,0 +1,142 @@
+use std::io::Write;
+
+use color_eyre::{eyre::WrapErr, Result};
+use csv::Writer;
+
+use crate::{cli::PacketFormat, columns::hex, input::Signal};
+
+/// Write a row per packet under a header: its source file if any, its
+/// signal, its index in the signal, its bits and hexadecimal value, the
+/// number of repeats following it, and the protocol, address, command and
+/// validity of the frame it was decoded into, if any.
+///
+/// With `errors`, an `error` column follows, and signals that couldn't be
+/// parsed have a single row with only their name and error.
+pub fn write(writer: &mut Writer<impl Write>, signals: &[Signal], errors: bool) -> Result<()> {
+    let several = signals.iter().any(|signal| signal.source_file.is_some());
+
+    let mut header = Vec::new();
+    if several {
+        header.push("source_file");
+    }
+    header.extend([
+        "signal",
+        "packet_index",
+        "bits",
+        "hex",
+        "repeats",
+        "protocol",
+        "address",
+        "command",
+        "valid",
+    ]);
+    if errors {
+        header.push("error");
+    }
+    writer
+        .write_record(&header)
+        .wrap_err("Failed to write header")?;
+
+    for signal in signals {
+        if let (true, Some(error)) = (errors, signal.error()) {
+            let mut record: Vec<String> = signal.source_file.iter().cloned().collect();
+            record.push(signal.name().to_owned());
+            record.extend(vec![String::new(); header.len() - record.len() - 1]);
+            record.push(error.to_owned());
+            writer
+                .write_record(record)
+                .wrap_err("Failed to write record")?;
+            continue;
+        }
+
+        for (i, packet) in signal.packets().iter().enumerate() {
+            let mut record: Vec<String> = signal.source_file.iter().cloned().collect();
+            record.extend([
+                signal.name().to_owned(),
+                i.to_string(),
+                PacketFormat::Bits.write(packet),
+                PacketFormat::Hex.write(packet),
+                packet.repeats().to_string(),
+            ]);
+            match packet.frame() {
+                Some(frame) => record.extend([
+                    frame.protocol.clone(),
+                    hex(frame.address),
+                    hex(frame.command),
+                    packet.is_valid().to_string(),
+                ]),
+                None => record.extend([String::new(), String::new(), String::new(), String::new()]),
+            }
+            if errors {
+                record.push(String::new());
+            }
+            writer
+                .write_record(record)
+                .wrap_err("Failed to write record")?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::tests::{csv, failed, held, nec, signal};
+
+    #[test]
+    fn test_write() {
+        let power = held("Power");
+        let blips = signal(
+            "Blips",
+            vec![
+                500, 500, 500, 1500, 500, 40000, 500, 500, 500, 500, 500, 1500, 500,
+            ],
+        );
+        assert_eq!(
+            csv(|writer| write(writer, &[power, blips], false)),
+            concat!(
+                "signal,packet_index,bits,hex,repeats,protocol,address,command,valid\n",
+                "Power,0,11110111000010001111101100000100,0xF708FB04,1,NEC,0x04,0x08,true\n",
+                "Blips,0,10,0x2,0,,,,\n",
+                "Blips,1,100,0x4,0,,,,\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_source_file() {
+        let power = Signal {
+            source_file: Some("tv.ir".to_owned()),
+            ..signal("Power", nec(0x04, 0x08))
+        };
+        assert_eq!(
+            csv(|writer| write(writer, &[power], false)),
+            concat!(
+                "source_file,signal,packet_index,bits,hex,repeats,protocol,address,command,valid\n",
+                "tv.ir,Power,0,11110111000010001111101100000100,0xF708FB04,0,NEC,0x04,0x08,true\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let signals = [
+            signal("Short", vec![500, 500, 500, 1500, 500]),
+            failed("Broken"),
+        ];
+        assert_eq!(
+            csv(|writer| write(writer, &signals, true)),
+            concat!(
+                "signal,packet_index,bits,hex,repeats,protocol,address,command,valid,error\n",
+                "Short,0,10,0x2,0,,,,,\n",
+                "Broken,,,,,,,,,no packets\n",
+            )
+        );
+        // Without `--keep-going`, failed signals have no packets to write.
+        assert_eq!(
+            csv(|writer| write(writer, &signals, false)),
+            "signal,packet_index,bits,hex,repeats,protocol,address,command,valid\nShort,0,10,0x2,0,,,,\n"
+        );
+    }
+}
@@ -0,0 +1,549 @@
+//! A minimal Parquet writer: a single row group of flat columns, each in a
+//! single uncompressed data page, plain encoded.
+
+use std::io::{self, Write};
+
+use crate::{cli::Column, input::Signal};
+
+const MAGIC: &[u8; 4] = b"PAR1";
+
+/// Physical types, as numbered by the Parquet format.
+#[derive(Clone, Copy)]
+enum Type {
+    Boolean = 0,
+    Int64 = 2,
+    Float = 4,
+    ByteArray = 6,
+}
+
+/// Values of a column, `None` for nulls.
+enum Values {
+    Boolean(Vec<Option<bool>>),
+    Int64(Vec<Option<i64>>),
+    Float(Vec<Option<f32>>),
+    Text(Vec<Option<String>>),
+}
+
+impl Values {
+    /// The values of `column` for `signals`.
+    fn of(column: Column, signals: &[Signal]) -> Self {
+        let int = |value: fn(&Signal) -> Option<i64>| signals.iter().map(value).collect();
+        let text = |value: fn(&Signal) -> Option<String>| signals.iter().map(value).collect();
+        let frame = |signal: &Signal| signal.protocol().map(|decoded| decoded.frame());
+
+        match column {
+            Column::Frequency => {
+                Values::Int64(int(|signal| Some(signal.raw.frequency().hz().into())))
+            }
+            Column::DutyCycle => Values::Float(
+                signals
+                    .iter()
+                    .map(|signal| Some(signal.raw.duty_cycle().get()))
+                    .collect(),
+            ),
+            Column::PacketCount => Values::Int64(int(|signal| Some(signal.packets().len() as i64))),
+            Column::BitLength => Values::Int64(int(|signal| {
+                Some(
+                    signal
+                        .packets()
+                        .iter()
+                        .map(|packet| packet.bit_len() as i64)
+                        .sum(),
+                )
+            })),
+            Column::Duration => Values::Int64(int(|signal| Some(signal.raw.duration() as i64))),
+            Column::Address => Values::Int64(
+                signals
+                    .iter()
+                    .map(|signal| frame(signal)?.address.map(i64::from))
+                    .collect(),
+            ),
+            Column::Command => Values::Int64(
+                signals
+                    .iter()
+                    .map(|signal| frame(signal)?.command.map(i64::from))
+                    .collect(),
+            ),
+            Column::Valid => Values::Boolean(
+                signals
+                    .iter()
+                    .map(|signal| signal.protocol().map(|_| signal.is_valid()))
+                    .collect(),
+            ),
+            Column::Protocol => Values::Text(text(|signal| {
+                Some(signal.protocol()?.protocol().to_owned())
+            })),
+            Column::SourceFile => Values::Text(text(|signal| signal.source_file.clone())),
+            Column::Error => Values::Text(text(|signal| signal.error().map(str::to_owned))),
+            Column::Name | Column::Bits | Column::Hex => Values::Text(
+                signals
+                    .iter()
+                    .map(|signal| Some(column.field(signal)))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn r#type(&self) -> Type {
+        match self {
+            Values::Boolean(_) => Type::Boolean,
+            Values::Int64(_) => Type::Int64,
+            Values::Float(_) => Type::Float,
+            Values::Text(_) => Type::ByteArray,
+        }
+    }
+
+    /// Which values aren't null.
+    fn defined(&self) -> Vec<bool> {
+        match self {
+            Values::Boolean(values) => values.iter().map(Option::is_some).collect(),
+            Values::Int64(values) => values.iter().map(Option::is_some).collect(),
+            Values::Float(values) => values.iter().map(Option::is_some).collect(),
+            Values::Text(values) => values.iter().map(Option::is_some).collect(),
+        }
+    }
+
+    /// The values that aren't null, plain encoded.
+    fn plain(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        match self {
+            Values::Boolean(values) => {
+                let bits: Vec<bool> = values.iter().flatten().copied().collect();
+                data.extend(pack_bits(&bits));
+            }
+            Values::Int64(values) => {
+                for value in values.iter().flatten() {
+                    data.extend(value.to_le_bytes());
+                }
+            }
+            Values::Float(values) => {
+                for value in values.iter().flatten() {
+                    data.extend(value.to_le_bytes());
+                }
+            }
+            Values::Text(values) => {
+                for value in values.iter().flatten() {
+                    data.extend((value.len() as u32).to_le_bytes());
+                    data.extend(value.as_bytes());
+                }
+            }
+        }
+        data
+    }
+}
+
+/// Write a Parquet file of `columns` of `signals`, with a row per signal.
+///
+/// Columns are typed: counts, durations, addresses and commands are 64-bit
+/// integers, duty cycles floats, and validity booleans. Protocols,
+/// addresses, commands and validity are null for signals no protocol
+/// decoded, and errors for signals that parsed.
+pub fn write(mut output: impl Write, signals: &[Signal], columns: &[Column]) -> io::Result<()> {
+    let mut file = Vec::from(*MAGIC);
+    let mut chunks = Vec::new();
+
+    for &column in columns {
+        let values = Values::of(column, signals);
+        let defined = values.defined();
+        let optional = defined.contains(&false);
+
+        let mut page = Vec::new();
+        if optional {
+            let levels = definition_levels(&defined);
+            page.extend((levels.len() as u32).to_le_bytes());
+            page.extend(levels);
+        }
+        page.extend(values.plain());
+
+        let mut header = Thrift::default();
+        header.i32(1, 0); // data page
+        header.i32(2, page.len() as i32);
+        header.i32(3, page.len() as i32);
+        header.begin(5);
+        header.i32(1, signals.len() as i32);
+        header.i32(2, 0); // plain
+        header.i32(3, 3); // RLE
+        header.i32(4, 3);
+        header.end();
+        let header = header.finish();
+
+        let offset = file.len() as i64;
+        file.extend(&header);
+        file.extend(&page);
+        chunks.push(Chunk {
+            name: column.title(),
+            r#type: values.r#type(),
+            optional,
+            offset,
+            size: (header.len() + page.len()) as i64,
+        });
+    }
+
+    let metadata = metadata(&chunks, signals.len());
+    file.extend(&metadata);
+    file.extend((metadata.len() as u32).to_le_bytes());
+    file.extend(MAGIC);
+
+    output.write_all(&file)?;
+    output.flush()
+}
+
+/// A column chunk written to the file.
+struct Chunk {
+    name: String,
+    r#type: Type,
+    optional: bool,
+    /// Offset of the data page in the file.
+    offset: i64,
+    /// Size of the data page, header included.
+    size: i64,
+}
+
+/// The footer of the file: its schema and where its column chunks are.
+fn metadata(chunks: &[Chunk], rows: usize) -> Vec<u8> {
+    let mut metadata = Thrift::default();
+    metadata.i32(1, 1); // version
+
+    metadata.list(2, chunks.len() + 1);
+    metadata.begin_element();
+    metadata.binary(4, b"schema");
+    metadata.i32(5, chunks.len() as i32);
+    metadata.end();
+    for chunk in chunks {
+        metadata.begin_element();
+        metadata.i32(1, chunk.r#type as i32);
+        metadata.i32(3, i32::from(chunk.optional));
+        metadata.binary(4, chunk.name.as_bytes());
+        if let Type::ByteArray = chunk.r#type {
+            metadata.i32(6, 0); // UTF-8
+            metadata.begin(10);
+            metadata.begin(1); // string
+            metadata.end();
+            metadata.end();
+        }
+        metadata.end();
+    }
+
+    metadata.i64(3, rows as i64);
+
+    metadata.list(4, 1);
+    metadata.begin_element();
+    metadata.list(1, chunks.len());
+    for chunk in chunks {
+        metadata.begin_element();
+        metadata.i64(2, chunk.offset);
+        metadata.begin(3);
+        metadata.i32(1, chunk.r#type as i32);
+        metadata.list_i32(2, &[0, 3]);
+        metadata.list_binary(3, &[chunk.name.as_bytes()]);
+        metadata.i32(4, 0); // uncompressed
+        metadata.i64(5, rows as i64);
+        metadata.i64(6, chunk.size);
+        metadata.i64(7, chunk.size);
+        metadata.i64(9, chunk.offset);
+        metadata.end();
+        metadata.end();
+    }
+    metadata.i64(2, chunks.iter().map(|chunk| chunk.size).sum());
+    metadata.i64(3, rows as i64);
+    metadata.end();
+
+    metadata.binary(6, b"flipper-ir-dumps-csv");
+    metadata.finish()
+}
+
+/// Definition levels of a column at most one level deep, in the RLE and
+/// bit-packed hybrid encoding, as a single bit-packed run.
+fn definition_levels(defined: &[bool]) -> Vec<u8> {
+    let groups = defined.len().div_ceil(8);
+    let mut levels = Vec::new();
+    varint(&mut levels, (groups as u64) << 1 | 1);
+    let mut bits = pack_bits(defined);
+    bits.resize(groups, 0);
+    levels.extend(bits);
+    levels
+}
+
+/// `bits`, eight to a byte, least significant bit first.
+fn pack_bits(bits: &[bool]) -> Vec<u8> {
+    bits.chunks(8)
+        .map(|byte| {
+            byte.iter()
+                .enumerate()
+                .fold(0, |packed, (i, &bit)| packed | u8::from(bit) << i)
+        })
+        .collect()
+}
+
+fn varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+/// A writer of Thrift structs in the compact protocol, the encoding of
+/// Parquet metadata.
+#[derive(Default)]
+struct Thrift {
+    buffer: Vec<u8>,
+    /// Id of the last field written in each open struct.
+    last_ids: Vec<i16>,
+    last_id: i16,
+}
+
+impl Thrift {
+    const I32: u8 = 5;
+    const I64: u8 = 6;
+    const BINARY: u8 = 8;
+    const LIST: u8 = 9;
+    const STRUCT: u8 = 12;
+
+    fn field(&mut self, id: i16, r#type: u8) {
+        let delta = id - self.last_id;
+        if (1..=15).contains(&delta) {
+            self.buffer.push((delta as u8) << 4 | r#type);
+        } else {
+            self.buffer.push(r#type);
+            self.zigzag(id.into());
+        }
+        self.last_id = id;
+    }
+
+    fn zigzag(&mut self, value: i64) {
+        varint(&mut self.buffer, ((value << 1) ^ (value >> 63)) as u64);
+    }
+
+    fn i32(&mut self, id: i16, value: i32) {
+        self.field(id, Self::I32);
+        self.zigzag(value.into());
+    }
+
+    fn i64(&mut self, id: i16, value: i64) {
+        self.field(id, Self::I64);
+        self.zigzag(value);
+    }
+
+    fn binary(&mut self, id: i16, value: &[u8]) {
+        self.field(id, Self::BINARY);
+        self.element_binary(value);
+    }
+
+    fn element_binary(&mut self, value: &[u8]) {
+        varint(&mut self.buffer, value.len() as u64);
+        self.buffer.extend(value);
+    }
+
+    fn list_header(&mut self, id: i16, len: usize, r#type: u8) {
+        self.field(id, Self::LIST);
+        if len < 15 {
+            self.buffer.push((len as u8) << 4 | r#type);
+        } else {
+            self.buffer.push(0xF0 | r#type);
+            varint(&mut self.buffer, len as u64);
+        }
+    }
+
+    /// Start a list of `len` structs, written with [`Thrift::begin_element`].
+    fn list(&mut self, id: i16, len: usize) {
+        self.list_header(id, len, Self::STRUCT);
+    }
+
+    fn list_binary(&mut self, id: i16, values: &[&[u8]]) {
+        self.list_header(id, values.len(), Self::BINARY);
+        for value in values {
+            self.element_binary(value);
+        }
+    }
+
+    fn list_i32(&mut self, id: i16, values: &[i32]) {
+        self.list_header(id, values.len(), Self::I32);
+        for &value in values {
+            self.zigzag(value.into());
+        }
+    }
+
+    /// Start a struct field.
+    fn begin(&mut self, id: i16) {
+        self.field(id, Self::STRUCT);
+        self.begin_element();
+    }
+
+    /// Start a struct in a list.
+    fn begin_element(&mut self) {
+        self.last_ids.push(self.last_id);
+        self.last_id = 0;
+    }
+
+    /// End the innermost struct.
+    fn end(&mut self) {
+        self.buffer.push(0);
+        self.last_id = self.last_ids.pop().expect("no struct to end");
+    }
+
+    /// The encoded top-level struct.
+    fn finish(mut self) -> Vec<u8> {
+        self.buffer.push(0);
+        self.buffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::tests::{failed, nec, signal};
+
+    fn signals() -> Vec<Signal> {
+        vec![signal("Power", nec(0x04, 0x08)), failed("Broken")]
+    }
+
+    #[test]
+    fn test_varint() {
+        let mut buffer = Vec::new();
+        for value in [0, 1, 127, 128, 300] {
+            varint(&mut buffer, value);
+        }
+        assert_eq!(buffer, [0x00, 0x01, 0x7F, 0x80, 0x01, 0xAC, 0x02]);
+    }
+
+    #[test]
+    fn test_pack_bits() {
+        let bits = [true, false, true, true, false, false, false, false, true];
+        assert_eq!(pack_bits(&bits), [0b0000_1101, 0b0000_0001]);
+        assert_eq!(pack_bits(&[]), []);
+    }
+
+    #[test]
+    fn test_definition_levels() {
+        assert_eq!(definition_levels(&[true, false, true]), [0x03, 0b101]);
+        assert_eq!(
+            definition_levels(&[false; 8].into_iter().chain([true]).collect::<Vec<_>>()),
+            [0x05, 0x00, 0x01]
+        );
+    }
+
+    #[test]
+    fn test_thrift_fields() {
+        let mut thrift = Thrift::default();
+        thrift.i32(1, 0);
+        thrift.i32(2, -1);
+        thrift.i64(3, -3);
+        thrift.binary(4, b"ab");
+        // Too far from the last field for the short form.
+        thrift.i32(20, 1);
+        // Going backwards too.
+        thrift.i32(5, 64);
+        assert_eq!(
+            thrift.finish(),
+            [
+                0x15, 0x00, 0x15, 0x01, 0x16, 0x05, 0x18, 0x02, b'a', b'b', 0x05, 0x28, 0x02, 0x05,
+                0x0A, 0x80, 0x01, 0x00
+            ]
+        );
+    }
+
+    #[test]
+    fn test_thrift_structs() {
+        let mut thrift = Thrift::default();
+        thrift.begin(5);
+        thrift.i32(1, 7);
+        thrift.begin(2);
+        thrift.end();
+        thrift.end();
+        // Field ids continue from the struct field's.
+        thrift.i32(6, 1);
+        thrift.list(7, 2);
+        thrift.begin_element();
+        thrift.i32(1, 1);
+        thrift.end();
+        thrift.begin_element();
+        thrift.end();
+        assert_eq!(
+            thrift.finish(),
+            [
+                0x5C, 0x15, 0x0E, 0x1C, 0x00, 0x00, 0x15, 0x02, 0x19, 0x2C, 0x15, 0x02, 0x00, 0x00,
+                0x00
+            ]
+        );
+    }
+
+    #[test]
+    fn test_thrift_lists() {
+        let mut thrift = Thrift::default();
+        thrift.list_i32(1, &[0, 3]);
+        thrift.list_binary(2, &[b"a"]);
+        thrift.list_i32(3, &[1; 15]);
+        let mut expected = vec![
+            0x19, 0x25, 0x00, 0x06, 0x19, 0x18, 0x01, b'a', 0x19, 0xF5, 0x0F,
+        ];
+        expected.extend([0x02; 15]);
+        expected.push(0x00);
+        assert_eq!(thrift.finish(), expected);
+    }
+
+    #[test]
+    fn test_layout() {
+        let mut file = Vec::new();
+        write(&mut file, &signals(), &[Column::Name, Column::Address]).unwrap();
+
+        let mut expected = Vec::from(*MAGIC);
+        // Names, required: the page is just their values.
+        expected.extend([
+            0x15, 0x00, 0x15, 0x26, 0x15, 0x26, 0x2C, 0x15, 0x04, 0x15, 0x00, 0x15, 0x06, 0x15,
+            0x06, 0x00, 0x00,
+        ]);
+        expected.extend(b"\x05\0\0\0Power\x06\0\0\0Broken");
+        // Addresses, optional: definition levels precede the values.
+        expected.extend([
+            0x15, 0x00, 0x15, 0x1C, 0x15, 0x1C, 0x2C, 0x15, 0x04, 0x15, 0x00, 0x15, 0x06, 0x15,
+            0x06, 0x00, 0x00,
+        ]);
+        expected.extend([0x02, 0x00, 0x00, 0x00, 0x03, 0b01]);
+        expected.extend(4i64.to_le_bytes());
+        assert_eq!(file[..expected.len()], expected);
+
+        let chunks = [
+            Chunk {
+                name: "name".to_owned(),
+                r#type: Type::ByteArray,
+                optional: false,
+                offset: 4,
+                size: 17 + 19,
+            },
+            Chunk {
+                name: "address".to_owned(),
+                r#type: Type::Int64,
+                optional: true,
+                offset: 4 + 17 + 19,
+                size: 17 + 14,
+            },
+        ];
+        let metadata = metadata(&chunks, 2);
+        assert_eq!(file[expected.len()..file.len() - 8], metadata);
+        assert_eq!(
+            file[file.len() - 8..file.len() - 4],
+            (metadata.len() as u32).to_le_bytes()
+        );
+        assert_eq!(file[file.len() - 4..], *MAGIC);
+    }
+
+    /// `fixtures/signals.parquet` was checked against a reader written from
+    /// the format's specification, independently of this writer.
+    #[test]
+    fn test_write_golden() {
+        let columns = [
+            Column::Name,
+            Column::Protocol,
+            Column::Address,
+            Column::Command,
+            Column::Valid,
+            Column::Frequency,
+            Column::DutyCycle,
+            Column::Error,
+        ];
+        let mut file = Vec::new();
+        write(&mut file, &signals(), &columns).unwrap();
+        assert_eq!(file, include_bytes!("../fixtures/signals.parquet"));
+    }
+}
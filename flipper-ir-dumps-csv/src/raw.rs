@@ -0,0 +1,119 @@
+use std::io::Write;
+
+use color_eyre::{eyre::WrapErr, Result};
+use csv::Writer;
+
+use flipper_ir_dumps::signal::SignalComponent;
+
+use crate::{cli::RawLayout, input::Signal};
+
+/// Write the timings of `signals`, in µs, laid out as asked.
+///
+/// - [`RawLayout::Wide`]: a row per signal, its source file if any, its name,
+///   then its durations;
+/// - [`RawLayout::Long`]: a row per duration under a header, with the signal,
+///   the index of the duration and whether it's a pulse or a pause.
+pub fn write(writer: &mut Writer<impl Write>, signals: &[Signal], layout: RawLayout) -> Result<()> {
+    let several = signals.iter().any(|signal| signal.source_file.is_some());
+
+    if layout == RawLayout::Long {
+        let mut header = Vec::new();
+        if several {
+            header.push("source_file");
+        }
+        header.extend(["signal", "index", "level", "duration"]);
+        writer
+            .write_record(header)
+            .wrap_err("Failed to write header")?;
+    }
+
+    for signal in signals {
+        let source_file = signal.source_file.iter().cloned();
+        let name = signal.raw.name();
+        match layout {
+            RawLayout::Wide => {
+                let mut record: Vec<String> = source_file.collect();
+                record.push(name.to_owned());
+                record.extend(signal.raw.data().iter().map(u32::to_string));
+                writer
+                    .write_record(record)
+                    .wrap_err("Failed to write record")?;
+            }
+            RawLayout::Long => {
+                for (i, (component, duration)) in signal.raw.slots().enumerate() {
+                    let level = match component {
+                        SignalComponent::Pulse => "pulse",
+                        SignalComponent::Pause => "pause",
+                    };
+                    let mut record: Vec<String> = source_file.clone().collect();
+                    record.extend([
+                        name.to_owned(),
+                        i.to_string(),
+                        level.to_owned(),
+                        duration.as_micros().to_string(),
+                    ]);
+                    writer
+                        .write_record(record)
+                        .wrap_err("Failed to write record")?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::tests::{csv, signal};
+
+    fn signals() -> Vec<Signal> {
+        vec![
+            signal("Short", vec![500, 500, 500, 1500, 500]),
+            signal("Blip", vec![600]),
+        ]
+    }
+
+    #[test]
+    fn test_wide() {
+        let signals = signals();
+        assert_eq!(
+            csv(|writer| write(writer, &signals, RawLayout::Wide)),
+            "Short,500,500,500,1500,500\nBlip,600\n"
+        );
+    }
+
+    #[test]
+    fn test_long() {
+        let signals = signals();
+        assert_eq!(
+            csv(|writer| write(writer, &signals, RawLayout::Long)),
+            concat!(
+                "signal,index,level,duration\n",
+                "Short,0,pulse,500\n",
+                "Short,1,pause,500\n",
+                "Short,2,pulse,500\n",
+                "Short,3,pause,1500\n",
+                "Short,4,pulse,500\n",
+                "Blip,0,pulse,600\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_source_file() {
+        let signals = [Signal {
+            source_file: Some("tv.ir".to_owned()),
+            ..signal("Blip", vec![600])
+        }];
+        assert_eq!(
+            csv(|writer| write(writer, &signals, RawLayout::Wide)),
+            "tv.ir,Blip,600\n"
+        );
+        assert_eq!(
+            csv(|writer| write(writer, &signals, RawLayout::Long)),
+            "source_file,signal,index,level,duration\ntv.ir,Blip,0,pulse,600\n"
+        );
+    }
+}
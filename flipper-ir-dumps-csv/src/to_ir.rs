@@ -0,0 +1,361 @@
+use std::{io::Write, path::PathBuf};
+
+use color_eyre::{
+    eyre::{bail, eyre, WrapErr},
+    Result,
+};
+use csv::{ReaderBuilder, StringRecord};
+
+use flipper_ir_dumps::{
+    dump::DumpFile,
+    formats::timings::{self, TimingListOptions},
+    protocol::{
+        nec::{Nec, NecExt},
+        rc5::Rc5,
+        rc6::{Rc6, Rc6Mode},
+        samsung::Samsung32,
+        Decoded, Registry,
+    },
+    signal::{DutyCycle, Polarity, RawSignal, TimingSequence},
+};
+
+use crate::input;
+
+/// Carrier of signals without a frequency column, as most protocols use.
+const DEFAULT_FREQUENCY: u32 = 38_000;
+/// Carrier of the Philips protocols.
+const RC_FREQUENCY: u32 = 36_000;
+
+/// Read the CSV files at `paths`, their fields separated by `delimiter`, and write a `.ir` dump of their signals.
+///
+/// Files start with a header naming their columns, in any order:
+///
+/// - `name`, required;
+/// - `protocol`, `address` and `command`: a signal encoded from a protocol
+///   known to the Flipper firmware, among NEC, NECext, Samsung32, RC5, RC5X
+///   and RC6, with address and command in hexadecimal, either as numbers
+///   (`0x04`) or as the little-endian bytes of `.ir` files (`04 00 00 00`);
+/// - `timings`: durations in µs, alternating pulses and pauses, pulse first,
+///   for signals without a protocol;
+/// - `frequency` and `duty_cycle`: the carrier, if not the protocol's.
+pub fn convert(paths: &[PathBuf], delimiter: u8, output: impl Write) -> Result<()> {
+    let registry = Registry::default();
+    let mut signals = Vec::new();
+
+    for path in paths {
+        let contents = input::read(path)?;
+        let mut reader = ReaderBuilder::new()
+            .flexible(true)
+            .delimiter(delimiter)
+            .from_reader(contents.as_slice());
+        let header = reader
+            .headers()
+            .wrap_err_with(|| format!("Failed to read header of {}", path.display()))?
+            .clone();
+
+        for record in reader.records() {
+            let record = record.wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+            let line = record.position().map_or(0, |position| position.line());
+            let signal = signal(&header, &record, &registry)
+                .wrap_err_with(|| format!("{}, line {}", path.display(), line))?;
+            signals.push(signal);
+        }
+    }
+
+    let mut output = output;
+    write!(output, "{}", DumpFile::new(1, signals)).wrap_err("Failed to write dump")?;
+    output.flush().wrap_err("Failed to write dump")?;
+
+    Ok(())
+}
+
+/// The signal of a row.
+fn signal(header: &StringRecord, record: &StringRecord, registry: &Registry) -> Result<RawSignal> {
+    let field = |column: &str| {
+        header
+            .iter()
+            .position(|title| title.trim().eq_ignore_ascii_case(column))
+            .and_then(|index| record.get(index))
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+    };
+
+    let name = field("name").ok_or_else(|| eyre!("Missing signal name"))?;
+    let duty_cycle = match field("duty_cycle") {
+        Some(duty_cycle) => {
+            let duty_cycle = duty_cycle
+                .parse()
+                .map_err(|_| eyre!("Invalid duty cycle `{}`", duty_cycle))?;
+            DutyCycle::new(duty_cycle)?
+        }
+        None => DutyCycle::default(),
+    };
+    let frequency = field("frequency")
+        .map(|frequency| {
+            frequency
+                .parse::<u32>()
+                .map_err(|_| eyre!("Invalid frequency `{}`", frequency))
+        })
+        .transpose()?;
+
+    let (data, protocol_frequency) = match (field("protocol"), field("timings")) {
+        (Some(protocol), _) => {
+            let address = number(field("address").ok_or_else(|| eyre!("Missing address"))?)?;
+            let command = number(field("command").ok_or_else(|| eyre!("Missing command"))?)?;
+            let (decoded, frequency) = decoded(protocol, address, command)?;
+            let data = registry
+                .encode(&decoded)
+                .ok_or_else(|| eyre!("Can't encode {} signals", protocol))?;
+            (data, frequency)
+        }
+        (None, Some(list)) => {
+            let signal = timings::parse(name, list, &TimingListOptions::default())?;
+            (signal.data().to_vec(), DEFAULT_FREQUENCY)
+        }
+        (None, None) => bail!("Signal `{}` has neither protocol nor timings", name),
+    };
+
+    let timings = TimingSequence::new(Polarity::StartsWithPulse, data);
+    let signal = RawSignal::new(
+        name,
+        frequency.unwrap_or(protocol_frequency),
+        duty_cycle,
+        timings,
+    )?;
+    Ok(signal)
+}
+
+/// A frame of the protocol named `protocol`, as the Flipper firmware names
+/// them, and the carrier frequency of the protocol.
+fn decoded(protocol: &str, address: u32, command: u32) -> Result<(Decoded, u32)> {
+    let out_of_range =
+        |field: &str, value: u32| eyre!("{} 0x{:X} out of range for {}", field, value, protocol);
+    let byte = |field: &str, value: u32, max: u32| {
+        if value > max {
+            Err(out_of_range(field, value))
+        } else {
+            Ok(value as u8)
+        }
+    };
+
+    let decoded = match protocol.to_ascii_uppercase().as_str() {
+        "NEC" => {
+            let address = byte("Address", address, 0xFF)?;
+            Decoded::Nec(Nec {
+                address,
+                inverted_address: !address,
+                command: byte("Command", command, 0xFF)?,
+                repeats: 0,
+            })
+        }
+        "NECEXT" => Decoded::NecExt(NecExt {
+            address: u16::try_from(address).map_err(|_| out_of_range("Address", address))?,
+            command: byte("Command", command, 0xFF)?,
+            repeats: 0,
+        }),
+        "SAMSUNG32" => Decoded::Samsung32(Samsung32 {
+            address: byte("Address", address, 0xFF)?,
+            command: byte("Command", command, 0xFF)?,
+            repeats: 0,
+        }),
+        "RC5" | "RC5X" => {
+            let max_command = if protocol.eq_ignore_ascii_case("RC5") {
+                0x3F
+            } else {
+                0x7F
+            };
+            let rc5 = Decoded::Rc5(Rc5 {
+                address: byte("Address", address, 0x1F)?,
+                command: byte("Command", command, max_command)?,
+                toggle: false,
+                repeats: 0,
+            });
+            return Ok((rc5, RC_FREQUENCY));
+        }
+        "RC6" => {
+            let rc6 = Decoded::Rc6(Rc6 {
+                mode: Rc6Mode::Mode0,
+                address: byte("Address", address, 0xFF)?,
+                command: byte("Command", command, 0xFF)?,
+                toggle: false,
+                repeats: 0,
+            });
+            return Ok((rc6, RC_FREQUENCY));
+        }
+        _ => bail!("Unsupported protocol `{}`", protocol),
+    };
+
+    Ok((decoded, DEFAULT_FREQUENCY))
+}
+
+/// A hexadecimal number, either `0x04` or `04`, or little-endian bytes
+/// separated by spaces as in `.ir` files: `04 00 00 00`.
+fn number(text: &str) -> Result<u32> {
+    let invalid = || eyre!("Invalid hexadecimal number `{}`", text);
+
+    if text.contains(char::is_whitespace) {
+        let bytes = text
+            .split_whitespace()
+            .map(|byte| u8::from_str_radix(byte, 16).map_err(|_| invalid()))
+            .collect::<Result<Vec<u8>>>()?;
+        if bytes.len() > 4 {
+            return Err(invalid());
+        }
+        return Ok(bytes
+            .iter()
+            .rev()
+            .fold(0, |value, &byte| value << 8 | u32::from(byte)));
+    }
+
+    let digits = text
+        .strip_prefix("0x")
+        .or_else(|| text.strip_prefix("0X"))
+        .unwrap_or(text);
+    u32::from_str_radix(digits, 16).map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use flipper_ir_dumps::signal::ParsedSignal;
+
+    use super::*;
+    use crate::{
+        cli::Column,
+        columns,
+        input::tests::{csv, nec, signal, temp_dir},
+    };
+
+    /// The signals of the dump converted from CSV files of `contents`.
+    fn converted(dir: &Path, contents: &[&str], delimiter: u8) -> Vec<RawSignal> {
+        let paths: Vec<PathBuf> = contents
+            .iter()
+            .enumerate()
+            .map(|(i, contents)| {
+                let path = dir.join(format!("{}.csv", i));
+                fs::write(&path, contents).unwrap();
+                path
+            })
+            .collect();
+        let mut output = Vec::new();
+        convert(&paths, delimiter, &mut output).unwrap();
+        DumpFile::from_bytes(&output).unwrap().signals().to_vec()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let dir = temp_dir("to-ir-round-trip");
+        let exported = [
+            signal("Power", nec(0x04, 0x08)),
+            signal("Mute", nec(0x04, 0x0D)),
+        ];
+        let columns = [
+            Column::Name,
+            Column::Protocol,
+            Column::Address,
+            Column::Command,
+        ];
+        let text = csv(|writer| columns::write(writer, &exported, &columns));
+
+        let signals = converted(&dir, &[&text], b',');
+        assert_eq!(signals.len(), 2);
+        for (signal, exported) in signals.iter().zip(&exported) {
+            let parsed = ParsedSignal::try_from(signal).unwrap();
+            assert_eq!(parsed.name(), exported.name());
+            assert!(exported.protocol().is_some());
+            assert_eq!(parsed.protocol(), exported.protocol());
+            assert_eq!(signal.frequency().hz(), 38_000);
+        }
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_convert() {
+        let dir = temp_dir("to-ir-convert");
+        let signals = converted(
+            &dir,
+            &[
+                "name;protocol;address;command\nPower;NEC;04 00 00 00;08 00 00 00\n",
+                concat!(
+                    "timings;Name;frequency;duty_cycle;protocol;address;command\n",
+                    ";Mute;;;RC5;0x01;0x0D\n",
+                    "500 500 500 1500 500;Blip;40000;0.5;;;\n",
+                ),
+            ],
+            b';',
+        );
+        assert_eq!(
+            signals.iter().map(RawSignal::name).collect::<Vec<_>>(),
+            ["Power", "Mute", "Blip"]
+        );
+
+        let power = ParsedSignal::try_from(&signals[0]).unwrap();
+        let frame = power.protocol().unwrap().frame();
+        assert_eq!((frame.address, frame.command), (Some(0x04), Some(0x08)));
+
+        let mute = ParsedSignal::try_from(&signals[1]).unwrap();
+        assert_eq!(mute.protocol().unwrap().protocol(), "RC5");
+        assert_eq!(signals[1].frequency().hz(), RC_FREQUENCY);
+
+        assert_eq!(signals[2].data(), [500, 500, 500, 1500, 500]);
+        assert_eq!(signals[2].frequency().hz(), 40_000);
+        assert_eq!(signals[2].duty_cycle().get(), 0.5);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_convert_errors() {
+        let dir = temp_dir("to-ir-errors");
+        let error = |contents: &str| {
+            let path = dir.join("signals.csv");
+            fs::write(&path, contents).unwrap();
+            let err = convert(&[path], b',', Vec::new()).unwrap_err();
+            format!("{:#}", err)
+        };
+
+        assert!(error("name,protocol,address\nPower,NEC,0x04\n").ends_with("Missing command"));
+        assert!(error("name\nPower\n").ends_with("Signal `Power` has neither protocol nor timings"));
+        assert!(error("protocol\nNEC\n").ends_with("Missing signal name"));
+        assert!(error("name,protocol,address,command\nPower,Sony,1,2\n")
+            .ends_with("Unsupported protocol `Sony`"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_decoded() {
+        assert!(decoded("nec", 0xFF, 0xFF).is_ok());
+        let err = decoded("NEC", 0x100, 0x08).unwrap_err();
+        assert_eq!(err.to_string(), "Address 0x100 out of range for NEC");
+        assert!(decoded("NECext", 0xFFFF, 0x08).is_ok());
+        assert!(decoded("NECext", 0x10000, 0x08).is_err());
+        assert!(decoded("RC5", 0x1F, 0x3F).is_ok());
+        let err = decoded("RC5", 0x01, 0x40).unwrap_err();
+        assert_eq!(err.to_string(), "Command 0x40 out of range for RC5");
+        assert!(decoded("RC5X", 0x01, 0x7F).is_ok());
+        assert_eq!(decoded("RC6", 0x00, 0x0C).unwrap().1, RC_FREQUENCY);
+        assert_eq!(
+            decoded("Samsung32", 0x07, 0x02).unwrap().1,
+            DEFAULT_FREQUENCY
+        );
+    }
+
+    #[test]
+    fn test_number() {
+        assert_eq!(number("0x04").unwrap(), 0x04);
+        assert_eq!(number("0XfF").unwrap(), 0xFF);
+        assert_eq!(number("1F").unwrap(), 0x1F);
+        assert_eq!(number("04 01 00 00").unwrap(), 0x0104);
+        assert_eq!(number("FF FF FF FF").unwrap(), u32::MAX);
+        assert!(number("00 00 00 00 01").is_err());
+        assert!(number("0x1_0000_0000").is_err());
+        assert!(number("0x100000000").is_err());
+        assert_eq!(
+            number("4g").unwrap_err().to_string(),
+            "Invalid hexadecimal number `4g`"
+        );
+    }
+}
@@ -13,4 +13,7 @@ pub struct Cli {
     pub file: PathBuf,
     #[clap(short, long)]
     pub output_dir: PathBuf,
+    /// Also write the signals as a value change dump, for GTKWave or PulseView.
+    #[clap(long)]
+    pub vcd: Option<PathBuf>,
 }
@@ -1,34 +1,48 @@
 use clap::Parser;
 use color_eyre::eyre::WrapErr;
 
-use flipper_ir_dumps::{dump::DumpFile, signal::ParsedSignal};
+use flipper_ir_dumps::{
+    formats::vcd,
+    pipeline::{self, Event},
+};
+use flipper_ir_dumps_vis::plotting;
 
 mod cli;
 use cli::Cli;
 
-mod plotting;
-
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     color_eyre::install()?;
 
     let cli = Cli::parse();
-    let contents = std::fs::read_to_string(cli.file).wrap_err("Failed to read file")?;
-    let dump = DumpFile::try_from(contents.as_str());
-
-    let dump = match dump {
-        Ok(dump) => dump,
+    let contents = std::fs::read(&cli.file).wrap_err("Failed to read file")?;
+    let output = pipeline::run(
+        &cli.file.to_string_lossy(),
+        &contents,
+        |event| match event {
+            Event::Progress { .. } => {}
+            event => eprintln!("{}", event),
+        },
+    );
+
+    let output = match output {
+        Ok(output) => output,
         Err(err) => {
-            eprintln!("Failed decoding dump: {:?}", err);
+            eprintln!("{}", err);
             return Ok(());
         }
     };
 
     std::fs::create_dir_all(&cli.output_dir).wrap_err("Failed to create output directory")?;
 
-    for signal in dump.signals() {
-        plotting::plot_signal(&signal, &cli.output_dir)?;
+    for signal in output.dump.signals() {
+        plotting::plot_signal(signal, &cli.output_dir)?;
+    }
+
+    if let Some(vcd_file) = &cli.vcd {
+        std::fs::write(vcd_file, vcd::to_vcd(&output.dump)).wrap_err("Failed to write VCD")?;
+    }
 
-        let parsed_signal = ParsedSignal::try_from(signal).wrap_err("Failed to parse signal")?;
+    for parsed_signal in &output.parsed {
         println!("parsed signal: {:#?}", parsed_signal);
     }
 
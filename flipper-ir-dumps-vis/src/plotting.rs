@@ -6,7 +6,7 @@ use plotters::{
     style::colors::full_palette::{BLUEGREY_900, GREEN_900, RED_900},
 };
 
-use flipper_ir_dumps::signal::RawSignal;
+use flipper_ir_dumps::signal::{RawSignal, SignalComponent};
 use flipper_utils::round_to;
 
 const IMAGE_WIDTH: u32 = 1512 * 2;
@@ -49,12 +49,11 @@ pub fn plot_signal(signal: &RawSignal, out_folder: &Path) -> Result<()> {
 
     const ROUND_TO: u32 = 550;
     let rounded_signal = signal
-        .data()
-        .iter()
-        .map(|x| round_to(*x, ROUND_TO))
-        .collect::<Vec<u32>>();
+        .slots()
+        .map(|(component, duration)| (component, round_to(duration.as_micros() as u32, ROUND_TO)))
+        .collect::<Vec<_>>();
 
-    let total_timing: u32 = rounded_signal.iter().sum();
+    let total_timing: u32 = rounded_signal.iter().map(|(_, timing)| timing).sum();
     let x_limit = total_timing.max(round_to(300_000, ROUND_TO));
 
     // use white sans-serif font for the captions
@@ -85,17 +84,15 @@ pub fn plot_signal(signal: &RawSignal, out_folder: &Path) -> Result<()> {
     let mut x = 0;
 
     chart
-        .draw_series(rounded_signal.iter().enumerate().map(|(i, timing)| {
+        .draw_series(rounded_signal.iter().map(|&(component, timing)| {
             let x0 = x;
             x += timing;
             let x1 = x;
             let y0 = 0;
-            let y1 = if i & 1 == 0 { 200 } else { 20 };
 
-            let style = if i & 1 == 0 {
-                positive_signal_style.clone()
-            } else {
-                negative_signal_style.clone()
+            let (y1, style) = match component {
+                SignalComponent::Pulse => (200, positive_signal_style),
+                SignalComponent::Pause => (20, negative_signal_style),
             };
 
             Rectangle::new([(x0, y0), (x1, y1)], style)
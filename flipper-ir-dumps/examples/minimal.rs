@@ -0,0 +1,34 @@
+//! Minimal embedded profile: the decoder core without `std`.
+//!
+//! Build it with
+//!
+//! ```sh
+//! cargo build -p flipper-ir-dumps --example minimal --no-default-features
+//! ```
+//!
+//! to check that the core only needs `core` and `alloc`, and no serde,
+//! plotting or CLI dependencies are pulled in.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use flipper_ir_dumps::{dump::DumpFile, signal::ParsedSignal, ENABLED_FEATURES};
+
+/// Decode all signals of a dump, returning the number of packets in each one.
+pub fn packet_counts(dump: &str) -> Option<Vec<usize>> {
+    let dump = DumpFile::try_from(dump).ok()?;
+
+    dump.signals()
+        .iter()
+        .map(|signal| ParsedSignal::try_from(signal).ok())
+        .map(|parsed| parsed.map(|parsed| parsed.packets().len()))
+        .collect()
+}
+
+/// Whether the core was built without the standard library.
+pub fn is_no_std() -> bool {
+    !ENABLED_FEATURES.contains(&"std")
+}
@@ -0,0 +1,24 @@
+//! Feeds arbitrary input through the dump parser and the signal decoder.
+//!
+//! Run with `cargo +nightly fuzz run parse_dump` from `flipper-ir-dumps`.
+//! Any input must be rejected with an error, never with a panic.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use flipper_ir_dumps::{dump::DumpFile, signal::ParsedSignal};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Ok(dump) = DumpFile::try_from(input) else {
+        return;
+    };
+
+    let _ = dump.validate();
+    for signal in dump.signals() {
+        let _ = ParsedSignal::try_from(signal);
+    }
+});
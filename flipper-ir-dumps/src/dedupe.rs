@@ -0,0 +1,148 @@
+//! Detecting whether two dumps are captures of the same physical remote.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::{
+    dump::{DumpFile, DUPLICATE_TOLERANCE},
+    signal::{ParsedSignal, RawSignal},
+};
+
+/// Default fraction of matching commands above which two dumps are
+/// considered to come from the same remote.
+pub const DEFAULT_THRESHOLD: f32 = 0.8;
+
+/// Result of comparing two dumps command by command.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RemoteComparison {
+    /// Pairs of signal names (first dump, second dump) carrying the same command.
+    pub matches: Vec<(String, String)>,
+    /// Signals of the first dump without a counterpart in the second one.
+    pub only_in_first: Vec<String>,
+    /// Signals of the second dump without a counterpart in the first one.
+    pub only_in_second: Vec<String>,
+}
+
+impl RemoteComparison {
+    /// Fraction of commands of the smaller dump found in the other one.
+    ///
+    /// Using the smaller dump lets a partial capture of a remote
+    /// still match a complete one.
+    pub fn similarity(&self) -> f32 {
+        let first = self.matches.len() + self.only_in_first.len();
+        let second = self.matches.len() + self.only_in_second.len();
+
+        match first.min(second) {
+            0 => 0.0,
+            smaller => self.matches.len() as f32 / smaller as f32,
+        }
+    }
+
+    /// Whether the dumps likely come from the same remote.
+    pub fn is_same_remote(&self, threshold: f32) -> bool {
+        self.similarity() >= threshold
+    }
+
+    /// Signals from the second dump that merging it into the first one would add.
+    pub fn proposed_additions(&self) -> &[String] {
+        &self.only_in_second
+    }
+
+    /// Matched signals named differently in the two dumps, as (first, second) pairs.
+    pub fn proposed_renames(&self) -> impl Iterator<Item = &(String, String)> {
+        self.matches
+            .iter()
+            .filter(|(first, second)| first != second)
+    }
+}
+
+/// Compare two dumps, pairing up signals carrying the same command.
+///
+/// Signals are compared by their decoded packets; signals that fail to
+/// decode are compared by raw timings instead.
+///
+/// # Examples
+/// ```rust
+/// use flipper_ir_dumps::{
+///     dedupe::{compare_dumps, DEFAULT_THRESHOLD},
+///     examples::EXAMPLES,
+/// };
+///
+/// let comparison = compare_dumps(&EXAMPLES[0].dump(), &EXAMPLES[0].dump());
+/// assert!(comparison.is_same_remote(DEFAULT_THRESHOLD));
+/// assert!(comparison.proposed_additions().is_empty());
+/// ```
+pub fn compare_dumps(first: &DumpFile, second: &DumpFile) -> RemoteComparison {
+    let first = decode_all(first);
+    let mut second = decode_all(second);
+
+    let mut comparison = RemoteComparison {
+        matches: Vec::new(),
+        only_in_first: Vec::new(),
+        only_in_second: Vec::new(),
+    };
+
+    for (raw, parsed) in &first {
+        let counterpart =
+            second
+                .iter()
+                .position(|(other_raw, other_parsed)| match (parsed, other_parsed) {
+                    (Some(parsed), Some(other_parsed)) => {
+                        parsed.packets() == other_parsed.packets()
+                    }
+                    _ => raw.is_duplicate_of(other_raw, DUPLICATE_TOLERANCE),
+                });
+
+        match counterpart {
+            Some(i) => {
+                let (other_raw, _) = second.remove(i);
+                comparison
+                    .matches
+                    .push((raw.name().to_string(), other_raw.name().to_string()));
+            }
+            None => comparison.only_in_first.push(raw.name().to_string()),
+        }
+    }
+
+    comparison.only_in_second = second
+        .into_iter()
+        .map(|(raw, _)| raw.name().to_string())
+        .collect();
+
+    comparison
+}
+
+fn decode_all(dump: &DumpFile) -> Vec<(&RawSignal, Option<&ParsedSignal>)> {
+    dump.signals()
+        .iter()
+        .map(|signal| (signal, signal.parsed().ok()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::examples::EXAMPLES;
+
+    use super::*;
+
+    #[test]
+    fn test_compare_dumps() {
+        let temperature = EXAMPLES[0].dump();
+        let fan_speed = EXAMPLES[1].dump();
+
+        let same = compare_dumps(&temperature, &temperature);
+        assert_eq!(same.similarity(), 1.0);
+        assert!(same.is_same_remote(DEFAULT_THRESHOLD));
+        assert!(same.proposed_additions().is_empty());
+        assert_eq!(same.proposed_renames().count(), 0);
+
+        let different = compare_dumps(&temperature, &fan_speed);
+        assert!(different.similarity() < DEFAULT_THRESHOLD);
+        assert_eq!(
+            different.matches.len() + different.only_in_second.len(),
+            fan_speed.signals().len()
+        );
+    }
+}
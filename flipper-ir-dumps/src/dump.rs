@@ -1,23 +1,437 @@
+use alloc::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use core::fmt;
+
+use displaydoc::Display;
 use nom::{
+    branch::alt,
     bytes::complete::tag,
-    character::complete::{digit1, line_ending, not_line_ending},
-    combinator::{all_consuming, map_res},
-    multi::{many0, separated_list0},
-    number, Finish, Parser,
+    combinator::{all_consuming, consumed, map_res, verify},
+    multi::many0,
+    Finish, Parser,
 };
+use thiserror::Error;
+
+use crate::{
+    flipper_format::{comment, field, float, header, key_value, string, uint, uint_list},
+    signal::{DutyCycle, Frequency, RawSignal, SignalType, SourceText, UnknownField},
+    validation::ValidationIssue,
+};
+
+/// Newest dump format version this parser fully understands.
+///
+/// Dumps with a higher version are parsed in best-effort mode, see [`DumpWarning`].
+pub const SUPPORTED_VERSION: u32 = 1;
+
+/// Keys of a signal block understood by the parser, in the order they appear.
+const KNOWN_KEYS: &[&str] = &["name", "type", "frequency", "duty_cycle", "data"];
 
-use crate::signal::{RawSignal, SignalType};
+/// Relative timing tolerance used to detect duplicate signals when merging dumps.
+pub const DUPLICATE_TOLERANCE: f32 = 0.2;
 
 #[derive(Debug, PartialEq)]
 pub struct DumpFile {
     version: u32,
     signals: Vec<RawSignal>,
+    /// Comment lines after the last signal, verbatim text after the `#`.
+    trailing_comments: Vec<String>,
+    warnings: Vec<DumpWarning>,
+    /// Position of the first signal with a given name in `signals`.
+    index: BTreeMap<String, usize>,
+    /// Line ending of the file read, `\n` or `\r\n`.
+    line_ending: &'static str,
+    /// Whether the last line of the file read ends with a line ending.
+    final_line_ending: bool,
+}
+
+/// A problem encountered while parsing a dump that didn't prevent loading it.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DumpWarning {
+    /// The dump version is newer than [`SUPPORTED_VERSION`], so the dump was
+    /// parsed in best-effort mode.
+    NewerVersion(u32),
+    /// A signal was skipped as it couldn't be parsed.
+    SkippedSignal(String),
+    /// The file isn't valid UTF-8, invalid sequences were replaced with U+FFFD.
+    InvalidUtf8,
+}
+
+impl fmt::Display for DumpWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DumpWarning::NewerVersion(version) => write!(
+                f,
+                "dump version {} is newer than supported version {}, parsed in best-effort mode",
+                version, SUPPORTED_VERSION
+            ),
+            DumpWarning::SkippedSignal(name) => {
+                write!(f, "skipped signal `{}` that couldn't be parsed", name)
+            }
+            DumpWarning::InvalidUtf8 => write!(
+                f,
+                "file is not valid UTF-8, invalid sequences were replaced with U+FFFD"
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+/// Error parsing an IR dump
+pub enum DumpError {
+    /// Failed parsing dump at line {line}: {kind:?}
+    Syntax {
+        line: usize,
+        kind: nom::error::ErrorKind,
+    },
+}
+
+/// What to do with an incoming signal that conflicts with one already in the dump.
+///
+/// A signal conflicts if it duplicates an existing signal's timings
+/// (see [`RawSignal::is_duplicate_of`]) or reuses an existing name.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum ConflictPolicy {
+    /// Keep the existing signal and drop the incoming one.
+    #[default]
+    Skip,
+    /// Keep both, giving the incoming signal a unique name if its name is taken.
+    Rename,
+    /// Abort the merge, leaving the dump unchanged.
+    Error,
+}
+
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+/// Error merging IR dumps
+pub enum MergeError {
+    /// Signal `{incoming}` duplicates the timings of signal `{existing}`
+    DuplicateSignal { incoming: String, existing: String },
+    /// Signal name `{0}` is already taken
+    NameClash(String),
 }
 
 impl DumpFile {
+    pub fn new(version: u32, signals: Vec<RawSignal>) -> Self {
+        let mut dump = Self {
+            version,
+            signals: Vec::new(),
+            trailing_comments: Vec::new(),
+            warnings: Vec::new(),
+            index: BTreeMap::new(),
+            line_ending: "\n",
+            final_line_ending: true,
+        };
+        dump.extend_signals(signals);
+        dump
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
     pub fn signals(&self) -> &[RawSignal] {
         &self.signals
     }
+
+    /// Problems found while parsing, e.g. signals skipped in a dump of a newer version.
+    pub fn warnings(&self) -> &[DumpWarning] {
+        &self.warnings
+    }
+
+    /// Look up a signal by name; with several signals of the same name, the first one wins.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use flipper_ir_dumps::examples::EXAMPLES;
+    ///
+    /// let dump = EXAMPLES[1].dump();
+    /// assert!(dump.signal("C_24_f_1").is_some());
+    /// assert!(dump.signal("power").is_none());
+    /// ```
+    pub fn signal(&self, name: &str) -> Option<&RawSignal> {
+        self.index.get(name).map(|&i| &self.signals[i])
+    }
+
+    /// Names of all signals, in file order.
+    pub fn signal_names(&self) -> impl Iterator<Item = &str> {
+        self.signals.iter().map(RawSignal::name)
+    }
+
+    /// Comment lines following the last signal.
+    ///
+    /// Comments preceding a signal are attached to it, see [`RawSignal::comments`].
+    pub fn trailing_comments(&self) -> &[String] {
+        &self.trailing_comments
+    }
+
+    /// Check all signals for problems, see [`RawSignal::validate`],
+    /// and additionally report names used by more than one signal.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use flipper_ir_dumps::{dump::DumpFile, validation::ValidationIssue};
+    ///
+    /// let dump = DumpFile::try_from(concat!(
+    ///     "Filetype: IR signals file\n",
+    ///     "Version: 1\n",
+    ///     "name: power\n",
+    ///     "type: raw\n",
+    ///     "frequency: 120000\n",
+    ///     "duty_cycle: 0.33\n",
+    ///     "data: 9000 4500 560\n",
+    /// ))
+    /// .unwrap();
+    ///
+    /// assert!(matches!(
+    ///     dump.validate()[..],
+    ///     [ValidationIssue::FrequencyOutOfRange { .. }]
+    /// ));
+    /// ```
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues: Vec<_> = self.signals.iter().flat_map(RawSignal::validate).collect();
+
+        let mut seen = BTreeSet::new();
+        let mut reported = BTreeSet::new();
+        for signal in &self.signals {
+            if !seen.insert(signal.name()) && reported.insert(signal.name()) {
+                issues.push(ValidationIssue::DuplicateName {
+                    signal: signal.name.clone(),
+                });
+            }
+        }
+
+        issues
+    }
+
+    /// Merge signals from `other` into this dump, resolving conflicts according to `policy`.
+    ///
+    /// Signals are appended in order; the version of this dump is kept.
+    /// On error this dump is left unchanged.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use flipper_ir_dumps::{
+    ///     dump::{ConflictPolicy, MergeError},
+    ///     examples::EXAMPLES,
+    /// };
+    ///
+    /// let mut dump = EXAMPLES[0].dump();
+    /// let count = dump.signals().len();
+    ///
+    /// // merging a dump into itself only produces duplicates
+    /// dump.merge(EXAMPLES[0].dump(), ConflictPolicy::Skip).unwrap();
+    /// assert_eq!(dump.signals().len(), count);
+    ///
+    /// let err = dump.merge(EXAMPLES[0].dump(), ConflictPolicy::Error);
+    /// assert!(matches!(err, Err(MergeError::DuplicateSignal { .. })));
+    /// ```
+    pub fn merge(&mut self, other: DumpFile, policy: ConflictPolicy) -> Result<(), MergeError> {
+        let mut merged = Vec::with_capacity(other.signals.len());
+
+        for mut signal in other.signals {
+            let existing = || self.signals.iter().chain(&merged);
+
+            let duplicate = existing()
+                .find(|s| s.is_duplicate_of(&signal, DUPLICATE_TOLERANCE))
+                .map(|s| s.name.clone());
+            let name_taken = existing().any(|s| s.name == signal.name);
+
+            if duplicate.is_none() && !name_taken {
+                merged.push(signal);
+                continue;
+            }
+
+            match policy {
+                ConflictPolicy::Skip => {}
+                ConflictPolicy::Rename => {
+                    if name_taken {
+                        signal.name = (1..)
+                            .map(|i| format!("{}_{}", signal.name, i))
+                            .find(|name| !existing().any(|s| &s.name == name))
+                            .expect("unbounded range always yields a free name");
+                    }
+                    merged.push(signal);
+                }
+                ConflictPolicy::Error => {
+                    return Err(match duplicate {
+                        Some(existing) => MergeError::DuplicateSignal {
+                            incoming: signal.name,
+                            existing,
+                        },
+                        None => MergeError::NameClash(signal.name),
+                    });
+                }
+            }
+        }
+
+        self.extend_signals(merged);
+        self.trailing_comments.extend(other.trailing_comments);
+
+        Ok(())
+    }
+}
+
+impl DumpFile {
+    /// Parse a dump from raw bytes.
+    ///
+    /// Unlike parsing from a `&str`, this doesn't require the file to be valid UTF-8:
+    /// dumps with mangled encoding in signal names are decoded lossily and
+    /// a [`DumpWarning::InvalidUtf8`] is recorded.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use flipper_ir_dumps::{dump::DumpFile, examples::EXAMPLES};
+    ///
+    /// let dump = DumpFile::from_bytes(EXAMPLES[2].contents.as_bytes()).unwrap();
+    /// assert_eq!(dump.signals().len(), 6);
+    /// assert!(dump.warnings().is_empty());
+    /// ```
+    pub fn from_bytes(input: &[u8]) -> Result<Self, DumpError> {
+        let input = String::from_utf8_lossy(input);
+
+        let mut dump = dump_file(&input)
+            .finish()
+            .map(|(_, dump)| dump)
+            .map_err(|err| {
+                let offset = input.len() - err.input.len();
+                DumpError::Syntax {
+                    line: input[..offset].matches('\n').count() + 1,
+                    kind: err.code,
+                }
+            })?;
+
+        if let Cow::Owned(_) = input {
+            dump.warnings.insert(0, DumpWarning::InvalidUtf8);
+        }
+
+        Ok(dump)
+    }
+
+    fn extend_signals(&mut self, signals: impl IntoIterator<Item = RawSignal>) {
+        for signal in signals {
+            self.index
+                .entry(signal.name.clone())
+                .or_insert(self.signals.len());
+            self.signals.push(signal);
+        }
+    }
+}
+
+/// Writes the dump in the Flipper `.ir` format.
+///
+/// Comments, unknown keys, the text of values and line endings are written
+/// back as they were found, so a dump read from a file and left unchanged is
+/// reproduced byte-for-byte.
+impl fmt::Display for DumpFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut lines = Lines {
+            f,
+            line_ending: self.line_ending,
+            first: true,
+        };
+        lines.write(format_args!("Filetype: IR signals file"))?;
+        lines.write(format_args!("Version: {}", self.version))?;
+
+        for signal in &self.signals {
+            for comment in &signal.comments {
+                lines.write(format_args!("#{}", comment))?;
+            }
+
+            let unknown_fields = |lines: &mut Lines<'_, '_>, position| {
+                signal
+                    .unknown_fields
+                    .iter()
+                    .filter(|field| field.position == position)
+                    .try_for_each(|field| {
+                        lines.write(format_args!("{}: {}", field.key, field.value))
+                    })
+            };
+            let source = &signal.source;
+
+            unknown_fields(&mut lines, 0)?;
+            lines.write(format_args!("name: {}", signal.name))?;
+            unknown_fields(&mut lines, 1)?;
+            match signal.r#type {
+                SignalType::Raw => lines.write(format_args!("type: raw"))?,
+            }
+            unknown_fields(&mut lines, 2)?;
+            let frequency = source_text(&source.frequency, &signal.frequency)
+                .map_or_else(|| frequency_text(signal.frequency), String::from);
+            lines.write(format_args!("frequency: {}", frequency))?;
+            unknown_fields(&mut lines, 3)?;
+            let duty_cycle = source_text(&source.duty_cycle, &signal.duty_cycle)
+                .map_or_else(|| duty_cycle_text(signal.duty_cycle), String::from);
+            lines.write(format_args!("duty_cycle: {}", duty_cycle))?;
+            unknown_fields(&mut lines, 4)?;
+            let data = source_text(&source.data, &signal.data)
+                .map_or_else(|| data_text(&signal.data), String::from);
+            lines.write(format_args!("data: {}", data))?;
+            unknown_fields(&mut lines, 5)?;
+        }
+
+        for comment in &self.trailing_comments {
+            lines.write(format_args!("#{}", comment))?;
+        }
+
+        if self.final_line_ending {
+            lines.f.write_str(self.line_ending)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Lines written separated by a line ending, which the last one lacks.
+struct Lines<'a, 'b> {
+    f: &'a mut fmt::Formatter<'b>,
+    line_ending: &'static str,
+    first: bool,
+}
+
+impl Lines<'_, '_> {
+    fn write(&mut self, line: fmt::Arguments<'_>) -> fmt::Result {
+        if !self.first {
+            self.f.write_str(self.line_ending)?;
+        }
+        self.first = false;
+        self.f.write_fmt(line)
+    }
+}
+
+fn frequency_text(frequency: Frequency) -> String {
+    frequency.hz().to_string()
+}
+
+fn duty_cycle_text(duty_cycle: DutyCycle) -> String {
+    format!("{:.6}", duty_cycle.get())
+}
+
+fn data_text(data: &[u32]) -> String {
+    data.iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The text `value` was read from, if it's still the value read.
+fn source_text<'a, T: PartialEq>(source: &'a Option<(T, String)>, value: &T) -> Option<&'a str> {
+    source
+        .as_ref()
+        .filter(|(read, _)| read == value)
+        .map(|(_, text)| text.as_str())
+}
+
+/// `value` along with the text it was read from, unless that's how `canonical` writes it anyway.
+fn keep_text<T>(text: &str, value: &T, canonical: impl Fn(&T) -> String) -> Option<(T, String)>
+where
+    T: Clone,
+{
+    (canonical(value) != text).then(|| (value.clone(), text.to_string()))
 }
 
 impl<'a> TryFrom<&'a str> for DumpFile {
@@ -29,43 +443,105 @@ impl<'a> TryFrom<&'a str> for DumpFile {
 }
 
 fn dump_file(input: &str) -> nom::IResult<&str, DumpFile> {
-    let (input, _) = tag("Filetype: IR signals file")(input)?;
-    let (input, _) = line_ending(input)?;
+    let full = input;
+    let (input, version) = verify(header("IR signals file"), |&version| version >= 1)(input)?;
+
+    // Known versions are parsed strictly. Newer ones may carry signal kinds
+    // we don't understand, so such signals are skipped instead of failing.
+    let best_effort = version > SUPPORTED_VERSION;
+    let signal = |input| {
+        if best_effort {
+            alt((saved_signal.map(Ok), skipped_signal.map(Err)))(input)
+        } else {
+            saved_signal.map(Ok).parse(input)
+        }
+    };
+
+    let (input, (signals, trailing_comments)) =
+        all_consuming(many0(signal).and(many0(comment)))(input)?;
 
-    let (input, version) = version(input)?;
-    let (input, _) = line_ending(input)?;
+    let mut warnings = Vec::new();
+    if best_effort {
+        warnings.push(DumpWarning::NewerVersion(version));
+    }
+
+    let signals: Vec<_> = signals
+        .into_iter()
+        .filter_map(|signal| {
+            signal
+                .map_err(|name| warnings.push(DumpWarning::SkippedSignal(name)))
+                .ok()
+        })
+        .collect();
 
-    let (input, signals) = all_consuming(many0(saved_signal))(input)?;
+    let mut dump = DumpFile::new(version, signals);
+    dump.trailing_comments = trailing_comments.into_iter().map(String::from).collect();
+    dump.warnings = warnings;
+    if let Some(end) = full.find('\n') {
+        if full[..end].ends_with('\r') {
+            dump.line_ending = "\r\n";
+        }
+    }
+    dump.final_line_ending = full.ends_with('\n');
 
-    Ok((input, DumpFile { version, signals }))
+    Ok((input, dump))
 }
 
-fn version(input: &str) -> nom::IResult<&str, u32> {
-    let (input, _) = tag("Version: ")(input)?;
-    let (input, version) = digit1(input)?;
+/// Consume a signal block without interpreting it, returning the signal name.
+///
+/// The block ends at the next comment line, as Flipper separates signals. In
+/// dumps without separators, unknown keys right before the next `name` are
+/// left to the next signal, which reads them as its leading fields.
+fn skipped_signal(input: &str) -> nom::IResult<&str, String> {
+    let not_name = || many0(verify(key_value, |(key, _)| *key != "name"));
+    let (input, _) = many0(comment)(input)?;
+    let (input, _) = not_name()(input)?;
+    let (input, name) = name(input)?;
 
-    Ok((input, version.parse().unwrap()))
+    let (rest, fields) = many0(consumed(verify(key_value, |(key, _)| *key != "name")))(input)?;
+    let own = match self::name(rest) {
+        Ok(_) => fields
+            .iter()
+            .rposition(|(_, (key, _))| KNOWN_KEYS.contains(key))
+            .map_or(0, |last| last + 1),
+        Err(_) => fields.len(),
+    };
+    let len = fields[..own].iter().map(|(text, _)| text.len()).sum();
+
+    Ok((&input[len..], name))
 }
 
 fn saved_signal(input: &str) -> nom::IResult<&str, RawSignal> {
-    let (input, _) = tag("#")(input)?;
-    let (input, _) = not_line_ending(input)?;
-    let (input, _) = line_ending(input)?;
+    let (input, comments) = many0(comment)(input)?;
 
-    let (input, name) = name(input)?;
-    let (input, _) = line_ending(input)?;
+    let mut unknown_fields = Vec::new();
+    let mut skip_unknown = |input, position| {
+        let (input, fields) = many0(unknown_field(position))(input)?;
+        unknown_fields.extend(fields);
+        Ok((input, ()))
+    };
 
+    let (input, _) = skip_unknown(input, 0)?;
+    let (input, name) = name(input)?;
+    let (input, _) = skip_unknown(input, 1)?;
     let (input, r#type) = signal_type(input)?;
-    let (input, _) = line_ending(input)?;
+    let (input, _) = skip_unknown(input, 2)?;
+    let (input, (frequency_source, frequency)) = consumed(frequency)(input)?;
+    let (input, _) = skip_unknown(input, 3)?;
+    let (input, (duty_cycle_source, duty_cycle)) = consumed(duty_cycle)(input)?;
+    let (input, _) = skip_unknown(input, 4)?;
+    let (input, (data_source, data)) = consumed(data)(input)?;
+    let (input, _) = skip_unknown(input, 5)?;
 
-    let (input, frequency) = frequency(input)?;
-    let (input, _) = line_ending(input)?;
-
-    let (input, duty_cycle) = duty_cycle(input)?;
-    let (input, _) = line_ending(input)?;
-
-    let (input, data) = data(input)?;
-    let (input, _) = line_ending(input)?;
+    let source = SourceText {
+        frequency: keep_text(value_text(frequency_source), &frequency, |frequency| {
+            frequency_text(*frequency)
+        }),
+        duty_cycle: keep_text(value_text(duty_cycle_source), &duty_cycle, |duty_cycle| {
+            duty_cycle_text(*duty_cycle)
+        }),
+        data: keep_text(value_text(data_source), &data, |data| data_text(data)),
+    };
 
     Ok((
         input,
@@ -75,60 +551,63 @@ fn saved_signal(input: &str) -> nom::IResult<&str, RawSignal> {
             frequency,
             duty_cycle,
             data,
+            comments: comments.into_iter().map(String::from).collect(),
+            unknown_fields,
+            source,
+            parsed: Default::default(),
         },
     ))
 }
 
-fn name(input: &str) -> nom::IResult<&str, String> {
-    let (input, _) = tag("name: ")(input)?;
-    let (input, name) = not_line_ending(input)?;
-
-    Ok((input, name.to_string()))
+/// The value of a `key: value` line.
+fn value_text(line: &str) -> &str {
+    let line = line.trim_end_matches(['\r', '\n']);
+    line.split_once(": ").map_or(line, |(_, value)| value)
 }
 
-fn frequency(input: &str) -> nom::IResult<&str, u32> {
-    let (input, _) = tag("frequency: ")(input)?;
-    let (input, frequency) = parse_u32_str(input)?;
+fn unknown_field(position: usize) -> impl FnMut(&str) -> nom::IResult<&str, UnknownField> {
+    move |input| {
+        let (input, (key, value)) = verify(key_value, |(key, _)| !KNOWN_KEYS.contains(key))(input)?;
 
-    Ok((input, frequency))
+        Ok((
+            input,
+            UnknownField {
+                key: key.to_string(),
+                value: value.to_string(),
+                position,
+            },
+        ))
+    }
 }
 
-fn duty_cycle(input: &str) -> nom::IResult<&str, f32> {
-    let (input, _) = tag("duty_cycle: ")(input)?;
-    let (input, duty_cycle) = number::complete::float(input)?;
-
-    Ok((input, duty_cycle))
+fn name(input: &str) -> nom::IResult<&str, String> {
+    field("name", string)(input)
 }
 
-fn data(input: &str) -> nom::IResult<&str, Vec<u32>> {
-    let (input, _) = tag("data: ")(input)?;
-    let (input, data) = separated_list0(tag(" "), parse_u32_str)(input)?;
+fn frequency(input: &str) -> nom::IResult<&str, Frequency> {
+    field("frequency", uint.map(Frequency::from_hz))(input)
+}
 
-    Ok((input, data))
+/// Duty cycles out of range are rejected, e.g. a percentage instead of a fraction.
+fn duty_cycle(input: &str) -> nom::IResult<&str, DutyCycle> {
+    field("duty_cycle", map_res(float, DutyCycle::new))(input)
 }
 
-fn parse_u32_str(input: &str) -> nom::IResult<&str, u32> {
-    map_res(digit1, |input: &str| input.parse::<u32>()).parse(input)
+fn data(input: &str) -> nom::IResult<&str, Vec<u32>> {
+    field("data", uint_list)(input)
 }
 
 fn signal_type(input: &str) -> nom::IResult<&str, SignalType> {
-    let (input, _) = tag("type: ")(input)?;
-    tag("raw")(input).map(|(input, _)| (input, SignalType::Raw))
+    field("type", tag("raw").map(|_| SignalType::Raw))(input)
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::signal::ParsedSignal;
+
     use super::*;
     use indoc::indoc;
 
-    #[test]
-    fn test_version() {
-        let input = "Version: 1\n";
-        let expected = 1;
-        let (_, actual) = version(input).unwrap();
-        assert_eq!(expected, actual);
-    }
-
     #[test]
     fn test_name() {
         let input = "name: test\n";
@@ -140,7 +619,7 @@ mod tests {
     #[test]
     fn test_frequency() {
         let input = "frequency: 1000\n";
-        let expected = 1000;
+        let expected = Frequency::from_hz(1000);
         let (_, actual) = frequency(input).unwrap();
         assert_eq!(expected, actual);
     }
@@ -148,9 +627,12 @@ mod tests {
     #[test]
     fn test_duty_cycle() {
         let input = "duty_cycle: 0.5\n";
-        let expected = 0.5;
+        let expected = DutyCycle::new(0.5).unwrap();
         let (_, actual) = duty_cycle(input).unwrap();
         assert_eq!(expected, actual);
+
+        // a percentage rather than a fraction
+        assert!(duty_cycle("duty_cycle: 33\n").is_err());
     }
 
     #[test]
@@ -182,9 +664,13 @@ mod tests {
         let expected = RawSignal {
             name: "test".to_string(),
             r#type: SignalType::Raw,
-            frequency: 1000,
-            duty_cycle: 0.5,
+            frequency: Frequency::from_hz(1000),
+            duty_cycle: DutyCycle::new(0.5).unwrap(),
             data: vec![1, 2, 3, 4, 5],
+            comments: vec!["".to_string()],
+            unknown_fields: vec![],
+            source: Default::default(),
+            parsed: Default::default(),
         };
         let (_, actual) = saved_signal(input).unwrap();
         assert_eq!(expected, actual);
@@ -202,17 +688,414 @@ mod tests {
             duty_cycle: 0.5
             data: 1 2 3 4 5
         "};
-        let expected = DumpFile {
-            version: 1,
-            signals: vec![RawSignal {
+        let expected = DumpFile::new(
+            1,
+            vec![RawSignal {
                 name: "test".to_string(),
                 r#type: SignalType::Raw,
-                frequency: 1000,
-                duty_cycle: 0.5,
+                frequency: Frequency::from_hz(1000),
+                duty_cycle: DutyCycle::new(0.5).unwrap(),
                 data: vec![1, 2, 3, 4, 5],
+                comments: vec!["".to_string()],
+                unknown_fields: vec![],
+                source: Default::default(),
+                parsed: Default::default(),
             }],
-        };
+        );
         let (_, actual) = dump_file(input).unwrap();
         assert_eq!(expected, actual);
     }
+
+    #[test]
+    fn test_comments_and_unknown_fields() {
+        let input = indoc! {"
+            Filetype: IR signals file
+            Version: 1
+            # remote: Samsung TV
+            #
+            name: power
+            type: raw
+            captured_by: someone
+            frequency: 38000
+            duty_cycle: 0.330000
+            data: 1 2 3
+            note: second capture
+            # end of file
+        "};
+        let dump = DumpFile::try_from(input).unwrap();
+
+        let [signal] = dump.signals() else {
+            panic!("expected a single signal");
+        };
+        assert_eq!(signal.comments(), [" remote: Samsung TV", ""]);
+        assert_eq!(
+            signal.unknown_fields(),
+            [
+                UnknownField {
+                    key: "captured_by".to_string(),
+                    value: "someone".to_string(),
+                    position: 2,
+                },
+                UnknownField {
+                    key: "note".to_string(),
+                    value: "second capture".to_string(),
+                    position: 5,
+                },
+            ]
+        );
+        assert_eq!(dump.trailing_comments(), [" end of file"]);
+    }
+
+    #[test]
+    fn test_versions() {
+        let input = indoc! {"
+            Filetype: IR signals file
+            Version: 0
+        "};
+        assert!(DumpFile::try_from(input).is_err());
+
+        let input = indoc! {"
+            Filetype: IR signals file
+            Version: 1
+            #
+            name: power
+            type: parsed
+            protocol: NEC
+        "};
+        assert!(DumpFile::try_from(input).is_err());
+
+        let input = indoc! {"
+            Filetype: IR signals file
+            Version: 2
+            #
+            name: power
+            type: parsed
+            protocol: NEC
+            #
+            name: mute
+            type: raw
+            frequency: 38000
+            duty_cycle: 0.330000
+            data: 1 2 3
+        "};
+        let dump = DumpFile::try_from(input).unwrap();
+        assert_eq!(dump.version(), 2);
+        assert_eq!(dump.signals().len(), 1);
+        assert_eq!(dump.signals()[0].name(), "mute");
+        assert_eq!(
+            dump.warnings(),
+            [
+                DumpWarning::NewerVersion(2),
+                DumpWarning::SkippedSignal("power".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_skipped_signal_keeps_next_fields() {
+        let separated = indoc! {"
+            Filetype: IR signals file
+            Version: 2
+            #
+            name: power
+            type: parsed
+            protocol: NEC
+            #
+            x-note: kept
+            name: mute
+            type: raw
+            frequency: 38000
+            duty_cycle: 0.330000
+            data: 1 2 3
+        "};
+        let unseparated = indoc! {"
+            Filetype: IR signals file
+            Version: 2
+            name: power
+            type: parsed
+            protocol: NEC
+            name: vol_up
+            type: future
+            x-note: kept
+            name: mute
+            type: raw
+            frequency: 38000
+            duty_cycle: 0.330000
+            data: 1 2 3
+        "};
+
+        for input in [separated, unseparated] {
+            let dump = DumpFile::try_from(input).unwrap();
+            assert_eq!(dump.signals().len(), 1);
+            let text = dump.to_string();
+            assert!(text.contains("x-note: kept\nname: mute\n"), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_warning_display() {
+        assert_eq!(
+            DumpWarning::NewerVersion(SUPPORTED_VERSION + 1).to_string(),
+            format!(
+                "dump version {} is newer than supported version {}, parsed in best-effort mode",
+                SUPPORTED_VERSION + 1,
+                SUPPORTED_VERSION
+            )
+        );
+        assert_eq!(
+            DumpWarning::SkippedSignal("power".to_string()).to_string(),
+            "skipped signal `power` that couldn't be parsed"
+        );
+    }
+
+    #[test]
+    fn test_pathological_input_is_an_error() {
+        let inputs = [
+            "",
+            "Filetype: IR signals file\nVersion: 99999999999\n",
+            "Filetype: IR signals file\nVersion: 1\nname: x\ntype: raw\nfrequency: 99999999999\n",
+            "Filetype: IR signals file\nVersion: 1\nname: x\ntype: raw\nfrequency: 38000\nduty_cycle: 0.33\ndata: 1 999999999999 1\n",
+            "Filetype: IR signals file\nVersion: 1\nname: x\ntype: raw\nfrequency: 38000\nduty_cycle: nan\ndata: 1 2\r",
+            "Filetype: IR signals file\nVersion: 1\nname: x\ntype: raw\nfrequency: 38000\nduty_cycle: 1e30\ndata: 1 2 1\n",
+        ];
+
+        for input in inputs {
+            assert!(DumpFile::try_from(input).is_err(), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_extreme_values_parse() {
+        let input = indoc! {"
+            Filetype: IR signals file
+            Version: 1
+            name: x
+            type: raw
+            frequency: 4294967295
+            duty_cycle: 1
+            data: 4294967295 0 4294967295
+        "};
+
+        let dump = DumpFile::try_from(input).unwrap();
+        assert!(!dump.validate().is_empty());
+        assert!(ParsedSignal::try_from(&dump.signals()[0]).is_err());
+    }
+
+    #[test]
+    fn test_from_bytes() {
+        let mut input = b"Filetype: IR signals file\nVersion: 1\n#\nname: caf".to_vec();
+        input.extend([0xe9, b'\n']);
+        input.extend(b"type: raw\nfrequency: 38000\nduty_cycle: 0.33\ndata: 1 2 3\n");
+
+        let dump = DumpFile::from_bytes(&input).unwrap();
+        assert_eq!(dump.signal_names().collect::<Vec<_>>(), ["caf\u{fffd}"]);
+        assert_eq!(dump.warnings(), [DumpWarning::InvalidUtf8]);
+
+        let input = b"Filetype: IR signals file\nVersion: 1\n#\nname: x\ntype: parsed\n";
+        assert_eq!(
+            DumpFile::from_bytes(input),
+            Err(DumpError::Syntax {
+                line: 4,
+                kind: nom::error::ErrorKind::Eof,
+            })
+        );
+    }
+
+    #[test]
+    fn test_write_round_trip() {
+        for example in crate::examples::EXAMPLES {
+            assert_eq!(example.dump().to_string(), example.contents);
+        }
+
+        let input = indoc! {"
+            Filetype: IR signals file
+            Version: 1
+            # remote: Samsung TV
+            name: power
+            captured_by: someone
+            type: raw
+            frequency: 38000
+            duty_cycle: 0.330000
+            data: 1 2 3
+            note: second capture
+            # end of file
+        "};
+        assert_eq!(DumpFile::try_from(input).unwrap().to_string(), input);
+    }
+
+    #[test]
+    fn test_write_round_trip_non_canonical() {
+        for example in crate::examples::EXAMPLES {
+            let crlf = example.contents.replace('\n', "\r\n");
+            assert_eq!(DumpFile::try_from(crlf.as_str()).unwrap().to_string(), crlf);
+
+            let unterminated = example.contents.trim_end_matches('\n');
+            assert_eq!(
+                DumpFile::try_from(unterminated).unwrap().to_string(),
+                unterminated
+            );
+        }
+
+        let input = indoc! {"
+            Filetype: IR signals file
+            Version: 1
+            #
+            remote: Samsung TV
+            name: power
+            type: raw
+            frequency: 038000
+            duty_cycle: 0.33
+            data: 9000 04500 560
+            #
+            captured_by: someone
+            name: mute
+            type: raw
+            frequency: 38000
+            duty_cycle: 1
+            data: 1 2 3
+        "};
+        let mut dump = DumpFile::try_from(input).unwrap();
+        assert_eq!(dump.signals()[0].frequency().hz(), 38_000);
+        assert_eq!(dump.signals()[0].data(), [9000, 4500, 560]);
+        assert_eq!(
+            dump.signals()[1].unknown_fields(),
+            [UnknownField {
+                key: "captured_by".to_string(),
+                value: "someone".to_string(),
+                position: 0,
+            }]
+        );
+        assert_eq!(dump.to_string(), input);
+
+        // values changed since are written as usual
+        dump.signals[0].duty_cycle = DutyCycle::new(0.5).unwrap();
+        dump.signals[0].data[1] = 4501;
+        let written = dump.to_string();
+        assert!(written.contains("frequency: 038000\nduty_cycle: 0.500000\ndata: 9000 4501 560\n"));
+        assert!(written.contains("duty_cycle: 1\n"));
+    }
+
+    #[test]
+    fn test_signal_lookup() {
+        let mut dump = DumpFile::new(1, vec![signal("power", &[550]), signal("mute", &[550])]);
+        dump.merge(
+            DumpFile::new(1, vec![signal("vol_up", &[1650])]),
+            ConflictPolicy::Skip,
+        )
+        .unwrap();
+
+        assert_eq!(dump.signal("mute").map(RawSignal::data), Some(&[550][..]));
+        assert_eq!(
+            dump.signal("vol_up").map(RawSignal::data),
+            Some(&[1650][..])
+        );
+        assert!(dump.signal("vol_down").is_none());
+        assert_eq!(
+            dump.signal_names().collect::<Vec<_>>(),
+            ["power", "mute", "vol_up"]
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut bad = signal("bad", &[550, 0, 2_000_000, 550]);
+        bad.frequency = Frequency::from_hz(120_000);
+        // 455kHz is fine for Bang & Olufsen remotes
+        let mut bang_olufsen = signal("bang_olufsen", &[200]);
+        bang_olufsen.frequency = Frequency::from_hz(455_000);
+
+        let dump = DumpFile::new(
+            1,
+            vec![
+                signal("good", &[550, 550, 550]),
+                bad,
+                bang_olufsen,
+                signal("good", &[550]),
+            ],
+        );
+
+        let bad = || "bad".to_string();
+        assert_eq!(
+            dump.validate(),
+            [
+                ValidationIssue::TrailingPause { signal: bad() },
+                ValidationIssue::ZeroDuration {
+                    signal: bad(),
+                    index: 1
+                },
+                ValidationIssue::AbsurdDuration {
+                    signal: bad(),
+                    index: 2,
+                    duration: 2_000_000
+                },
+                ValidationIssue::FrequencyOutOfRange {
+                    signal: bad(),
+                    frequency: 120_000
+                },
+                ValidationIssue::DuplicateName {
+                    signal: "good".to_string()
+                },
+            ]
+        );
+    }
+
+    fn signal(name: &str, data: &[u32]) -> RawSignal {
+        RawSignal {
+            name: name.to_string(),
+            r#type: SignalType::Raw,
+            frequency: Frequency::from_hz(38000),
+            duty_cycle: DutyCycle::default(),
+            data: data.to_vec(),
+            comments: vec![],
+            unknown_fields: vec![],
+            source: Default::default(),
+            parsed: Default::default(),
+        }
+    }
+
+    fn names(dump: &DumpFile) -> Vec<&str> {
+        dump.signals().iter().map(RawSignal::name).collect()
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut dump = DumpFile::new(1, vec![signal("power", &[550, 1650, 550])]);
+        let other = DumpFile::new(
+            1,
+            vec![
+                // same timings within tolerance, different name
+                signal("power_again", &[560, 1600, 540]),
+                // same name, different timings
+                signal("power", &[9000, 4500, 560]),
+                signal("mute", &[550, 550, 550]),
+            ],
+        );
+
+        let mut skipped = DumpFile::new(1, dump.signals.clone());
+        skipped
+            .merge(
+                DumpFile::new(1, other.signals.clone()),
+                ConflictPolicy::Skip,
+            )
+            .unwrap();
+        assert_eq!(names(&skipped), ["power", "mute"]);
+
+        let err = skipped
+            .merge(
+                DumpFile::new(1, other.signals.clone()),
+                ConflictPolicy::Error,
+            )
+            .unwrap_err();
+        assert_eq!(
+            err,
+            MergeError::DuplicateSignal {
+                incoming: "power_again".to_string(),
+                existing: "power".to_string(),
+            }
+        );
+        assert_eq!(names(&skipped), ["power", "mute"]);
+
+        dump.merge(other, ConflictPolicy::Rename).unwrap();
+        assert_eq!(names(&dump), ["power", "power_again", "power_1", "mute"]);
+    }
 }
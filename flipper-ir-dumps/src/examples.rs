@@ -0,0 +1,61 @@
+//! Example dumps bundled with the crate.
+//!
+//! These are real captures of a Samsung air conditioner remote, handy for
+//! trying out the toolchain and as test fixtures.
+
+use crate::dump::DumpFile;
+
+/// An example `.ir` file embedded into the crate.
+#[derive(Debug, Clone, Copy)]
+pub struct Example {
+    /// File name of the example, e.g. `samsung_ac_temperature.ir`.
+    pub name: &'static str,
+    /// Contents of the `.ir` file.
+    pub contents: &'static str,
+}
+
+impl Example {
+    /// Parse the example into a [`DumpFile`].
+    ///
+    /// Bundled examples are known to be valid, so this never fails.
+    pub fn dump(&self) -> DumpFile {
+        DumpFile::try_from(self.contents).expect("bundled example dumps are valid")
+    }
+}
+
+macro_rules! example {
+    ($name:literal) => {
+        Example {
+            name: $name,
+            contents: include_str!(concat!("../fixtures/", $name)),
+        }
+    };
+}
+
+/// All bundled example dumps.
+pub const EXAMPLES: &[Example] = &[
+    example!("samsung_ac_temperature.ir"),
+    example!("samsung_ac_fan_speed.ir"),
+    example!("samsung_ac_direction.ir"),
+];
+
+#[cfg(test)]
+mod tests {
+    use crate::signal::ParsedSignal;
+
+    use super::*;
+
+    #[test]
+    fn test_examples_decode() {
+        for example in EXAMPLES {
+            let dump = example.dump();
+            assert!(!dump.signals().is_empty(), "{} is empty", example.name);
+
+            for signal in dump.signals() {
+                let parsed = ParsedSignal::try_from(signal)
+                    .unwrap_or_else(|e| panic!("{}: {}: {}", example.name, signal.name(), e));
+                assert!(!parsed.packets().is_empty());
+            }
+        }
+    }
+}
@@ -0,0 +1,160 @@
+//! Parsers for the generic Flipper Zero "key: value" file format.
+//!
+//! Every Flipper file starts with a header naming the file type and its version:
+//!
+//! ```text
+//! Filetype: IR signals file
+//! Version: 1
+//! ```
+//!
+//! followed by `key: value` lines, optionally interleaved with `#` comments.
+//! This module provides the building blocks to parse such files; the schema
+//! of a concrete file type (e.g. [`DumpFile`](crate::dump::DumpFile)) is built on top of them.
+//!
+//! ```rust
+//! use flipper_ir_dumps::flipper_format::{field, header, string, uint};
+//!
+//! let input = "Filetype: Flipper SubGhz Key File\nVersion: 1\nFrequency: 433920000\nProtocol: Princeton\n";
+//!
+//! let (input, version) = header("Flipper SubGhz Key File")(input).unwrap();
+//! let (input, frequency) = field("Frequency", uint)(input).unwrap();
+//! let (input, protocol) = field("Protocol", string)(input).unwrap();
+//!
+//! assert_eq!((version, frequency, protocol.as_str()), (1, 433_920_000, "Princeton"));
+//! assert!(input.is_empty());
+//! ```
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take_till1},
+    character::complete::{digit1, line_ending, not_line_ending},
+    combinator::{eof, map_res, verify},
+    multi::separated_list0,
+    number, Parser,
+};
+
+/// Parse the file header, checking the file type, and return the file version.
+pub fn header<'a>(filetype: &'static str) -> impl FnMut(&'a str) -> nom::IResult<&'a str, u32> {
+    move |input| {
+        let (input, _) = field("Filetype", tag(filetype))(input)?;
+        field("Version", uint)(input)
+    }
+}
+
+/// Parse a single `key: value` line, with the value parsed by `value`.
+///
+/// The line ending is consumed as well; the last line of the file may lack one.
+pub fn field<'a, O, F>(
+    key: &'static str,
+    mut value: F,
+) -> impl FnMut(&'a str) -> nom::IResult<&'a str, O>
+where
+    F: Parser<&'a str, O, nom::error::Error<&'a str>>,
+{
+    move |input| {
+        let (input, _) = tag(key)(input)?;
+        let (input, _) = tag(": ")(input)?;
+        let (input, value) = value.parse(input)?;
+        let (input, _) = end_of_line(input)?;
+
+        Ok((input, value))
+    }
+}
+
+/// Parse any `key: value` line, returning the key and the raw value text.
+pub fn key_value(input: &str) -> nom::IResult<&str, (&str, &str)> {
+    let (input, key) = verify(
+        take_till1(|c: char| c == ':' || c == '\r' || c == '\n'),
+        |key: &str| !key.starts_with('#'),
+    )(input)?;
+    let (input, _) = tag(": ")(input)?;
+    let (input, value) = not_line_ending(input)?;
+    let (input, _) = end_of_line(input)?;
+
+    Ok((input, (key, value)))
+}
+
+/// Parse a comment line, returning its text after the leading `#` verbatim.
+pub fn comment(input: &str) -> nom::IResult<&str, &str> {
+    let (input, _) = tag("#")(input)?;
+    let (input, text) = not_line_ending(input)?;
+    let (input, _) = end_of_line(input)?;
+
+    Ok((input, text))
+}
+
+/// Parse a string value spanning the rest of the line.
+pub fn string(input: &str) -> nom::IResult<&str, String> {
+    not_line_ending(input).map(|(input, value)| (input, value.to_string()))
+}
+
+/// Parse an unsigned integer value.
+pub fn uint(input: &str) -> nom::IResult<&str, u32> {
+    map_res(digit1, |input: &str| input.parse::<u32>()).parse(input)
+}
+
+/// Parse a floating point value.
+pub fn float(input: &str) -> nom::IResult<&str, f32> {
+    number::complete::float(input)
+}
+
+/// Parse a space-separated list of unsigned integers.
+pub fn uint_list(input: &str) -> nom::IResult<&str, Vec<u32>> {
+    separated_list0(tag(" "), uint)(input)
+}
+
+fn end_of_line(input: &str) -> nom::IResult<&str, &str> {
+    alt((line_ending, eof))(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header() {
+        let input = "Filetype: IR signals file\nVersion: 1\nrest";
+        assert_eq!(header("IR signals file")(input), Ok(("rest", 1)));
+
+        let input = "Filetype: SubGhz RAW File\nVersion: 1\n";
+        assert!(header("IR signals file")(input).is_err());
+    }
+
+    #[test]
+    fn test_field() {
+        assert_eq!(field("key", uint)("key: 42\nrest"), Ok(("rest", 42)));
+        assert_eq!(field("key", uint)("key: 42"), Ok(("", 42)));
+        assert!(field("key", uint)("other: 42\n").is_err());
+        assert!(field("key", uint)("key: 42 trailing\n").is_err());
+    }
+
+    #[test]
+    fn test_key_value() {
+        assert_eq!(
+            key_value("key: some value\nrest"),
+            Ok(("rest", ("key", "some value")))
+        );
+        assert!(key_value("# key: value\n").is_err());
+        assert!(key_value("no separator\n").is_err());
+    }
+
+    #[test]
+    fn test_comment() {
+        assert_eq!(comment("# remote: TV\nrest"), Ok(("rest", " remote: TV")));
+        assert_eq!(comment("#\n"), Ok(("", "")));
+        assert!(comment("name: test\n").is_err());
+    }
+
+    #[test]
+    fn test_values() {
+        assert_eq!(string("some name\n"), Ok(("\n", "some name".to_string())));
+        assert_eq!(uint("1000"), Ok(("", 1000)));
+        assert_eq!(float("0.33"), Ok(("", 0.33)));
+        assert_eq!(uint_list("1 2 3"), Ok(("", vec![1, 2, 3])));
+    }
+}
@@ -0,0 +1,27 @@
+//! Conversion from and to the file formats of other IR tools.
+//!
+//! Each format has its own module, turning its files into [`DumpFile`](crate::dump::DumpFile)s
+//! and [`RawSignal`](crate::signal::RawSignal)s and back where it makes sense.
+
+pub mod arduino;
+pub mod arrow;
+mod base64;
+pub mod broadlink;
+pub mod esphome;
+pub mod girr;
+pub mod global_cache;
+pub mod json;
+pub mod lirc;
+pub mod mode2;
+pub mod numpy;
+pub mod report;
+pub mod rust;
+pub mod saleae;
+pub mod sigrok;
+pub mod smartir;
+pub mod timings;
+pub mod tuya;
+pub mod vcd;
+pub mod wav;
+pub mod xlsx;
+mod zip;
@@ -0,0 +1,227 @@
+//! C arrays of the signals of a dump, to replay them from an Arduino with
+//! [IRremote](https://github.com/Arduino-IRremote/Arduino-IRremote) or
+//! [IRremoteESP8266](https://github.com/crankyoldgit/IRremoteESP8266).
+//!
+//! [`header`] writes each signal as a `PROGMEM` array along with its length
+//! and carrier, and [`sketch`] a sketch sending them all in turn with
+//! whichever of the libraries fits the board.
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::Write;
+
+use crate::{dump::DumpFile, signal::RawSignal};
+
+/// Durations on each line of an array.
+const DURATIONS_PER_LINE: usize = 12;
+
+/// A header with an array for each signal of `dump`, such as:
+///
+/// ```c
+/// // Power
+/// const uint16_t raw_Power_len = 67;
+/// const uint16_t raw_Power_khz = 38;
+/// const uint16_t raw_Power[] PROGMEM = {
+///   9000, 4500, 560, 560, ...
+/// };
+/// ```
+///
+/// Names are turned into C identifiers, see [`identifier`]. Durations over
+/// 65ms don't fit the arrays and are cut short. Carriers are in kHz, up to
+/// 65MHz; IRremote takes them as a byte, so carriers over 255kHz such as
+/// Bang & Olufsen's 455kHz only send with IRremoteESP8266.
+pub fn header(dump: &DumpFile) -> String {
+    let mut header = String::new();
+    writeln!(header, "// Generated by flipper-ir-dumps.").unwrap();
+    writeln!(
+        header,
+        "// Durations in µs, alternating marks and spaces, mark first."
+    )
+    .unwrap();
+    writeln!(header, "#pragma once").unwrap();
+    writeln!(header).unwrap();
+    writeln!(header, "#include <Arduino.h>").unwrap();
+
+    for (signal, name) in dump.signals().iter().zip(identifiers(dump)) {
+        writeln!(header).unwrap();
+        writeln!(header, "// {}", signal.name()).unwrap();
+        writeln!(
+            header,
+            "const uint16_t {}_len = {};",
+            name,
+            signal.data().len()
+        )
+        .unwrap();
+        writeln!(header, "const uint16_t {}_khz = {};", name, khz(signal)).unwrap();
+        writeln!(header, "const uint16_t {}[] PROGMEM = {{", name).unwrap();
+        for line in signal.data().chunks(DURATIONS_PER_LINE) {
+            let line: Vec<_> = line
+                .iter()
+                .map(|&duration| duration.min(u16::MAX.into()).to_string())
+                .collect();
+            writeln!(header, "  {},", line.join(", ")).unwrap();
+        }
+        writeln!(header, "}};").unwrap();
+    }
+
+    header
+}
+
+/// A sketch including the header written by [`header`] as `header_file`,
+/// and sending each signal of `dump` in turn, a second apart.
+///
+/// The LED goes on pin 4 on ESP boards and on IRremote's default pin
+/// elsewhere.
+///
+/// # Examples
+/// ```rust
+/// use flipper_ir_dumps::{examples::EXAMPLES, formats::arduino};
+///
+/// let dump = EXAMPLES[0].dump();
+/// let sketch = arduino::sketch(&dump, "aircon.h");
+/// assert!(sketch.contains("#include \"aircon.h\"\n"));
+/// assert!(sketch.contains("  SEND_RAW(raw_On_h_16);\n"));
+/// ```
+pub fn sketch(dump: &DumpFile, header_file: &str) -> String {
+    let mut sketch = String::new();
+    sketch.push_str(SKETCH_PRELUDE);
+    sketch.push_str(&format!("#include \"{}\"\n", header_file));
+    sketch.push_str(SKETCH_SEND);
+
+    sketch.push_str("\nvoid loop() {\n");
+    for name in identifiers(dump) {
+        writeln!(sketch, "  SEND_RAW({});", name).unwrap();
+        writeln!(sketch, "  delay(1000);").unwrap();
+    }
+    sketch.push_str("}\n");
+
+    sketch
+}
+
+const SKETCH_PRELUDE: &str = "\
+// Generated by flipper-ir-dumps.
+#include <Arduino.h>
+";
+
+const SKETCH_SEND: &str = "
+#if defined(ESP8266) || defined(ESP32)
+#include <IRremoteESP8266.h>
+#include <IRsend.h>
+
+IRsend irsend(4);
+
+// IRremoteESP8266 reads the durations from RAM
+#define SEND_RAW(name)                                \\
+  do {                                                \\
+    uint16_t buffer[name##_len];                      \\
+    memcpy_P(buffer, name, sizeof(buffer));           \\
+    irsend.sendRaw(buffer, name##_len, name##_khz);   \\
+  } while (0)
+#define BEGIN_SEND() irsend.begin()
+#else
+#include <IRremote.hpp>
+
+#define SEND_RAW(name) IrSender.sendRaw_P(name, name##_len, name##_khz)
+#define BEGIN_SEND() IrSender.begin()
+#endif
+
+void setup() {
+  BEGIN_SEND();
+}
+";
+
+/// A C identifier for a signal name: `raw_` followed by the name, with
+/// anything but ASCII letters and digits replaced by underscores.
+///
+/// ```rust
+/// use flipper_ir_dumps::formats::arduino::identifier;
+///
+/// assert_eq!(identifier("Vol+"), "raw_Vol_");
+/// assert_eq!(identifier("18°C cool"), "raw_18_C_cool");
+/// ```
+pub fn identifier(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("raw_{}", name)
+}
+
+/// [`identifier`]s of the signals of a dump, numbered when names clash.
+fn identifiers(dump: &DumpFile) -> Vec<String> {
+    let mut identifiers: Vec<String> = Vec::new();
+    for signal in dump.signals() {
+        let base = identifier(signal.name());
+        let mut name = base.clone();
+        let mut count = 1;
+        while identifiers.contains(&name) {
+            count += 1;
+            name = format!("{}_{}", base, count);
+        }
+        identifiers.push(name);
+    }
+    identifiers
+}
+
+fn khz(signal: &RawSignal) -> u16 {
+    let khz = signal.frequency().hz().saturating_add(500) / 1000;
+    u16::try_from(khz).unwrap_or(u16::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::{
+        examples::EXAMPLES,
+        signal::{DutyCycle, Polarity, TimingSequence},
+    };
+
+    #[test]
+    fn test_header() {
+        let header = header(&EXAMPLES[0].dump());
+        assert!(header.starts_with("// Generated by flipper-ir-dumps.\n"));
+        assert!(header.contains(
+            "// On_h_16\nconst uint16_t raw_On_h_16_len = 349;\nconst uint16_t raw_On_h_16_khz = 38;\n\
+             const uint16_t raw_On_h_16[] PROGMEM = {\n  662, 17715, 2993, 8929,"
+        ));
+        assert!(header.ends_with("};\n"));
+    }
+
+    #[test]
+    fn test_identifiers() {
+        let signal = |name: &str, frequency: u32| {
+            let timings = TimingSequence::new(Polarity::StartsWithPulse, vec![100_000, 200, 300]);
+            RawSignal::new(name, frequency, DutyCycle::default(), timings).unwrap()
+        };
+        let dump = DumpFile::new(
+            1,
+            vec![
+                signal("Vol+", 36_000),
+                signal("Vol-", 38_000),
+                signal("Vol_", 56_000),
+                signal("B&O", 455_000),
+                signal("Max", u32::MAX),
+            ],
+        );
+        assert_eq!(
+            identifiers(&dump),
+            ["raw_Vol_", "raw_Vol__2", "raw_Vol__3", "raw_B_O", "raw_Max"]
+        );
+
+        let header = header(&dump);
+        assert!(header.contains("raw_Vol__3_khz = 56;"));
+        assert!(header.contains("const uint16_t raw_B_O_khz = 455;"));
+        assert!(header.contains("const uint16_t raw_Max_khz = 65535;"));
+        assert!(header.contains("// Vol-\n"));
+        assert!(header.contains("  65535, 200, 300,\n"));
+
+        let sketch = sketch(&dump, "volume.h");
+        assert!(sketch.contains("  SEND_RAW(raw_Vol__2);\n  delay(1000);\n"));
+        assert!(sketch.ends_with("}\n"));
+    }
+}
@@ -0,0 +1,545 @@
+//! [Arrow IPC](https://arrow.apache.org/docs/format/Columnar.html#ipc-file-format)
+//! files, also known as Feather v2, to load captures straight into pandas,
+//! polars or DuckDB:
+//!
+//! ```python
+//! import pandas
+//! timings = pandas.read_feather("remote.timings.arrow")
+//! ```
+//!
+//! Two tables are available: [`timings`], one row per duration, and
+//! [`packets`], one row per packet found by parsing the signals. Numbers
+//! are 64-bit signed integers, the type dataframes default to.
+
+use alloc::{
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+use crate::dump::DumpFile;
+
+mod flatbuffer;
+
+use flatbuffer::{Builder, Field, Ref};
+
+/// Magic bytes at the start and the end of files.
+const MAGIC: &[u8; 6] = b"ARROW1";
+/// Arrow metadata version 5, as written since Arrow 1.0.
+const METADATA_VERSION: i16 = 4;
+/// Ids of the `MessageHeader` union.
+const SCHEMA_HEADER: u8 = 1;
+const RECORD_BATCH_HEADER: u8 = 3;
+/// Ids of the `Type` union.
+const INT_TYPE: u8 = 2;
+const UTF8_TYPE: u8 = 5;
+const BOOL_TYPE: u8 = 6;
+
+/// A table of the timings of `dump`, with columns:
+///
+/// - `signal`: name of the signal;
+/// - `index`: position of the duration in the signal;
+/// - `pulse`: whether the duration is a pulse rather than a pause;
+/// - `start_us`: time since the start of the signal, in µs;
+/// - `duration_us`: the duration, in µs.
+///
+/// # Examples
+/// ```rust
+/// use flipper_ir_dumps::{examples::EXAMPLES, formats::arrow};
+///
+/// let file = arrow::timings(&EXAMPLES[0].dump());
+/// assert_eq!(file[..6], *b"ARROW1");
+/// assert_eq!(file[file.len() - 6..], *b"ARROW1");
+/// ```
+pub fn timings(dump: &DumpFile) -> Vec<u8> {
+    let mut signal = Vec::new();
+    let mut index = Vec::new();
+    let mut pulse = Vec::new();
+    let mut start = Vec::new();
+    let mut duration = Vec::new();
+
+    for raw in dump.signals() {
+        let mut time = 0;
+        for (i, &timing) in raw.data().iter().enumerate() {
+            signal.push(Some(raw.name().to_string()));
+            index.push(Some(i as i64));
+            pulse.push(Some(i & 1 == 0));
+            start.push(Some(time));
+            duration.push(Some(i64::from(timing)));
+            time += i64::from(timing);
+        }
+    }
+
+    to_file(&[
+        Column::utf8("signal", signal),
+        Column::int("index", index),
+        Column::bool("pulse", pulse),
+        Column::int("start_us", start),
+        Column::int("duration_us", duration),
+    ])
+}
+
+/// A table of the packets of `dump`, as parsed by
+/// [`RawSignal::parsed`](crate::signal::RawSignal::parsed), with columns:
+///
+/// - `signal`: name of the signal;
+/// - `packet`: position of the packet in the signal;
+/// - `bits`: length of the packet;
+/// - `hex`: the packet as a hexadecimal number;
+/// - `repeats`: number of repeats following the packet;
+/// - `protocol`, `address` and `command`: the frame the packet was decoded
+///   into, null if no protocol decoded it;
+/// - `valid`: whether the packet passed the integrity checks of its
+///   protocol.
+///
+/// Signals that can't be parsed have no rows.
+///
+/// # Examples
+/// ```rust
+/// use flipper_ir_dumps::{examples::EXAMPLES, formats::arrow};
+///
+/// let file = arrow::packets(&EXAMPLES[0].dump());
+/// assert_eq!(file[..6], *b"ARROW1");
+/// ```
+pub fn packets(dump: &DumpFile) -> Vec<u8> {
+    let mut signal = Vec::new();
+    let mut packet = Vec::new();
+    let mut bits = Vec::new();
+    let mut hex = Vec::new();
+    let mut repeats = Vec::new();
+    let mut protocol = Vec::new();
+    let mut address = Vec::new();
+    let mut command = Vec::new();
+    let mut valid = Vec::new();
+
+    for raw in dump.signals() {
+        let Ok(parsed) = raw.parsed() else {
+            continue;
+        };
+        for (i, parsed_packet) in parsed.packets().iter().enumerate() {
+            let frame = parsed_packet.frame();
+            signal.push(Some(raw.name().to_string()));
+            packet.push(Some(i as i64));
+            bits.push(Some(parsed_packet.bit_len() as i64));
+            hex.push(Some(parsed_packet.to_hex()));
+            repeats.push(Some(parsed_packet.repeats() as i64));
+            protocol.push(frame.map(|frame| frame.protocol.clone()));
+            address.push(frame.and_then(|frame| frame.address).map(i64::from));
+            command.push(frame.and_then(|frame| frame.command).map(i64::from));
+            valid.push(Some(parsed_packet.is_valid()));
+        }
+    }
+
+    to_file(&[
+        Column::utf8("signal", signal),
+        Column::int("packet", packet),
+        Column::int("bits", bits),
+        Column::utf8("hex", hex),
+        Column::int("repeats", repeats),
+        Column::utf8("protocol", protocol),
+        Column::int("address", address),
+        Column::int("command", command),
+        Column::bool("valid", valid),
+    ])
+}
+
+struct Column {
+    name: &'static str,
+    values: Values,
+}
+
+enum Values {
+    Int(Vec<Option<i64>>),
+    Utf8(Vec<Option<String>>),
+    Bool(Vec<Option<bool>>),
+}
+
+impl Column {
+    fn int(name: &'static str, values: Vec<Option<i64>>) -> Self {
+        Self {
+            name,
+            values: Values::Int(values),
+        }
+    }
+
+    fn utf8(name: &'static str, values: Vec<Option<String>>) -> Self {
+        Self {
+            name,
+            values: Values::Utf8(values),
+        }
+    }
+
+    fn bool(name: &'static str, values: Vec<Option<bool>>) -> Self {
+        Self {
+            name,
+            values: Values::Bool(values),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match &self.values {
+            Values::Int(values) => values.len(),
+            Values::Utf8(values) => values.len(),
+            Values::Bool(values) => values.len(),
+        }
+    }
+
+    /// Whether each value is present.
+    fn validity(&self) -> Vec<bool> {
+        match &self.values {
+            Values::Int(values) => values.iter().map(Option::is_some).collect(),
+            Values::Utf8(values) => values.iter().map(Option::is_some).collect(),
+            Values::Bool(values) => values.iter().map(Option::is_some).collect(),
+        }
+    }
+
+    fn null_count(&self) -> usize {
+        self.validity().iter().filter(|&&valid| !valid).count()
+    }
+
+    /// The `Field` of the column in the schema.
+    fn field(&self, builder: &mut Builder) -> Ref {
+        let (type_id, r#type) = match self.values {
+            Values::Int(_) => (
+                INT_TYPE,
+                // bitWidth, is_signed
+                builder.table(&[(0, Field::I32(64)), (1, Field::Bool(true))]),
+            ),
+            Values::Utf8(_) => (UTF8_TYPE, builder.table(&[])),
+            Values::Bool(_) => (BOOL_TYPE, builder.table(&[])),
+        };
+        let children = builder.offsets(&[]);
+        let name = builder.string(self.name);
+        builder.table(&[
+            (0, Field::Offset(name)),
+            (1, Field::Bool(self.null_count() > 0)),
+            (2, Field::U8(type_id)),
+            (3, Field::Offset(r#type)),
+            (5, Field::Offset(children)),
+        ])
+    }
+
+    /// The buffers of the column, in the order of the Arrow layout of its
+    /// type: validity bitmap, then offsets for strings, then values.
+    fn buffers(&self) -> Vec<Vec<u8>> {
+        let validity = if self.null_count() > 0 {
+            bitmap(self.validity())
+        } else {
+            // all valid, as an empty validity buffer says
+            Vec::new()
+        };
+
+        match &self.values {
+            Values::Int(values) => {
+                let data = values
+                    .iter()
+                    .flat_map(|value| value.unwrap_or_default().to_le_bytes())
+                    .collect();
+                vec![validity, data]
+            }
+            Values::Utf8(values) => {
+                let mut offsets = Vec::from(0i32.to_le_bytes());
+                let mut data = Vec::new();
+                for value in values {
+                    data.extend(value.as_deref().unwrap_or_default().as_bytes());
+                    offsets.extend((data.len() as i32).to_le_bytes());
+                }
+                vec![validity, offsets, data]
+            }
+            Values::Bool(values) => {
+                let data = bitmap(values.iter().map(|value| value.unwrap_or_default()));
+                vec![validity, data]
+            }
+        }
+    }
+}
+
+/// Bits packed least significant first, as Arrow bitmaps are.
+fn bitmap(bits: impl IntoIterator<Item = bool>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for (i, bit) in bits.into_iter().enumerate() {
+        if i % 8 == 0 {
+            bytes.push(0);
+        }
+        *bytes.last_mut().unwrap() |= u8::from(bit) << (i % 8);
+    }
+    bytes
+}
+
+/// Position and size of a message in the file, as listed in the footer.
+struct Block {
+    offset: usize,
+    metadata_length: usize,
+    body_length: usize,
+}
+
+/// An IPC file of a single record batch of `columns`.
+fn to_file(columns: &[Column]) -> Vec<u8> {
+    let mut file = Vec::from(*MAGIC);
+    pad(&mut file);
+
+    let mut builder = Builder::default();
+    let header = schema(&mut builder, columns);
+    let header = message(&mut builder, SCHEMA_HEADER, header, 0);
+    write_message(&mut file, builder.finish(header), &[]);
+
+    let (batch, body) = record_batch(columns);
+    let block = write_message(&mut file, batch, &body);
+
+    // end of the stream
+    file.extend(u32::MAX.to_le_bytes());
+    file.extend(0u32.to_le_bytes());
+
+    let mut builder = Builder::default();
+    let footer_schema = schema(&mut builder, columns);
+    let dictionaries = builder.structs(&[], 0);
+    let mut blocks = Vec::new();
+    blocks.extend((block.offset as i64).to_le_bytes());
+    blocks.extend((block.metadata_length as i32).to_le_bytes());
+    // padding of the struct
+    blocks.extend([0; 4]);
+    blocks.extend((block.body_length as i64).to_le_bytes());
+    let batches = builder.structs(&blocks, 1);
+    let footer = builder.table(&[
+        (0, Field::I16(METADATA_VERSION)),
+        (1, Field::Offset(footer_schema)),
+        (2, Field::Offset(dictionaries)),
+        (3, Field::Offset(batches)),
+    ]);
+    let footer = builder.finish(footer);
+
+    file.extend(&footer);
+    file.extend((footer.len() as i32).to_le_bytes());
+    file.extend(MAGIC);
+    file
+}
+
+fn schema(builder: &mut Builder, columns: &[Column]) -> Ref {
+    let fields: Vec<Ref> = columns.iter().map(|column| column.field(builder)).collect();
+    let fields = builder.offsets(&fields);
+    // little endian
+    builder.table(&[(0, Field::I16(0)), (1, Field::Offset(fields))])
+}
+
+fn message(builder: &mut Builder, header_type: u8, header: Ref, body_length: usize) -> Ref {
+    builder.table(&[
+        (0, Field::I16(METADATA_VERSION)),
+        (1, Field::U8(header_type)),
+        (2, Field::Offset(header)),
+        (3, Field::I64(body_length as i64)),
+    ])
+}
+
+/// The metadata and body of the record batch of `columns`.
+fn record_batch(columns: &[Column]) -> (Vec<u8>, Vec<u8>) {
+    let length = columns.first().map_or(0, Column::len);
+    let mut nodes = Vec::new();
+    let mut buffers = Vec::new();
+    let mut body = Vec::new();
+
+    for column in columns {
+        nodes.extend((column.len() as i64).to_le_bytes());
+        nodes.extend((column.null_count() as i64).to_le_bytes());
+        for buffer in column.buffers() {
+            buffers.extend((body.len() as i64).to_le_bytes());
+            buffers.extend((buffer.len() as i64).to_le_bytes());
+            body.extend(buffer);
+            pad(&mut body);
+        }
+    }
+
+    let mut builder = Builder::default();
+    let node_count = nodes.len() / 16;
+    let nodes = builder.structs(&nodes, node_count);
+    let buffer_count = buffers.len() / 16;
+    let buffers = builder.structs(&buffers, buffer_count);
+    let batch = builder.table(&[
+        (0, Field::I64(length as i64)),
+        (1, Field::Offset(nodes)),
+        (2, Field::Offset(buffers)),
+    ]);
+    let header = message(&mut builder, RECORD_BATCH_HEADER, batch, body.len());
+    (builder.finish(header), body)
+}
+
+/// Append an encapsulated message: a continuation marker, the length of
+/// the metadata, the metadata and the body, each padded to 8 bytes.
+fn write_message(file: &mut Vec<u8>, mut metadata: Vec<u8>, body: &[u8]) -> Block {
+    let offset = file.len();
+    pad(&mut metadata);
+    file.extend(u32::MAX.to_le_bytes());
+    file.extend((metadata.len() as i32).to_le_bytes());
+    file.extend(&metadata);
+    file.extend(body);
+    Block {
+        offset,
+        metadata_length: 8 + metadata.len(),
+        body_length: body.len(),
+    }
+}
+
+fn pad(bytes: &mut Vec<u8>) {
+    bytes.resize(bytes.len().next_multiple_of(8), 0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{flatbuffer::reader, *};
+    use crate::signal::{DutyCycle, Polarity, RawSignal, TimingSequence};
+
+    fn i64_at(bytes: &[u8], position: usize) -> i64 {
+        i64::from_le_bytes(bytes[position..position + 8].try_into().unwrap())
+    }
+
+    /// Names of the fields of the schema in the footer, and the blocks of
+    /// the record batches.
+    fn footer(file: &[u8]) -> (Vec<String>, Vec<(usize, usize, usize)>) {
+        assert_eq!(file[..8], *b"ARROW1\0\0");
+        assert_eq!(file[file.len() - 6..], *MAGIC);
+        let length = i32::from_le_bytes(file[file.len() - 10..file.len() - 6].try_into().unwrap());
+        let footer = &file[file.len() - 10 - length as usize..file.len() - 10];
+        // the footer follows the end of stream marker
+        assert_eq!(
+            file[file.len() - 18 - length as usize..file.len() - 10 - length as usize],
+            [0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0, 0]
+        );
+
+        let root = reader::root(footer);
+        let schema = reader::follow(footer, reader::field(footer, root, 1).unwrap());
+        let fields = reader::follow(footer, reader::field(footer, schema, 1).unwrap());
+        let names = reader::items(footer, fields, 4)
+            .map(|field| {
+                let field = reader::follow(footer, field);
+                let name = reader::follow(footer, reader::field(footer, field, 0).unwrap());
+                reader::string(footer, name).to_string()
+            })
+            .collect();
+
+        let batches = reader::follow(footer, reader::field(footer, root, 3).unwrap());
+        let blocks = reader::items(footer, batches, 24)
+            .map(|block| {
+                (
+                    i64_at(footer, block) as usize,
+                    i32::from_le_bytes(footer[block + 8..block + 12].try_into().unwrap()) as usize,
+                    i64_at(footer, block + 16) as usize,
+                )
+            })
+            .collect();
+        (names, blocks)
+    }
+
+    /// Length of the record batch of the block, and the contents of its
+    /// buffers.
+    fn batch(
+        file: &[u8],
+        (offset, metadata_length, body_length): (usize, usize, usize),
+    ) -> (i64, Vec<&[u8]>) {
+        assert_eq!(file[offset..offset + 4], [0xFF; 4]);
+        let metadata = &file[offset + 8..offset + metadata_length];
+        let body = &file[offset + metadata_length..offset + metadata_length + body_length];
+
+        let message = reader::root(metadata);
+        assert_eq!(
+            metadata[reader::field(metadata, message, 1).unwrap()],
+            RECORD_BATCH_HEADER
+        );
+        let batch = reader::follow(metadata, reader::field(metadata, message, 2).unwrap());
+        let length = i64_at(metadata, reader::field(metadata, batch, 0).unwrap());
+        let buffers = reader::follow(metadata, reader::field(metadata, batch, 2).unwrap());
+        let buffers = reader::items(metadata, buffers, 16)
+            .map(|buffer| {
+                let offset = i64_at(metadata, buffer) as usize;
+                assert_eq!(offset % 8, 0);
+                &body[offset..offset + i64_at(metadata, buffer + 8) as usize]
+            })
+            .collect();
+        (length, buffers)
+    }
+
+    #[test]
+    fn test_timings() {
+        let sequence = TimingSequence::new(Polarity::StartsWithPulse, vec![100, 50, 100]);
+        let signal = RawSignal::new("Power", 38_000, DutyCycle::default(), sequence).unwrap();
+        let file = timings(&DumpFile::new(1, vec![signal]));
+
+        let (names, blocks) = footer(&file);
+        assert_eq!(
+            names,
+            ["signal", "index", "pulse", "start_us", "duration_us"]
+        );
+        assert_eq!(blocks.len(), 1);
+
+        let (length, buffers) = batch(&file, blocks[0]);
+        assert_eq!(length, 3);
+        assert_eq!(buffers.len(), 3 + 2 * 4);
+        // signal: no nulls, offsets, data
+        assert_eq!(buffers[0], []);
+        assert_eq!(
+            buffers[1],
+            [0, 0, 0, 0, 5, 0, 0, 0, 10, 0, 0, 0, 15, 0, 0, 0]
+        );
+        assert_eq!(buffers[2], b"PowerPowerPower");
+        // pulse
+        assert_eq!(buffers[6], [0b101]);
+        // start_us
+        assert_eq!(buffers[8][8..16], 100i64.to_le_bytes());
+        assert_eq!(buffers[8][16..], 150i64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_packets() {
+        // NEC address 0x04, command 0x08
+        let mut data = vec![9024, 4512];
+        for byte in [0x04u8, !0x04, 0x08, !0x08] {
+            for bit in 0..8 {
+                data.push(564);
+                data.push(if byte >> bit & 1 == 1 { 1692 } else { 564 });
+            }
+        }
+        data.push(564);
+        let nec = TimingSequence::new(Polarity::StartsWithPulse, data);
+        let nec = RawSignal::new("Power", 38_000, DutyCycle::default(), nec).unwrap();
+        let file = packets(&DumpFile::new(1, vec![nec]));
+
+        let (names, blocks) = footer(&file);
+        assert_eq!(
+            names,
+            [
+                "signal", "packet", "bits", "hex", "repeats", "protocol", "address", "command",
+                "valid"
+            ]
+        );
+        let (length, buffers) = batch(&file, blocks[0]);
+        assert_eq!(length, 1);
+        // protocol
+        assert_eq!(buffers[14], b"NEC");
+        // address
+        assert_eq!(buffers[16], 4i64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_nulls() {
+        let column = Column::int("address", vec![Some(1), None, Some(3)]);
+        assert_eq!(column.null_count(), 1);
+        let buffers = column.buffers();
+        assert_eq!(buffers[0], [0b101]);
+        assert_eq!(buffers[1].len(), 24);
+
+        let mut builder = Builder::default();
+        let field = column.field(&mut builder);
+        let buffer = builder.finish(field);
+        let root = reader::root(&buffer);
+        // nullable
+        assert_eq!(buffer[reader::field(&buffer, root, 1).unwrap()], 1);
+    }
+
+    #[test]
+    fn test_empty() {
+        let file = timings(&DumpFile::new(1, vec![]));
+        let (names, blocks) = footer(&file);
+        assert_eq!(names.len(), 5);
+        let (length, buffers) = batch(&file, blocks[0]);
+        assert_eq!(length, 0);
+        assert_eq!(buffers[1], [0, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,253 @@
+//! Just enough of FlatBuffers to write the metadata of Arrow IPC files.
+//!
+//! As with the reference builders, the buffer grows from its end: objects
+//! are written before the ones referring to them, and are identified by
+//! their distance from the end until the buffer is finished.
+
+use alloc::vec::Vec;
+
+/// An object written to a [`Builder`], by the distance of its start from
+/// the end of the buffer.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(super) struct Ref(u32);
+
+/// A field of a table.
+pub(super) enum Field {
+    U8(u8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    Bool(bool),
+    /// A table, a vector or a string.
+    Offset(Ref),
+}
+
+impl Field {
+    fn size(&self) -> usize {
+        match self {
+            Field::U8(_) | Field::Bool(_) => 1,
+            Field::I16(_) => 2,
+            Field::I32(_) | Field::Offset(_) => 4,
+            Field::I64(_) => 8,
+        }
+    }
+}
+
+#[derive(Default)]
+pub(super) struct Builder {
+    /// The buffer written so far, reversed.
+    reversed: Vec<u8>,
+}
+
+impl Builder {
+    pub(super) fn string(&mut self, text: &str) -> Ref {
+        self.align(4 + text.len() + 1, 4);
+        self.prepend(&[0]);
+        self.prepend(text.as_bytes());
+        self.prepend(&(text.len() as u32).to_le_bytes());
+        self.head()
+    }
+
+    /// A vector of `count` structs, 8-byte aligned, laid out in `items`.
+    pub(super) fn structs(&mut self, items: &[u8], count: usize) -> Ref {
+        self.align(items.len(), 8);
+        self.prepend(items);
+        self.prepend(&(count as u32).to_le_bytes());
+        self.head()
+    }
+
+    /// A vector of tables or strings.
+    pub(super) fn offsets(&mut self, items: &[Ref]) -> Ref {
+        self.align(4 * items.len() + 4, 4);
+        for item in items.iter().rev() {
+            let position = self.reversed.len() as u32 + 4;
+            self.prepend(&(position - item.0).to_le_bytes());
+        }
+        self.prepend(&(items.len() as u32).to_le_bytes());
+        self.head()
+    }
+
+    /// A table of `fields` by id, with its vtable right before it.
+    pub(super) fn table(&mut self, fields: &[(u16, Field)]) -> Ref {
+        // widest fields first, so that they pack without padding
+        let mut order: Vec<usize> = (0..fields.len()).collect();
+        order.sort_by_key(|&i| core::cmp::Reverse(fields[i].1.size()));
+
+        let mut offsets = alloc::vec![0u16; fields.len()];
+        let mut size: usize = 4;
+        for &i in &order {
+            let field_size = fields[i].1.size();
+            size = size.next_multiple_of(field_size);
+            offsets[i] = size as u16;
+            size += field_size;
+        }
+
+        self.align(size, 8);
+        let start = self.reversed.len() + size;
+        let mut table = alloc::vec![0u8; size];
+        for ((_, field), &offset) in fields.iter().zip(&offsets) {
+            let offset = usize::from(offset);
+            let bytes = &mut table[offset..offset + field.size()];
+            match *field {
+                Field::U8(value) => bytes.copy_from_slice(&[value]),
+                Field::Bool(value) => bytes.copy_from_slice(&[u8::from(value)]),
+                Field::I16(value) => bytes.copy_from_slice(&value.to_le_bytes()),
+                Field::I32(value) => bytes.copy_from_slice(&value.to_le_bytes()),
+                Field::I64(value) => bytes.copy_from_slice(&value.to_le_bytes()),
+                Field::Offset(target) => {
+                    let position = (start - offset) as u32;
+                    bytes.copy_from_slice(&(position - target.0).to_le_bytes());
+                }
+            }
+        }
+
+        let slots = fields.iter().map(|&(id, _)| usize::from(id) + 1).max();
+        let vtable_size = 4 + 2 * slots.unwrap_or_default();
+        let mut vtable = alloc::vec![0u8; vtable_size];
+        vtable[..2].copy_from_slice(&(vtable_size as u16).to_le_bytes());
+        vtable[2..4].copy_from_slice(&(size as u16).to_le_bytes());
+        for (&(id, _), offset) in fields.iter().zip(offsets) {
+            let slot = 4 + 2 * usize::from(id);
+            vtable[slot..slot + 2].copy_from_slice(&offset.to_le_bytes());
+        }
+        // the vtable is right before the table: the buffer is 8-byte
+        // aligned there and vtables have an even size
+        table[..4].copy_from_slice(&(vtable_size as i32).to_le_bytes());
+
+        self.prepend(&table);
+        self.prepend(&vtable);
+        Ref(start as u32)
+    }
+
+    /// The finished buffer, starting with the offset of `root`.
+    pub(super) fn finish(mut self, root: Ref) -> Vec<u8> {
+        self.align(4, 8);
+        let position = self.reversed.len() as u32 + 4;
+        self.prepend(&(position - root.0).to_le_bytes());
+        self.reversed.reverse();
+        self.reversed
+    }
+
+    fn head(&self) -> Ref {
+        Ref(self.reversed.len() as u32)
+    }
+
+    fn prepend(&mut self, bytes: &[u8]) {
+        self.reversed.extend(bytes.iter().rev());
+    }
+
+    /// Pad so that an object of `size` bytes written next starts aligned:
+    /// buffers are finished to a multiple of 8 bytes, so alignment from
+    /// the end is alignment from the start.
+    fn align(&mut self, size: usize, alignment: usize) {
+        while !(self.reversed.len() + size).is_multiple_of(alignment) {
+            self.reversed.push(0);
+        }
+    }
+}
+
+#[cfg(test)]
+pub(super) mod reader {
+    //! Reading back what [`Builder`](super::Builder) writes, for tests.
+
+    fn u32_at(buffer: &[u8], position: usize) -> usize {
+        u32::from_le_bytes(buffer[position..position + 4].try_into().unwrap()) as usize
+    }
+
+    /// Position of the root table.
+    pub(in crate::formats::arrow) fn root(buffer: &[u8]) -> usize {
+        u32_at(buffer, 0)
+    }
+
+    /// Position of field `id` of the table at `table`, if present.
+    pub(in crate::formats::arrow) fn field(
+        buffer: &[u8],
+        table: usize,
+        id: usize,
+    ) -> Option<usize> {
+        let soffset = i32::from_le_bytes(buffer[table..table + 4].try_into().unwrap());
+        let vtable = (table as i64 - i64::from(soffset)) as usize;
+        let size = usize::from(u16::from_le_bytes([buffer[vtable], buffer[vtable + 1]]));
+        if 4 + 2 * id >= size {
+            return None;
+        }
+        let slot = vtable + 4 + 2 * id;
+        match u16::from_le_bytes([buffer[slot], buffer[slot + 1]]) {
+            0 => None,
+            offset => Some(table + usize::from(offset)),
+        }
+    }
+
+    /// Position of the object the offset at `position` refers to.
+    pub(in crate::formats::arrow) fn follow(buffer: &[u8], position: usize) -> usize {
+        position + u32_at(buffer, position)
+    }
+
+    /// The string at `position`.
+    pub(in crate::formats::arrow) fn string(buffer: &[u8], position: usize) -> &str {
+        let len = u32_at(buffer, position);
+        assert_eq!(buffer[position + 4 + len], 0);
+        core::str::from_utf8(&buffer[position + 4..position + 4 + len]).unwrap()
+    }
+
+    /// Positions of the items of the vector at `position`, `size` bytes each.
+    pub(in crate::formats::arrow) fn items(
+        buffer: &[u8],
+        position: usize,
+        size: usize,
+    ) -> impl Iterator<Item = usize> {
+        (0..u32_at(buffer, position)).map(move |i| position + 4 + i * size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table() {
+        let mut builder = Builder::default();
+        let name = builder.string("timings");
+        let names = builder.offsets(&[name, name]);
+        let table = builder.table(&[
+            (0, Field::Offset(name)),
+            (1, Field::Bool(true)),
+            (3, Field::I64(-2)),
+            (4, Field::Offset(names)),
+        ]);
+        let buffer = builder.finish(table);
+        assert_eq!(buffer.len() % 8, 0);
+
+        let root = reader::root(&buffer);
+        assert_eq!(root % 8, 0);
+        let name = reader::follow(&buffer, reader::field(&buffer, root, 0).unwrap());
+        assert_eq!(reader::string(&buffer, name), "timings");
+        assert_eq!(buffer[reader::field(&buffer, root, 1).unwrap()], 1);
+        assert_eq!(reader::field(&buffer, root, 2), None);
+        let long = reader::field(&buffer, root, 3).unwrap();
+        assert_eq!(long % 8, 0);
+        assert_eq!(buffer[long..long + 8], (-2i64).to_le_bytes());
+        assert_eq!(reader::field(&buffer, root, 5), None);
+
+        let names = reader::follow(&buffer, reader::field(&buffer, root, 4).unwrap());
+        let names: Vec<_> = reader::items(&buffer, names, 4)
+            .map(|item| reader::string(&buffer, reader::follow(&buffer, item)))
+            .collect();
+        assert_eq!(names, ["timings", "timings"]);
+    }
+
+    #[test]
+    fn test_structs() {
+        let mut builder = Builder::default();
+        let items = builder.structs(&[1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0], 2);
+        let table = builder.table(&[(0, Field::Offset(items))]);
+        let buffer = builder.finish(table);
+
+        let root = reader::root(&buffer);
+        let items = reader::follow(&buffer, reader::field(&buffer, root, 0).unwrap());
+        let items: Vec<_> = reader::items(&buffer, items, 8).collect();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0] % 8, 0);
+        assert_eq!(buffer[items[1]], 2);
+    }
+}
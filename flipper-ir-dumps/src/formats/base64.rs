@@ -0,0 +1,64 @@
+//! Standard base64 with padding, as most IR blasters wrap their binary codes in.
+
+use alloc::{string::String, vec::Vec};
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub(crate) fn encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, &byte)| {
+            group | u32::from(byte) << (16 - 8 * i)
+        });
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(group >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Decode base64, ignoring whitespace and tolerating missing padding.
+pub(crate) fn decode(input: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(input.len() / 4 * 3);
+    let (mut group, mut bits) = (0u32, 0);
+
+    let input = input.trim_end_matches(|c: char| c == '=' || c.is_whitespace());
+    for c in input.bytes().filter(|c| !c.is_ascii_whitespace()) {
+        let value = ALPHABET.iter().position(|&letter| letter == c)?;
+        group = group << 6 | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((group >> bits) as u8);
+        }
+    }
+
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        for (bytes, encoded) in [
+            (&b""[..], ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foob", "Zm9vYg=="),
+            (&[0xFF, 0x00, 0xFE], "/wD+"),
+        ] {
+            assert_eq!(encode(bytes), encoded);
+            assert_eq!(decode(encoded).as_deref(), Some(bytes));
+        }
+        assert_eq!(decode("Zm9v\nYg").as_deref(), Some(&b"foob"[..]));
+        assert_eq!(decode("Zm9v!"), None);
+    }
+}
@@ -0,0 +1,250 @@
+//! Broadlink IR codes, as used by Home Assistant and the Broadlink apps.
+//!
+//! A code is a base64 encoded packet: a `0x26` byte marking IR, a repeat
+//! count, the length of the payload and the payload itself, alternating
+//! pulses and pauses in ticks of 269/8192 ms (about 32.84µs). Durations over
+//! 255 ticks take three bytes: a zero and the big-endian tick count.
+
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+
+use displaydoc::Display;
+use thiserror::Error;
+
+use super::base64;
+use crate::signal::{DutyCycle, Frequency, Polarity, RawSignal, TimingSequence};
+
+/// Type byte of IR packets, RF ones use other values.
+const IR_PACKET: u8 = 0x26;
+/// A tick lasts 269/8192 ms, that is `TICK_US / TICK_DIVISOR` µs.
+const TICK_US: u64 = 269_000;
+const TICK_DIVISOR: u64 = 8192;
+/// Packets are padded with zeros to a multiple of this many bytes.
+const PADDING: usize = 16;
+/// Pause ending each packet, in ticks, as sent by the Broadlink apps.
+const END_PAUSE: u16 = 0x0D05;
+/// Broadlink blasters transmit all codes on this carrier.
+const FREQUENCY: u32 = 38_000;
+
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+/// Error decoding a Broadlink code
+pub enum BroadlinkError {
+    /// Code is not valid base64
+    InvalidBase64,
+    /// Packet of type 0x{0:02x} is not an IR code
+    NotIr(u8),
+    /// Packet is shorter than its header claims
+    Truncated,
+    /// Code has no pulses
+    Empty,
+}
+
+/// A Broadlink IR code.
+///
+/// # Examples
+/// ```rust
+/// use flipper_ir_dumps::formats::broadlink::BroadlinkCode;
+///
+/// let code: BroadlinkCode = "JgAGAB0PDwANBQAAAAAAAA==".parse().unwrap();
+/// assert_eq!(code.repeats, 0);
+/// assert_eq!(code.data, [952, 493, 493, 109_445]);
+/// assert_eq!(code.to_string(), "JgAGAB0PDwANBQAAAAAAAA==");
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BroadlinkCode {
+    /// Number of times the code is sent again after the first time.
+    pub repeats: u8,
+    /// Alternating pulses and pauses in µs, pulse first.
+    pub data: Vec<u32>,
+}
+
+impl BroadlinkCode {
+    /// The code of a signal, sent once.
+    ///
+    /// Broadlink blasters send every code at 38kHz, whatever the carrier
+    /// of the signal.
+    pub fn from_signal(signal: &RawSignal) -> Self {
+        Self {
+            repeats: 0,
+            data: signal.data().to_vec(),
+        }
+    }
+
+    /// A raw signal sending the code once, ignoring the repeats.
+    pub fn to_signal(&self, name: impl Into<String>) -> Result<RawSignal, BroadlinkError> {
+        let timings = TimingSequence::new(Polarity::StartsWithPulse, self.data.clone());
+        RawSignal::new(
+            name,
+            Frequency::from_hz(FREQUENCY),
+            DutyCycle::default(),
+            timings,
+        )
+        .map_err(|_| BroadlinkError::Empty)
+    }
+
+    /// The packet, padded with zeros, before base64 encoding.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut payload = Vec::new();
+        let mut push = |ticks: u64| match u8::try_from(ticks) {
+            Ok(ticks) if ticks > 0 => payload.push(ticks),
+            _ => {
+                payload.push(0);
+                payload.extend((ticks.min(u16::MAX.into()) as u16).to_be_bytes());
+            }
+        };
+
+        for &duration in &self.data {
+            push(to_ticks(duration).max(1));
+        }
+        if self.data.len() & 1 == 1 {
+            push(END_PAUSE.into());
+        }
+
+        let mut bytes = Vec::from([IR_PACKET, self.repeats]);
+        bytes.extend((payload.len() as u16).to_le_bytes());
+        bytes.extend(payload);
+        bytes.resize(bytes.len().next_multiple_of(PADDING), 0);
+        bytes
+    }
+
+    /// Parse a packet, before base64 encoding.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BroadlinkError> {
+        let [kind, repeats, low, high, payload @ ..] = bytes else {
+            return Err(BroadlinkError::Truncated);
+        };
+        if *kind != IR_PACKET {
+            return Err(BroadlinkError::NotIr(*kind));
+        }
+        let payload = payload
+            .get(..usize::from(u16::from_le_bytes([*low, *high])))
+            .ok_or(BroadlinkError::Truncated)?;
+
+        let mut data = Vec::new();
+        let mut bytes = payload.iter();
+        while let Some(&ticks) = bytes.next() {
+            let ticks = match ticks {
+                0 => match (bytes.next(), bytes.next()) {
+                    (Some(&high), Some(&low)) => u16::from_be_bytes([high, low]).into(),
+                    _ => return Err(BroadlinkError::Truncated),
+                },
+                ticks => u64::from(ticks),
+            };
+            data.push(from_ticks(ticks));
+        }
+
+        Ok(Self {
+            repeats: *repeats,
+            data,
+        })
+    }
+}
+
+impl core::str::FromStr for BroadlinkCode {
+    type Err = BroadlinkError;
+
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        let bytes = base64::decode(code).ok_or(BroadlinkError::InvalidBase64)?;
+        Self::from_bytes(&bytes)
+    }
+}
+
+/// The base64 encoded packet.
+impl fmt::Display for BroadlinkCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&base64::encode(&self.to_bytes()))
+    }
+}
+
+fn to_ticks(duration: u32) -> u64 {
+    (u64::from(duration) * TICK_DIVISOR + TICK_US / 2) / TICK_US
+}
+
+fn from_ticks(ticks: u64) -> u32 {
+    ((ticks * TICK_US + TICK_DIVISOR / 2) / TICK_DIVISOR) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::*;
+    use crate::{examples::EXAMPLES, protocol};
+
+    #[test]
+    fn test_ticks() {
+        assert_eq!(to_ticks(9000), 274);
+        assert_eq!(from_ticks(274), 8997);
+        assert_eq!(from_ticks(END_PAUSE.into()), 109_445);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let dump = EXAMPLES[0].dump();
+        for signal in dump.signals() {
+            let code = BroadlinkCode::from_signal(signal);
+            let parsed: BroadlinkCode = code.to_string().parse().unwrap();
+            assert_eq!(parsed.repeats, 0);
+
+            let converted = parsed.to_signal(signal.name()).unwrap();
+            assert_eq!(converted.data().len(), signal.data().len());
+            assert!(converted.is_duplicate_of(signal, 0.05), "{}", signal.name());
+        }
+    }
+
+    #[test]
+    fn test_decodes() {
+        // NEC address 0x04 command 0x08, as learned by a Broadlink RM
+        let mut data = Vec::from([9000, 4500]);
+        for byte in [0x04u8, 0xFB, 0x08, 0xF7] {
+            for bit in 0..8 {
+                data.extend([560, if byte >> bit & 1 == 1 { 1690 } else { 560 }]);
+            }
+        }
+        data.push(560);
+        let code = BroadlinkCode { repeats: 2, data };
+
+        let bytes = code.to_bytes();
+        assert_eq!(bytes[..2], [IR_PACKET, 2]);
+        // header pulse as three bytes, closing pause as three bytes
+        assert_eq!(bytes[4..7], [0x00, 0x01, 0x12]);
+        let length = usize::from(bytes[2]);
+        assert_eq!(bytes[length + 1..length + 4], [0x00, 0x0D, 0x05]);
+        assert_eq!(bytes.len() % PADDING, 0);
+        assert!(bytes[length + 4..].iter().all(|&byte| byte == 0));
+
+        let parsed = BroadlinkCode::from_bytes(&bytes).unwrap();
+        let signal = parsed.to_signal("power").unwrap();
+        match protocol::decode(signal.data(), 38_000) {
+            Some(protocol::Decoded::Nec(nec)) => {
+                assert_eq!((nec.address, nec.command), (0x04, 0x08))
+            }
+            decoded => panic!("decoded as {decoded:?}"),
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            "not base64!".parse::<BroadlinkCode>(),
+            Err(BroadlinkError::InvalidBase64)
+        );
+        assert_eq!(
+            BroadlinkCode::from_bytes(&[0xB2, 0, 0, 0]),
+            Err(BroadlinkError::NotIr(0xB2))
+        );
+        assert_eq!(
+            BroadlinkCode::from_bytes(&[IR_PACKET, 0, 4, 0, 10]),
+            Err(BroadlinkError::Truncated)
+        );
+        assert_eq!(
+            BroadlinkCode::from_bytes(&[IR_PACKET, 0, 2, 0, 0, 1]),
+            Err(BroadlinkError::Truncated)
+        );
+        assert_eq!(
+            BroadlinkCode::from_bytes(&[IR_PACKET, 0, 0, 0])
+                .unwrap()
+                .to_signal("empty"),
+            Err(BroadlinkError::Empty)
+        );
+    }
+}
@@ -0,0 +1,218 @@
+//! [ESPHome](https://esphome.io) configuration sending the signals of a dump
+//! through a `remote_transmitter`.
+//!
+//! Each signal becomes a template button. Signals decoded as NEC, RC5 or RC6
+//! frames are sent with the matching ESPHome action, so ESPHome times them
+//! exactly and they read as more than numbers; anything else is sent as raw
+//! timings.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::Write;
+
+use crate::{
+    dump::DumpFile,
+    protocol::{rc6::Rc6Mode, Decoded, Registry},
+    signal::RawSignal,
+};
+
+/// Durations on each line of a raw code.
+const DURATIONS_PER_LINE: usize = 12;
+
+/// A `button:` section with a template button sending each signal of `dump`,
+/// decoding them with the protocols of `registry`.
+///
+/// # Examples
+/// ```rust
+/// use flipper_ir_dumps::{examples::EXAMPLES, formats::esphome, protocol::Registry};
+///
+/// let yaml = esphome::buttons(&EXAMPLES[0].dump(), &Registry::default());
+/// assert!(yaml.contains("\n  - platform: template\n    name: \"On_h_16\"\n"));
+/// assert!(yaml.contains("\n      - remote_transmitter.transmit_raw:\n"));
+/// ```
+pub fn buttons(dump: &DumpFile, registry: &Registry) -> String {
+    let mut yaml = String::new();
+    yaml.push_str(PRELUDE);
+
+    for signal in dump.signals() {
+        writeln!(yaml, "  - platform: template").unwrap();
+        writeln!(yaml, "    name: {}", quoted(signal.name())).unwrap();
+        writeln!(yaml, "    on_press:").unwrap();
+
+        let decoded = registry.decode(signal.data(), signal.frequency().hz());
+        match decoded.as_ref().and_then(action) {
+            Some(action) => yaml.push_str(&action),
+            None => yaml.push_str(&transmit_raw(signal)),
+        }
+    }
+
+    yaml
+}
+
+const PRELUDE: &str = "\
+# Generated by flipper-ir-dumps.
+# Needs a remote_transmitter, such as:
+#
+# remote_transmitter:
+#   pin: GPIO4
+#   carrier_duty_percent: 50%
+button:
+";
+
+/// The ESPHome action sending a decoded frame, for the protocols ESPHome
+/// and this crate agree on.
+fn action(decoded: &Decoded) -> Option<String> {
+    let (name, fields): (&str, Vec<(&str, u32)>) = match decoded {
+        // ESPHome takes 16 bits of address and command, with their
+        // inverses in the high bytes, as transmitted
+        Decoded::Nec(nec) => (
+            "nec",
+            Vec::from([
+                ("address", nec.raw() & 0xFFFF),
+                ("command", nec.raw() >> 16),
+            ]),
+        ),
+        Decoded::NecExt(ext) => (
+            "nec",
+            Vec::from([
+                ("address", ext.raw() & 0xFFFF),
+                ("command", ext.raw() >> 16),
+            ]),
+        ),
+        Decoded::Rc5(rc5) => (
+            "rc5",
+            Vec::from([
+                ("address", rc5.address.into()),
+                ("command", rc5.command.into()),
+            ]),
+        ),
+        Decoded::Rc6(rc6) if rc6.mode == Rc6Mode::Mode0 => (
+            "rc6",
+            Vec::from([
+                ("address", rc6.address.into()),
+                ("command", rc6.command.into()),
+            ]),
+        ),
+        _ => return None,
+    };
+
+    let mut action = String::new();
+    writeln!(action, "      - remote_transmitter.transmit_{}:", name).unwrap();
+    for (field, value) in fields {
+        writeln!(action, "          {}: 0x{:02X}", field, value).unwrap();
+    }
+    Some(action)
+}
+
+/// The action sending the timings of a signal: positive durations for
+/// pulses, negative ones for pauses.
+fn transmit_raw(signal: &RawSignal) -> String {
+    let mut action = String::new();
+    writeln!(action, "      - remote_transmitter.transmit_raw:").unwrap();
+    writeln!(
+        action,
+        "          carrier_frequency: {}Hz",
+        signal.frequency().hz()
+    )
+    .unwrap();
+    writeln!(action, "          code: [").unwrap();
+
+    let durations: Vec<i64> = signal
+        .data()
+        .iter()
+        .zip([1, -1].into_iter().cycle())
+        .map(|(&duration, sign)| sign * i64::from(duration))
+        .collect();
+    for line in durations.chunks(DURATIONS_PER_LINE) {
+        let line: Vec<_> = line.iter().map(i64::to_string).collect();
+        writeln!(action, "            {},", line.join(", ")).unwrap();
+    }
+    writeln!(action, "          ]").unwrap();
+
+    action
+}
+
+/// A double quoted YAML string.
+fn quoted(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' | '\\' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::{
+        examples::EXAMPLES,
+        protocol::{nec, rc5},
+        signal::{DutyCycle, Polarity, TimingSequence},
+    };
+
+    fn signal(name: &str, data: Vec<u32>) -> RawSignal {
+        let timings = TimingSequence::new(Polarity::StartsWithPulse, data);
+        RawSignal::new(name, 38_000, DutyCycle::default(), timings).unwrap()
+    }
+
+    #[test]
+    fn test_raw() {
+        let yaml = buttons(&EXAMPLES[0].dump(), &Registry::default());
+        assert!(yaml.starts_with("# Generated by flipper-ir-dumps.\n"));
+        assert!(yaml.contains(
+            "          carrier_frequency: 38000Hz\n          code: [\n            \
+             662, -17715, 2993, -8929, 580, -410,"
+        ));
+        assert!(yaml.ends_with("          ]\n"));
+        assert_eq!(
+            yaml.matches("platform: template").count(),
+            EXAMPLES[0].dump().signals().len()
+        );
+    }
+
+    #[test]
+    fn test_protocols() {
+        let nec = nec::Nec {
+            address: 0x04,
+            inverted_address: !0x04,
+            command: 0x08,
+            repeats: 0,
+        };
+        let rc5 = rc5::Rc5 {
+            address: 0x05,
+            command: 0x35,
+            toggle: false,
+            repeats: 0,
+        };
+        let dump = DumpFile::new(
+            1,
+            vec![
+                signal("Power \"main\"", nec::encode(&nec)),
+                signal("Mute", rc5::encode(&rc5)),
+            ],
+        );
+
+        let yaml = buttons(&dump, &Registry::default());
+        assert!(yaml.contains(
+            "    name: \"Power \\\"main\\\"\"\n    on_press:\n      \
+             - remote_transmitter.transmit_nec:\n          \
+             address: 0xFB04\n          command: 0xF708\n"
+        ));
+        assert!(yaml.contains(
+            "      - remote_transmitter.transmit_rc5:\n          \
+             address: 0x05\n          command: 0x35\n"
+        ));
+        assert!(!yaml.contains("transmit_raw"));
+    }
+}
@@ -0,0 +1,877 @@
+//! [Girr](https://www.harctoolbox.org/Girr.html) XML, the remote format of
+//! IrScrutinizer and IrpTransmogrifier.
+//!
+//! A Girr command holds its timings as an intro sent once, a repeat sent
+//! while the button is held, and an ending sent on release, along with the
+//! protocol and parameters it was rendered from:
+//!
+//! ```xml
+//! <command name="Power" master="raw">
+//!   <parameters protocol="NEC1">
+//!     <parameter name="D" value="4"/>
+//!     <parameter name="F" value="8"/>
+//!   </parameters>
+//!   <raw frequency="38000">
+//!     <intro>+9024 -4512 +564 -564 ... +564 -39756</intro>
+//!     <repeat>+9024 -2256 +564 -96156</repeat>
+//!   </raw>
+//! </command>
+//! ```
+//!
+//! Only the first `raw` of each command is read; commands given only as
+//! parameters or Pronto hex are skipped.
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::Write;
+
+use displaydoc::Display;
+use thiserror::Error;
+
+use crate::{
+    dump::DumpFile,
+    protocol::{rc6::Rc6Mode, Decoded, Registry},
+    signal::{DutyCycle, Frequency, RawSignal, SignalType},
+};
+
+/// Pause ending exported intros, as Girr sequences end with a gap.
+const FINAL_GAP: u32 = 100_000;
+/// Frequency of raw codes without one, as assumed by IrScrutinizer.
+const DEFAULT_FREQUENCY: u32 = 38_000;
+
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+/// Error parsing Girr XML
+pub enum GirrError {
+    /// Malformed XML at byte {0}
+    Syntax(usize),
+    /// Element `{0}` is closed but not open
+    UnbalancedTag(String),
+    /// Invalid number `{0}`
+    InvalidNumber(String),
+    /// Not a Girr document, the root is `{0}`
+    NotGirr(String),
+    /// Command `{0}` has no pulses
+    Empty(String),
+}
+
+/// The protocol a command was rendered from, with IrpTransmogrifier names,
+/// such as `NEC1` with parameters `D` and `F`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct GirrProtocol {
+    pub name: String,
+    pub parameters: Vec<(String, u64)>,
+}
+
+impl GirrProtocol {
+    /// The protocol and parameters of a decoded frame, for the protocols
+    /// IrpTransmogrifier knows under the same definition.
+    pub fn of(decoded: &Decoded) -> Option<Self> {
+        let (name, parameters): (&str, Vec<(&str, u64)>) = match decoded {
+            Decoded::Nec(nec) => (
+                "NEC1",
+                Vec::from([("D", nec.address.into()), ("F", nec.command.into())]),
+            ),
+            Decoded::NecExt(ext) => (
+                "NEC1",
+                Vec::from([
+                    ("D", (ext.address & 0xFF).into()),
+                    ("S", (ext.address >> 8).into()),
+                    ("F", ext.command.into()),
+                ]),
+            ),
+            Decoded::Rc5(rc5) if !rc5.is_extended() => (
+                "RC5",
+                Vec::from([
+                    ("D", rc5.address.into()),
+                    ("F", rc5.command.into()),
+                    ("T", rc5.toggle.into()),
+                ]),
+            ),
+            Decoded::Rc6(rc6) if rc6.mode == Rc6Mode::Mode0 => (
+                "RC6",
+                Vec::from([
+                    ("D", rc6.address.into()),
+                    ("F", rc6.command.into()),
+                    ("T", rc6.toggle.into()),
+                ]),
+            ),
+            _ => return None,
+        };
+
+        Some(Self {
+            name: name.to_string(),
+            parameters: parameters
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect(),
+        })
+    }
+}
+
+/// A command of a Girr remote.
+#[derive(Debug, PartialEq, Clone)]
+pub struct GirrCommand {
+    pub name: String,
+    /// Free text notes about the command.
+    pub notes: Option<String>,
+    pub protocol: Option<GirrProtocol>,
+    pub frequency: Frequency,
+    pub duty_cycle: Option<DutyCycle>,
+    /// Alternating pulses and pauses in µs, pulse first, sent once.
+    pub intro: Vec<u32>,
+    /// Durations sent again for as long as the button is held.
+    pub repeat: Vec<u32>,
+    /// Durations sent once the button is released.
+    pub ending: Vec<u32>,
+}
+
+impl GirrCommand {
+    /// A command sending a signal, with its comments as notes and the
+    /// protocol it decodes as with `registry`, if Girr has a name for it.
+    pub fn from_signal(signal: &RawSignal, registry: &Registry) -> Self {
+        let mut intro = signal.data().to_vec();
+        if intro.len() & 1 == 1 {
+            intro.push(FINAL_GAP);
+        }
+        let notes: Vec<_> = signal
+            .comments()
+            .iter()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .collect();
+
+        Self {
+            name: signal.name().to_string(),
+            notes: (!notes.is_empty()).then(|| notes.join("\n")),
+            protocol: registry
+                .decode(signal.data(), signal.frequency().hz())
+                .and_then(|decoded| GirrProtocol::of(&decoded)),
+            frequency: signal.frequency(),
+            duty_cycle: Some(signal.duty_cycle()),
+            intro,
+            repeat: Vec::new(),
+            ending: Vec::new(),
+        }
+    }
+
+    /// Durations of the command held for `repeats` repeats: the intro, the
+    /// repeat that many times, and the ending.
+    ///
+    /// Commands without an intro send their repeat at least once.
+    pub fn durations(&self, repeats: usize) -> Vec<u32> {
+        let repeats = if self.intro.is_empty() {
+            repeats.max(1)
+        } else {
+            repeats
+        };
+
+        let mut durations = self.intro.clone();
+        for _ in 0..repeats {
+            durations.extend(&self.repeat);
+        }
+        durations.extend(&self.ending);
+        durations
+    }
+
+    /// A raw signal sending the command held for `repeats` repeats, with its
+    /// notes as comments.
+    pub fn to_signal(&self, repeats: usize) -> Result<RawSignal, GirrError> {
+        let mut data = self.durations(repeats);
+        // Flipper signals end with a pulse
+        if data.len() & 1 == 0 {
+            data.pop();
+        }
+        if data.is_empty() {
+            return Err(GirrError::Empty(self.name.clone()));
+        }
+
+        Ok(RawSignal {
+            name: self.name.clone(),
+            r#type: SignalType::Raw,
+            frequency: self.frequency,
+            duty_cycle: self.duty_cycle.unwrap_or_default(),
+            data,
+            comments: self
+                .notes
+                .iter()
+                .flat_map(|notes| notes.lines())
+                .map(|line| format!(" {}", line))
+                .collect(),
+            unknown_fields: Vec::new(),
+            source: Default::default(),
+            parsed: Default::default(),
+        })
+    }
+}
+
+/// A remote of a Girr document.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct GirrRemote {
+    pub name: String,
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+    /// Kind of device the remote controls, such as `TV`.
+    pub device_class: Option<String>,
+    pub notes: Option<String>,
+    pub commands: Vec<GirrCommand>,
+}
+
+impl GirrRemote {
+    /// A remote with a command for each signal of a dump, see
+    /// [`GirrCommand::from_signal`].
+    pub fn from_dump(name: impl Into<String>, dump: &DumpFile, registry: &Registry) -> Self {
+        Self {
+            name: name.into(),
+            commands: dump
+                .signals()
+                .iter()
+                .map(|signal| GirrCommand::from_signal(signal, registry))
+                .collect(),
+            ..Self::default()
+        }
+    }
+
+    /// A dump with a signal for each command, held for `repeats` repeats.
+    pub fn to_dump(&self, repeats: usize) -> Result<DumpFile, GirrError> {
+        let signals = self
+            .commands
+            .iter()
+            .map(|command| command.to_signal(repeats))
+            .collect::<Result<_, _>>()?;
+        Ok(DumpFile::new(1, signals))
+    }
+}
+
+/// A Girr document with `remotes`.
+///
+/// # Examples
+/// ```rust
+/// use flipper_ir_dumps::{
+///     examples::EXAMPLES,
+///     formats::girr::{self, GirrRemote},
+///     protocol::Registry,
+/// };
+///
+/// let dump = EXAMPLES[0].dump();
+/// let remote = GirrRemote::from_dump("aircon", &dump, &Registry::default());
+/// let xml = girr::to_xml(&[remote]);
+///
+/// let parsed = girr::parse(&xml).unwrap();
+/// assert_eq!(parsed[0].name, "aircon");
+/// let converted = parsed[0].to_dump(0).unwrap();
+/// assert_eq!(converted.signals()[0].data(), dump.signals()[0].data());
+/// ```
+pub fn to_xml(remotes: &[GirrRemote]) -> String {
+    let mut xml = String::new();
+    writeln!(xml, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>").unwrap();
+    writeln!(
+        xml,
+        "<remotes xmlns=\"http://www.harctoolbox.org/Girr\" girrVersion=\"1.2\" \
+         title=\"Generated by flipper-ir-dumps\">"
+    )
+    .unwrap();
+
+    for remote in remotes {
+        write!(xml, "  <remote name=\"{}\"", escape(&remote.name)).unwrap();
+        for (attribute, value) in [
+            ("manufacturer", &remote.manufacturer),
+            ("model", &remote.model),
+            ("deviceClass", &remote.device_class),
+        ] {
+            if let Some(value) = value {
+                write!(xml, " {}=\"{}\"", attribute, escape(value)).unwrap();
+            }
+        }
+        writeln!(xml, ">").unwrap();
+        if let Some(notes) = &remote.notes {
+            writeln!(xml, "    <notes>{}</notes>", escape(notes)).unwrap();
+        }
+
+        writeln!(xml, "    <commandSet name=\"{}\">", escape(&remote.name)).unwrap();
+        for command in &remote.commands {
+            write_command(&mut xml, command);
+        }
+        writeln!(xml, "    </commandSet>").unwrap();
+        writeln!(xml, "  </remote>").unwrap();
+    }
+
+    writeln!(xml, "</remotes>").unwrap();
+    xml
+}
+
+fn write_command(xml: &mut String, command: &GirrCommand) {
+    writeln!(
+        xml,
+        "      <command name=\"{}\" master=\"raw\">",
+        escape(&command.name)
+    )
+    .unwrap();
+    if let Some(notes) = &command.notes {
+        writeln!(xml, "        <notes>{}</notes>", escape(notes)).unwrap();
+    }
+    if let Some(protocol) = &command.protocol {
+        writeln!(
+            xml,
+            "        <parameters protocol=\"{}\">",
+            escape(&protocol.name)
+        )
+        .unwrap();
+        for (name, value) in &protocol.parameters {
+            writeln!(
+                xml,
+                "          <parameter name=\"{}\" value=\"{}\"/>",
+                escape(name),
+                value
+            )
+            .unwrap();
+        }
+        writeln!(xml, "        </parameters>").unwrap();
+    }
+
+    write!(xml, "        <raw frequency=\"{}\"", command.frequency.hz()).unwrap();
+    if let Some(duty_cycle) = command.duty_cycle {
+        write!(xml, " dutyCycle=\"{}\"", duty_cycle.get()).unwrap();
+    }
+    writeln!(xml, ">").unwrap();
+    for (section, durations) in [
+        ("intro", &command.intro),
+        ("repeat", &command.repeat),
+        ("ending", &command.ending),
+    ] {
+        if durations.is_empty() {
+            continue;
+        }
+        let durations: Vec<_> = durations
+            .iter()
+            .zip(['+', '-'].into_iter().cycle())
+            .map(|(duration, sign)| format!("{}{}", sign, duration))
+            .collect();
+        writeln!(
+            xml,
+            "          <{0}>{1}</{0}>",
+            section,
+            durations.join(" ")
+        )
+        .unwrap();
+    }
+    writeln!(xml, "        </raw>").unwrap();
+    writeln!(xml, "      </command>").unwrap();
+}
+
+/// Parse the remotes of a Girr document.
+///
+/// Documents whose root is a single `remote` or `command` are accepted
+/// too, as IrScrutinizer exports them.
+pub fn parse(xml: &str) -> Result<Vec<GirrRemote>, GirrError> {
+    let mut remotes: Vec<GirrRemote> = Vec::new();
+    let mut command: Option<GirrCommand> = None;
+    let mut in_raw = false;
+    // the open elements, by local name
+    let mut open: Vec<String> = Vec::new();
+
+    for token in tokenize(xml)? {
+        match token {
+            Token::Start {
+                name,
+                attributes,
+                empty,
+            } => {
+                let attribute = |key: &str| {
+                    attributes
+                        .iter()
+                        .find(|(name, _)| name == key)
+                        .map(|(_, value)| value.clone())
+                };
+
+                match name.as_str() {
+                    _ if open.is_empty()
+                        && !["remotes", "remote", "command"].contains(&name.as_str()) =>
+                    {
+                        return Err(GirrError::NotGirr(name));
+                    }
+                    "remote" => remotes.push(GirrRemote {
+                        name: attribute("name").unwrap_or_default(),
+                        manufacturer: attribute("manufacturer"),
+                        model: attribute("model"),
+                        device_class: attribute("deviceClass"),
+                        ..GirrRemote::default()
+                    }),
+                    "command" => {
+                        command = Some(GirrCommand {
+                            name: attribute("name").unwrap_or_default(),
+                            notes: attribute("comment"),
+                            protocol: None,
+                            frequency: Frequency::from_hz(DEFAULT_FREQUENCY),
+                            duty_cycle: None,
+                            intro: Vec::new(),
+                            repeat: Vec::new(),
+                            ending: Vec::new(),
+                        })
+                    }
+                    "parameters" => {
+                        if let Some(command) = &mut command {
+                            command.protocol = Some(GirrProtocol {
+                                name: attribute("protocol").unwrap_or_default(),
+                                parameters: Vec::new(),
+                            });
+                        }
+                    }
+                    "parameter" => {
+                        let protocol = command.as_mut().and_then(|c| c.protocol.as_mut());
+                        if let (Some(protocol), Some(name), Some(value)) =
+                            (protocol, attribute("name"), attribute("value"))
+                        {
+                            protocol.parameters.push((name, parse_number(&value)?));
+                        }
+                    }
+                    "raw" => {
+                        let first = command.as_ref().is_some_and(|c| {
+                            c.intro.is_empty() && c.repeat.is_empty() && c.ending.is_empty()
+                        });
+                        in_raw = first && !empty;
+                        if let (true, Some(command)) = (first, &mut command) {
+                            if let Some(frequency) = attribute("frequency") {
+                                let hz = parse_number(&frequency)?;
+                                command.frequency = Frequency::from_hz(hz as u32);
+                            }
+                            command.duty_cycle = attribute("dutyCycle")
+                                .and_then(|duty_cycle| duty_cycle.parse::<f32>().ok())
+                                .and_then(|duty_cycle| DutyCycle::new(duty_cycle).ok());
+                        }
+                    }
+                    _ => {}
+                }
+
+                if !empty {
+                    open.push(name);
+                } else if name == "command" {
+                    close_command(&mut remotes, &mut command);
+                }
+            }
+            Token::End(name) => {
+                if open.pop().as_deref() != Some(&name) {
+                    return Err(GirrError::UnbalancedTag(name));
+                }
+                match name.as_str() {
+                    "command" => close_command(&mut remotes, &mut command),
+                    "raw" => in_raw = false,
+                    _ => {}
+                }
+            }
+            Token::Text(text) => {
+                let (Some(element), parent) = (open.last(), open.iter().rev().nth(1)) else {
+                    continue;
+                };
+                match (element.as_str(), parent.map(String::as_str)) {
+                    ("notes", Some("command")) => {
+                        if let Some(command) = &mut command {
+                            command.notes = Some(text.trim().to_string());
+                        }
+                    }
+                    ("notes", Some("remote")) => {
+                        if let Some(remote) = remotes.last_mut() {
+                            remote.notes = Some(text.trim().to_string());
+                        }
+                    }
+                    (section, _) if in_raw => {
+                        // durations are either the text of the section, or
+                        // the flash and gap elements within it
+                        let section = match section {
+                            "flash" | "gap" => parent.map(String::as_str).unwrap_or_default(),
+                            section => section,
+                        };
+                        let Some(command) = &mut command else {
+                            continue;
+                        };
+                        let durations = match section {
+                            "intro" => &mut command.intro,
+                            "repeat" => &mut command.repeat,
+                            "ending" => &mut command.ending,
+                            _ => continue,
+                        };
+                        for duration in text.split(|c: char| c.is_whitespace() || c == ',') {
+                            let duration = duration.trim_start_matches(['+', '-']);
+                            if !duration.is_empty() {
+                                durations.push(parse_number(duration)? as u32);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    if let Some(name) = open.pop() {
+        return Err(GirrError::UnbalancedTag(name));
+    }
+    Ok(remotes)
+}
+
+/// Add the command being parsed to the last remote, if it has timings.
+fn close_command(remotes: &mut Vec<GirrRemote>, command: &mut Option<GirrCommand>) {
+    let Some(command) = command.take() else {
+        return;
+    };
+    if command.intro.is_empty() && command.repeat.is_empty() && command.ending.is_empty() {
+        return;
+    }
+    if remotes.is_empty() {
+        remotes.push(GirrRemote::default());
+    }
+    if let Some(remote) = remotes.last_mut() {
+        remote.commands.push(command);
+    }
+}
+
+fn parse_number(value: &str) -> Result<u64, GirrError> {
+    let value = value.trim();
+    let parsed = match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+    parsed.map_err(|_| GirrError::InvalidNumber(value.to_string()))
+}
+
+/// The parts of an XML document Girr needs.
+#[derive(Debug, PartialEq)]
+enum Token {
+    /// An opening tag, by local name, or an empty element if `empty`.
+    Start {
+        name: String,
+        attributes: Vec<(String, String)>,
+        empty: bool,
+    },
+    End(String),
+    Text(String),
+}
+
+/// Split an XML document into tags and text, skipping the declaration,
+/// comments, doctypes and processing instructions. Namespace prefixes are
+/// dropped.
+fn tokenize(xml: &str) -> Result<Vec<Token>, GirrError> {
+    let mut tokens = Vec::new();
+    let mut rest = xml;
+
+    while !rest.is_empty() {
+        let position = xml.len() - rest.len();
+        let syntax = || GirrError::Syntax(position);
+
+        let Some(tag) = rest.strip_prefix('<') else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            let text = &rest[..end];
+            if !text.trim().is_empty() {
+                tokens.push(Token::Text(unescape(text)));
+            }
+            rest = &rest[end..];
+            continue;
+        };
+
+        if let Some(comment) = tag.strip_prefix("!--") {
+            let end = comment.find("-->").ok_or_else(syntax)?;
+            rest = &comment[end + 3..];
+        } else if let Some(data) = tag.strip_prefix("![CDATA[") {
+            let end = data.find("]]>").ok_or_else(syntax)?;
+            tokens.push(Token::Text(data[..end].to_string()));
+            rest = &data[end + 3..];
+        } else if tag.starts_with(['?', '!']) {
+            let end = tag.find('>').ok_or_else(syntax)?;
+            rest = &tag[end + 1..];
+        } else if let Some(name) = tag.strip_prefix('/') {
+            let end = name.find('>').ok_or_else(syntax)?;
+            tokens.push(Token::End(local_name(name[..end].trim()).to_string()));
+            rest = &name[end + 1..];
+        } else {
+            let end = tag_end(tag).ok_or_else(syntax)?;
+            let (inner, empty) = match tag[..end].strip_suffix('/') {
+                Some(inner) => (inner, true),
+                None => (&tag[..end], false),
+            };
+            let (name, attributes) = inner.split_once(char::is_whitespace).unwrap_or((inner, ""));
+            if name.is_empty() {
+                return Err(syntax());
+            }
+            tokens.push(Token::Start {
+                name: local_name(name).to_string(),
+                attributes: parse_attributes(attributes).ok_or_else(syntax)?,
+                empty,
+            });
+            rest = &tag[end + 1..];
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Index of the `>` ending a tag, skipping those in quoted attributes.
+fn tag_end(tag: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in tag.char_indices() {
+        match (c, quote) {
+            ('"' | '\'', None) => quote = Some(c),
+            (c, Some(open)) if c == open => quote = None,
+            ('>', None) => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn parse_attributes(mut text: &str) -> Option<Vec<(String, String)>> {
+    let mut attributes = Vec::new();
+    loop {
+        text = text.trim_start();
+        if text.is_empty() {
+            return Some(attributes);
+        }
+        let (name, value) = text.split_once('=')?;
+        let value = value.trim_start();
+        let quote = value.chars().next().filter(|c| ['"', '\''].contains(c))?;
+        let end = value[1..].find(quote)? + 1;
+        attributes.push((
+            local_name(name.trim()).to_string(),
+            unescape(&value[1..end]),
+        ));
+        text = &value[end + 1..];
+    }
+}
+
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+/// Replace XML entities and character references.
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        let entity = &rest[1..end];
+        let c = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        match c {
+            Some(c) => {
+                unescaped.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+/// Escape text for attributes and elements.
+pub(super) fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use indoc::indoc;
+
+    use super::*;
+    use crate::{examples::EXAMPLES, protocol::nec};
+
+    /// As exported by IrScrutinizer, trimmed down.
+    const GIRR: &str = indoc! {r#"
+        <?xml version="1.0" encoding="UTF-8" standalone="no"?>
+        <!--This file is in the Girr format.-->
+        <girr:remotes xmlns:girr="http://www.harctoolbox.org/Girr" girrVersion="1.0" title="IrScrutinizer export">
+            <girr:remote name="Yamaha &amp; co" manufacturer="Yamaha" model="RAV" deviceClass="receiver">
+                <girr:notes>Living room</girr:notes>
+                <girr:commandSet name="commandSet">
+                    <girr:command name="power_on" master="parameters" comment="from the manual">
+                        <girr:parameters protocol="NEC1">
+                            <girr:parameter name="D" value="122"/>
+                            <girr:parameter name="F" value="0x1D"/>
+                        </girr:parameters>
+                        <girr:raw frequency="38400" dutyCycle="0.4">
+                            <girr:intro>+9024 -4512 +564 -564 +564 -39756</girr:intro>
+                            <girr:repeat>+9024 -2256 +564 -96156</girr:repeat>
+                        </girr:raw>
+                        <girr:raw frequency="36000">
+                            <girr:intro>+1 -1</girr:intro>
+                        </girr:raw>
+                    </girr:command>
+                    <girr:command name="mute">
+                        <girr:raw>
+                            <girr:intro>
+                                <girr:flash>500</girr:flash>
+                                <girr:gap>1000</girr:gap>
+                                <girr:flash>500</girr:flash>
+                            </girr:intro>
+                        </girr:raw>
+                    </girr:command>
+                    <girr:command name="only_parameters">
+                        <girr:parameters protocol="RC5">
+                            <girr:parameter name="D" value="0"/>
+                        </girr:parameters>
+                    </girr:command>
+                </girr:commandSet>
+            </girr:remote>
+        </girr:remotes>
+    "#};
+
+    #[test]
+    fn test_parse() {
+        let remotes = parse(GIRR).unwrap();
+        assert_eq!(remotes.len(), 1);
+        let remote = &remotes[0];
+        assert_eq!(remote.name, "Yamaha & co");
+        assert_eq!(remote.manufacturer.as_deref(), Some("Yamaha"));
+        assert_eq!(remote.device_class.as_deref(), Some("receiver"));
+        assert_eq!(remote.notes.as_deref(), Some("Living room"));
+        assert_eq!(remote.commands.len(), 2);
+
+        let power = &remote.commands[0];
+        assert_eq!(power.notes.as_deref(), Some("from the manual"));
+        assert_eq!(
+            power.protocol,
+            Some(GirrProtocol {
+                name: "NEC1".to_string(),
+                parameters: vec![("D".to_string(), 122), ("F".to_string(), 0x1D)],
+            })
+        );
+        assert_eq!(power.frequency.hz(), 38_400);
+        assert_eq!(power.duty_cycle, Some(DutyCycle::new(0.4).unwrap()));
+        assert_eq!(power.intro, [9024, 4512, 564, 564, 564, 39756]);
+        assert_eq!(power.repeat, [9024, 2256, 564, 96156]);
+
+        let signal = power.to_signal(2).unwrap();
+        assert_eq!(
+            signal.data(),
+            [9024, 4512, 564, 564, 564, 39756, 9024, 2256, 564, 96156, 9024, 2256, 564]
+        );
+        assert_eq!(signal.comments(), [" from the manual"]);
+
+        let mute = remote.commands[1].to_signal(0).unwrap();
+        assert_eq!(mute.frequency().hz(), 38_000);
+        assert_eq!(mute.data(), [500, 1000, 500]);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let registry = Registry::default();
+        let nec = nec::Nec {
+            address: 0x04,
+            inverted_address: !0x04,
+            command: 0x08,
+            repeats: 0,
+        };
+        let mut power = RawSignal::new(
+            "Power <main>",
+            38_000,
+            DutyCycle::default(),
+            crate::signal::TimingSequence::new(
+                crate::signal::Polarity::StartsWithPulse,
+                nec::encode(&nec),
+            ),
+        )
+        .unwrap();
+        power.comments.push(" a comment".to_string());
+        let mut signals = EXAMPLES[0].dump().signals().to_vec();
+        signals.push(power);
+        let dump = DumpFile::new(1, signals);
+
+        let remote = GirrRemote {
+            manufacturer: Some("Samsung".to_string()),
+            ..GirrRemote::from_dump("aircon", &dump, &registry)
+        };
+        let xml = to_xml(core::slice::from_ref(&remote));
+        assert!(xml.contains(
+            "      <command name=\"Power &lt;main&gt;\" master=\"raw\">\n        \
+             <notes>a comment</notes>\n        <parameters protocol=\"NEC1\">\n          \
+             <parameter name=\"D\" value=\"4\"/>\n"
+        ));
+
+        let parsed = parse(&xml).unwrap();
+        assert_eq!(parsed, [remote]);
+        let converted = parsed[0].to_dump(0).unwrap();
+        for (converted, signal) in converted.signals().iter().zip(dump.signals()) {
+            assert_eq!(converted.name(), signal.name());
+            assert_eq!(converted.data(), signal.data());
+        }
+        assert_eq!(
+            converted.signals().last().unwrap().comments(),
+            [" a comment"]
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            parse("<html><body/></html>"),
+            Err(GirrError::NotGirr("html".to_string()))
+        );
+        assert_eq!(
+            parse("<remotes><remote></remotes>"),
+            Err(GirrError::UnbalancedTag("remotes".to_string()))
+        );
+        assert_eq!(parse("<remotes"), Err(GirrError::Syntax(0)));
+        assert_eq!(
+            parse("<command name='x'><raw><intro>+1 -x</intro></raw></command>"),
+            Err(GirrError::InvalidNumber("x".to_string()))
+        );
+        assert_eq!(
+            GirrCommand {
+                name: "empty".to_string(),
+                notes: None,
+                protocol: None,
+                frequency: Frequency::from_hz(38_000),
+                duty_cycle: None,
+                intro: vec![],
+                repeat: vec![],
+                ending: vec![],
+            }
+            .to_signal(0)
+            .unwrap_err(),
+            GirrError::Empty("empty".to_string())
+        );
+    }
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(
+            unescape("a &lt;b&gt; &#65;&#x42; &unknown; &"),
+            "a <b> AB &unknown; &"
+        );
+        assert_eq!(escape("\"a\" & <b>"), "&quot;a&quot; &amp; &lt;b&gt;");
+    }
+}
@@ -0,0 +1,314 @@
+//! Global Caché `sendir` commands, the lingua franca of commercial AV
+//! control systems.
+//!
+//! A command names the output connector, an ID, the carrier frequency, how
+//! many times to send the code and where its repeated part starts, followed
+//! by alternating pulses and pauses counted in carrier periods:
+//!
+//! ```text
+//! sendir,1:1,1,38000,1,1,342,171,21,21,21,64,...
+//! ```
+//!
+//! Compressed commands, where letters stand for pulse and pause pairs seen
+//! before, are understood too.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+
+use displaydoc::Display;
+use thiserror::Error;
+
+use crate::signal::{DutyCycle, Frequency, Polarity, RawSignal, TimingSequence};
+
+/// Letters of compressed commands, each standing for one of the first
+/// distinct pulse and pause pairs.
+const PAIR_LETTERS: &str = "ABCDEFGHIJKLMNO";
+
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+/// Error parsing a Global Caché command
+pub enum GlobalCacheError {
+    /// Not a sendir command
+    NotSendir,
+    /// Command is missing its {0}
+    MissingField(&'static str),
+    /// Invalid number `{0}`
+    InvalidNumber(String),
+    /// Letter {0} doesn't stand for any pair yet
+    UnknownPair(char),
+    /// Repeats start at {0}, which is not a pulse of the code
+    InvalidOffset(u32),
+    /// Code has no pulses
+    Empty,
+}
+
+/// A `sendir` command.
+///
+/// # Examples
+/// ```rust
+/// use flipper_ir_dumps::formats::global_cache::SendIr;
+///
+/// let command: SendIr = "sendir,1:1,1,40000,1,1,96,24,48,24,24,1000".parse().unwrap();
+/// let signal = command.to_signal("power").unwrap();
+/// assert_eq!(signal.frequency().hz(), 40_000);
+/// assert_eq!(signal.data(), [2400, 600, 1200, 600, 600]);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SendIr {
+    /// Module and port of the output, such as `1:1`.
+    pub connector: String,
+    /// ID echoed back by the device once the command is done.
+    pub id: u32,
+    pub frequency: Frequency,
+    /// Number of times the code is sent, at least 1.
+    pub repeat: u32,
+    /// Index of the pulse, counting from 1, where the part sent again on
+    /// repeats starts.
+    pub offset: u32,
+    /// Alternating pulses and pauses in carrier periods, pulse first.
+    pub periods: Vec<u32>,
+}
+
+impl SendIr {
+    /// A command sending a signal once, on the first output.
+    pub fn from_signal(signal: &RawSignal) -> Self {
+        let hz = u64::from(signal.frequency().hz());
+        let periods = signal
+            .data()
+            .iter()
+            .map(|&duration| ((u64::from(duration) * hz + 500_000) / 1_000_000).max(1) as u32)
+            .collect();
+
+        Self {
+            connector: "1:1".to_string(),
+            id: 1,
+            frequency: signal.frequency(),
+            repeat: 1,
+            offset: 1,
+            periods,
+        }
+    }
+
+    /// Durations of the code in µs, pulse first, with the repeated part
+    /// appended as many times as the code is repeated.
+    pub fn durations(&self) -> Vec<u32> {
+        let hz = u64::from(self.frequency.hz().max(1));
+        let durations: Vec<u32> = self
+            .periods
+            .iter()
+            .map(|&periods| ((u64::from(periods) * 1_000_000 + hz / 2) / hz) as u32)
+            .collect();
+
+        let start = (self.offset.max(1) - 1) as usize;
+        let repeated = durations.get(start..).unwrap_or_default();
+        let mut all = durations.clone();
+        for _ in 1..self.repeat {
+            all.extend(repeated);
+        }
+        all
+    }
+
+    /// A raw signal sending the code as many times as the command does.
+    pub fn to_signal(&self, name: impl Into<String>) -> Result<RawSignal, GlobalCacheError> {
+        let timings = TimingSequence::new(Polarity::StartsWithPulse, self.durations());
+        RawSignal::new(name, self.frequency, DutyCycle::default(), timings)
+            .map_err(|_| GlobalCacheError::Empty)
+    }
+}
+
+impl core::str::FromStr for SendIr {
+    type Err = GlobalCacheError;
+
+    fn from_str(command: &str) -> Result<Self, Self::Err> {
+        let mut fields = command.trim().splitn(7, ',');
+        if fields.next().map(str::trim) != Some("sendir") {
+            return Err(GlobalCacheError::NotSendir);
+        }
+        let mut field = |name| {
+            fields
+                .next()
+                .map(str::trim)
+                .filter(|field| !field.is_empty())
+                .ok_or(GlobalCacheError::MissingField(name))
+        };
+
+        let connector = field("connector")?.to_string();
+        let id = parse_number(field("ID")?)?;
+        let frequency = Frequency::from_hz(parse_number(field("frequency")?)?);
+        let repeat = parse_number(field("repeat count")?)?;
+        let offset = parse_number(field("offset")?)?;
+        let periods = parse_periods(field("timings")?)?;
+
+        if offset == 0 || offset & 1 == 0 || offset as usize > periods.len() {
+            return Err(GlobalCacheError::InvalidOffset(offset));
+        }
+
+        Ok(Self {
+            connector,
+            id,
+            frequency,
+            repeat,
+            offset,
+            periods,
+        })
+    }
+}
+
+impl fmt::Display for SendIr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "sendir,{},{},{},{},{}",
+            self.connector,
+            self.id,
+            self.frequency.hz(),
+            self.repeat,
+            self.offset
+        )?;
+        for periods in &self.periods {
+            write!(f, ",{}", periods)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse timings, expanding the letters of compressed commands.
+fn parse_periods(timings: &str) -> Result<Vec<u32>, GlobalCacheError> {
+    let mut periods = Vec::new();
+    let mut pairs: Vec<(u32, u32)> = Vec::new();
+    let mut number = String::new();
+
+    let end_number = |number: &mut String, periods: &mut Vec<u32>, pairs: &mut Vec<_>| {
+        let value = number.trim();
+        if !value.is_empty() {
+            periods.push(parse_number(value)?);
+            // a pair given in full gets the next free letter
+            if let [.., pulse, pause] = periods[..] {
+                if periods.len() & 1 == 0
+                    && pairs.len() < PAIR_LETTERS.len()
+                    && !pairs.contains(&(pulse, pause))
+                {
+                    pairs.push((pulse, pause));
+                }
+            }
+        }
+        number.clear();
+        Ok::<_, GlobalCacheError>(())
+    };
+
+    for c in timings.chars() {
+        match c {
+            ',' => end_number(&mut number, &mut periods, &mut pairs)?,
+            'A'..='O' => {
+                end_number(&mut number, &mut periods, &mut pairs)?;
+                let index = PAIR_LETTERS.find(c).unwrap_or_default();
+                let &(pulse, pause) = pairs.get(index).ok_or(GlobalCacheError::UnknownPair(c))?;
+                periods.extend([pulse, pause]);
+            }
+            c => number.push(c),
+        }
+    }
+    end_number(&mut number, &mut periods, &mut pairs)?;
+
+    Ok(periods)
+}
+
+fn parse_number(value: &str) -> Result<u32, GlobalCacheError> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| GlobalCacheError::InvalidNumber(value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::{examples::EXAMPLES, protocol};
+
+    /// NEC address 0x04 command 0x08, as found in the Global Caché database.
+    const NEC: &str = "sendir,1:1,1,38000,1,69,342,171,21,21,21,21,21,64,21,21,21,21,21,21,\
+        21,21,21,21,21,64,21,64,21,21,21,64,21,64,21,64,21,64,21,64,21,21,21,21,21,21,21,64,\
+        21,21,21,21,21,21,21,21,21,64,21,64,21,64,21,21,21,64,21,64,21,64,21,64,21,1517,\
+        342,85,21,3655";
+
+    #[test]
+    fn test_parse() {
+        let command: SendIr = NEC.parse().unwrap();
+        assert_eq!(command.connector, "1:1");
+        assert_eq!(command.frequency.hz(), 38_000);
+        assert_eq!((command.repeat, command.offset), (1, 69));
+        assert_eq!(command.periods.len(), 72);
+        assert_eq!(command.to_string(), NEC);
+
+        let signal = command.to_signal("power").unwrap();
+        assert_eq!(signal.data()[..3], [9000, 4500, 553]);
+        match protocol::decode(signal.data(), 38_000) {
+            Some(protocol::Decoded::Nec(nec)) => {
+                assert_eq!((nec.address, nec.command), (0x04, 0x08))
+            }
+            decoded => panic!("decoded as {decoded:?}"),
+        }
+    }
+
+    #[test]
+    fn test_repeats() {
+        let command: SendIr = "sendir,1:1,1,40000,3,3,96,24,48,24,24,1000"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            command.durations(),
+            vec![2400, 600, 1200, 600, 600, 25_000, 1200, 600, 600, 25_000, 1200, 600, 600, 25_000]
+        );
+    }
+
+    #[test]
+    fn test_compressed() {
+        let compressed: SendIr = "sendir,1:1,1,38000,1,1,342,171,21,21,21,64BBAB,21,1517"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            compressed.periods,
+            vec![342, 171, 21, 21, 21, 64, 21, 21, 21, 21, 342, 171, 21, 21, 21, 1517]
+        );
+        assert_eq!(
+            "sendir,1:1,1,38000,1,1,10,10,C".parse::<SendIr>(),
+            Err(GlobalCacheError::UnknownPair('C'))
+        );
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        for signal in EXAMPLES[0].dump().signals() {
+            let command = SendIr::from_signal(signal);
+            let parsed: SendIr = command.to_string().parse().unwrap();
+            assert_eq!(parsed, command);
+
+            let converted = parsed.to_signal(signal.name()).unwrap();
+            assert!(converted.is_duplicate_of(signal, 0.05), "{}", signal.name());
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            "sendirx,1:1".parse::<SendIr>(),
+            Err(GlobalCacheError::NotSendir)
+        );
+        assert_eq!(
+            "sendir,1:1,1,38000".parse::<SendIr>(),
+            Err(GlobalCacheError::MissingField("repeat count"))
+        );
+        assert_eq!(
+            "sendir,1:1,1,38k,1,1,10,10".parse::<SendIr>(),
+            Err(GlobalCacheError::InvalidNumber("38k".to_string()))
+        );
+        assert_eq!(
+            "sendir,1:1,1,38000,1,2,10,10".parse::<SendIr>(),
+            Err(GlobalCacheError::InvalidOffset(2))
+        );
+    }
+}
@@ -0,0 +1,356 @@
+//! A JSON document model of dumps, for tools in other languages and on
+//! the web to read and write signals without parsing the `.ir` format:
+//!
+//! ```json
+//! {
+//!   "format": "flipper-ir-dumps",
+//!   "version": 1,
+//!   "filetype_version": 1,
+//!   "signals": [
+//!     {
+//!       "name": "Power",
+//!       "frequency": 38000,
+//!       "duty_cycle": 0.33,
+//!       "comments": [],
+//!       "timings": [9024, 4512, 564],
+//!       "frames": [
+//!         {"protocol": "NEC", "address": 4, "command": 8, "extra": {}, "valid": true}
+//!       ]
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! `version` is the version of this model, bumped on incompatible changes,
+//! and `filetype_version` the one of the dump. Timings are in µs and start
+//! with a pulse. `frames` are the frames of the packets of the signal as
+//! decoded by [`RawSignal::parsed`]: they're informational, ignored by
+//! [`from_json`]. [`SCHEMA`] describes documents as a JSON Schema.
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::Write;
+
+use displaydoc::Display;
+use thiserror::Error;
+
+use crate::{
+    dump::DumpFile,
+    signal::{DutyCycle, DutyCycleError, Frequency, RawSignal, SignalType},
+};
+
+mod value;
+
+pub(crate) use value::quote;
+use value::Value;
+
+/// Value of the `format` member, telling documents apart from other JSON.
+pub const FORMAT: &str = "flipper-ir-dumps";
+
+/// Version of the document model written, and the newest one read.
+pub const VERSION: u32 = 1;
+
+/// A JSON Schema (draft 2020-12) of documents.
+pub const SCHEMA: &str = r##"{
+  "$schema": "https://json-schema.org/draft/2020-12/schema",
+  "title": "Flipper IR dump",
+  "type": "object",
+  "required": ["format", "version", "filetype_version", "signals"],
+  "properties": {
+    "format": { "const": "flipper-ir-dumps" },
+    "version": { "const": 1 },
+    "filetype_version": { "type": "integer", "minimum": 0 },
+    "signals": {
+      "type": "array",
+      "items": {
+        "type": "object",
+        "required": ["name", "frequency", "duty_cycle", "timings"],
+        "properties": {
+          "name": { "type": "string" },
+          "frequency": { "type": "integer", "minimum": 0, "description": "Carrier frequency in Hz" },
+          "duty_cycle": { "type": "number", "exclusiveMinimum": 0, "maximum": 1 },
+          "comments": { "type": "array", "items": { "type": "string" } },
+          "timings": {
+            "type": "array",
+            "items": { "type": "integer", "minimum": 0 },
+            "description": "Alternating pulse and pause durations in µs, starting with a pulse"
+          },
+          "frames": {
+            "type": "array",
+            "items": {
+              "type": "object",
+              "required": ["protocol", "valid"],
+              "properties": {
+                "protocol": { "type": "string" },
+                "address": { "type": ["integer", "null"] },
+                "command": { "type": ["integer", "null"] },
+                "extra": { "type": "object", "additionalProperties": { "type": "integer" } },
+                "valid": { "type": "boolean" }
+              }
+            }
+          }
+        }
+      }
+    }
+  }
+}
+"##;
+
+#[derive(Debug, Display, Error, PartialEq)]
+/// Error reading a JSON dump
+pub enum JsonError {
+    /// Invalid JSON at byte {0}
+    Syntax(usize),
+    /// Not a flipper-ir-dumps document
+    NotDump,
+    /// Document version {0} is newer than supported version 1
+    UnsupportedVersion(u32),
+    /// Missing or invalid member `{0}`
+    InvalidMember(String),
+    /// {0}
+    DutyCycle(#[from] DutyCycleError),
+}
+
+/// The document of `dump`.
+///
+/// # Examples
+/// ```rust
+/// use flipper_ir_dumps::{examples::EXAMPLES, formats::json};
+///
+/// let document = json::to_json(&EXAMPLES[0].dump());
+/// assert!(document.contains("\"name\": \"On_h_16\",\n"));
+/// assert!(document.contains("\"timings\": [662, 17715, 2993, 8929, "));
+/// ```
+pub fn to_json(dump: &DumpFile) -> String {
+    let mut json = String::from("{\n");
+    writeln!(json, "  \"format\": {},", quote(FORMAT)).unwrap();
+    writeln!(json, "  \"version\": {},", VERSION).unwrap();
+    writeln!(json, "  \"filetype_version\": {},", dump.version()).unwrap();
+    json.push_str("  \"signals\": [");
+
+    for (i, signal) in dump.signals().iter().enumerate() {
+        json.push_str(if i == 0 { "\n" } else { ",\n" });
+        json.push_str("    {\n");
+        writeln!(json, "      \"name\": {},", quote(signal.name())).unwrap();
+        writeln!(json, "      \"frequency\": {},", signal.frequency().hz()).unwrap();
+        writeln!(json, "      \"duty_cycle\": {},", signal.duty_cycle().get()).unwrap();
+        let comments: Vec<_> = signal.comments().iter().map(|c| quote(c)).collect();
+        writeln!(json, "      \"comments\": [{}],", comments.join(", ")).unwrap();
+        let timings: Vec<_> = signal.data().iter().map(u32::to_string).collect();
+        writeln!(json, "      \"timings\": [{}],", timings.join(", ")).unwrap();
+
+        let frames: Vec<_> = signal
+            .parsed()
+            .map(|parsed| {
+                parsed
+                    .packets()
+                    .iter()
+                    .filter_map(|packet| packet.frame())
+                    .map(|frame| {
+                        let number = |field: Option<u32>| {
+                            field.map_or_else(|| "null".to_string(), |field| field.to_string())
+                        };
+                        let extra: Vec<_> = frame
+                            .extra
+                            .iter()
+                            .map(|(name, value)| format!("{}: {}", quote(name), value))
+                            .collect();
+                        format!(
+                            "        {{\"protocol\": {}, \"address\": {}, \"command\": {}, \"extra\": {{{}}}, \"valid\": {}}}",
+                            quote(&frame.protocol),
+                            number(frame.address),
+                            number(frame.command),
+                            extra.join(", "),
+                            frame.is_valid(),
+                        )
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        if frames.is_empty() {
+            json.push_str("      \"frames\": []\n");
+        } else {
+            writeln!(json, "      \"frames\": [\n{}\n      ]", frames.join(",\n")).unwrap();
+        }
+        json.push_str("    }");
+    }
+
+    json.push_str(if dump.signals().is_empty() {
+        "]\n}\n"
+    } else {
+        "\n  ]\n}\n"
+    });
+    json
+}
+
+/// Read a dump from its document.
+///
+/// Members other than those of the model are ignored, so that newer
+/// writers may add some without bumping the version.
+///
+/// # Examples
+/// ```rust
+/// use flipper_ir_dumps::{examples::EXAMPLES, formats::json};
+///
+/// let dump = EXAMPLES[0].dump();
+/// let document = json::to_json(&dump);
+/// assert_eq!(json::from_json(&document).unwrap(), dump);
+/// ```
+pub fn from_json(document: &str) -> Result<DumpFile, JsonError> {
+    let root = value::parse(document).map_err(JsonError::Syntax)?;
+    if root.get("format").and_then(Value::as_str) != Some(FORMAT) {
+        return Err(JsonError::NotDump);
+    }
+    let version = member(&root, "version", "version", Value::as_u32)?;
+    if version > VERSION {
+        return Err(JsonError::UnsupportedVersion(version));
+    }
+    let filetype_version = member(&root, "filetype_version", "filetype_version", Value::as_u32)?;
+
+    let signals = member(&root, "signals", "signals", Value::as_array)?
+        .iter()
+        .enumerate()
+        .map(|(i, signal)| {
+            let path = |key: &str| format!("signals[{}].{}", i, key);
+            let name = member(signal, "name", &path("name"), Value::as_str)?;
+            let frequency = member(signal, "frequency", &path("frequency"), Value::as_u32)?;
+            let duty_cycle = member(signal, "duty_cycle", &path("duty_cycle"), Value::as_f64)?;
+            let comments = match signal.get("comments") {
+                None => Vec::new(),
+                Some(comments) => list(comments, |comment| comment.as_str().map(String::from))
+                    .ok_or_else(|| JsonError::InvalidMember(path("comments")))?,
+            };
+            let timings = member(signal, "timings", &path("timings"), |timings| {
+                list(timings, Value::as_u32)
+            })?;
+
+            Ok(RawSignal {
+                name: name.into(),
+                r#type: SignalType::Raw,
+                frequency: Frequency::from_hz(frequency),
+                duty_cycle: DutyCycle::new(duty_cycle as f32)?,
+                data: timings,
+                comments,
+                unknown_fields: Vec::new(),
+                source: Default::default(),
+                parsed: Default::default(),
+            })
+        })
+        .collect::<Result<Vec<_>, JsonError>>()?;
+
+    Ok(DumpFile::new(filetype_version, signals))
+}
+
+/// The member `key` of `object` as read by `read`, or an error naming it
+/// by `path`.
+fn member<'a, T>(
+    object: &'a Value,
+    key: &str,
+    path: &str,
+    read: impl FnOnce(&'a Value) -> Option<T>,
+) -> Result<T, JsonError> {
+    object
+        .get(key)
+        .and_then(read)
+        .ok_or_else(|| JsonError::InvalidMember(path.into()))
+}
+
+/// The items of an array, if all of them can be read by `read`.
+fn list<T>(array: &Value, read: impl FnMut(&Value) -> Option<T>) -> Option<Vec<T>> {
+    array.as_array()?.iter().map(read).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::signal::{Polarity, TimingSequence};
+
+    fn nec() -> RawSignal {
+        // NEC address 0x04, command 0x08
+        let mut data = vec![9024, 4512];
+        for byte in [0x04u8, !0x04, 0x08, !0x08] {
+            for bit in 0..8 {
+                data.push(564);
+                data.push(if byte >> bit & 1 == 1 { 1692 } else { 564 });
+            }
+        }
+        data.push(564);
+        let timings = TimingSequence::new(Polarity::StartsWithPulse, data);
+        let mut signal =
+            RawSignal::new("Power \"on\"", 38_000, DutyCycle::default(), timings).unwrap();
+        signal.comments.push(" TV".into());
+        signal
+    }
+
+    #[test]
+    fn test_to_json() {
+        let dump = DumpFile::new(1, vec![nec()]);
+        let document = to_json(&dump);
+
+        assert!(document.starts_with(
+            "{\n  \"format\": \"flipper-ir-dumps\",\n  \"version\": 1,\n  \"filetype_version\": 1,\n  \"signals\": [\n    {\n      \"name\": \"Power \\\"on\\\"\",\n      \"frequency\": 38000,\n      \"duty_cycle\": 0.33,\n      \"comments\": [\" TV\"],\n      \"timings\": [9024, 4512, 564, 564, "
+        ));
+        assert!(document.ends_with(
+            "      \"frames\": [\n        {\"protocol\": \"NEC\", \"address\": 4, \"command\": 8, \"extra\": {\"inverted_address\": 251}, \"valid\": true}\n      ]\n    }\n  ]\n}\n"
+        ));
+        assert_eq!(
+            to_json(&DumpFile::new(1, vec![])),
+            "{\n  \"format\": \"flipper-ir-dumps\",\n  \"version\": 1,\n  \"filetype_version\": 1,\n  \"signals\": []\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let dump = DumpFile::new(1, vec![nec()]);
+        let read = from_json(&to_json(&dump)).unwrap();
+        assert_eq!(read, dump);
+        assert_eq!(read.signals()[0].comments(), [" TV"]);
+    }
+
+    #[test]
+    fn test_schema() {
+        let schema = value::parse(SCHEMA).unwrap();
+        assert_eq!(
+            schema
+                .get("properties")
+                .and_then(|properties| properties.get("format"))
+                .and_then(|format| format.get("const"))
+                .and_then(Value::as_str),
+            Some(FORMAT)
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let document = |body: &str| {
+            format!(
+                "{{\"format\": \"flipper-ir-dumps\", \"version\": 1, \"filetype_version\": 1, {}}}",
+                body
+            )
+        };
+
+        assert_eq!(from_json("{"), Err(JsonError::Syntax(1)));
+        assert_eq!(from_json("{\"signals\": []}"), Err(JsonError::NotDump));
+        assert_eq!(
+            from_json(&document("\"signals\": []").replace("\"version\": 1", "\"version\": 2")),
+            Err(JsonError::UnsupportedVersion(2))
+        );
+        assert_eq!(
+            from_json(&document("\"signals\": [{\"name\": \"a\", \"frequency\": 38000, \"duty_cycle\": 0.33, \"timings\": [1, -2]}]")),
+            Err(JsonError::InvalidMember("signals[0].timings".into()))
+        );
+        assert_eq!(
+            from_json(&document("\"signals\": [{\"name\": \"a\", \"frequency\": 38000, \"duty_cycle\": 2, \"timings\": [1]}]")),
+            Err(JsonError::DutyCycle(DutyCycleError(2.0)))
+        );
+        assert_eq!(
+            from_json(&document("\"signal\": []")),
+            Err(JsonError::InvalidMember("signals".into()))
+        );
+    }
+}
@@ -0,0 +1,320 @@
+//! Just enough of JSON to read documents into a tree of values and to
+//! quote strings, for the formats built on it.
+
+use alloc::{string::String, vec::Vec};
+use core::fmt::Write;
+
+/// A parsed JSON value. Object members keep their order.
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// The member `key` of an object.
+    pub(crate) fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(members) => members
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Number(number) => Some(*number),
+            _ => None,
+        }
+    }
+
+    /// The value as a `u32`, if it's a whole number in range.
+    pub(crate) fn as_u32(&self) -> Option<u32> {
+        let number = self.as_f64()?;
+        let whole = number as u32;
+        (f64::from(whole) == number).then_some(whole)
+    }
+
+    pub(crate) fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a JSON document, or return the byte offset of the first error.
+pub(crate) fn parse(text: &str) -> Result<Value, usize> {
+    let mut parser = Parser {
+        text: text.as_bytes(),
+        position: 0,
+    };
+    let value = parser.value(0)?;
+    parser.skip_whitespace();
+    if parser.position < parser.text.len() {
+        return Err(parser.position);
+    }
+    Ok(value)
+}
+
+/// A JSON string literal.
+pub(crate) fn quote(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c if (c as u32) < 0x20 => write!(quoted, "\\u{:04x}", c as u32).unwrap(),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Nesting deeper than this is rejected rather than overflowing the stack.
+const MAX_DEPTH: usize = 64;
+
+struct Parser<'a> {
+    text: &'a [u8],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn value(&mut self, depth: usize) -> Result<Value, usize> {
+        self.skip_whitespace();
+        let start = self.position;
+        match self.text.get(start) {
+            Some(b'{') if depth < MAX_DEPTH => {
+                self.position += 1;
+                let mut members = Vec::new();
+                if !self.eat(b'}') {
+                    loop {
+                        self.skip_whitespace();
+                        let key = self.string()?;
+                        self.expect(b':')?;
+                        members.push((key, self.value(depth + 1)?));
+                        if self.eat(b'}') {
+                            break;
+                        }
+                        self.expect(b',')?;
+                    }
+                }
+                Ok(Value::Object(members))
+            }
+            Some(b'[') if depth < MAX_DEPTH => {
+                self.position += 1;
+                let mut items = Vec::new();
+                if !self.eat(b']') {
+                    loop {
+                        items.push(self.value(depth + 1)?);
+                        if self.eat(b']') {
+                            break;
+                        }
+                        self.expect(b',')?;
+                    }
+                }
+                Ok(Value::Array(items))
+            }
+            Some(b'"') => self.string().map(Value::String),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            _ => {
+                for (literal, value) in [
+                    (&b"null"[..], Value::Null),
+                    (b"true", Value::Bool(true)),
+                    (b"false", Value::Bool(false)),
+                ] {
+                    if self.text[start..].starts_with(literal) {
+                        self.position += literal.len();
+                        return Ok(value);
+                    }
+                }
+                Err(start)
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, usize> {
+        let start = self.position;
+        while let Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9') =
+            self.text.get(self.position)
+        {
+            self.position += 1;
+        }
+        // Rust accepts a superset of JSON numbers, close enough for reading
+        core::str::from_utf8(&self.text[start..self.position])
+            .ok()
+            .and_then(|number| number.parse().ok())
+            .map(Value::Number)
+            .ok_or(start)
+    }
+
+    fn string(&mut self) -> Result<String, usize> {
+        if !self.eat(b'"') {
+            return Err(self.position);
+        }
+        let mut string = String::new();
+        loop {
+            let start = self.position;
+            while let Some(&byte) = self.text.get(self.position) {
+                if byte == b'"' || byte == b'\\' || byte < 0x20 {
+                    break;
+                }
+                self.position += 1;
+            }
+            // runs between escapes are whole characters of the source text
+            string.push_str(core::str::from_utf8(&self.text[start..self.position]).unwrap());
+
+            match self.text.get(self.position) {
+                Some(b'"') => {
+                    self.position += 1;
+                    return Ok(string);
+                }
+                Some(b'\\') => {
+                    self.position += 1;
+                    let escaped = match self.text.get(self.position) {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => self.unicode_escape()?,
+                        _ => return Err(self.position),
+                    };
+                    self.position += 1;
+                    string.push(escaped);
+                }
+                _ => return Err(self.position),
+            }
+        }
+    }
+
+    /// The character of a `\u` escape, with `position` on the `u`, left on
+    /// the last digit.
+    fn unicode_escape(&mut self) -> Result<char, usize> {
+        let start = self.position;
+        let mut code = self.hex4()?;
+        if (0xD800..0xDC00).contains(&code) {
+            // a surrogate pair
+            if self.text.get(self.position + 1..self.position + 3) != Some(b"\\u") {
+                return Err(start);
+            }
+            self.position += 2;
+            let low = self.hex4()?;
+            if !(0xDC00..0xE000).contains(&low) {
+                return Err(start);
+            }
+            code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+        }
+        char::from_u32(code).ok_or(start)
+    }
+
+    /// The 4 hex digits after `position`, left on the last one.
+    fn hex4(&mut self) -> Result<u32, usize> {
+        let digits = self
+            .text
+            .get(self.position + 1..self.position + 5)
+            .and_then(|digits| core::str::from_utf8(digits).ok())
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+            .ok_or(self.position)?;
+        self.position += 4;
+        Ok(digits)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.text.get(self.position) {
+            self.position += 1;
+        }
+    }
+
+    /// Skip `byte`, after whitespace, if it's next.
+    fn eat(&mut self, byte: u8) -> bool {
+        self.skip_whitespace();
+        let found = self.text.get(self.position) == Some(&byte);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), usize> {
+        if self.eat(byte) {
+            Ok(())
+        } else {
+            Err(self.position)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let value =
+            parse(r#" {"a": [1, -2.5e1, true, null], "b": {}, "c": "x\"\u00e9\ud83d\ude00"} "#)
+                .unwrap();
+        assert_eq!(
+            value,
+            Value::Object(vec![
+                (
+                    "a".into(),
+                    Value::Array(vec![
+                        Value::Number(1.0),
+                        Value::Number(-25.0),
+                        Value::Bool(true),
+                        Value::Null
+                    ])
+                ),
+                ("b".into(), Value::Object(vec![])),
+                ("c".into(), Value::String("x\"é😀".into())),
+            ])
+        );
+        assert_eq!(
+            value.get("a").unwrap().as_array().unwrap()[0].as_u32(),
+            Some(1)
+        );
+        assert_eq!(
+            value.get("a").unwrap().as_array().unwrap()[1].as_u32(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(parse("[1, 2"), Err(5));
+        assert_eq!(parse("[1,]"), Err(3));
+        assert_eq!(parse("{\"a\" 1}"), Err(5));
+        assert_eq!(parse("\"\\ud800\""), Err(2));
+        assert_eq!(parse("nul"), Err(0));
+        assert_eq!(parse("1 2"), Err(2));
+        assert!(parse(&"[".repeat(MAX_DEPTH + 1)).is_err());
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("a \"b\"\\\n\u{1}"), r#""a \"b\"\\\n\u0001""#);
+        let quoted = quote("é\t");
+        assert_eq!(parse(&quoted), Ok(Value::String("é\t".into())));
+    }
+}
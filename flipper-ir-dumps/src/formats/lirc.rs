@@ -0,0 +1,792 @@
+//! LIRC remote definitions, as found in `lircd.conf` files.
+//!
+//! A definition either lists the timings of each button (`raw_codes`), or
+//! describes how the protocol encodes bits and gives each button a code. Both
+//! are turned into raw signals, see [`parse`]. Pulse distance (`SPACE_ENC`),
+//! RC5 and RC6 encodings are supported, which covers most configs out there.
+//!
+//! Dumps are written back as `raw_codes` definitions, see [`LircRemote::from_dump`].
+
+use alloc::{
+    collections::BTreeSet,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+
+use displaydoc::Display;
+use thiserror::Error;
+
+use crate::{
+    dump::DumpFile,
+    signal::{DutyCycle, Frequency, Polarity, RawSignal, TimingSequence},
+};
+
+/// Carrier frequency of remotes that don't specify one.
+const DEFAULT_FREQUENCY: u32 = 38_000;
+
+/// Silence LIRC leaves between repeated frames of written remotes, in µs.
+const RAW_GAP: u32 = 100_000;
+/// Durations on each line of the written `raw_codes`.
+const DURATIONS_PER_LINE: usize = 6;
+
+/// Flags of encodings this module doesn't know how to produce.
+const UNSUPPORTED_FLAGS: &[&str] = &["SPACE_FIRST", "GRUNDIG", "BO", "XMP", "SERIAL", "RCMM"];
+
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+/// Error parsing a LIRC config
+pub enum LircError {
+    /// Unexpected `{text}` at line {line}
+    Unexpected { line: usize, text: String },
+    /// Invalid number `{value}` at line {line}
+    InvalidNumber { line: usize, value: String },
+    /// Config ends inside of a remote definition
+    UnexpectedEnd,
+    /// Remote `{remote}` uses the {flag} encoding, which isn't supported
+    UnsupportedEncoding { remote: String, flag: String },
+    /// Button `{button}` of remote `{remote}` has no pulses
+    EmptyButton { remote: String, button: String },
+    /// Remote `{remote}` sends {bits} bits per frame, more than 64
+    TooManyBits { remote: String, bits: u32 },
+}
+
+/// A remote of a LIRC config, with a raw signal for each button.
+#[derive(Debug, PartialEq, Clone)]
+pub struct LircRemote {
+    pub name: String,
+    pub signals: Vec<RawSignal>,
+}
+
+impl LircRemote {
+    /// A remote with the signals of a dump, to be written as a LIRC config.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use flipper_ir_dumps::{examples::EXAMPLES, formats::lirc::LircRemote};
+    ///
+    /// let remote = LircRemote::from_dump("aircon", &EXAMPLES[0].dump());
+    /// let config = remote.to_string();
+    /// assert!(config.starts_with("begin remote\n  name  aircon\n  flags RAW_CODES\n"));
+    /// ```
+    pub fn from_dump(name: impl Into<String>, dump: &DumpFile) -> Self {
+        Self {
+            name: name.into(),
+            signals: dump.signals().to_vec(),
+        }
+    }
+
+    /// A dump with the buttons of the remote.
+    pub fn into_dump(self) -> DumpFile {
+        DumpFile::new(1, self.signals)
+    }
+}
+
+/// A `raw_codes` remote definition.
+///
+/// LIRC has a single carrier for a whole remote, the one of the first
+/// signal. Whitespace in names, which LIRC would split on, is replaced
+/// with underscores.
+impl fmt::Display for LircRemote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let word = |name: &str| name.split_whitespace().collect::<Vec<_>>().join("_");
+
+        writeln!(f, "begin remote")?;
+        writeln!(f, "  name  {}", word(&self.name))?;
+        writeln!(f, "  flags RAW_CODES")?;
+        writeln!(f, "  eps   30")?;
+        writeln!(f, "  aeps  100")?;
+        if let Some(signal) = self.signals.first() {
+            writeln!(f, "  frequency  {}", signal.frequency().hz())?;
+            writeln!(f, "  duty_cycle {:.0}", signal.duty_cycle().get() * 100.0)?;
+        }
+        writeln!(f, "  gap   {}", RAW_GAP)?;
+        writeln!(f)?;
+
+        writeln!(f, "  begin raw_codes")?;
+        for signal in &self.signals {
+            writeln!(f, "    name {}", word(signal.name()))?;
+
+            // LIRC codes end with a pulse
+            let data = match signal.data().len() & 1 {
+                0 => &signal.data()[..signal.data().len().saturating_sub(1)],
+                _ => signal.data(),
+            };
+            for line in data.chunks(DURATIONS_PER_LINE) {
+                write!(f, "     ")?;
+                for duration in line {
+                    write!(f, " {:>6}", duration)?;
+                }
+                writeln!(f)?;
+            }
+        }
+        writeln!(f, "  end raw_codes")?;
+        writeln!(f, "end remote")
+    }
+}
+
+/// Parse all remotes of a `lircd.conf` file.
+///
+/// # Examples
+/// ```rust
+/// use flipper_ir_dumps::formats::lirc;
+///
+/// let config = "
+/// begin remote
+///   name  tv
+///   bits  8
+///   flags SPACE_ENC
+///   header 9000 4500
+///   one   560 1690
+///   zero  560 560
+///   ptrail 560
+///   begin codes
+///     KEY_POWER 0x0F
+///   end codes
+/// end remote
+/// ";
+///
+/// let remotes = lirc::parse(config).unwrap();
+/// let power = &remotes[0].signals[0];
+/// assert_eq!(power.name(), "KEY_POWER");
+/// assert_eq!(power.data()[..4], [9000, 4500, 560, 560]);
+/// ```
+pub fn parse(input: &str) -> Result<Vec<LircRemote>, LircError> {
+    let mut lines = input
+        .lines()
+        .enumerate()
+        .map(|(index, line)| {
+            let line = line.split('#').next().unwrap_or_default();
+            (index + 1, line.split_whitespace().collect::<Vec<_>>())
+        })
+        .filter(|(_, words)| !words.is_empty());
+
+    let mut remotes = Vec::new();
+    while let Some((line, words)) = lines.next() {
+        match words.as_slice() {
+            ["begin", "remote"] => remotes.push(parse_remote(&mut lines)?),
+            _ => return Err(unexpected(line, &words)),
+        }
+    }
+
+    Ok(remotes)
+}
+
+/// Parameters of a remote, in microseconds for durations.
+#[derive(Debug, Default, Clone)]
+struct Remote {
+    name: String,
+    flags: BTreeSet<String>,
+    bits: u32,
+    header: Option<(u32, u32)>,
+    one: (u32, u32),
+    zero: (u32, u32),
+    plead: u32,
+    ptrail: u32,
+    foot: Option<(u32, u32)>,
+    pre: Option<(u32, u32)>,
+    post: Option<(u32, u32)>,
+    pre_data: u64,
+    pre_data_bits: u32,
+    post_data: u64,
+    post_data_bits: u32,
+    gap: u32,
+    rc6_mask: u64,
+    /// Position of the toggle bit, counting from 1 for the first bit sent.
+    toggle_bit: u32,
+    frequency: Option<u32>,
+    duty_cycle: Option<u32>,
+}
+
+impl Remote {
+    fn has_flag(&self, flag: &str) -> bool {
+        self.flags.contains(flag)
+    }
+
+    fn bit_count(&self) -> u32 {
+        self.pre_data_bits
+            .saturating_add(self.bits)
+            .saturating_add(self.post_data_bits)
+    }
+
+    /// Timings of a frame sending `code`, as LIRC transmits it.
+    fn encode(&self, code: u64) -> Timings {
+        let mut timings = Timings::default();
+
+        if let Some((pulse, space)) = self.header {
+            timings.pulse(pulse);
+            timings.space(space);
+        }
+        timings.pulse(self.plead);
+
+        let mut done = 0;
+        self.encode_bits(&mut timings, self.pre_data, self.pre_data_bits, &mut done);
+        if let Some((pulse, space)) = self.pre {
+            timings.pulse(pulse);
+            timings.space(space);
+        }
+        self.encode_bits(&mut timings, code, self.bits, &mut done);
+        if let Some((pulse, space)) = self.post {
+            timings.pulse(pulse);
+            timings.space(space);
+        }
+        self.encode_bits(&mut timings, self.post_data, self.post_data_bits, &mut done);
+
+        timings.pulse(self.ptrail);
+        if let Some((space, pulse)) = self.foot {
+            timings.space(space);
+            timings.pulse(pulse);
+        }
+
+        timings
+    }
+
+    /// Encode the lowest `count` bits of `value`, most significant first
+    /// unless the remote is `REVERSE`d; `done` counts the bits of the frame
+    /// sent so far, to find the double length bits of RC6.
+    fn encode_bits(&self, timings: &mut Timings, value: u64, count: u32, done: &mut u32) {
+        let rc6 = self.has_flag("RC6");
+        let rc5 = self.has_flag("RC5") || self.has_flag("SHIFT_ENC");
+
+        for i in 0..count {
+            let bit = if self.has_flag("REVERSE") {
+                value >> i & 1
+            } else {
+                value >> (count - 1 - i) & 1
+            };
+            let position = self.bit_count() - 1 - *done;
+            let scale = if rc6 && self.rc6_mask >> position & 1 == 1 {
+                2
+            } else {
+                1
+            };
+            let (pulse, space) = if bit == 1 { self.one } else { self.zero };
+            let (pulse, space) = (pulse.saturating_mul(scale), space.saturating_mul(scale));
+
+            // bi-phase codes mark ones with a rising edge in RC5 and a
+            // falling one in RC6, and zeros the other way around
+            if (rc5 && bit == 1) || (rc6 && bit == 0) {
+                timings.space(space);
+                timings.pulse(pulse);
+            } else {
+                timings.pulse(pulse);
+                timings.space(space);
+            }
+            *done += 1;
+        }
+    }
+
+    fn frequency(&self) -> Frequency {
+        Frequency::from_hz(self.frequency.unwrap_or(DEFAULT_FREQUENCY))
+    }
+
+    fn duty_cycle(&self) -> DutyCycle {
+        self.duty_cycle
+            .and_then(|percent| DutyCycle::new(percent as f32 / 100.0).ok())
+            .unwrap_or_default()
+    }
+
+    fn signal(&self, button: &str, timings: Timings) -> Result<RawSignal, LircError> {
+        let timings = TimingSequence::new(Polarity::StartsWithPulse, timings.data);
+        RawSignal::new(button, self.frequency(), self.duty_cycle(), timings).map_err(|_| {
+            LircError::EmptyButton {
+                remote: self.name.clone(),
+                button: button.to_string(),
+            }
+        })
+    }
+}
+
+/// Pulse-first timings, merging consecutive pulses or spaces.
+#[derive(Debug, Default)]
+struct Timings {
+    data: Vec<u32>,
+}
+
+impl Timings {
+    fn pulse(&mut self, duration: u32) {
+        self.push(true, duration);
+    }
+
+    fn space(&mut self, duration: u32) {
+        self.push(false, duration);
+    }
+
+    fn push(&mut self, pulse: bool, duration: u32) {
+        let last_is_pulse = self.data.len() & 1 == 1;
+        if duration == 0 || (self.data.is_empty() && !pulse) {
+            return;
+        }
+
+        if last_is_pulse == pulse {
+            if let Some(last) = self.data.last_mut() {
+                *last = last.saturating_add(duration);
+            }
+        } else {
+            self.data.push(duration);
+        }
+    }
+
+    fn duration(&self) -> u32 {
+        self.data
+            .iter()
+            .fold(0, |total, &duration| total.saturating_add(duration))
+    }
+
+    /// Append another frame after `gap`, counted from the start of this
+    /// one for `CONST_LENGTH` remotes.
+    fn append(&mut self, other: Timings, gap: u32, const_length: bool) {
+        let gap = match const_length {
+            true => gap.saturating_sub(self.duration()).max(1),
+            false => gap.max(1),
+        };
+        if self.data.len() & 1 == 0 {
+            self.data.pop();
+        }
+        self.data.push(gap);
+        self.data.extend(other.data);
+    }
+}
+
+fn parse_remote<'a>(
+    lines: &mut impl Iterator<Item = (usize, Vec<&'a str>)>,
+) -> Result<LircRemote, LircError> {
+    let mut remote = Remote::default();
+    let mut signals = Vec::new();
+
+    loop {
+        let (line, words) = lines.next().ok_or(LircError::UnexpectedEnd)?;
+        match words.as_slice() {
+            ["end", "remote"] => break,
+            ["begin", "codes"] => {
+                check_encoding(&remote)?;
+                parse_codes(lines, &remote, &mut signals)?;
+            }
+            ["begin", "raw_codes"] => parse_raw_codes(lines, &remote, &mut signals)?,
+            [key, values @ ..] => parse_parameter(&mut remote, line, key, values)?,
+            [] => {}
+        }
+    }
+
+    Ok(LircRemote {
+        name: remote.name,
+        signals,
+    })
+}
+
+fn parse_parameter(
+    remote: &mut Remote,
+    line: usize,
+    key: &str,
+    values: &[&str],
+) -> Result<(), LircError> {
+    let number = |index: usize| match values.get(index) {
+        Some(value) => parse_number(line, value),
+        None => Err(LircError::Unexpected {
+            line,
+            text: key.to_string(),
+        }),
+    };
+    let small = |index: usize| {
+        number(index).and_then(|value| {
+            u32::try_from(value).map_err(|_| LircError::InvalidNumber {
+                line,
+                value: values[index].to_string(),
+            })
+        })
+    };
+    let pair = || Ok::<_, LircError>((small(0)?, small(1)?));
+
+    match key {
+        "name" => remote.name = values.join(" "),
+        "flags" => {
+            remote.flags = values
+                .join("")
+                .split('|')
+                .filter(|flag| !flag.is_empty())
+                .map(ToString::to_string)
+                .collect();
+        }
+        "bits" => remote.bits = small(0)?,
+        "header" => remote.header = Some(pair()?),
+        "one" => remote.one = pair()?,
+        "zero" => remote.zero = pair()?,
+        "plead" => remote.plead = small(0)?,
+        "ptrail" => remote.ptrail = small(0)?,
+        "foot" => remote.foot = Some(pair()?),
+        "pre" => remote.pre = Some(pair()?),
+        "post" => remote.post = Some(pair()?),
+        "pre_data" => remote.pre_data = number(0)?,
+        "pre_data_bits" => remote.pre_data_bits = small(0)?,
+        "post_data" => remote.post_data = number(0)?,
+        "post_data_bits" => remote.post_data_bits = small(0)?,
+        "gap" => remote.gap = small(0)?,
+        "rc6_mask" => remote.rc6_mask = number(0)?,
+        "toggle_bit" => remote.toggle_bit = small(0)?,
+        "frequency" => remote.frequency = Some(small(0)?),
+        "duty_cycle" => remote.duty_cycle = Some(small(0)?),
+        // tolerances, repeat frames and driver settings don't change the
+        // timings of a button press
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn check_encoding(remote: &Remote) -> Result<(), LircError> {
+    // codes, and the bits sent of them, are 64 bits at most
+    if remote.bit_count() > 64 {
+        return Err(LircError::TooManyBits {
+            remote: remote.name.clone(),
+            bits: remote.bit_count(),
+        });
+    }
+
+    match UNSUPPORTED_FLAGS.iter().find(|flag| remote.has_flag(flag)) {
+        Some(flag) => Err(LircError::UnsupportedEncoding {
+            remote: remote.name.clone(),
+            flag: flag.to_string(),
+        }),
+        None => Ok(()),
+    }
+}
+
+fn parse_codes<'a>(
+    lines: &mut impl Iterator<Item = (usize, Vec<&'a str>)>,
+    remote: &Remote,
+    signals: &mut Vec<RawSignal>,
+) -> Result<(), LircError> {
+    let mut remote = remote.clone();
+    if remote.has_flag("RC6")
+        && remote.rc6_mask == 0
+        && (1..=remote.bit_count()).contains(&remote.toggle_bit)
+    {
+        // LIRC's default: the toggle bit, counted from the first bit sent
+        remote.rc6_mask = 1 << (remote.bit_count() - remote.toggle_bit);
+    }
+
+    loop {
+        let (line, words) = lines.next().ok_or(LircError::UnexpectedEnd)?;
+        match words.as_slice() {
+            ["end", "codes"] => return Ok(()),
+            [button, codes @ ..] if !codes.is_empty() => {
+                let mut frames = codes
+                    .iter()
+                    .map(|code| parse_number(line, code).map(|code| remote.encode(code)));
+                let mut timings = frames.next().expect("codes aren't empty")?;
+                for frame in frames {
+                    timings.append(frame?, remote.gap, remote.has_flag("CONST_LENGTH"));
+                }
+                signals.push(remote.signal(button, timings)?);
+            }
+            _ => return Err(unexpected(line, &words)),
+        }
+    }
+}
+
+fn parse_raw_codes<'a>(
+    lines: &mut impl Iterator<Item = (usize, Vec<&'a str>)>,
+    remote: &Remote,
+    signals: &mut Vec<RawSignal>,
+) -> Result<(), LircError> {
+    let mut button: Option<(String, Vec<u32>)> = None;
+    let mut finish = |button: Option<(String, Vec<u32>)>| -> Result<(), LircError> {
+        if let Some((name, data)) = button {
+            signals.push(remote.signal(&name, Timings { data })?);
+        }
+        Ok(())
+    };
+
+    loop {
+        let (line, words) = lines.next().ok_or(LircError::UnexpectedEnd)?;
+        match words.as_slice() {
+            ["end", "raw_codes"] => return finish(button),
+            ["name", name @ ..] if !name.is_empty() => {
+                finish(button.take())?;
+                button = Some((name.join(" "), Vec::new()));
+            }
+            durations => {
+                let Some((_, data)) = &mut button else {
+                    return Err(unexpected(line, &words));
+                };
+                for duration in durations {
+                    let value = parse_number(line, duration)?;
+                    data.push(u32::try_from(value).map_err(|_| LircError::InvalidNumber {
+                        line,
+                        value: duration.to_string(),
+                    })?);
+                }
+            }
+        }
+    }
+}
+
+fn parse_number(line: usize, value: &str) -> Result<u64, LircError> {
+    let parsed = match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+    parsed.map_err(|_| LircError::InvalidNumber {
+        line,
+        value: value.to_string(),
+    })
+}
+
+fn unexpected(line: usize, words: &[&str]) -> LircError {
+    LircError::Unexpected {
+        line,
+        text: words.join(" "),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use indoc::indoc;
+
+    use super::*;
+    use crate::protocol::{self, Decoded};
+
+    const NEC: &str = indoc! {"
+        # LG TV, NEC with the bits reversed as LIRC sends them
+        begin remote
+          name  lg
+          bits           16
+          flags SPACE_ENC|CONST_LENGTH
+          eps            30
+          aeps          100
+          header       9000  4500
+          one           560  1690
+          zero          560   560
+          ptrail        560
+          pre_data_bits   16
+          pre_data       0x20DF
+          gap          108000
+          toggle_bit_mask 0x0
+
+              begin codes
+                  KEY_POWER                0x10EF   # on and off
+                  KEY_POWER_TWICE          0x10EF 0x10EF
+              end codes
+        end remote
+    "};
+
+    #[test]
+    fn test_space_enc() {
+        let remotes = parse(NEC).unwrap();
+        assert_eq!(remotes.len(), 1);
+        assert_eq!(remotes[0].name, "lg");
+
+        let power = &remotes[0].signals[0];
+        assert_eq!(power.name(), "KEY_POWER");
+        assert_eq!(power.frequency().hz(), 38_000);
+        match protocol::decode(power.data(), 38_000) {
+            Some(Decoded::Nec(nec)) => assert_eq!((nec.address, nec.command), (0x04, 0x08)),
+            decoded => panic!("decoded as {decoded:?}"),
+        }
+
+        // the second frame starts 108ms after the first one
+        let twice = remotes[0].signals[1].data();
+        let frame = power.data().len();
+        assert_eq!(twice.len(), 2 * frame + 1);
+        let first: u32 = twice[..frame].iter().sum();
+        assert_eq!(first + twice[frame], 108_000);
+        assert_eq!(twice[frame + 1..], *power.data());
+    }
+
+    #[test]
+    fn test_rc5() {
+        let config = indoc! {"
+            begin remote
+              name  philips
+              bits  13
+              flags RC5|CONST_LENGTH
+              one   889 889
+              zero  889 889
+              plead 889
+              gap   113792
+              frequency 36000
+              duty_cycle 25
+              begin codes
+                KEY_MUTE 0x100D
+              end codes
+            end remote
+        "};
+
+        let mute = &parse(config).unwrap()[0].signals[0];
+        assert_eq!(mute.frequency().hz(), 36_000);
+        assert_eq!(mute.duty_cycle().get(), 0.25);
+        match protocol::decode(mute.data(), 36_000) {
+            Some(Decoded::Rc5(rc5)) => assert_eq!((rc5.address, rc5.command), (0x00, 0x0D)),
+            decoded => panic!("decoded as {decoded:?}"),
+        }
+    }
+
+    #[test]
+    fn test_rc6() {
+        // start bit, mode 0, double length toggle bit, address and command
+        let config = indoc! {"
+            begin remote
+              name  mce
+              bits  16
+              flags RC6|CONST_LENGTH
+              header 2666 889
+              one   444 444
+              zero  444 444
+              pre_data_bits 5
+              pre_data 0x10
+              toggle_bit 5
+              gap   83000
+              begin codes
+                KEY_OK 0x0422
+              end codes
+            end remote
+        "};
+
+        let ok = &parse(config).unwrap()[0].signals[0];
+        match protocol::decode(ok.data(), 36_000) {
+            Some(Decoded::Rc6(rc6)) => assert_eq!((rc6.address, rc6.command), (0x04, 0x22)),
+            decoded => panic!("decoded as {decoded:?}"),
+        }
+    }
+
+    #[test]
+    fn test_raw_codes() {
+        let config = indoc! {"
+            begin remote
+              name  aircon
+              flags RAW_CODES
+              eps   30
+              gap   100000
+              begin raw_codes
+                name  power on
+                  3000 1500  500 1000
+                  500  500   500
+                name off
+                  3000 1500 500
+              end raw_codes
+            end remote
+
+            begin remote
+              name empty
+            end remote
+        "};
+
+        let remotes = parse(config).unwrap();
+        assert_eq!(remotes.len(), 2);
+        assert!(remotes[1].signals.is_empty());
+
+        let dump = remotes[0].clone().into_dump();
+        assert_eq!(dump.signals().len(), 2);
+        let power = dump.signal("power on").unwrap();
+        assert_eq!(power.data(), [3000, 1500, 500, 1000, 500, 500, 500]);
+        assert_eq!(dump.signal("off").unwrap().data(), [3000, 1500, 500]);
+    }
+
+    #[test]
+    fn test_write() {
+        let dump = crate::examples::EXAMPLES[0].dump();
+        let remote = LircRemote::from_dump("aircon remote", &dump);
+        let config = remote.to_string();
+        assert!(config.contains("\n  name  aircon_remote\n"));
+        assert!(config.contains("\n  frequency  38000\n  duty_cycle 33\n"));
+
+        let reparsed = parse(&config).unwrap();
+        assert_eq!(reparsed.len(), 1);
+        assert_eq!(reparsed[0].signals.len(), dump.signals().len());
+        for (signal, original) in reparsed[0].signals.iter().zip(dump.signals()) {
+            assert_eq!(signal.name(), original.name());
+            assert_eq!(signal.data(), original.data());
+            assert_eq!(signal.frequency(), original.frequency());
+        }
+
+        // even length data loses its trailing pause
+        let mut remote = parse(NEC).unwrap().remove(0);
+        remote.signals.truncate(1);
+        let signal = &mut remote.signals[0];
+        signal.data.push(20_000);
+        let config = remote.to_string();
+        assert!(!config.contains("20000"));
+        assert!(config.ends_with("    560\n  end raw_codes\nend remote\n"));
+    }
+
+    #[test]
+    fn test_huge_durations() {
+        let config = indoc! {"
+            begin remote
+              name  huge
+              bits  8
+              flags SPACE_ENC|CONST_LENGTH
+              header 4294967295 4294967295
+              one   4294967295 4294967295
+              zero  560 560
+              ptrail 4294967295
+              gap   4294967295
+              begin codes
+                KEY_POWER 0xFF 0xFF
+              end codes
+            end remote
+        "};
+        let remotes = parse(config).unwrap();
+        let data = remotes[0].signals[0].data();
+        assert_eq!(data[..2], [u32::MAX, u32::MAX]);
+        assert!(data.iter().all(|&duration| duration > 0));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            parse("begin remote\n  name x\n"),
+            Err(LircError::UnexpectedEnd)
+        );
+        assert_eq!(
+            parse("\n\nbegin codes\n"),
+            Err(LircError::Unexpected {
+                line: 3,
+                text: "begin codes".to_string(),
+            })
+        );
+
+        let err = parse("begin remote\n  bits 0xZZ\nend remote\n").unwrap_err();
+        assert_eq!(err.to_string(), "Invalid number `0xZZ` at line 2");
+
+        let grundig = "begin remote\n name g\n flags GRUNDIG\n begin codes\n end codes\nend remote";
+        assert_eq!(
+            parse(grundig),
+            Err(LircError::UnsupportedEncoding {
+                remote: "g".to_string(),
+                flag: "GRUNDIG".to_string(),
+            })
+        );
+
+        let wide = "begin remote\n name w\n bits 65\n begin codes\n end codes\nend remote";
+        assert_eq!(
+            parse(wide),
+            Err(LircError::TooManyBits {
+                remote: "w".to_string(),
+                bits: 65,
+            })
+        );
+        let wide =
+            "begin remote\n name w\n bits 32\n pre_data_bits 32\n post_data_bits 4294967295\n \
+                    begin codes\n end codes\nend remote";
+        assert_eq!(
+            parse(wide),
+            Err(LircError::TooManyBits {
+                remote: "w".to_string(),
+                bits: u32::MAX,
+            })
+        );
+        let err = parse("begin remote\n  gap 4294967296\nend remote\n").unwrap_err();
+        assert_eq!(err.to_string(), "Invalid number `4294967296` at line 2");
+
+        let stray = "begin remote\n begin raw_codes\n 100 200\n end raw_codes\nend remote";
+        assert!(matches!(
+            parse(stray),
+            Err(LircError::Unexpected { line: 3, .. })
+        ));
+    }
+}
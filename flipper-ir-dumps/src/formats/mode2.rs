@@ -0,0 +1,231 @@
+//! Output of LIRC's `mode2` tool, the usual way to capture raw IR on Linux:
+//!
+//! ```text
+//! Using driver default on device /dev/lirc0
+//! space 16777215
+//! pulse 9024
+//! space 4512
+//! pulse 564
+//! timeout 125000
+//! ```
+//!
+//! A capture usually holds several button presses: it's split into signals
+//! at long spaces. `carrier` lines, printed by receivers able to measure the
+//! carrier, set the frequency of the signals after them. Other lines, such
+//! as the banners of the tool, are skipped.
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use displaydoc::Display;
+use thiserror::Error;
+
+use crate::signal::{DutyCycle, Frequency, RawSignal, SignalType};
+
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+/// Error parsing `mode2` output
+pub enum Mode2Error {
+    /// Invalid number `{value}` at line {line}
+    InvalidNumber { line: usize, value: String },
+    /// Output has no pulses
+    Empty,
+}
+
+/// How a capture is split into signals, see [`parse`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Mode2Options {
+    /// Spaces at least this long, in µs, end a signal. The default keeps the
+    /// repeats of a held button in the same signal.
+    pub gap: u32,
+    /// Carrier of signals before any `carrier` line.
+    pub frequency: Frequency,
+}
+
+impl Default for Mode2Options {
+    fn default() -> Self {
+        Self {
+            gap: 100_000,
+            frequency: Frequency::from_hz(38_000),
+        }
+    }
+}
+
+/// Parse `mode2` output into signals, named `signal_1`, `signal_2`, ...
+///
+/// # Examples
+/// ```rust
+/// use flipper_ir_dumps::formats::mode2::{self, Mode2Options};
+///
+/// let capture = "\
+/// space 16777215
+/// pulse 9024
+/// space 4512
+/// pulse 564
+/// timeout 125000
+/// pulse 9024
+/// space 2256
+/// pulse 564
+/// ";
+///
+/// let signals = mode2::parse(capture, &Mode2Options::default()).unwrap();
+/// assert_eq!(signals.len(), 2);
+/// assert_eq!(signals[0].data(), [9024, 4512, 564]);
+/// ```
+pub fn parse(output: &str, options: &Mode2Options) -> Result<Vec<RawSignal>, Mode2Error> {
+    let mut signals = Vec::new();
+    let mut data: Vec<u32> = Vec::new();
+    let mut frequency = options.frequency;
+    let mut finish = |data: &mut Vec<u32>, frequency: Frequency| {
+        // signals end with a pulse
+        if data.len() & 1 == 0 {
+            data.pop();
+        }
+        if !data.is_empty() {
+            signals.push(RawSignal {
+                name: format!("signal_{}", signals.len() + 1),
+                r#type: SignalType::Raw,
+                frequency,
+                duty_cycle: DutyCycle::default(),
+                data: core::mem::take(data),
+                comments: Vec::new(),
+                unknown_fields: Vec::new(),
+                source: Default::default(),
+                parsed: Default::default(),
+            });
+        }
+        data.clear();
+    };
+
+    for (number, line) in output.lines().enumerate() {
+        let mut words = line.split_whitespace();
+        let (Some(kind), Some(value), None) = (words.next(), words.next(), words.next()) else {
+            continue;
+        };
+        let pulse = match kind {
+            "pulse" => true,
+            "space" | "timeout" => false,
+            "carrier" => {
+                frequency = Frequency::from_hz(parse_number(number + 1, value)?);
+                continue;
+            }
+            _ => continue,
+        };
+        let duration = parse_number(number + 1, value)?;
+
+        if pulse {
+            match data.len() & 1 {
+                // a pulse after a pulse, as drivers split long ones
+                1 => {
+                    let last = data.last_mut().expect("odd length");
+                    *last = last.saturating_add(duration);
+                }
+                _ => data.push(duration),
+            }
+        } else if duration >= options.gap {
+            finish(&mut data, frequency);
+        } else {
+            match data.len() & 1 {
+                // a space after a space adds up, and one before the first
+                // pulse doesn't count
+                0 => {
+                    if let Some(last) = data.last_mut() {
+                        *last = last.saturating_add(duration);
+                    }
+                }
+                _ => data.push(duration),
+            }
+        }
+    }
+    finish(&mut data, frequency);
+
+    if signals.is_empty() {
+        return Err(Mode2Error::Empty);
+    }
+    Ok(signals)
+}
+
+fn parse_number(line: usize, value: &str) -> Result<u32, Mode2Error> {
+    value.parse().map_err(|_| Mode2Error::InvalidNumber {
+        line,
+        value: value.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let capture = indoc! {"
+            Using driver default on device /dev/lirc0
+            Trying device: /dev/lirc0
+            Using device: /dev/lirc0
+            space 16777215
+            pulse 9024
+            space 4512
+            pulse 300
+            pulse 264
+            space 500
+            space 64
+            pulse 564
+            space 40000
+            pulse 9024
+            space 2256
+            pulse 564
+            space 98000
+            timeout 125000
+            carrier 36000
+            pulse 889
+            space 889
+        "};
+
+        let signals = parse(capture, &Mode2Options::default()).unwrap();
+        assert_eq!(signals.len(), 2);
+        assert_eq!(signals[0].name(), "signal_1");
+        assert_eq!(
+            signals[0].data(),
+            [9024, 4512, 564, 564, 564, 40000, 9024, 2256, 564]
+        );
+        assert_eq!(signals[0].frequency().hz(), 38_000);
+        assert_eq!(signals[1].data(), [889]);
+        assert_eq!(signals[1].frequency().hz(), 36_000);
+
+        let split = Mode2Options {
+            gap: 30_000,
+            ..Mode2Options::default()
+        };
+        assert_eq!(parse(capture, &split).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_huge_durations() {
+        let options = Mode2Options {
+            gap: u32::MAX,
+            ..Mode2Options::default()
+        };
+        let capture = "pulse 4294967295\npulse 10\nspace 4294967294\nspace 10\npulse 1\n";
+        let signals = parse(capture, &options).unwrap();
+        assert_eq!(signals[0].data(), [u32::MAX, u32::MAX, 1]);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            parse("pulse 9000\nspace x\n", &Mode2Options::default()),
+            Err(Mode2Error::InvalidNumber {
+                line: 2,
+                value: "x".to_string()
+            })
+        );
+        assert_eq!(
+            parse("space 16777215\ntimeout 1000\n", &Mode2Options::default()),
+            Err(Mode2Error::Empty)
+        );
+    }
+}
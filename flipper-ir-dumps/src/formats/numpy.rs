@@ -0,0 +1,258 @@
+//! NumPy `.npz` archives of the signals of a dump, for signal processing
+//! experiments in Python:
+//!
+//! ```python
+//! import numpy
+//! dump = numpy.load("remote.npz")
+//! offsets = dump["offsets"]
+//! power = dump["timings"][offsets[0]:offsets[1]]
+//! ```
+//!
+//! Signals having different lengths, their timings are concatenated in
+//! `timings`, and `offsets` tells where each signal starts in it, with the
+//! end of the last one as an extra item. Other arrays have an item per
+//! signal:
+//!
+//! - `names`: names of the signals;
+//! - `frequency` and `duty_cycle`: carrier of the signals;
+//! - `protocol`, `address` and `command`: the first frame decoded from the
+//!   signal, empty or -1 if none was.
+
+use alloc::{format, vec::Vec};
+
+use super::zip;
+use crate::{dump::DumpFile, protocol::DecodedFrame};
+
+/// Magic bytes and version 1.0 of the `.npy` format.
+const NPY_MAGIC: &[u8; 8] = b"\x93NUMPY\x01\x00";
+/// Alignment of the data of `.npy` files, as NumPy writes them.
+const NPY_ALIGNMENT: usize = 64;
+
+/// A `.npz` archive of the arrays of `dump`.
+///
+/// # Examples
+/// ```rust
+/// use flipper_ir_dumps::{examples::EXAMPLES, formats::numpy};
+///
+/// let archive = numpy::to_npz(&EXAMPLES[0].dump());
+/// // a ZIP archive
+/// assert_eq!(archive[..2], *b"PK");
+/// ```
+pub fn to_npz(dump: &DumpFile) -> Vec<u8> {
+    let signals = dump.signals();
+    let frames: Vec<Option<&DecodedFrame>> = signals
+        .iter()
+        .map(|signal| {
+            let parsed = signal.parsed().ok()?;
+            parsed.packets().iter().find_map(|packet| packet.frame())
+        })
+        .collect();
+
+    let mut offsets = Vec::from([0]);
+    let mut timings = Vec::new();
+    for signal in signals {
+        timings.extend(signal.data().iter().copied());
+        offsets.push(timings.len() as i64);
+    }
+    let number = |field: Option<u32>| field.map_or(-1, i64::from);
+
+    let arrays = [
+        (
+            "names.npy",
+            strings(signals.iter().map(|signal| signal.name())),
+        ),
+        (
+            "frequency.npy",
+            npy(
+                "<u4",
+                signals.len(),
+                signals
+                    .iter()
+                    .flat_map(|signal| signal.frequency().hz().to_le_bytes()),
+            ),
+        ),
+        (
+            "duty_cycle.npy",
+            npy(
+                "<f4",
+                signals.len(),
+                signals
+                    .iter()
+                    .flat_map(|signal| signal.duty_cycle().get().to_le_bytes()),
+            ),
+        ),
+        (
+            "offsets.npy",
+            npy(
+                "<i8",
+                offsets.len(),
+                offsets.iter().flat_map(|offset| offset.to_le_bytes()),
+            ),
+        ),
+        (
+            "timings.npy",
+            npy(
+                "<u4",
+                timings.len(),
+                timings.iter().flat_map(|timing| timing.to_le_bytes()),
+            ),
+        ),
+        (
+            "protocol.npy",
+            strings(
+                frames
+                    .iter()
+                    .map(|frame| frame.map_or("", |frame| frame.protocol.as_str())),
+            ),
+        ),
+        (
+            "address.npy",
+            npy(
+                "<i8",
+                frames.len(),
+                frames
+                    .iter()
+                    .flat_map(|frame| number(frame.and_then(|frame| frame.address)).to_le_bytes()),
+            ),
+        ),
+        (
+            "command.npy",
+            npy(
+                "<i8",
+                frames.len(),
+                frames
+                    .iter()
+                    .flat_map(|frame| number(frame.and_then(|frame| frame.command)).to_le_bytes()),
+            ),
+        ),
+    ];
+
+    let files: Vec<(&str, &[u8])> = arrays
+        .iter()
+        .map(|(name, array)| (*name, array.as_slice()))
+        .collect();
+    zip::stored(&files)
+}
+
+/// A `.npy` file of a one-dimensional array of `len` items of type
+/// `descr`, laid out in `data`.
+fn npy(descr: &str, len: usize, data: impl IntoIterator<Item = u8>) -> Vec<u8> {
+    let mut header = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': ({},), }}",
+        descr, len
+    );
+    // padded with spaces and a newline, so that the data is aligned
+    let unpadded = NPY_MAGIC.len() + 2 + header.len() + 1;
+    let padding = unpadded.next_multiple_of(NPY_ALIGNMENT) - unpadded;
+    header.extend(core::iter::repeat_n(' ', padding));
+    header.push('\n');
+
+    let mut file = Vec::from(*NPY_MAGIC);
+    file.extend((header.len() as u16).to_le_bytes());
+    file.extend(header.as_bytes());
+    file.extend(data);
+    file
+}
+
+/// A `.npy` file of an array of `strings`, as fixed-width UTF-32 items wide
+/// enough for the longest one.
+fn strings<'a>(strings: impl Iterator<Item = &'a str> + Clone) -> Vec<u8> {
+    let width = strings
+        .clone()
+        .map(|string| string.chars().count())
+        .max()
+        .unwrap_or_default()
+        .max(1);
+    let mut data = Vec::new();
+    let mut len = 0;
+    for string in strings {
+        let chars = string.chars().count();
+        data.extend(string.chars().flat_map(|c| u32::from(c).to_le_bytes()));
+        data.extend(core::iter::repeat_n(0, 4 * (width - chars)));
+        len += 1;
+    }
+
+    npy(&format!("<U{}", width), len, data)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::String, vec};
+
+    use super::*;
+    use crate::signal::{DutyCycle, Polarity, RawSignal, TimingSequence};
+
+    /// Header and data of a `.npy` file.
+    fn parse(file: &[u8]) -> (&str, &[u8]) {
+        assert_eq!(file[..8], *NPY_MAGIC);
+        let length = usize::from(u16::from_le_bytes([file[8], file[9]]));
+        assert_eq!((10 + length) % NPY_ALIGNMENT, 0);
+        let header = core::str::from_utf8(&file[10..10 + length]).unwrap();
+        assert!(header.ends_with('\n'));
+        (header.trim_end(), &file[10 + length..])
+    }
+
+    #[test]
+    fn test_npy() {
+        let file = npy("<u4", 2, [1, 0, 0, 0, 2, 0, 0, 0]);
+        let (header, data) = parse(&file);
+        assert_eq!(
+            header,
+            "{'descr': '<u4', 'fortran_order': False, 'shape': (2,), }"
+        );
+        assert_eq!(data, [1, 0, 0, 0, 2, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_strings() {
+        let file = strings(["ab", "é"].into_iter());
+        let (header, data) = parse(&file);
+        assert!(header.starts_with("{'descr': '<U2',"));
+        assert!(header.contains("'shape': (2,)"));
+        assert_eq!(
+            data,
+            [b'a', 0, 0, 0, b'b', 0, 0, 0, 0xE9, 0, 0, 0, 0, 0, 0, 0]
+        );
+
+        let file = strings([].into_iter());
+        let (header, data) = parse(&file);
+        assert!(header.starts_with("{'descr': '<U1',"));
+        assert!(header.contains("'shape': (0,)"));
+        assert_eq!(data, []);
+    }
+
+    #[test]
+    fn test_to_npz() {
+        let signal = |name: &str, data: Vec<u32>| {
+            let timings = TimingSequence::new(Polarity::StartsWithPulse, data);
+            RawSignal::new(name, 38_000, DutyCycle::default(), timings).unwrap()
+        };
+        let dump = DumpFile::new(
+            1,
+            vec![
+                signal("Power", vec![100, 50, 100]),
+                signal("Mute", vec![200]),
+            ],
+        );
+        let archive = to_npz(&dump);
+
+        let text = String::from_utf8_lossy(&archive);
+        for name in [
+            "names.npy",
+            "frequency.npy",
+            "offsets.npy",
+            "timings.npy",
+            "command.npy",
+        ] {
+            assert!(text.contains(name));
+        }
+        let offsets = npy("<i8", 3, [0i64, 3, 4].iter().flat_map(|o| o.to_le_bytes()));
+        assert!(archive
+            .windows(offsets.len())
+            .any(|window| window == offsets.as_slice()));
+        let address = npy("<i8", 2, [-1i64, -1].iter().flat_map(|a| a.to_le_bytes()));
+        assert!(archive
+            .windows(address.len())
+            .any(|window| window == address.as_slice()));
+    }
+}
@@ -0,0 +1,154 @@
+//! Tables summarizing the buttons of a dump, to paste into Flipper-IRDB
+//! pull requests and wikis, as [`markdown`] or [`html`].
+//!
+//! Each row is a signal: its name, the protocol of its packets, the address
+//! and command of the first decoded frame and the number of frames sent,
+//! repeats included. Signals that don't decode leave the protocol, address
+//! and command cells empty.
+
+use alloc::{format, string::String, vec::Vec};
+use core::fmt::Write;
+
+use super::girr::escape;
+use crate::dump::DumpFile;
+
+/// Titles of the columns.
+const HEADER: [&str; 5] = ["Button", "Protocol", "Address", "Command", "Frames"];
+
+/// A Markdown table of the buttons of `dump`.
+///
+/// # Examples
+/// ```rust
+/// use flipper_ir_dumps::{examples::EXAMPLES, formats::report};
+///
+/// let table = report::markdown(&EXAMPLES[0].dump());
+/// assert!(table.starts_with("| Button | Protocol | Address | Command | Frames |\n|---|"));
+/// assert!(table.contains("\n| On_h_16 | "));
+/// ```
+pub fn markdown(dump: &DumpFile) -> String {
+    let mut table = String::new();
+    writeln!(table, "| {} |", HEADER.join(" | ")).unwrap();
+    writeln!(table, "|{}", "---|".repeat(HEADER.len())).unwrap();
+    for row in rows(dump) {
+        let cells: Vec<String> = row
+            .iter()
+            .map(|cell| cell.replace('\\', "\\\\").replace('|', "\\|"))
+            .collect();
+        writeln!(table, "| {} |", cells.join(" | ")).unwrap();
+    }
+    table
+}
+
+/// An HTML table of the buttons of `dump`.
+///
+/// # Examples
+/// ```rust
+/// use flipper_ir_dumps::{examples::EXAMPLES, formats::report};
+///
+/// let table = report::html(&EXAMPLES[0].dump());
+/// assert!(table.starts_with("<table>\n  <thead>\n    <tr><th>Button</th>"));
+/// assert!(table.contains("<tr><td>On_h_16</td>"));
+/// ```
+pub fn html(dump: &DumpFile) -> String {
+    let mut table = String::from("<table>\n  <thead>\n    <tr>");
+    for title in HEADER {
+        write!(table, "<th>{}</th>", title).unwrap();
+    }
+    table.push_str("</tr>\n  </thead>\n  <tbody>\n");
+    for row in rows(dump) {
+        table.push_str("    <tr>");
+        for cell in row {
+            write!(table, "<td>{}</td>", escape(&cell)).unwrap();
+        }
+        table.push_str("</tr>\n");
+    }
+    table.push_str("  </tbody>\n</table>\n");
+    table
+}
+
+/// The cells of each row, as text.
+fn rows(dump: &DumpFile) -> Vec<[String; 5]> {
+    dump.signals()
+        .iter()
+        .map(|signal| {
+            let parsed = signal.parsed().ok();
+            let protocol = parsed
+                .and_then(|parsed| parsed.protocol())
+                .map(|decoded| decoded.protocol().into())
+                .unwrap_or_default();
+            let frame =
+                parsed.and_then(|parsed| parsed.packets().iter().find_map(|packet| packet.frame()));
+            let hex = |field: Option<u32>| {
+                field
+                    .map(|value| format!("0x{:02X}", value))
+                    .unwrap_or_default()
+            };
+            let frames: usize = parsed
+                .map(|parsed| {
+                    parsed
+                        .packets()
+                        .iter()
+                        .map(|packet| 1 + packet.repeats())
+                        .sum()
+                })
+                .unwrap_or_default();
+
+            [
+                signal.name().into(),
+                protocol,
+                hex(frame.and_then(|frame| frame.address)),
+                hex(frame.and_then(|frame| frame.command)),
+                format!("{}", frames),
+            ]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::signal::{DutyCycle, Polarity, RawSignal, TimingSequence};
+
+    fn nec(name: &str) -> RawSignal {
+        // NEC address 0x04, command 0x08
+        let mut data = vec![9024, 4512];
+        for byte in [0x04u8, !0x04, 0x08, !0x08] {
+            for bit in 0..8 {
+                data.push(564);
+                data.push(if byte >> bit & 1 == 1 { 1692 } else { 564 });
+            }
+        }
+        data.push(564);
+        let timings = TimingSequence::new(Polarity::StartsWithPulse, data);
+        RawSignal::new(name, 38_000, DutyCycle::default(), timings).unwrap()
+    }
+
+    #[test]
+    fn test_markdown() {
+        let dump = DumpFile::new(1, vec![nec("Power|On")]);
+        assert_eq!(
+            markdown(&dump),
+            "| Button | Protocol | Address | Command | Frames |\n\
+             |---|---|---|---|---|\n\
+             | Power\\|On | NEC | 0x04 | 0x08 | 1 |\n"
+        );
+    }
+
+    #[test]
+    fn test_html() {
+        let timings = TimingSequence::new(Polarity::StartsWithPulse, vec![100]);
+        let noise = RawSignal::new("<noise>", 38_000, DutyCycle::default(), timings).unwrap();
+        let dump = DumpFile::new(1, vec![nec("Power"), noise]);
+        assert_eq!(
+            html(&dump),
+            "<table>\n  <thead>\n    \
+             <tr><th>Button</th><th>Protocol</th><th>Address</th><th>Command</th><th>Frames</th></tr>\n  \
+             </thead>\n  <tbody>\n    \
+             <tr><td>Power</td><td>NEC</td><td>0x04</td><td>0x08</td><td>1</td></tr>\n    \
+             <tr><td>&lt;noise&gt;</td><td></td><td></td><td></td><td>0</td></tr>\n  \
+             </tbody>\n</table>\n"
+        );
+    }
+}
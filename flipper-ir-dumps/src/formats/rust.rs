@@ -0,0 +1,178 @@
+//! Rust constants of the signals of a dump, to embed captured codes in
+//! firmware at compile time:
+//!
+//! ```rust
+//! /// Power
+//! pub const POWER: &[u32] = &[
+//!     9024, 4512, 564, 564,
+//! ];
+//! pub const POWER_FREQUENCY: u32 = 38000;
+//! pub const POWER_DUTY_CYCLE: f32 = 0.33;
+//! ```
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::Write;
+
+use crate::dump::DumpFile;
+
+/// Durations on each line of an array.
+const DURATIONS_PER_LINE: usize = 12;
+/// Suffixes of the constants of each signal, after its timings.
+const SUFFIXES: [&str; 2] = ["_FREQUENCY", "_DUTY_CYCLE"];
+
+/// Source code of constants for the timings, carrier frequency and duty
+/// cycle of each signal of `dump`.
+///
+/// Names are turned into Rust identifiers, see [`identifier`].
+///
+/// # Examples
+/// ```rust
+/// use flipper_ir_dumps::{examples::EXAMPLES, formats::rust};
+///
+/// let code = rust::constants(&EXAMPLES[0].dump());
+/// assert!(code.contains("/// On_h_16\npub const ON_H_16: &[u32] = &[\n    662, 17715, "));
+/// assert!(code.contains("pub const ON_H_16_FREQUENCY: u32 = 38000;\n"));
+/// ```
+pub fn constants(dump: &DumpFile) -> String {
+    let mut code = String::new();
+    writeln!(code, "// Generated by flipper-ir-dumps.").unwrap();
+    writeln!(
+        code,
+        "// Durations in µs, alternating pulses and pauses, pulse first."
+    )
+    .unwrap();
+
+    for (signal, name) in dump.signals().iter().zip(identifiers(dump)) {
+        writeln!(code).unwrap();
+        writeln!(code, "/// {}", signal.name()).unwrap();
+        writeln!(code, "pub const {}: &[u32] = &[", name).unwrap();
+        for line in signal.data().chunks(DURATIONS_PER_LINE) {
+            let line: Vec<_> = line.iter().map(u32::to_string).collect();
+            writeln!(code, "    {},", line.join(", ")).unwrap();
+        }
+        writeln!(code, "];").unwrap();
+        writeln!(
+            code,
+            "pub const {}{}: u32 = {};",
+            name,
+            SUFFIXES[0],
+            signal.frequency().hz()
+        )
+        .unwrap();
+        // debug formatting keeps the decimal point of whole numbers
+        writeln!(
+            code,
+            "pub const {}{}: f32 = {:?};",
+            name,
+            SUFFIXES[1],
+            signal.duty_cycle().get()
+        )
+        .unwrap();
+    }
+
+    code
+}
+
+/// A Rust constant name for a signal name: uppercase, with anything but
+/// ASCII letters and digits replaced by underscores, and `SIGNAL_` before
+/// names that don't start with a letter.
+///
+/// ```rust
+/// use flipper_ir_dumps::formats::rust::identifier;
+///
+/// assert_eq!(identifier("Vol+"), "VOL_");
+/// assert_eq!(identifier("18°C cool"), "SIGNAL_18_C_COOL");
+/// ```
+pub fn identifier(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        name
+    } else {
+        format!("SIGNAL_{}", name)
+    }
+}
+
+/// [`identifier`]s of the signals of a dump, numbered when any of their
+/// constants clash.
+fn identifiers(dump: &DumpFile) -> Vec<String> {
+    let mut taken: Vec<String> = Vec::new();
+    let mut identifiers = Vec::new();
+    for signal in dump.signals() {
+        let base = identifier(signal.name());
+        let constants = |name: &str| {
+            let mut constants = Vec::from([name.to_string()]);
+            constants.extend(SUFFIXES.iter().map(|suffix| format!("{}{}", name, suffix)));
+            constants
+        };
+        let mut name = base.clone();
+        let mut count = 1;
+        while constants(&name)
+            .iter()
+            .any(|constant| taken.contains(constant))
+        {
+            count += 1;
+            name = format!("{}_{}", base, count);
+        }
+        taken.extend(constants(&name));
+        identifiers.push(name);
+    }
+    identifiers
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::signal::{DutyCycle, Polarity, RawSignal, TimingSequence};
+
+    fn signal(name: &str, duty_cycle: f32) -> RawSignal {
+        let timings = TimingSequence::new(Polarity::StartsWithPulse, vec![9024, 4512, 564]);
+        RawSignal::new(name, 38_000, DutyCycle::new(duty_cycle).unwrap(), timings).unwrap()
+    }
+
+    #[test]
+    fn test_constants() {
+        let dump = DumpFile::new(1, vec![signal("Power", 1.0)]);
+        assert_eq!(
+            constants(&dump),
+            "// Generated by flipper-ir-dumps.\n\
+             // Durations in µs, alternating pulses and pauses, pulse first.\n\
+             \n\
+             /// Power\n\
+             pub const POWER: &[u32] = &[\n    9024, 4512, 564,\n];\n\
+             pub const POWER_FREQUENCY: u32 = 38000;\n\
+             pub const POWER_DUTY_CYCLE: f32 = 1.0;\n"
+        );
+    }
+
+    #[test]
+    fn test_identifiers() {
+        let dump = DumpFile::new(
+            1,
+            vec![
+                signal("Power", 0.33),
+                signal("power", 0.33),
+                signal("Power frequency", 0.33),
+                signal("", 0.33),
+            ],
+        );
+        assert_eq!(
+            identifiers(&dump),
+            ["POWER", "POWER_2", "POWER_FREQUENCY_2", "SIGNAL_"]
+        );
+    }
+}
@@ -0,0 +1,109 @@
+//! CSV captures as exported by [Saleae Logic 2](https://www.saleae.com),
+//! to run its analyzers over signals.
+//!
+//! Each signal is a channel of its own, all starting at time 0: rows list
+//! the level of every channel at each time one of them changes. Channels are
+//! active low, as the demodulated output of IR receivers.
+
+use alloc::{string::String, vec::Vec};
+use core::fmt::Write;
+
+use crate::dump::DumpFile;
+
+/// A Logic 2 digital CSV export of the signals of `dump`, with channels
+/// named after them.
+///
+/// # Examples
+/// ```rust
+/// use flipper_ir_dumps::{examples::EXAMPLES, formats::saleae};
+///
+/// let csv = saleae::to_csv(&EXAMPLES[0].dump());
+/// assert!(csv.starts_with("Time [s],On_h_16,"));
+/// assert!(csv.contains("\n0.000662000,1,"));
+/// ```
+pub fn to_csv(dump: &DumpFile) -> String {
+    let signals = dump.signals();
+
+    // times the level of each channel changes, pulses starting at even ones
+    let edges: Vec<Vec<u64>> = signals
+        .iter()
+        .map(|signal| {
+            signal
+                .data()
+                .iter()
+                .scan(0, |time, &duration| {
+                    let start = *time;
+                    *time += u64::from(duration);
+                    Some(start)
+                })
+                .chain([signal.duration()])
+                .collect()
+        })
+        .collect();
+    let mut times: Vec<u64> = edges.iter().flatten().copied().collect();
+    times.sort_unstable();
+    times.dedup();
+
+    let mut csv = String::from("Time [s]");
+    for signal in signals {
+        write!(csv, ",{}", field(signal.name())).unwrap();
+    }
+    csv.push('\n');
+
+    for time in times {
+        write!(csv, "{}.{:09}", time / 1_000_000, time % 1_000_000 * 1000).unwrap();
+        for edges in &edges {
+            // low from each pulse start up to the next edge
+            let passed = edges.partition_point(|&edge| edge <= time);
+            let pulse = passed & 1 == 1 && passed < edges.len();
+            csv.push_str(if pulse { ",0" } else { ",1" });
+        }
+        csv.push('\n');
+    }
+
+    csv
+}
+
+/// A CSV field, quoted if needed.
+fn field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        let mut quoted = String::from("\"");
+        quoted.push_str(&text.replace('"', "\"\""));
+        quoted.push('"');
+        quoted
+    } else {
+        text.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::signal::{DutyCycle, Polarity, RawSignal, TimingSequence};
+
+    #[test]
+    fn test_to_csv() {
+        let signal = |name: &str, data: Vec<u32>| {
+            let timings = TimingSequence::new(Polarity::StartsWithPulse, data);
+            RawSignal::new(name, 38_000, DutyCycle::default(), timings).unwrap()
+        };
+        let dump = DumpFile::new(
+            1,
+            vec![
+                signal("Power", vec![100, 50, 1_000_000]),
+                signal("Vol \"up\", loud", vec![150]),
+            ],
+        );
+
+        assert_eq!(
+            to_csv(&dump),
+            "Time [s],Power,\"Vol \"\"up\"\", loud\"\n\
+             0.000000000,0,0\n\
+             0.000100000,1,0\n\
+             0.000150000,0,1\n\
+             1.000150000,1,1\n"
+        );
+    }
+}
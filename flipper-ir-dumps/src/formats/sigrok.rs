@@ -0,0 +1,178 @@
+//! [sigrok](https://sigrok.org) session files, to feed signals to the IR
+//! protocol decoders of PulseView and `sigrok-cli`.
+//!
+//! A session is a ZIP archive of the capture settings and raw logic
+//! samples. Each signal is a channel of its own, sampled at 1MHz, all
+//! starting at time 0. Channels are active low, as the demodulated output
+//! of IR receivers that sigrok's decoders expect by default.
+
+use alloc::{format, string::String, vec, vec::Vec};
+use core::fmt::Write;
+
+use displaydoc::Display;
+use thiserror::Error;
+
+use super::zip;
+use crate::dump::DumpFile;
+
+/// Samples per second, one per µs of the timings.
+const SAMPLE_RATE_MHZ: u32 = 1;
+/// Idle samples after the longest signal, so its last edge shows.
+const TAIL: usize = 10_000;
+/// Name of the file holding the samples, as sigrok names it.
+const CAPTURE_FILE: &str = "logic-1";
+/// Largest size of the samples written, a byte per µs for every 8 channels:
+/// a minute of a single channel, far more than any IR signal lasts.
+const MAX_SIZE: u64 = 64 << 20;
+
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+/// Error writing a sigrok session
+pub enum SigrokError {
+    /// Session of {samples} samples of {channels} channels is too large
+    TooLarge { samples: u64, channels: usize },
+}
+
+/// A sigrok session, the contents of a `.sr` file, with the signals of
+/// `dump` as channels named after them.
+///
+/// # Examples
+/// ```rust
+/// use flipper_ir_dumps::{examples::EXAMPLES, formats::sigrok};
+///
+/// let session = sigrok::to_session(&EXAMPLES[0].dump()).unwrap();
+/// // a ZIP archive
+/// assert_eq!(session[..2], *b"PK");
+/// ```
+///
+/// Samples take a byte per µs for every 8 signals, so sessions of signals
+/// lasting minutes are refused rather than filling the memory.
+pub fn to_session(dump: &DumpFile) -> Result<Vec<u8>, SigrokError> {
+    let channels = dump.signals().len();
+    let unit_size = channels.div_ceil(8).max(1);
+
+    let samples = dump
+        .signals()
+        .iter()
+        .map(|signal| signal.duration())
+        .max()
+        .unwrap_or_default()
+        .saturating_add(TAIL as u64);
+    if samples.saturating_mul(unit_size as u64) > MAX_SIZE {
+        return Err(SigrokError::TooLarge { samples, channels });
+    }
+    let length = samples as usize;
+
+    // all channels idle high, pulled low during pulses
+    let mut samples = vec![0xFF; length * unit_size];
+    for (channel, signal) in dump.signals().iter().enumerate() {
+        let (byte, bit) = (channel / 8, channel % 8);
+        let mut time = 0;
+        for (i, &duration) in signal.data().iter().enumerate() {
+            let end = time + duration as usize;
+            if i & 1 == 0 {
+                for sample in time..end {
+                    samples[sample * unit_size + byte] &= !(1 << bit);
+                }
+            }
+            time = end;
+        }
+    }
+
+    let metadata = metadata(dump, unit_size);
+    Ok(zip::stored(&[
+        ("version", b"2"),
+        ("metadata", metadata.as_bytes()),
+        (&format!("{}-1", CAPTURE_FILE), &samples),
+    ]))
+}
+
+fn metadata(dump: &DumpFile, unit_size: usize) -> String {
+    let mut metadata = String::new();
+    writeln!(metadata, "[global]").unwrap();
+    writeln!(metadata, "sigrok version=0.5.2").unwrap();
+    writeln!(metadata).unwrap();
+    writeln!(metadata, "[device 1]").unwrap();
+    writeln!(metadata, "capturefile={}", CAPTURE_FILE).unwrap();
+    writeln!(metadata, "total probes={}", dump.signals().len()).unwrap();
+    writeln!(metadata, "samplerate={} MHz", SAMPLE_RATE_MHZ).unwrap();
+    writeln!(metadata, "total analog=0").unwrap();
+    for (i, signal) in dump.signals().iter().enumerate() {
+        writeln!(metadata, "probe{}={}", i + 1, signal.name()).unwrap();
+    }
+    writeln!(metadata, "unitsize={}", unit_size).unwrap();
+    metadata
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signal::{DutyCycle, Polarity, RawSignal, TimingSequence};
+
+    fn signal(name: &str, data: Vec<u32>) -> RawSignal {
+        let timings = TimingSequence::new(Polarity::StartsWithPulse, data);
+        RawSignal::new(name, 38_000, DutyCycle::default(), timings).unwrap()
+    }
+
+    /// Contents of a file of a stored archive.
+    fn file<'a>(archive: &'a [u8], name: &str) -> &'a [u8] {
+        let mut rest = archive;
+        while rest[..4] == [0x50, 0x4b, 0x03, 0x04] {
+            let size = u32::from_le_bytes([rest[18], rest[19], rest[20], rest[21]]) as usize;
+            let name_length = usize::from(u16::from_le_bytes([rest[26], rest[27]]));
+            let contents = &rest[30 + name_length..30 + name_length + size];
+            if &rest[30..30 + name_length] == name.as_bytes() {
+                return contents;
+            }
+            rest = &rest[30 + name_length + size..];
+        }
+        panic!("no file {name}");
+    }
+
+    #[test]
+    fn test_session() {
+        let dump = DumpFile::new(
+            1,
+            vec![signal("Power", vec![3, 2, 1]), signal("Mute", vec![2])],
+        );
+        let session = to_session(&dump).unwrap();
+
+        assert_eq!(file(&session, "version"), b"2");
+        let metadata = core::str::from_utf8(file(&session, "metadata")).unwrap();
+        assert!(metadata.contains("capturefile=logic-1\ntotal probes=2\nsamplerate=1 MHz\n"));
+        assert!(metadata.contains("probe1=Power\nprobe2=Mute\nunitsize=1\n"));
+
+        let samples = file(&session, "logic-1-1");
+        assert_eq!(samples.len(), 6 + TAIL);
+        assert_eq!(samples[..7], [0xFC, 0xFC, 0xFE, 0xFF, 0xFF, 0xFE, 0xFF]);
+    }
+
+    #[test]
+    fn test_unit_size() {
+        let signals = (0..9).map(|i| signal(&format!("{i}"), vec![1])).collect();
+        let session = to_session(&DumpFile::new(1, signals)).unwrap();
+        let metadata = core::str::from_utf8(file(&session, "metadata")).unwrap();
+        assert!(metadata.contains("unitsize=2\n"));
+        assert_eq!(file(&session, "logic-1-1")[..4], [0x00, 0xFE, 0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn test_too_large() {
+        let dump = DumpFile::new(1, vec![signal("Long", vec![u32::MAX, 1, 1])]);
+        assert_eq!(
+            to_session(&dump),
+            Err(SigrokError::TooLarge {
+                samples: u64::from(u32::MAX) + 2 + TAIL as u64,
+                channels: 1,
+            })
+        );
+
+        // a minute fits a single channel, not nine
+        let signals = (0..9)
+            .map(|i| signal(&format!("{i}"), vec![60_000_000]))
+            .collect();
+        assert!(matches!(
+            to_session(&DumpFile::new(1, signals)),
+            Err(SigrokError::TooLarge { channels: 9, .. })
+        ));
+    }
+}
@@ -0,0 +1,421 @@
+//! Device code files of [SmartIR](https://github.com/smartHomeHub/SmartIR),
+//! the Home Assistant integration controlling climate and media devices
+//! through IR blasters.
+//!
+//! SmartIR looks codes up by what they do rather than by name, so signals
+//! are mapped by their names:
+//!
+//! - for [`climate`], `off` and `<mode>_<fan>_<temperature>`, such as
+//!   `cool_auto_24` or `fan_only_low_20`;
+//! - for [`media_player`], the button names of the Flipper universal remotes,
+//!   such as `Power`, `Vol_up` or `Ch_next`, and `Source_<name>` for inputs.
+//!
+//! Other signals are left out. Codes are written for Broadlink blasters,
+//! base64 encoded, or for ESPHome ones, as signed durations.
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::Write;
+
+use displaydoc::Display;
+use thiserror::Error;
+
+use super::{broadlink::BroadlinkCode, json::quote};
+use crate::{dump::DumpFile, signal::RawSignal};
+
+/// Media player commands by the Flipper names of their buttons, lowercase.
+const MEDIA_COMMANDS: &[(&str, &[&str])] = &[
+    ("on", &["on", "power_on"]),
+    ("off", &["off", "power_off"]),
+    (
+        "previousChannel",
+        &["ch_prev", "ch_dn", "channel_down", "prev"],
+    ),
+    ("nextChannel", &["ch_next", "ch_up", "channel_up", "next"]),
+    ("volumeDown", &["vol_dn", "vol_down", "volume_down"]),
+    ("volumeUp", &["vol_up", "volume_up"]),
+    ("mute", &["mute"]),
+];
+
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+/// Error exporting a dump to SmartIR
+pub enum SmartIrError {
+    /// No signal of the dump is named after a SmartIR command
+    NoCommands,
+    /// No signal of the dump turns the device off
+    NoOff,
+}
+
+/// The blaster SmartIR sends codes through.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum SmartIrController {
+    /// Broadlink blasters, taking base64 encoded Broadlink packets.
+    #[default]
+    Broadlink,
+    /// ESPHome nodes, taking raw durations.
+    Esphome,
+}
+
+impl SmartIrController {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Broadlink => "Broadlink",
+            Self::Esphome => "ESPHome",
+        }
+    }
+
+    fn encoding(self) -> &'static str {
+        match self {
+            Self::Broadlink => "Base64",
+            Self::Esphome => "Raw",
+        }
+    }
+
+    /// The code of a signal, as a JSON value.
+    fn code(self, signal: &RawSignal) -> String {
+        match self {
+            Self::Broadlink => quote(&BroadlinkCode::from_signal(signal).to_string()),
+            Self::Esphome => {
+                let durations: Vec<_> = signal
+                    .data()
+                    .iter()
+                    .zip([1, -1].into_iter().cycle())
+                    .map(|(&duration, sign)| (sign * i64::from(duration)).to_string())
+                    .collect();
+                format!("[{}]", durations.join(", "))
+            }
+        }
+    }
+}
+
+/// What the device file describes, besides its codes.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct SmartIrDevice {
+    pub manufacturer: String,
+    pub models: Vec<String>,
+    pub controller: SmartIrController,
+}
+
+/// The climate settings a signal applies, see [`climate_key`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ClimateKey<'a> {
+    /// HVAC mode, such as `cool` or `fan_only`.
+    pub mode: &'a str,
+    /// Fan mode, such as `auto` or `low`.
+    pub fan: &'a str,
+    pub temperature: u32,
+}
+
+/// The settings of a climate signal named `<mode>_<fan>_<temperature>`.
+///
+/// ```rust
+/// use flipper_ir_dumps::formats::smartir::{climate_key, ClimateKey};
+///
+/// assert_eq!(
+///     climate_key("fan_only_low_20"),
+///     Some(ClimateKey { mode: "fan_only", fan: "low", temperature: 20 })
+/// );
+/// assert_eq!(climate_key("off"), None);
+/// ```
+pub fn climate_key(name: &str) -> Option<ClimateKey<'_>> {
+    let (rest, temperature) = name.rsplit_once('_')?;
+    let (mode, fan) = rest.rsplit_once('_')?;
+    if mode.is_empty() || fan.is_empty() {
+        return None;
+    }
+    Some(ClimateKey {
+        mode,
+        fan,
+        temperature: temperature.parse().ok()?,
+    })
+}
+
+/// A SmartIR climate device file with the signals of `dump`, see the
+/// [module docs](self) for how they should be named.
+///
+/// # Examples
+/// ```rust
+/// use flipper_ir_dumps::{
+///     dump::DumpFile,
+///     formats::smartir::{self, SmartIrDevice},
+///     signal::{DutyCycle, Polarity, RawSignal, TimingSequence},
+/// };
+///
+/// let signal = |name: &str| {
+///     let timings = TimingSequence::new(Polarity::StartsWithPulse, vec![3000, 9000, 500]);
+///     RawSignal::new(name, 38_000, DutyCycle::default(), timings).unwrap()
+/// };
+/// let dump = DumpFile::new(1, vec![signal("off"), signal("cool_auto_24")]);
+/// let device = SmartIrDevice {
+///     manufacturer: "Samsung".to_string(),
+///     models: vec!["AR09".to_string()],
+///     ..SmartIrDevice::default()
+/// };
+///
+/// let json = smartir::climate(&dump, &device).unwrap();
+/// assert!(json.contains("\"operationModes\": [\"cool\"],\n"));
+/// ```
+pub fn climate(dump: &DumpFile, device: &SmartIrDevice) -> Result<String, SmartIrError> {
+    let off = dump
+        .signals()
+        .iter()
+        .find(|signal| signal.name().eq_ignore_ascii_case("off"))
+        .ok_or(SmartIrError::NoOff)?;
+
+    let keyed: Vec<_> = dump
+        .signals()
+        .iter()
+        .filter_map(|signal| Some((climate_key(signal.name())?, signal)))
+        .collect();
+    let (Some(min), Some(max)) = (
+        keyed.iter().map(|(key, _)| key.temperature).min(),
+        keyed.iter().map(|(key, _)| key.temperature).max(),
+    ) else {
+        return Err(SmartIrError::NoCommands);
+    };
+    let modes = distinct(keyed.iter().map(|(key, _)| key.mode));
+    let fans = distinct(keyed.iter().map(|(key, _)| key.fan));
+
+    let mut json = header(device);
+    writeln!(json, "  \"minTemperature\": {},", min).unwrap();
+    writeln!(json, "  \"maxTemperature\": {},", max).unwrap();
+    writeln!(json, "  \"precision\": 1,").unwrap();
+    writeln!(json, "  \"operationModes\": {},", json_list(&modes)).unwrap();
+    writeln!(json, "  \"fanModes\": {},", json_list(&fans)).unwrap();
+    writeln!(json, "  \"commands\": {{").unwrap();
+    write!(json, "    \"off\": {}", device.controller.code(off)).unwrap();
+
+    for mode in &modes {
+        write!(json, ",\n    {}: {{", quote(mode)).unwrap();
+        let mut first_fan = true;
+        for fan in &fans {
+            let mut temperatures: Vec<_> = keyed
+                .iter()
+                .filter(|(key, _)| key.mode == *mode && key.fan == *fan)
+                .collect();
+            if temperatures.is_empty() {
+                continue;
+            }
+            temperatures.sort_by_key(|(key, _)| key.temperature);
+
+            let separator = if first_fan { "" } else { "," };
+            first_fan = false;
+            write!(json, "{}\n      {}: {{", separator, quote(fan)).unwrap();
+            let codes: Vec<_> = temperatures
+                .iter()
+                .map(|(key, signal)| {
+                    format!(
+                        "\n        \"{}\": {}",
+                        key.temperature,
+                        device.controller.code(signal)
+                    )
+                })
+                .collect();
+            write!(json, "{}\n      }}", codes.join(",")).unwrap();
+        }
+        write!(json, "\n    }}").unwrap();
+    }
+    json.push_str("\n  }\n}\n");
+
+    Ok(json)
+}
+
+/// A SmartIR media player device file with the signals of `dump`, see the
+/// [module docs](self) for how they should be named.
+///
+/// A `Power` button toggling the device stands for both `on` and `off`,
+/// unless the dump has separate ones.
+pub fn media_player(dump: &DumpFile, device: &SmartIrDevice) -> Result<String, SmartIrError> {
+    let named = |names: &[&str]| {
+        dump.signals()
+            .iter()
+            .find(|signal| names.contains(&signal.name().to_ascii_lowercase().as_str()))
+    };
+    let power = named(&["power"]);
+
+    let commands: Vec<(&str, &RawSignal)> = MEDIA_COMMANDS
+        .iter()
+        .filter_map(|&(command, names)| {
+            let signal = named(names).or(match command {
+                "on" | "off" => power,
+                _ => None,
+            })?;
+            Some((command, signal))
+        })
+        .collect();
+    let sources: Vec<_> = dump
+        .signals()
+        .iter()
+        .filter_map(|signal| {
+            let source = signal.name().strip_prefix("Source_")?;
+            Some((source.replace('_', " "), signal))
+        })
+        .collect();
+    if commands.is_empty() && sources.is_empty() {
+        return Err(SmartIrError::NoCommands);
+    }
+
+    let mut entries: Vec<String> = commands
+        .into_iter()
+        .map(|(command, signal)| format!("    \"{}\": {}", command, device.controller.code(signal)))
+        .collect();
+    if !sources.is_empty() {
+        let sources: Vec<_> = sources
+            .iter()
+            .map(|(source, signal)| {
+                format!(
+                    "      {}: {}",
+                    quote(source),
+                    device.controller.code(signal)
+                )
+            })
+            .collect();
+        entries.push(format!(
+            "    \"sources\": {{\n{}\n    }}",
+            sources.join(",\n")
+        ));
+    }
+
+    let mut json = header(device);
+    writeln!(json, "  \"commands\": {{").unwrap();
+    json.push_str(&entries.join(",\n"));
+    json.push_str("\n  }\n}\n");
+
+    Ok(json)
+}
+
+/// The fields both kinds of devices start with, up to the ones of their own.
+fn header(device: &SmartIrDevice) -> String {
+    let models: Vec<_> = device.models.iter().map(String::as_str).collect();
+
+    let mut json = String::from("{\n");
+    writeln!(json, "  \"manufacturer\": {},", quote(&device.manufacturer)).unwrap();
+    writeln!(json, "  \"supportedModels\": {},", json_list(&models)).unwrap();
+    writeln!(
+        json,
+        "  \"supportedController\": \"{}\",",
+        device.controller.name()
+    )
+    .unwrap();
+    writeln!(
+        json,
+        "  \"commandsEncoding\": \"{}\",",
+        device.controller.encoding()
+    )
+    .unwrap();
+    json
+}
+
+/// Items in the order they first appear.
+fn distinct<'a>(items: impl Iterator<Item = &'a str>) -> Vec<&'a str> {
+    let mut distinct = Vec::new();
+    for item in items {
+        if !distinct.contains(&item) {
+            distinct.push(item);
+        }
+    }
+    distinct
+}
+
+fn json_list(items: &[&str]) -> String {
+    let items: Vec<_> = items.iter().map(|item| quote(item)).collect();
+    format!("[{}]", items.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::signal::{DutyCycle, Polarity, TimingSequence};
+
+    fn signal(name: &str, first: u32) -> RawSignal {
+        let timings = TimingSequence::new(Polarity::StartsWithPulse, vec![first, 9000, 500]);
+        RawSignal::new(name, 38_000, DutyCycle::default(), timings).unwrap()
+    }
+
+    fn device(controller: SmartIrController) -> SmartIrDevice {
+        SmartIrDevice {
+            manufacturer: "Samsung \"AC\"".to_string(),
+            models: vec!["AR09".to_string(), "AR12".to_string()],
+            controller,
+        }
+    }
+
+    #[test]
+    fn test_climate() {
+        let dump = DumpFile::new(
+            1,
+            vec![
+                signal("cool_auto_24", 1),
+                signal("Off", 2),
+                signal("cool_auto_18", 3),
+                signal("fan_only_low_20", 4),
+                signal("Swing", 5),
+            ],
+        );
+
+        let json = climate(&dump, &device(SmartIrController::Esphome)).unwrap();
+        assert_eq!(
+            json,
+            "{\n  \"manufacturer\": \"Samsung \\\"AC\\\"\",\n  \
+             \"supportedModels\": [\"AR09\", \"AR12\"],\n  \
+             \"supportedController\": \"ESPHome\",\n  \
+             \"commandsEncoding\": \"Raw\",\n  \
+             \"minTemperature\": 18,\n  \"maxTemperature\": 24,\n  \"precision\": 1,\n  \
+             \"operationModes\": [\"cool\", \"fan_only\"],\n  \
+             \"fanModes\": [\"auto\", \"low\"],\n  \
+             \"commands\": {\n    \
+             \"off\": [2, -9000, 500],\n    \
+             \"cool\": {\n      \"auto\": {\n        \
+             \"18\": [3, -9000, 500],\n        \"24\": [1, -9000, 500]\n      }\n    },\n    \
+             \"fan_only\": {\n      \"low\": {\n        \"20\": [4, -9000, 500]\n      }\n    }\n  \
+             }\n}\n"
+        );
+
+        let only_off = DumpFile::new(1, vec![signal("off", 1)]);
+        assert_eq!(
+            climate(&only_off, &SmartIrDevice::default()),
+            Err(SmartIrError::NoCommands)
+        );
+        let no_off = DumpFile::new(1, vec![signal("cool_auto_24", 1)]);
+        assert_eq!(
+            climate(&no_off, &SmartIrDevice::default()),
+            Err(SmartIrError::NoOff)
+        );
+    }
+
+    #[test]
+    fn test_media_player() {
+        let dump = DumpFile::new(
+            1,
+            vec![
+                signal("Power", 1),
+                signal("Vol_up", 2),
+                signal("MUTE", 3),
+                signal("Source_HDMI_1", 4),
+            ],
+        );
+
+        let json = media_player(&dump, &device(SmartIrController::Esphome)).unwrap();
+        assert!(json.ends_with(
+            "  \"commands\": {\n    \"on\": [1, -9000, 500],\n    \"off\": [1, -9000, 500],\n    \
+             \"volumeUp\": [2, -9000, 500],\n    \"mute\": [3, -9000, 500],\n    \
+             \"sources\": {\n      \"HDMI 1\": [4, -9000, 500]\n    }\n  }\n}\n"
+        ));
+
+        let broadlink = media_player(&dump, &device(SmartIrController::Broadlink)).unwrap();
+        let code = BroadlinkCode::from_signal(&dump.signals()[1]).to_string();
+        assert!(broadlink.contains(&format!("\"volumeUp\": \"{}\"", code)));
+        assert!(broadlink.contains("\"commandsEncoding\": \"Base64\""));
+
+        let unrelated = DumpFile::new(1, vec![signal("Swing", 1)]);
+        assert_eq!(
+            media_player(&unrelated, &SmartIrDevice::default()),
+            Err(SmartIrError::NoCommands)
+        );
+    }
+}
@@ -0,0 +1,227 @@
+//! Bare lists of timings, as oscilloscopes, serial logs and scripts print
+//! them:
+//!
+//! ```text
+//! 9024, 4512, 564, 564
+//! ```
+//!
+//! Numbers are separated by commas, semicolons or whitespace, newlines
+//! included. They alternate between pulses and pauses, unless they are
+//! signed: then `+` marks a pulse and `-` a pause, as in the timings of
+//! IrScrutinizer and Pronto tools.
+
+use alloc::{string::String, vec::Vec};
+
+use displaydoc::Display;
+use thiserror::Error;
+
+use crate::signal::{DutyCycle, Frequency, Polarity, PolarityError, RawSignal, TimingSequence};
+
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+/// Error parsing a timing list
+pub enum TimingListError {
+    /// Invalid timing `{0}`
+    InvalidNumber(String),
+    /// Some timings are signed and some aren't, can't tell pulses from pauses
+    MixedSigns,
+    /// List has no timings
+    Empty,
+    /// {0}
+    Polarity(#[from] PolarityError),
+}
+
+/// Unit of the numbers of a timing list.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum TimingUnit {
+    Nanoseconds,
+    #[default]
+    Microseconds,
+    Milliseconds,
+    Seconds,
+}
+
+impl TimingUnit {
+    /// Microseconds in one unit.
+    fn microseconds(self) -> f64 {
+        match self {
+            TimingUnit::Nanoseconds => 0.001,
+            TimingUnit::Microseconds => 1.0,
+            TimingUnit::Milliseconds => 1_000.0,
+            TimingUnit::Seconds => 1_000_000.0,
+        }
+    }
+}
+
+/// How the numbers of a timing list are read, see [`parse`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct TimingListOptions {
+    pub unit: TimingUnit,
+    /// Level of the first number of an unsigned list. Signed lists carry
+    /// their own.
+    pub polarity: Polarity,
+    pub frequency: Frequency,
+}
+
+impl Default for TimingListOptions {
+    fn default() -> Self {
+        Self {
+            unit: TimingUnit::default(),
+            polarity: Polarity::StartsWithPulse,
+            frequency: Frequency::from_hz(38_000),
+        }
+    }
+}
+
+/// Parse a timing list into a signal named `name`.
+///
+/// Timings are rounded to whole µs. Leading and trailing pauses are
+/// dropped, as by [`RawSignal::new`].
+///
+/// # Examples
+/// ```rust
+/// use flipper_ir_dumps::formats::timings::{self, TimingListOptions, TimingUnit};
+///
+/// let signal = timings::parse("power", "9024, 4512, 564", &TimingListOptions::default()).unwrap();
+/// assert_eq!(signal.data(), [9024, 4512, 564]);
+///
+/// let options = TimingListOptions {
+///     unit: TimingUnit::Milliseconds,
+///     ..TimingListOptions::default()
+/// };
+/// let signal = timings::parse("power", "-20\n+9.024\n-4.512\n+0.564\n", &options).unwrap();
+/// assert_eq!(signal.data(), [9024, 4512, 564]);
+/// ```
+pub fn parse(
+    name: &str,
+    list: &str,
+    options: &TimingListOptions,
+) -> Result<RawSignal, TimingListError> {
+    let scale = options.unit.microseconds();
+    let timings = list
+        .split(|c: char| c == ',' || c == ';' || c.is_whitespace())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let (sign, magnitude) = match word.as_bytes()[0] {
+                b'+' => (Some(true), &word[1..]),
+                b'-' => (Some(false), &word[1..]),
+                _ => (None, word),
+            };
+            match magnitude.parse::<f64>() {
+                Ok(value) if value.is_finite() && !magnitude.starts_with(['+', '-']) => {
+                    Ok((sign, (value * scale + 0.5) as u32))
+                }
+                _ => Err(TimingListError::InvalidNumber(word.into())),
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let Some(&(first_sign, _)) = timings.first() else {
+        return Err(TimingListError::Empty);
+    };
+    let sequence = if first_sign.is_some() {
+        let levels = timings
+            .into_iter()
+            .map(|(sign, duration)| sign.map(|pulse| (pulse, duration)))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(TimingListError::MixedSigns)?;
+        TimingSequence::from_levels(levels)
+    } else {
+        let durations = timings
+            .into_iter()
+            .map(|(sign, duration)| sign.is_none().then_some(duration))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(TimingListError::MixedSigns)?;
+        TimingSequence::new(options.polarity, durations)
+    };
+
+    Ok(RawSignal::new(
+        name,
+        options.frequency,
+        DutyCycle::default(),
+        sequence,
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let options = TimingListOptions::default();
+        let list = indoc! {"
+            9024
+            4512
+            564;564
+            564, 1692\t564
+        "};
+        assert_eq!(
+            parse("power", list, &options).unwrap().data(),
+            [9024, 4512, 564, 564, 564, 1692, 564]
+        );
+
+        let options = TimingListOptions {
+            unit: TimingUnit::Nanoseconds,
+            polarity: Polarity::StartsWithPause,
+            frequency: Frequency::from_hz(36_000),
+        };
+        let signal = parse("power", "50000000,889400,889600,889000", &options).unwrap();
+        assert_eq!(signal.data(), [889, 890, 889]);
+        assert_eq!(signal.frequency().hz(), 36_000);
+    }
+
+    #[test]
+    fn test_signed() {
+        let options = TimingListOptions {
+            // signs take over
+            polarity: Polarity::StartsWithPause,
+            ..TimingListOptions::default()
+        };
+        assert_eq!(
+            parse("power", "+9024 -4512 +300 +264 -564", &options)
+                .unwrap()
+                .data(),
+            [9024, 4512, 564]
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let options = TimingListOptions::default();
+        assert_eq!(
+            parse("power", "9024, 45x12", &options),
+            Err(TimingListError::InvalidNumber("45x12".into()))
+        );
+        assert_eq!(
+            parse("power", "+-9024", &options),
+            Err(TimingListError::InvalidNumber("+-9024".into()))
+        );
+        assert_eq!(
+            parse("power", "inf", &options),
+            Err(TimingListError::InvalidNumber("inf".into()))
+        );
+        assert_eq!(
+            parse("power", "+9024 4512", &options),
+            Err(TimingListError::MixedSigns)
+        );
+        assert_eq!(
+            parse("power", "9024 -4512", &options),
+            Err(TimingListError::MixedSigns)
+        );
+        assert_eq!(
+            parse("power", " ,\n", &options),
+            Err(TimingListError::Empty)
+        );
+
+        let unknown = TimingListOptions {
+            polarity: Polarity::Unknown,
+            ..TimingListOptions::default()
+        };
+        assert_eq!(
+            parse("power", "9024 4512 564", &unknown),
+            Err(TimingListError::Polarity(PolarityError::Unknown))
+        );
+    }
+}
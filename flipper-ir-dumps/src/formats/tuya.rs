@@ -0,0 +1,287 @@
+//! Tuya IR codes, as used by cheap Wi-Fi IR blasters.
+//!
+//! A code is the base64 encoding of alternating pulses and pauses in µs,
+//! pulse first, each a little-endian `u16`, compressed with FastLZ (level 1).
+//! The carrier isn't part of the code, the blasters send at 38kHz.
+
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+
+use displaydoc::Display;
+use thiserror::Error;
+
+use super::base64;
+use crate::signal::{DutyCycle, Frequency, Polarity, RawSignal, TimingSequence};
+
+/// Tuya blasters transmit all codes on this carrier.
+const FREQUENCY: u32 = 38_000;
+/// Longest run of literal bytes of a FastLZ block.
+const MAX_LITERALS: usize = 32;
+/// Shortest and longest back references of FastLZ level 1.
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 7 + 255 + 2;
+/// Farthest back a FastLZ level 1 reference reaches.
+const MAX_DISTANCE: usize = 1 << 13;
+
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+/// Error decoding a Tuya code
+pub enum TuyaError {
+    /// Code is not valid base64
+    InvalidBase64,
+    /// Compressed data is truncated
+    Truncated,
+    /// Compressed data refers back to {distance} bytes before byte {position}
+    InvalidReference { position: usize, distance: usize },
+    /// Durations have an odd number of bytes
+    OddLength,
+    /// Code has no pulses
+    Empty,
+}
+
+/// A Tuya IR code.
+///
+/// # Examples
+/// ```rust
+/// use flipper_ir_dumps::formats::tuya::TuyaCode;
+///
+/// let code = TuyaCode {
+///     data: vec![9000, 4500, 560, 560, 560, 560, 560],
+/// };
+/// let encoded = code.to_string();
+/// assert_eq!(encoded.parse::<TuyaCode>().unwrap(), code);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TuyaCode {
+    /// Alternating pulses and pauses in µs, pulse first.
+    pub data: Vec<u32>,
+}
+
+impl TuyaCode {
+    /// The code of a signal; durations over 65ms are cut short.
+    pub fn from_signal(signal: &RawSignal) -> Self {
+        Self {
+            data: signal.data().to_vec(),
+        }
+    }
+
+    /// A raw signal sending the code.
+    pub fn to_signal(&self, name: impl Into<String>) -> Result<RawSignal, TuyaError> {
+        let timings = TimingSequence::new(Polarity::StartsWithPulse, self.data.clone());
+        RawSignal::new(
+            name,
+            Frequency::from_hz(FREQUENCY),
+            DutyCycle::default(),
+            timings,
+        )
+        .map_err(|_| TuyaError::Empty)
+    }
+}
+
+impl core::str::FromStr for TuyaCode {
+    type Err = TuyaError;
+
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        let compressed = base64::decode(code).ok_or(TuyaError::InvalidBase64)?;
+        let bytes = decompress(&compressed)?;
+        if bytes.len() & 1 == 1 {
+            return Err(TuyaError::OddLength);
+        }
+
+        let data = bytes
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]).into())
+            .collect();
+        Ok(Self { data })
+    }
+}
+
+/// The base64 encoded, compressed code.
+impl fmt::Display for TuyaCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bytes: Vec<u8> = self
+            .data
+            .iter()
+            .flat_map(|&duration| (duration.min(u16::MAX.into()) as u16).to_le_bytes())
+            .collect();
+        f.write_str(&base64::encode(&compress(&bytes)))
+    }
+}
+
+/// Decompress FastLZ level 1 blocks: runs of literal bytes, and references
+/// copying bytes already output.
+fn decompress(input: &[u8]) -> Result<Vec<u8>, TuyaError> {
+    let mut output = Vec::new();
+    let mut input = input.iter().copied();
+
+    while let Some(header) = input.next() {
+        let (length, high) = (usize::from(header >> 5), usize::from(header & 0x1F));
+        if length == 0 {
+            for _ in 0..=high {
+                output.push(input.next().ok_or(TuyaError::Truncated)?);
+            }
+            continue;
+        }
+
+        let length = match length {
+            7 => length + usize::from(input.next().ok_or(TuyaError::Truncated)?),
+            _ => length,
+        } + 2;
+        let distance = (high << 8 | usize::from(input.next().ok_or(TuyaError::Truncated)?)) + 1;
+        let start = output
+            .len()
+            .checked_sub(distance)
+            .ok_or(TuyaError::InvalidReference {
+                position: output.len(),
+                distance,
+            })?;
+        for i in 0..length {
+            output.push(output[start + i]);
+        }
+    }
+
+    Ok(output)
+}
+
+/// Compress with FastLZ level 1, greedily taking the longest reference.
+fn compress(input: &[u8]) -> Vec<u8> {
+    let mut output = Vec::new();
+    let mut literals: &[u8] = &[];
+    let flush = |literals: &mut &[u8], output: &mut Vec<u8>| {
+        for chunk in literals.chunks(MAX_LITERALS) {
+            output.push((chunk.len() - 1) as u8);
+            output.extend(chunk);
+        }
+        *literals = &[];
+    };
+
+    let mut position = 0;
+    while position < input.len() {
+        let longest = (position.saturating_sub(MAX_DISTANCE)..position)
+            .map(|start| {
+                let length = input[position..]
+                    .iter()
+                    .zip(&input[start..])
+                    .take(MAX_MATCH)
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                (length, position - start)
+            })
+            .max_by_key(|&(length, distance)| (length, usize::MAX - distance));
+
+        match longest {
+            Some((length, distance)) if length >= MIN_MATCH => {
+                flush(&mut literals, &mut output);
+                let (extra, distance) = (length - 2, distance - 1);
+                let high = (distance >> 8) as u8;
+                if extra < 7 {
+                    output.push((extra as u8) << 5 | high);
+                } else {
+                    output.extend([7 << 5 | high, (extra - 7) as u8]);
+                }
+                output.push(distance as u8);
+                position += length;
+            }
+            _ => {
+                literals = &input[position - literals.len()..=position];
+                position += 1;
+            }
+        }
+    }
+    flush(&mut literals, &mut output);
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::ToString, vec};
+
+    use super::*;
+    use crate::{examples::EXAMPLES, protocol};
+
+    #[test]
+    fn test_fastlz() {
+        // a literal run, then three bytes back from one byte before
+        assert_eq!(
+            decompress(&[0x02, b'a', b'b', b'c', 0x20, 0x02]).unwrap(),
+            b"abcabc"
+        );
+        // long reference, overlapping its own output
+        assert_eq!(
+            decompress(&[0x00, b'x', 0xE0, 0x01, 0x00]).unwrap(),
+            b"xxxxxxxxxxx"
+        );
+        assert_eq!(
+            decompress(&[0x20, 0x05]),
+            Err(TuyaError::InvalidReference {
+                position: 0,
+                distance: 6
+            })
+        );
+        assert_eq!(decompress(&[0x03, b'a']), Err(TuyaError::Truncated));
+
+        for input in [
+            &b""[..],
+            b"a",
+            b"abcabcabcabc",
+            &[7; 1000],
+            &(0..=255).cycle().take(9000).collect::<Vec<u8>>(),
+        ] {
+            let compressed = compress(input);
+            assert_eq!(decompress(&compressed).unwrap(), input);
+        }
+        assert!(compress(&[7; 1000]).len() < 20);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        for signal in EXAMPLES[0].dump().signals() {
+            let code = TuyaCode::from_signal(signal);
+            let parsed: TuyaCode = code.to_string().parse().unwrap();
+            assert_eq!(parsed, code);
+            assert_eq!(
+                parsed.to_signal(signal.name()).unwrap().data(),
+                signal.data()
+            );
+        }
+    }
+
+    #[test]
+    fn test_decodes() {
+        // NEC address 0x04 command 0x08
+        let mut data = vec![9000, 4500];
+        for byte in [0x04u8, 0xFB, 0x08, 0xF7] {
+            for bit in 0..8 {
+                data.extend([560, if byte >> bit & 1 == 1 { 1690 } else { 560 }]);
+            }
+        }
+        data.push(560);
+
+        let encoded = TuyaCode { data }.to_string();
+        // 134 bytes of durations, mostly repeats
+        assert!(encoded.len() < 100, "{encoded}");
+
+        let signal = encoded
+            .parse::<TuyaCode>()
+            .unwrap()
+            .to_signal("power")
+            .unwrap();
+        match protocol::decode(signal.data(), 38_000) {
+            Some(protocol::Decoded::Nec(nec)) => {
+                assert_eq!((nec.address, nec.command), (0x04, 0x08))
+            }
+            decoded => panic!("decoded as {decoded:?}"),
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!("%%".parse::<TuyaCode>(), Err(TuyaError::InvalidBase64));
+        // a single literal byte
+        assert_eq!("AAE=".parse::<TuyaCode>(), Err(TuyaError::OddLength));
+        assert_eq!(
+            TuyaCode { data: vec![] }.to_signal("empty"),
+            Err(TuyaError::Empty)
+        );
+    }
+}
@@ -0,0 +1,134 @@
+//! Value change dumps of signals, to look at them in waveform viewers such
+//! as GTKWave or PulseView next to logic analyzer traces.
+//!
+//! Each signal is a wire of its own, high during pulses, all starting at
+//! time 0 with a 1µs timescale. The carrier isn't rendered, the wires
+//! follow what an IR receiver would output, with the opposite polarity.
+
+use alloc::{string::String, vec::Vec};
+use core::fmt::Write;
+
+use crate::dump::DumpFile;
+
+/// Characters VCD identifier codes are made of.
+const ID_CHARS: core::ops::RangeInclusive<u8> = b'!'..=b'~';
+
+/// A value change dump of the signals of `dump`.
+///
+/// Names are written with whitespace replaced by underscores, which VCD
+/// would split on.
+///
+/// # Examples
+/// ```rust
+/// use flipper_ir_dumps::{examples::EXAMPLES, formats::vcd};
+///
+/// let vcd = vcd::to_vcd(&EXAMPLES[0].dump());
+/// assert!(vcd.contains("$var wire 1 ! On_h_16 $end\n"));
+/// assert!(vcd.contains("#0\n1!\n"));
+/// ```
+pub fn to_vcd(dump: &DumpFile) -> String {
+    let ids: Vec<String> = (0..dump.signals().len()).map(identifier).collect();
+
+    let mut vcd = String::new();
+    writeln!(vcd, "$version flipper-ir-dumps $end").unwrap();
+    writeln!(vcd, "$timescale 1us $end").unwrap();
+    writeln!(vcd, "$scope module ir $end").unwrap();
+    for (signal, id) in dump.signals().iter().zip(&ids) {
+        let name: Vec<_> = signal.name().split_whitespace().collect();
+        writeln!(vcd, "$var wire 1 {} {} $end", id, name.join("_")).unwrap();
+    }
+    writeln!(vcd, "$upscope $end").unwrap();
+    writeln!(vcd, "$enddefinitions $end").unwrap();
+
+    writeln!(vcd, "$dumpvars").unwrap();
+    for id in &ids {
+        writeln!(vcd, "0{}", id).unwrap();
+    }
+    writeln!(vcd, "$end").unwrap();
+
+    // changes of all wires, in time order and then in the order of signals
+    let mut changes: Vec<(u64, usize, bool)> = Vec::new();
+    for (index, signal) in dump.signals().iter().enumerate() {
+        let mut time = 0;
+        for (i, &duration) in signal.data().iter().enumerate() {
+            changes.push((time, index, i & 1 == 0));
+            time += u64::from(duration);
+        }
+        changes.push((time, index, false));
+    }
+    changes.sort_by_key(|&(time, index, _)| (time, index));
+
+    let mut last_time = None;
+    for (time, index, high) in changes {
+        if last_time != Some(time) {
+            writeln!(vcd, "#{}", time).unwrap();
+            last_time = Some(time);
+        }
+        writeln!(vcd, "{}{}", u8::from(high), ids[index]).unwrap();
+    }
+
+    vcd
+}
+
+/// The identifier code of the `index`th wire: `!`, `"`, ... `~`, then two
+/// characters and so on.
+fn identifier(mut index: usize) -> String {
+    let base = ID_CHARS.len();
+    let mut id = String::new();
+    loop {
+        id.push(char::from(ID_CHARS.start() + (index % base) as u8));
+        index /= base;
+        if index == 0 {
+            return id;
+        }
+        index -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::signal::{DutyCycle, Polarity, RawSignal, TimingSequence};
+
+    #[test]
+    fn test_to_vcd() {
+        let signal = |name: &str, data: Vec<u32>| {
+            let timings = TimingSequence::new(Polarity::StartsWithPulse, data);
+            RawSignal::new(name, 38_000, DutyCycle::default(), timings).unwrap()
+        };
+        let dump = DumpFile::new(
+            1,
+            vec![
+                signal("Power on", vec![100, 50, 100]),
+                signal("Mute", vec![150]),
+            ],
+        );
+
+        assert_eq!(
+            to_vcd(&dump),
+            "$version flipper-ir-dumps $end\n\
+             $timescale 1us $end\n\
+             $scope module ir $end\n\
+             $var wire 1 ! Power_on $end\n\
+             $var wire 1 \" Mute $end\n\
+             $upscope $end\n\
+             $enddefinitions $end\n\
+             $dumpvars\n0!\n0\"\n$end\n\
+             #0\n1!\n1\"\n\
+             #100\n0!\n\
+             #150\n1!\n0\"\n\
+             #250\n0!\n"
+        );
+    }
+
+    #[test]
+    fn test_identifier() {
+        assert_eq!(identifier(0), "!");
+        assert_eq!(identifier(93), "~");
+        assert_eq!(identifier(94), "!!");
+        assert_eq!(identifier(95), "\"!");
+        assert_eq!(identifier(94 + 94 * 94), "!!!");
+    }
+}
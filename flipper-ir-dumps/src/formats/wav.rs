@@ -0,0 +1,580 @@
+//! WAV audio of signals, to send them from a sound card or look at them in
+//! an audio editor such as Audacity.
+//!
+//! Sound cards can drive IR LEDs straight from their output: the
+//! [`WavCarrier`] picks how the carrier is rendered, as sound cards rarely
+//! reach the sample rates a 38kHz square wave needs.
+//!
+//! The other way around, [`from_wav`] reads the recording of an IR
+//! photodiode plugged into a microphone input as a signal.
+
+use alloc::{string::String, vec::Vec};
+
+use displaydoc::Display;
+use thiserror::Error;
+
+use crate::signal::{DutyCycle, Frequency, Polarity, RawSignal, TimingSequence};
+
+/// Peak level of rendered samples, a little under full scale.
+const AMPLITUDE: i16 = 30_000;
+
+/// WAV format tags of integer and floating point samples, and of the
+/// extensible format giving either further in.
+const FORMAT_PCM: u16 = 1;
+const FORMAT_FLOAT: u16 = 3;
+const FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// Most samples rendered, 64MiB of them: over two minutes at 192kHz,
+/// far more than any IR signal lasts.
+const MAX_SAMPLES: u64 = 32 << 20;
+
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+/// Error reading or writing WAV audio
+pub enum WavError {
+    /// Not a WAV file
+    NotWav,
+    /// File is missing its {0} chunk
+    MissingChunk(&'static str),
+    /// Samples of format {format} and {bits} bits aren't supported
+    UnsupportedFormat { format: u16, bits: u16 },
+    /// Recording has no channel {0}
+    NoChannel(u16),
+    /// Recording has no pulses above the threshold
+    NoSignal,
+    /// Signal of {0} samples is too long to render
+    TooLong(u64),
+}
+
+/// How the carrier of pulses is rendered.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum WavCarrier {
+    /// A mono square wave at the carrier frequency and duty cycle, needing
+    /// a sample rate several times the carrier.
+    #[default]
+    Modulated,
+    /// Square waves at half the carrier frequency, inverted between the
+    /// left and right channels, for the common transmitter with two LEDs
+    /// wired in anti-parallel across the channels: each of them lights on
+    /// every other half period, together at the full carrier frequency.
+    StereoHalfFrequency,
+    /// Pulses as a constant level, without carrier, as IR receivers output
+    /// them.
+    Envelope,
+}
+
+/// How signals are rendered, see [`to_wav`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct WavOptions {
+    /// Samples per second.
+    pub sample_rate: u32,
+    pub carrier: WavCarrier,
+}
+
+impl Default for WavOptions {
+    fn default() -> Self {
+        Self {
+            sample_rate: 192_000,
+            carrier: WavCarrier::default(),
+        }
+    }
+}
+
+/// A 16 bit PCM WAV file of a signal, pauses silent.
+///
+/// Signals without a carrier frequency are rendered as their envelope
+/// whatever the options. Signals lasting minutes are refused rather than
+/// filling the memory, see [`WavError::TooLong`].
+///
+/// # Examples
+/// ```rust
+/// use flipper_ir_dumps::{
+///     examples::EXAMPLES,
+///     formats::wav::{self, WavCarrier, WavOptions},
+/// };
+///
+/// let dump = EXAMPLES[0].dump();
+/// let options = WavOptions {
+///     sample_rate: 48_000,
+///     carrier: WavCarrier::StereoHalfFrequency,
+/// };
+/// let audio = wav::to_wav(&dump.signals()[0], &options).unwrap();
+/// assert_eq!(&audio[..4], b"RIFF");
+/// ```
+pub fn to_wav(signal: &RawSignal, options: &WavOptions) -> Result<Vec<u8>, WavError> {
+    let rate = f64::from(options.sample_rate);
+    let frequency = f64::from(signal.frequency().hz());
+    let duty_cycle = f64::from(signal.duty_cycle().get());
+    let carrier = if frequency > 0.0 {
+        options.carrier
+    } else {
+        WavCarrier::Envelope
+    };
+    let channels = match carrier {
+        WavCarrier::StereoHalfFrequency => 2,
+        WavCarrier::Modulated | WavCarrier::Envelope => 1,
+    };
+
+    let length = sample_at(signal.duration(), rate).saturating_mul(channels.into());
+    if length > MAX_SAMPLES {
+        return Err(WavError::TooLong(length));
+    }
+
+    let mut samples: Vec<i16> = Vec::with_capacity(length as usize);
+    let mut elapsed = 0u64;
+    for (i, &duration) in signal.data().iter().enumerate() {
+        // sample boundaries from the total time so far, to not drift
+        let start = sample_at(elapsed, rate);
+        elapsed += u64::from(duration);
+        let end = sample_at(elapsed, rate);
+        let pulse = i & 1 == 0;
+
+        for sample in start..end {
+            let phase = sample as f64 * frequency / rate;
+            match (pulse, carrier) {
+                (false, WavCarrier::StereoHalfFrequency) => samples.extend([0, 0]),
+                (false, _) => samples.push(0),
+                (true, WavCarrier::Envelope) => samples.push(AMPLITUDE),
+                (true, WavCarrier::Modulated) => samples.push(square(phase, duty_cycle)),
+                (true, WavCarrier::StereoHalfFrequency) => {
+                    let left = square(phase / 2.0, 0.5);
+                    samples.extend([left, -left]);
+                }
+            }
+        }
+    }
+
+    let mut wav = header(options.sample_rate, channels, samples.len());
+    for sample in samples {
+        wav.extend(sample.to_le_bytes());
+    }
+    Ok(wav)
+}
+
+/// Index of the sample at `micros` µs.
+fn sample_at(micros: u64, rate: f64) -> u64 {
+    (micros as f64 * rate / 1_000_000.0 + 0.5) as u64
+}
+
+/// A square wave `phase` periods in, high for the `duty_cycle` of each.
+fn square(phase: f64, duty_cycle: f64) -> i16 {
+    if phase - ((phase as u64) as f64) < duty_cycle {
+        AMPLITUDE
+    } else {
+        -AMPLITUDE
+    }
+}
+
+/// How recordings are turned into signals, see [`from_wav`].
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct WavDecodeOptions {
+    /// Level above which the LED counts as on, as a fraction of the
+    /// loudest sample.
+    pub threshold: f32,
+    /// Quiet stretches up to this long, in µs, are the gaps of the carrier
+    /// within a pulse rather than pauses.
+    pub max_carrier_gap: u32,
+    /// Channel to read, counting from 0.
+    pub channel: u16,
+    /// Carrier of the signal, which recordings are too coarse to measure.
+    pub frequency: Frequency,
+}
+
+impl Default for WavDecodeOptions {
+    fn default() -> Self {
+        Self {
+            threshold: 0.3,
+            max_carrier_gap: 150,
+            channel: 0,
+            frequency: Frequency::from_hz(38_000),
+        }
+    }
+}
+
+/// A signal from a WAV recording, such as one of an IR photodiode plugged
+/// into a microphone input.
+///
+/// The recording is rectified, as sound cards may invert it, then
+/// thresholded: loud stretches are pulses, and quiet ones pauses unless
+/// they are short enough to be within the carrier. Silence before the first
+/// pulse and after the last one is dropped.
+///
+/// Integer samples of 8 to 32 bits and floating point ones are read.
+///
+/// # Examples
+/// ```rust
+/// use flipper_ir_dumps::{
+///     examples::EXAMPLES,
+///     formats::wav::{self, WavDecodeOptions, WavOptions},
+/// };
+///
+/// let dump = EXAMPLES[0].dump();
+/// let recording = wav::to_wav(&dump.signals()[0], &WavOptions::default()).unwrap();
+/// let signal = wav::from_wav("On", &recording, &WavDecodeOptions::default()).unwrap();
+/// assert!(signal.is_duplicate_of(&dump.signals()[0], 0.05));
+/// ```
+pub fn from_wav(
+    name: impl Into<String>,
+    wav: &[u8],
+    options: &WavDecodeOptions,
+) -> Result<RawSignal, WavError> {
+    let recording = Recording::parse(wav)?;
+    let levels = recording.levels(options.channel)?;
+
+    let peak = levels.iter().copied().fold(0.0, f32::max);
+    if peak == 0.0 {
+        return Err(WavError::NoSignal);
+    }
+    let threshold = peak * options.threshold;
+    let rate = u64::from(recording.sample_rate.max(1));
+    let max_gap = (u64::from(options.max_carrier_gap) * rate / 1_000_000) as usize;
+
+    // pulses as ranges of samples
+    let mut pulses: Vec<(usize, usize)> = Vec::new();
+    for (i, _) in levels
+        .iter()
+        .enumerate()
+        .filter(|(_, &level)| level >= threshold)
+    {
+        match pulses.last_mut() {
+            Some((_, end)) if i - *end <= max_gap => *end = i + 1,
+            _ => pulses.push((i, i + 1)),
+        }
+    }
+
+    let micros = |sample: usize| (sample as u64 * 1_000_000 + rate / 2) / rate;
+    let mut data = Vec::with_capacity(pulses.len() * 2);
+    let mut previous_end = None;
+    for (start, end) in pulses {
+        if let Some(previous_end) = previous_end {
+            data.push((micros(start) - micros(previous_end)) as u32);
+        }
+        data.push((micros(end) - micros(start)).max(1) as u32);
+        previous_end = Some(end);
+    }
+
+    let timings = TimingSequence::new(Polarity::StartsWithPulse, data);
+    RawSignal::new(name, options.frequency, DutyCycle::default(), timings)
+        .map_err(|_| WavError::NoSignal)
+}
+
+/// The parts of a WAV file needed to read its samples.
+struct Recording<'a> {
+    format: u16,
+    channels: u16,
+    sample_rate: u32,
+    bits: u16,
+    data: &'a [u8],
+}
+
+impl<'a> Recording<'a> {
+    fn parse(wav: &'a [u8]) -> Result<Self, WavError> {
+        if wav.len() < 12 || &wav[..4] != b"RIFF" || &wav[8..12] != b"WAVE" {
+            return Err(WavError::NotWav);
+        }
+
+        let mut format = None;
+        let mut data = None;
+        let mut rest = &wav[12..];
+        while rest.len() >= 8 {
+            let size = u32::from_le_bytes([rest[4], rest[5], rest[6], rest[7]]) as usize;
+            // recorders may leave the size of the last chunk unset
+            let body = &rest[8..(8 + size).min(rest.len())];
+            match &rest[..4] {
+                b"fmt " if body.len() >= 16 => format = Some(body),
+                b"data" => data = Some(body),
+                _ => {}
+            }
+            // chunks are padded to even sizes
+            rest = rest.get(8 + size + (size & 1)..).unwrap_or_default();
+        }
+
+        let format = format.ok_or(WavError::MissingChunk("fmt"))?;
+        let u16_at = |i: usize| u16::from_le_bytes([format[i], format[i + 1]]);
+        let tag = match u16_at(0) {
+            // the tag is the first two bytes of the subformat GUID
+            FORMAT_EXTENSIBLE if format.len() >= 26 => u16_at(24),
+            tag => tag,
+        };
+
+        Ok(Self {
+            format: tag,
+            channels: u16_at(2),
+            sample_rate: u32::from_le_bytes([format[4], format[5], format[6], format[7]]),
+            bits: u16_at(14),
+            data: data.ok_or(WavError::MissingChunk("data"))?,
+        })
+    }
+
+    /// Absolute levels of the samples of `channel`, from 0 to 1.
+    fn levels(&self, channel: u16) -> Result<Vec<f32>, WavError> {
+        if channel >= self.channels {
+            return Err(WavError::NoChannel(channel));
+        }
+        let unsupported = WavError::UnsupportedFormat {
+            format: self.format,
+            bits: self.bits,
+        };
+        let width = usize::from(self.bits / 8);
+        let level: fn(&[u8]) -> f32 = match (self.format, self.bits) {
+            (FORMAT_PCM, 8) => |bytes| (f32::from(bytes[0]) - 128.0) / 128.0,
+            (FORMAT_PCM, 16) => {
+                |bytes| f32::from(i16::from_le_bytes([bytes[0], bytes[1]])) / 32_768.0
+            }
+            (FORMAT_PCM, 24) => |bytes| {
+                (i32::from_le_bytes([0, bytes[0], bytes[1], bytes[2]]) >> 8) as f32 / 8_388_608.0
+            },
+            (FORMAT_PCM, 32) => |bytes| {
+                i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32
+                    / 2_147_483_648.0
+            },
+            (FORMAT_FLOAT, 32) => {
+                |bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+            }
+            _ => return Err(unsupported),
+        };
+
+        let frame = width * usize::from(self.channels);
+        let offset = width * usize::from(channel);
+        Ok(self
+            .data
+            .chunks_exact(frame)
+            .map(|frame| {
+                let level = level(&frame[offset..offset + width]);
+                if level < 0.0 {
+                    -level
+                } else {
+                    level
+                }
+            })
+            .collect())
+    }
+}
+
+/// The RIFF header of a 16 bit PCM file of `samples` samples, counting
+/// those of all channels.
+fn header(sample_rate: u32, channels: u16, samples: usize) -> Vec<u8> {
+    let data_size = (samples * 2) as u32;
+    let block_align = channels * 2;
+
+    let mut header = Vec::with_capacity(44 + samples * 2);
+    header.extend(b"RIFF");
+    header.extend((36 + data_size).to_le_bytes());
+    header.extend(b"WAVE");
+    header.extend(b"fmt ");
+    header.extend(16u32.to_le_bytes());
+    // PCM
+    header.extend(1u16.to_le_bytes());
+    header.extend(channels.to_le_bytes());
+    header.extend(sample_rate.to_le_bytes());
+    header.extend((sample_rate * u32::from(block_align)).to_le_bytes());
+    header.extend(block_align.to_le_bytes());
+    header.extend(16u16.to_le_bytes());
+    header.extend(b"data");
+    header.extend(data_size.to_le_bytes());
+    header
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::examples::EXAMPLES;
+
+    fn signal(frequency: u32, data: Vec<u32>) -> RawSignal {
+        let timings = TimingSequence::new(Polarity::StartsWithPulse, data);
+        RawSignal::new("test", frequency, DutyCycle::new(0.5).unwrap(), timings).unwrap()
+    }
+
+    fn samples(wav: &[u8]) -> Vec<i16> {
+        wav[44..]
+            .chunks_exact(2)
+            .map(|pair| i16::from_le_bytes([pair[0], pair[1]]))
+            .collect()
+    }
+
+    #[test]
+    fn test_header() {
+        let options = WavOptions {
+            sample_rate: 48_000,
+            carrier: WavCarrier::Envelope,
+        };
+        let wav = to_wav(&signal(38_000, vec![1000, 500, 1000]), &options).unwrap();
+        // 2.5ms at 48kHz
+        assert_eq!(wav.len(), 44 + 120 * 2);
+        assert_eq!(&wav[..4], b"RIFF");
+        assert_eq!(&wav[4..8], (36u32 + 240).to_le_bytes());
+        assert_eq!(&wav[8..16], b"WAVEfmt ");
+        assert_eq!(&wav[22..24], [1, 0]);
+        assert_eq!(&wav[24..28], 48_000u32.to_le_bytes());
+        assert_eq!(&wav[36..40], b"data");
+        assert_eq!(&wav[40..44], 240u32.to_le_bytes());
+    }
+
+    #[test]
+    fn test_too_long() {
+        let options = WavOptions::default();
+        let long = signal(38_000, vec![u32::MAX, 1, 1]);
+        assert!(matches!(
+            to_wav(&long, &options),
+            Err(WavError::TooLong(samples)) if samples > MAX_SAMPLES
+        ));
+
+        // two minutes at 192kHz fit a single channel, not two
+        let stereo = WavOptions {
+            carrier: WavCarrier::StereoHalfFrequency,
+            ..options
+        };
+        let minutes = signal(38_000, vec![120_000_000]);
+        assert_eq!(
+            to_wav(&minutes, &stereo),
+            Err(WavError::TooLong(2 * 23_040_000))
+        );
+    }
+
+    #[test]
+    fn test_envelope() {
+        let options = WavOptions {
+            sample_rate: 10_000,
+            carrier: WavCarrier::Envelope,
+        };
+        // boundaries at 0.1ms per sample: 2 samples, 3, then 1
+        let wav = to_wav(&signal(38_000, vec![200, 300, 100]), &options).unwrap();
+        assert_eq!(samples(&wav), [AMPLITUDE, AMPLITUDE, 0, 0, 0, AMPLITUDE]);
+    }
+
+    #[test]
+    fn test_modulated() {
+        let options = WavOptions {
+            sample_rate: 400_000,
+            carrier: WavCarrier::Modulated,
+        };
+        // 10 samples per carrier period, half of them on
+        let modulated = samples(&to_wav(&signal(40_000, vec![50, 50, 50]), &options).unwrap());
+        assert_eq!(modulated.len(), 60);
+        assert_eq!(
+            modulated[..10],
+            [
+                AMPLITUDE, AMPLITUDE, AMPLITUDE, AMPLITUDE, AMPLITUDE, -AMPLITUDE, -AMPLITUDE,
+                -AMPLITUDE, -AMPLITUDE, -AMPLITUDE
+            ]
+        );
+        assert!(modulated[20..40].iter().all(|&sample| sample == 0));
+
+        // no carrier to render
+        let unmodulated = samples(&to_wav(&signal(0, vec![50, 50, 50]), &options).unwrap());
+        assert!(unmodulated[..20].iter().all(|&sample| sample == AMPLITUDE));
+        assert!(unmodulated[20..40].iter().all(|&sample| sample == 0));
+    }
+
+    #[test]
+    fn test_decode() {
+        let dump = EXAMPLES[0].dump();
+        let original = &dump.signals()[0];
+        for carrier in [
+            WavCarrier::Modulated,
+            WavCarrier::StereoHalfFrequency,
+            WavCarrier::Envelope,
+        ] {
+            for channel in [0, 1] {
+                let options = WavOptions {
+                    sample_rate: 192_000,
+                    carrier,
+                };
+                let decode = WavDecodeOptions {
+                    channel,
+                    ..WavDecodeOptions::default()
+                };
+                let decoded = from_wav("On", &to_wav(original, &options).unwrap(), &decode);
+                if channel == 1 && carrier != WavCarrier::StereoHalfFrequency {
+                    assert_eq!(decoded.unwrap_err(), WavError::NoChannel(1));
+                    continue;
+                }
+
+                let decoded = decoded.unwrap();
+                assert_eq!(decoded.data().len(), original.data().len(), "{carrier:?}");
+                for (decoded, original) in decoded.data().iter().zip(original.data()) {
+                    assert!(decoded.abs_diff(*original) <= 6, "{decoded} {original}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_formats() {
+        // a 1ms pulse, then a 1ms pause, a 1ms pulse and a trailing silence,
+        // at 1kHz with a leading quiet sample
+        let wav = |format: u16, bits: u16, samples: &[&[u8]]| {
+            let mut wav = header(1000, 1, 0);
+            wav[20..22].copy_from_slice(&format.to_le_bytes());
+            wav[34..36].copy_from_slice(&bits.to_le_bytes());
+            wav.truncate(40);
+            let data: Vec<u8> = samples.concat();
+            wav.extend((data.len() as u32).to_le_bytes());
+            wav.extend(data);
+            wav
+        };
+        let options = WavDecodeOptions {
+            max_carrier_gap: 0,
+            ..WavDecodeOptions::default()
+        };
+
+        let eight_bit = wav(FORMAT_PCM, 8, &[&[128], &[255], &[128], &[0], &[128]]);
+        let decoded = from_wav("8 bit", &eight_bit, &options).unwrap();
+        assert_eq!(decoded.data(), [1000, 1000, 1000]);
+
+        let quiet = 0.01f32.to_le_bytes();
+        let loud = (-0.5f32).to_le_bytes();
+        let float = wav(FORMAT_FLOAT, 32, &[&quiet, &loud, &loud, &quiet, &loud]);
+        let decoded = from_wav("float", &float, &options).unwrap();
+        assert_eq!(decoded.data(), [2000, 1000, 1000]);
+
+        let twenty_four = wav(FORMAT_PCM, 24, &[&[0, 0, 0x40], &[0, 0, 0xC0]]);
+        assert_eq!(
+            from_wav("24 bit", &twenty_four, &options).unwrap().data(),
+            [2000]
+        );
+
+        assert_eq!(
+            from_wav("silence", &wav(FORMAT_PCM, 8, &[&[128]]), &options).unwrap_err(),
+            WavError::NoSignal
+        );
+        assert_eq!(
+            from_wav("a-law", &wav(6, 8, &[&[128]]), &options).unwrap_err(),
+            WavError::UnsupportedFormat { format: 6, bits: 8 }
+        );
+        assert_eq!(
+            from_wav("text", b"hello", &options).unwrap_err(),
+            WavError::NotWav
+        );
+        assert_eq!(
+            from_wav("header only", &eight_bit[..36], &options).unwrap_err(),
+            WavError::MissingChunk("data")
+        );
+    }
+
+    #[test]
+    fn test_stereo() {
+        let options = WavOptions {
+            sample_rate: 80_000,
+            carrier: WavCarrier::StereoHalfFrequency,
+        };
+        let wav = to_wav(&signal(40_000, vec![100, 50, 50]), &options).unwrap();
+        assert_eq!(&wav[22..24], [2, 0]);
+
+        let samples = samples(&wav);
+        // 16 frames of two samples; each channel at 20kHz, inverted
+        assert_eq!(samples.len(), 32);
+        let left: Vec<_> = samples.iter().step_by(2).copied().collect();
+        let right: Vec<_> = samples.iter().skip(1).step_by(2).copied().collect();
+        assert_eq!(
+            left[..8],
+            [
+                AMPLITUDE, AMPLITUDE, -AMPLITUDE, -AMPLITUDE, AMPLITUDE, AMPLITUDE, -AMPLITUDE,
+                -AMPLITUDE
+            ]
+        );
+        assert!(left.iter().zip(&right).all(|(left, right)| *left == -right));
+        assert_eq!(left[8..12], [0, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,285 @@
+//! Excel workbooks cataloging remotes, one sheet per remote and one row
+//! per button, for collaborators who'd rather not open CSV files.
+//!
+//! Columns are the name of the signal, the protocol it was decoded with,
+//! its packets as hexadecimal numbers and its timings.
+
+use alloc::{format, string::String, vec::Vec};
+use core::fmt::Write;
+
+use super::{girr::escape, zip};
+use crate::{dump::DumpFile, signal::RawSignal};
+
+/// Titles of the columns.
+const HEADER: [&str; 4] = ["Name", "Protocol", "Hex code", "Timings"];
+/// Most characters Excel takes in a cell: longer timings are cut short.
+const MAX_CELL: usize = 32_767;
+/// Most characters Excel takes in a sheet name.
+const MAX_SHEET_NAME: usize = 31;
+
+const CONTENT_TYPES_HEAD: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/>"#;
+const ROOT_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#;
+
+/// A workbook of `remotes`, by sheet name and dump.
+///
+/// Sheet names are made acceptable to Excel: characters it rejects are
+/// replaced with underscores, and names are shortened and made unique.
+///
+/// # Examples
+/// ```rust
+/// use flipper_ir_dumps::{examples::EXAMPLES, formats::xlsx};
+///
+/// let dump = EXAMPLES[0].dump();
+/// let workbook = xlsx::to_xlsx(&[("Samsung AC", &dump)]);
+/// // a ZIP archive
+/// assert_eq!(workbook[..2], *b"PK");
+/// ```
+pub fn to_xlsx(remotes: &[(&str, &DumpFile)]) -> Vec<u8> {
+    let mut names: Vec<String> = Vec::new();
+    for (name, _) in remotes {
+        let name = unique_name(sheet_name(name), &names);
+        names.push(name);
+    }
+    let mut sheets: Vec<String> = remotes
+        .iter()
+        .map(|(_, dump)| sheet(dump.signals()))
+        .collect();
+    if sheets.is_empty() {
+        // workbooks need a sheet
+        names.push("Sheet1".into());
+        sheets.push(sheet(&[]));
+    }
+
+    let mut content_types = String::from(CONTENT_TYPES_HEAD);
+    let mut workbook = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets>"#,
+    );
+    let mut workbook_rels = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+    );
+    for (i, name) in names.iter().enumerate() {
+        let id = i + 1;
+        write!(
+            content_types,
+            r#"<Override PartName="/xl/worksheets/sheet{id}.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/>"#
+        )
+        .unwrap();
+        write!(
+            workbook,
+            r#"<sheet name="{}" sheetId="{id}" r:id="rId{id}"/>"#,
+            escape(name)
+        )
+        .unwrap();
+        write!(
+            workbook_rels,
+            r#"<Relationship Id="rId{id}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet{id}.xml"/>"#
+        )
+        .unwrap();
+    }
+    content_types.push_str("</Types>");
+    workbook.push_str("</sheets></workbook>");
+    workbook_rels.push_str("</Relationships>");
+
+    let paths: Vec<String> = (1..=sheets.len())
+        .map(|id| format!("xl/worksheets/sheet{}.xml", id))
+        .collect();
+    let mut files: Vec<(&str, &[u8])> = Vec::from([
+        ("[Content_Types].xml", content_types.as_bytes()),
+        ("_rels/.rels", ROOT_RELS.as_bytes()),
+        ("xl/workbook.xml", workbook.as_bytes()),
+        ("xl/_rels/workbook.xml.rels", workbook_rels.as_bytes()),
+    ]);
+    files.extend(
+        paths
+            .iter()
+            .zip(&sheets)
+            .map(|(path, sheet)| (path.as_str(), sheet.as_bytes())),
+    );
+    zip::stored(&files)
+}
+
+/// The worksheet of `signals`, under a header row.
+fn sheet(signals: &[RawSignal]) -> String {
+    let mut xml = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetData>"#,
+    );
+    row(&mut xml, 1, HEADER);
+
+    for (i, signal) in signals.iter().enumerate() {
+        let parsed = signal.parsed().ok();
+        let protocol = parsed
+            .and_then(|parsed| parsed.protocol())
+            .map(|decoded| decoded.protocol())
+            .unwrap_or_default();
+        let hex: Vec<String> = parsed
+            .map(|parsed| {
+                parsed
+                    .packets()
+                    .iter()
+                    .map(|packet| packet.to_hex())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let mut timings = String::new();
+        for duration in signal.data() {
+            let start = timings.len();
+            if start > 0 {
+                timings.push(' ');
+            }
+            write!(timings, "{}", duration).unwrap();
+            if timings.len() > MAX_CELL {
+                timings.truncate(start);
+                break;
+            }
+        }
+
+        row(
+            &mut xml,
+            i + 2,
+            [signal.name(), protocol, &hex.join(" "), &timings],
+        );
+    }
+
+    xml.push_str("</sheetData></worksheet>");
+    xml
+}
+
+/// Append row `number` of text cells, leaving empty ones out.
+fn row<'a>(xml: &mut String, number: usize, cells: impl IntoIterator<Item = &'a str>) {
+    write!(xml, r#"<row r="{}">"#, number).unwrap();
+    for (column, text) in (b'A'..).zip(cells) {
+        if text.is_empty() {
+            continue;
+        }
+        write!(
+            xml,
+            r#"<c r="{}{}" t="inlineStr"><is><t xml:space="preserve">{}</t></is></c>"#,
+            char::from(column),
+            number,
+            escape(&cell_text(text))
+        )
+        .unwrap();
+    }
+    xml.push_str("</row>");
+}
+
+/// `text` without the control characters XML can't hold.
+fn cell_text(text: &str) -> String {
+    text.chars()
+        .filter(|&c| c >= ' ' || c == '\t' || c == '\n')
+        .collect()
+}
+
+/// `name` with the characters Excel rejects in sheet names replaced, and
+/// shortened to fit.
+fn sheet_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| match c {
+            '[' | ']' | ':' | '*' | '?' | '/' | '\\' => '_',
+            c if c < ' ' => '_',
+            c => c,
+        })
+        .take(MAX_SHEET_NAME)
+        .collect();
+    // names can't start or end with an apostrophe, nor be blank
+    let name = name.trim_matches('\'').trim();
+    if name.is_empty() {
+        "Sheet".into()
+    } else {
+        name.into()
+    }
+}
+
+/// `name`, or `name (2)`, `name (3)`... if it's already `taken`, as Excel
+/// compares sheet names regardless of case.
+fn unique_name(name: String, taken: &[String]) -> String {
+    let is_taken = |candidate: &str| {
+        taken
+            .iter()
+            .any(|other| other.to_lowercase() == candidate.to_lowercase())
+    };
+    if !is_taken(&name) {
+        return name;
+    }
+    (2..)
+        .map(|n| {
+            let suffix = format!(" ({})", n);
+            let keep = MAX_SHEET_NAME - suffix.chars().count();
+            let base: String = name.chars().take(keep).collect();
+            base + &suffix
+        })
+        .find(|candidate| !is_taken(candidate))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::signal::{DutyCycle, Polarity, TimingSequence};
+
+    fn signal(name: &str, data: Vec<u32>) -> RawSignal {
+        let timings = TimingSequence::new(Polarity::StartsWithPulse, data);
+        RawSignal::new(name, 38_000, DutyCycle::default(), timings).unwrap()
+    }
+
+    #[test]
+    fn test_sheet() {
+        let xml = sheet(&[signal("Vol <up>", vec![100, 50, 100])]);
+        assert!(xml.contains(
+            r#"<row r="1"><c r="A1" t="inlineStr"><is><t xml:space="preserve">Name</t></is></c>"#
+        ));
+        assert!(xml.contains(
+            r#"<row r="2"><c r="A2" t="inlineStr"><is><t xml:space="preserve">Vol &lt;up&gt;</t></is></c><c r="D2" t="inlineStr"><is><t xml:space="preserve">100 50 100</t></is></c></row>"#
+        ));
+    }
+
+    #[test]
+    fn test_long_timings() {
+        let xml = sheet(&[signal("Long", vec![10_000; 9_999])]);
+        let start = xml.find("D2").unwrap();
+        let timings = &xml[start..];
+        let text = &timings[timings.find("\">").unwrap() + 2..];
+        let text = &text[text.find("\">").unwrap() + 2..text.find("</t>").unwrap()];
+        assert!(text.len() <= MAX_CELL);
+        assert!(text.ends_with("10000"));
+    }
+
+    #[test]
+    fn test_sheet_names() {
+        assert_eq!(sheet_name("TV: Living room [old]"), "TV_ Living room _old_");
+        assert_eq!(sheet_name("'quoted'"), "quoted");
+        assert_eq!(sheet_name(""), "Sheet");
+        assert_eq!(sheet_name(&"a".repeat(40)).len(), MAX_SHEET_NAME);
+
+        let taken = vec!["TV".into(), "tv (2)".into()];
+        assert_eq!(unique_name("Tv".into(), &taken), "Tv (3)");
+        assert_eq!(unique_name("AC".into(), &taken), "AC");
+        let long = "b".repeat(MAX_SHEET_NAME);
+        assert_eq!(
+            unique_name(long.clone(), core::slice::from_ref(&long))
+                .chars()
+                .count(),
+            MAX_SHEET_NAME
+        );
+    }
+
+    #[test]
+    fn test_to_xlsx() {
+        let dump = DumpFile::new(1, vec![signal("Power", vec![100])]);
+        let workbook = to_xlsx(&[("TV", &dump), ("tv", &dump)]);
+        let text = String::from_utf8_lossy(&workbook);
+        assert!(text.contains(r#"<sheet name="TV" sheetId="1" r:id="rId1"/><sheet name="tv (2)" sheetId="2" r:id="rId2"/>"#));
+        assert!(text.contains("xl/worksheets/sheet2.xml"));
+
+        let empty = to_xlsx(&[]);
+        assert!(String::from_utf8_lossy(&empty).contains(r#"<sheet name="Sheet1""#));
+    }
+}
@@ -0,0 +1,120 @@
+//! Just enough of ZIP to write archives, for the formats packaged as ones.
+
+use alloc::vec::Vec;
+
+/// MS-DOS date of 1980-01-01, the earliest ZIP has: the archives aren't
+/// timestamped.
+const DOS_DATE: u16 = 0x21;
+/// ZIP 2.0, the version of the stored method.
+const VERSION: u16 = 20;
+
+/// An archive of `files`, by name and contents, stored uncompressed.
+pub(crate) fn stored(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut archive = Vec::new();
+    let mut directory = Vec::new();
+
+    for &(name, contents) in files {
+        let offset = archive.len() as u32;
+        let crc = crc32(contents);
+        let entry = Entry {
+            name: name.as_bytes(),
+            crc,
+            size: contents.len() as u32,
+        };
+
+        archive.extend(0x0403_4b50u32.to_le_bytes());
+        entry.write_header(&mut archive);
+        archive.extend(name.as_bytes());
+        archive.extend(contents);
+
+        directory.extend(0x0201_4b50u32.to_le_bytes());
+        directory.extend(VERSION.to_le_bytes());
+        entry.write_header(&mut directory);
+        // comment length, disk, internal and external attributes
+        directory.extend([0; 10]);
+        directory.extend(offset.to_le_bytes());
+        directory.extend(name.as_bytes());
+    }
+
+    let directory_offset = archive.len() as u32;
+    let directory_size = directory.len() as u32;
+    archive.extend(directory);
+
+    archive.extend(0x0605_4b50u32.to_le_bytes());
+    // disk numbers
+    archive.extend([0; 4]);
+    archive.extend((files.len() as u16).to_le_bytes());
+    archive.extend((files.len() as u16).to_le_bytes());
+    archive.extend(directory_size.to_le_bytes());
+    archive.extend(directory_offset.to_le_bytes());
+    // comment length
+    archive.extend([0; 2]);
+
+    archive
+}
+
+struct Entry<'a> {
+    name: &'a [u8],
+    crc: u32,
+    size: u32,
+}
+
+impl Entry<'_> {
+    /// The fields local headers and directory entries share.
+    fn write_header(&self, out: &mut Vec<u8>) {
+        out.extend(VERSION.to_le_bytes());
+        // flags and method: stored
+        out.extend([0; 4]);
+        // time
+        out.extend([0; 2]);
+        out.extend(DOS_DATE.to_le_bytes());
+        out.extend(self.crc.to_le_bytes());
+        out.extend(self.size.to_le_bytes());
+        out.extend(self.size.to_le_bytes());
+        out.extend((self.name.len() as u16).to_le_bytes());
+        // extra field length
+        out.extend([0; 2]);
+    }
+}
+
+/// CRC-32 as used by ZIP.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                crc >> 1 ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_stored() {
+        let archive = stored(&[("version", b"2"), ("metadata", b"[global]\n")]);
+        assert_eq!(archive[..4], [0x50, 0x4b, 0x03, 0x04]);
+        // local header, then the name and contents of the first file
+        assert_eq!(&archive[30..38], b"version2");
+
+        let end = &archive[archive.len() - 22..];
+        assert_eq!(end[..4], [0x50, 0x4b, 0x05, 0x06]);
+        assert_eq!(end[10..12], [2, 0]);
+        let directory = u32::from_le_bytes([end[16], end[17], end[18], end[19]]) as usize;
+        assert_eq!(archive[directory..directory + 4], [0x50, 0x4b, 0x01, 0x02]);
+        assert_eq!(&archive[directory + 46..directory + 53], b"version");
+    }
+}
@@ -0,0 +1,500 @@
+//! Identifying remotes by looking their codes up in a clone of
+//! [Flipper-IRDB](https://github.com/Lucaslhm/Flipper-IRDB).
+//!
+//! The database is a tree of `.ir` files laid out as
+//! `Category/Brand/Model.ir`. An [`IrdbIndex`] maps the decoded code of every
+//! button of those files to where it was found, so that a capture can be
+//! matched against the whole database with [`IrdbIndex::identify`]. Indexing
+//! takes a while, so the index can be saved and loaded back as text, see
+//! its [`Display`](fmt::Display) and [`FromStr`](core::str::FromStr) impls.
+
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+
+use displaydoc::Display;
+use thiserror::Error;
+
+use crate::{
+    dump::DumpFile,
+    flipper_format::key_value,
+    protocol::{DecodedFrame, Registry},
+    signal::RawSignal,
+};
+
+/// First line of saved indexes.
+const INDEX_HEADER: &str = "# flipper-irdb index v1";
+
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+/// Error loading a saved IRDB index
+pub enum IrdbIndexError {
+    /// Not a saved index, or one of another version
+    UnknownHeader,
+    /// Invalid index entry at line {0}
+    InvalidLine(usize),
+}
+
+/// A decoded code, as compared between captures and the database.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct IrdbCode {
+    /// Protocol name, as used by the Flipper firmware.
+    pub protocol: String,
+    /// Address, 0 for protocols without addresses.
+    pub address: u32,
+    pub command: u32,
+}
+
+impl IrdbCode {
+    /// The code of a decoded frame, if it has a command.
+    pub fn of(frame: &DecodedFrame) -> Option<Self> {
+        Some(Self {
+            protocol: frame.protocol.clone(),
+            address: frame.address.unwrap_or_default(),
+            command: frame.command?,
+        })
+    }
+
+    /// The code of a raw signal, if it decodes with the protocols of `registry`.
+    pub fn of_signal(signal: &RawSignal, registry: &Registry) -> Option<Self> {
+        let decoded = registry.decode(signal.data(), signal.frequency().hz())?;
+        Self::of(&decoded.frame())
+    }
+}
+
+impl fmt::Display for IrdbCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} address 0x{:02X} command 0x{:02X}",
+            self.protocol, self.address, self.command
+        )
+    }
+}
+
+/// A button of a database file.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub struct IrdbButton {
+    /// Path of the file, relative to the root of the database.
+    pub file: String,
+    /// Name of the signal in the file.
+    pub name: String,
+}
+
+/// A database file sharing codes with a capture, see [`IrdbIndex::identify`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct IrdbMatch {
+    /// Path of the file, relative to the root of the database.
+    pub file: String,
+    /// Pairs of signal names, of the capture and of the file, sending the same code.
+    pub buttons: Vec<(String, String)>,
+    /// Signals of the capture that decode to a code at all.
+    pub decoded: usize,
+}
+
+impl IrdbMatch {
+    /// Category of the remote, the top directory of its file.
+    pub fn category(&self) -> Option<&str> {
+        let parts: Vec<_> = self.file.split('/').collect();
+        match parts[..] {
+            [category, _, _, ..] => Some(category),
+            _ => None,
+        }
+    }
+
+    /// Brand of the remote, the directory of its file.
+    pub fn brand(&self) -> Option<&str> {
+        let mut parts = self.file.rsplit('/');
+        parts.next();
+        parts.next()
+    }
+
+    /// Model of the remote, the name of its file.
+    pub fn model(&self) -> &str {
+        let name = self.file.rsplit('/').next().unwrap_or_default();
+        name.strip_suffix(".ir").unwrap_or(name)
+    }
+
+    /// Fraction of the decoded signals of the capture found in the file.
+    pub fn score(&self) -> f32 {
+        match self.decoded {
+            0 => 0.0,
+            decoded => self.buttons.len() as f32 / decoded as f32,
+        }
+    }
+}
+
+/// Buttons of database files by their code.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct IrdbIndex {
+    codes: BTreeMap<IrdbCode, Vec<IrdbButton>>,
+}
+
+impl IrdbIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of indexed buttons.
+    pub fn len(&self) -> usize {
+        self.codes.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.codes.is_empty()
+    }
+
+    /// Index the buttons of a database file, at `path` relative to the root.
+    ///
+    /// Both parsed signals, as most of the database is saved, and raw ones
+    /// decoding with the protocols of `registry` are indexed. Anything else,
+    /// including files that aren't IR dumps, is skipped. Returns the number
+    /// of buttons indexed.
+    pub fn add_file(&mut self, path: &str, contents: &str, registry: &Registry) -> usize {
+        let path = path.replace('\\', "/");
+        let mut added = 0;
+
+        for block in signal_blocks(contents) {
+            let Some(code) = block_code(&block, registry) else {
+                continue;
+            };
+            let button = IrdbButton {
+                file: path.clone(),
+                name: block["name"].to_string(),
+            };
+            self.codes.entry(code).or_default().push(button);
+            added += 1;
+        }
+
+        added
+    }
+
+    /// Buttons sending `code`.
+    pub fn lookup(&self, code: &IrdbCode) -> &[IrdbButton] {
+        self.codes.get(code).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Database files sharing codes with the signals of `capture`, decoded
+    /// with the protocols of `registry`, best matches first.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use flipper_ir_dumps::{dump::DumpFile, irdb::IrdbIndex, protocol::Registry};
+    ///
+    /// let file = "\
+    /// Filetype: IR signals file
+    /// Version: 1
+    /// #
+    /// name: Power
+    /// type: parsed
+    /// protocol: NEC
+    /// address: 04 00 00 00
+    /// command: 08 00 00 00
+    /// ";
+    /// let registry = Registry::default();
+    /// let mut index = IrdbIndex::new();
+    /// index.add_file("TVs/LG/LG_AKB.ir", file, &registry);
+    ///
+    /// // a NEC capture of address 0x04 and command 0x08
+    /// let mut data = vec![9000, 4500];
+    /// for byte in [0x04u8, 0xFB, 0x08, 0xF7] {
+    ///     for bit in 0..8 {
+    ///         data.extend([560, if byte >> bit & 1 == 1 { 1690 } else { 560 }]);
+    ///     }
+    /// }
+    /// data.push(560);
+    /// let data: Vec<_> = data.iter().map(u32::to_string).collect();
+    /// let capture = format!(
+    ///     "Filetype: IR signals file\nVersion: 1\n#\nname: On\ntype: raw\n\
+    ///      frequency: 38000\nduty_cycle: 0.330000\ndata: {}\n",
+    ///     data.join(" ")
+    /// );
+    ///
+    /// let matches = index.identify(&DumpFile::try_from(capture.as_str()).unwrap(), &registry);
+    /// assert_eq!(matches[0].brand(), Some("LG"));
+    /// assert_eq!(matches[0].buttons, [("On".to_string(), "Power".to_string())]);
+    /// ```
+    pub fn identify(&self, capture: &DumpFile, registry: &Registry) -> Vec<IrdbMatch> {
+        let mut files: BTreeMap<&str, Vec<(String, String)>> = BTreeMap::new();
+        let mut decoded = 0;
+
+        for signal in capture.signals() {
+            let Some(code) = IrdbCode::of_signal(signal, registry) else {
+                continue;
+            };
+            decoded += 1;
+
+            // a file counts once per signal, even with several such buttons
+            let mut seen = BTreeSet::new();
+            for button in self.lookup(&code) {
+                if seen.insert(button.file.as_str()) {
+                    files
+                        .entry(&button.file)
+                        .or_default()
+                        .push((signal.name().to_string(), button.name.clone()));
+                }
+            }
+        }
+
+        let mut matches: Vec<_> = files
+            .into_iter()
+            .map(|(file, buttons)| IrdbMatch {
+                file: file.to_string(),
+                buttons,
+                decoded,
+            })
+            .collect();
+        matches.sort_by_key(|found| core::cmp::Reverse(found.buttons.len()));
+        matches
+    }
+}
+
+/// The index as tab-separated lines of file, button, protocol, address and
+/// command, to be loaded back with [`str::parse`].
+impl fmt::Display for IrdbIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", INDEX_HEADER)?;
+        for (code, buttons) in &self.codes {
+            for button in buttons {
+                writeln!(
+                    f,
+                    "{}\t{}\t{}\t{:X}\t{:X}",
+                    button.file, button.name, code.protocol, code.address, code.command
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl core::str::FromStr for IrdbIndex {
+    type Err = IrdbIndexError;
+
+    fn from_str(saved: &str) -> Result<Self, Self::Err> {
+        let mut lines = saved.lines();
+        if lines.next() != Some(INDEX_HEADER) {
+            return Err(IrdbIndexError::UnknownHeader);
+        }
+
+        let mut index = Self::new();
+        for (number, line) in lines.enumerate() {
+            let invalid = || IrdbIndexError::InvalidLine(number + 2);
+            let fields: Vec<_> = line.split('\t').collect();
+            let [file, name, protocol, address, command] = fields[..] else {
+                return Err(invalid());
+            };
+
+            let code = IrdbCode {
+                protocol: protocol.to_string(),
+                address: u32::from_str_radix(address, 16).map_err(|_| invalid())?,
+                command: u32::from_str_radix(command, 16).map_err(|_| invalid())?,
+            };
+            let button = IrdbButton {
+                file: file.to_string(),
+                name: name.to_string(),
+            };
+            index.codes.entry(code).or_default().push(button);
+        }
+
+        Ok(index)
+    }
+}
+
+/// The `key: value` fields of each signal of a Flipper IR file, leniently:
+/// unknown lines are skipped rather than failing the file.
+fn signal_blocks(contents: &str) -> Vec<BTreeMap<&str, &str>> {
+    let mut blocks: Vec<BTreeMap<&str, &str>> = Vec::new();
+
+    for line in contents.lines() {
+        let Ok((_, (key, value))) = key_value(line) else {
+            continue;
+        };
+        match (key, blocks.last_mut()) {
+            ("name", _) => blocks.push(BTreeMap::from([(key, value)])),
+            (_, Some(block)) => {
+                block.insert(key, value);
+            }
+            (_, None) => {}
+        }
+    }
+
+    blocks
+}
+
+/// The code of a signal block, parsed or raw.
+fn block_code(block: &BTreeMap<&str, &str>, registry: &Registry) -> Option<IrdbCode> {
+    match *block.get("type")? {
+        "parsed" => Some(IrdbCode {
+            protocol: block.get("protocol")?.to_string(),
+            address: le_bytes(block.get("address")?)?,
+            command: le_bytes(block.get("command")?)?,
+        }),
+        "raw" => {
+            let frequency = block.get("frequency")?.parse().ok()?;
+            let data = block
+                .get("data")?
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<Vec<u32>, _>>()
+                .ok()?;
+            let decoded = registry.decode(&data, frequency)?;
+            IrdbCode::of(&decoded.frame())
+        }
+        _ => None,
+    }
+}
+
+/// Parse hex bytes, least significant first, such as `04 00 00 00`.
+fn le_bytes(value: &str) -> Option<u32> {
+    value
+        .split_whitespace()
+        .take(4)
+        .enumerate()
+        .try_fold(0, |sum, (i, byte)| {
+            Some(sum | u32::from(u8::from_str_radix(byte, 16).ok()?) << (8 * i))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use indoc::indoc;
+
+    use super::*;
+    use crate::protocol::nec;
+
+    const LG: &str = indoc! {"
+        Filetype: IR signals file
+        Version: 1
+        # Model: LG AKB
+        #
+        name: Power
+        type: parsed
+        protocol: NEC
+        address: 04 00 00 00
+        command: 08 00 00 00
+        #
+        name: Vol_up
+        type: parsed
+        protocol: NEC
+        address: 04 00 00 00
+        command: 02 00 00 00
+        #
+        name: Mute
+        type: raw
+        frequency: 38000
+        duty_cycle: 0.330000
+        data: 1000 2000 3000
+    "};
+
+    fn nec_signal(name: &str, address: u8, command: u8) -> RawSignal {
+        let nec = nec::Nec {
+            address,
+            inverted_address: !address,
+            command,
+            repeats: 0,
+        };
+        let timings = crate::signal::TimingSequence::new(
+            crate::signal::Polarity::StartsWithPulse,
+            nec::encode(&nec),
+        );
+        RawSignal::new(name, 38_000, Default::default(), timings).unwrap()
+    }
+
+    fn index() -> IrdbIndex {
+        let registry = Registry::default();
+        let mut index = IrdbIndex::new();
+        assert_eq!(index.add_file("TVs/LG/LG_AKB.ir", LG, &registry), 2);
+
+        let samsung = DumpFile::new(1, Vec::from([nec_signal("Power", 0x07, 0x02)]));
+        let samsung = samsung.to_string();
+        assert_eq!(
+            index.add_file("TVs\\Samsung\\Samsung_BN59.ir", &samsung, &registry),
+            1
+        );
+        assert_eq!(index.add_file("README.md", "# Flipper-IRDB", &registry), 0);
+        index
+    }
+
+    #[test]
+    fn test_lookup() {
+        let index = index();
+        assert_eq!(index.len(), 3);
+
+        let power = IrdbCode {
+            protocol: "NEC".to_string(),
+            address: 0x04,
+            command: 0x08,
+        };
+        assert_eq!(
+            index.lookup(&power),
+            [IrdbButton {
+                file: "TVs/LG/LG_AKB.ir".to_string(),
+                name: "Power".to_string(),
+            }]
+        );
+        let samsung = IrdbCode {
+            protocol: "NEC".to_string(),
+            address: 0x07,
+            command: 0x02,
+        };
+        assert_eq!(
+            index.lookup(&samsung)[0].file,
+            "TVs/Samsung/Samsung_BN59.ir"
+        );
+        assert!(index
+            .lookup(&IrdbCode {
+                command: 0x99,
+                ..power
+            })
+            .is_empty());
+    }
+
+    #[test]
+    fn test_identify() {
+        let index = index();
+        let capture = DumpFile::new(
+            1,
+            Vec::from([
+                nec_signal("on off", 0x04, 0x08),
+                nec_signal("louder", 0x04, 0x02),
+                nec_signal("unknown", 0x04, 0x55),
+            ]),
+        );
+
+        let matches = index.identify(&capture, &Registry::default());
+        assert_eq!(matches.len(), 1);
+        let lg = &matches[0];
+        assert_eq!(
+            (lg.category(), lg.brand(), lg.model()),
+            (Some("TVs"), Some("LG"), "LG_AKB")
+        );
+        assert_eq!(lg.buttons.len(), 2);
+        assert_eq!(lg.buttons[1], ("louder".to_string(), "Vol_up".to_string()));
+        assert!((lg.score() - 2.0 / 3.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_saved_index() {
+        let index = index();
+        let saved = index.to_string();
+        assert!(saved.starts_with("# flipper-irdb index v1\nTVs/LG/LG_AKB.ir\tVol_up\tNEC\t4\t2\n"));
+        assert_eq!(saved.parse::<IrdbIndex>().unwrap(), index);
+
+        assert_eq!("".parse::<IrdbIndex>(), Err(IrdbIndexError::UnknownHeader));
+        assert_eq!(
+            "# flipper-irdb index v1\na\tb\tNEC\tzz\t1\n".parse::<IrdbIndex>(),
+            Err(IrdbIndexError::InvalidLine(2))
+        );
+    }
+
+    #[test]
+    fn test_le_bytes() {
+        assert_eq!(le_bytes("04 00 00 00"), Some(0x04));
+        assert_eq!(le_bytes("EE 87 00 00"), Some(0x87EE));
+        assert_eq!(le_bytes("zz"), None);
+    }
+}
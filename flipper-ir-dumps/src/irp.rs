@@ -0,0 +1,584 @@
+//! Protocols described in IRP notation, the protocol language of
+//! IrScrutinizer and MakeHex, e.g. NEC:
+//!
+//! ```text
+//! {38.4k,564}<1,-1|1,-3>(16,-8,D:8,S:8,F:8,~F:8,1,^108m,(16,-4,1,^108m)*)[D:0..255,S:0..255=255-D,F:0..255]
+//! ```
+//!
+//! The general spec in braces sets the carrier frequency, the time unit and
+//! the bit order. The bit spec in angle brackets lists the durations coding
+//! each bit value, positive for pulses and negative for pauses, in units.
+//! The IR stream in parentheses is made of durations, extents (`^`, pausing
+//! until the given time since the start of the stream), bit fields
+//! (`value:length[:offset]`, `~` inverting the value), assignments and nested
+//! streams, repeated by a trailing `*` (any number of times), `+` (at least
+//! once) or count. The parameter specs in brackets give the range and the
+//! default value of each parameter.
+//!
+//! Definitions in braces after the IR stream and other rarely used features
+//! aren't supported.
+//!
+//! ```rust
+//! use std::collections::BTreeMap;
+//!
+//! use flipper_ir_dumps::irp::Irp;
+//!
+//! let nec = Irp::parse(
+//!     "NEC",
+//!     "{38.4k,564}<1,-1|1,-3>(16,-8,D:8,S:8,F:8,~F:8,1,^108m,(16,-4,1,^108m)*)\
+//!      [D:0..255,S:0..255=255-D,F:0..255]",
+//! )
+//! .unwrap();
+//!
+//! let parameters = BTreeMap::from([("D".into(), 4), ("F".into(), 8)]);
+//! let data = nec.encode(&parameters, 1).unwrap();
+//!
+//! let decoded = nec.decode(&data).unwrap();
+//! assert_eq!(decoded.parameters["S"], 251);
+//! assert_eq!(decoded.repeats, 1);
+//! ```
+
+use alloc::{boxed::Box, collections::BTreeMap, string::String, vec::Vec};
+
+use displaydoc::Display;
+use nom::Finish;
+use thiserror::Error;
+
+use crate::{
+    protocol::{DecodeError, Decoded, Protocol},
+    signal::Packet,
+};
+
+mod engine;
+mod parser;
+
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+/// Error parsing or executing IRP notation
+pub enum IrpError {
+    /// Failed parsing IRP notation at offset {offset}: {kind:?}
+    Syntax {
+        offset: usize,
+        kind: nom::error::ErrorKind,
+    },
+    /// Bit spec has {0} symbols instead of 2, 4, 8 or 16
+    BitSpec(usize),
+    /// Unknown name `{0}`
+    UnknownName(String),
+    /// Parameter {name} = {value} is out of range
+    OutOfRange { name: String, value: i64 },
+    /// Division by zero
+    DivisionByZero,
+    /// Notation of {0} characters is too long
+    TooLong(usize),
+    /// Parentheses nested {0} levels deep, too deep to parse
+    TooDeep(usize),
+    /// Bit field of {0} bits is longer than 64
+    FieldLength(u32),
+    /// Streams repeated {0} times in total, too many to expand
+    TooManyRepeats(u64),
+}
+
+/// Notations longer than this are rejected: expressions are evaluated
+/// recursively, and real protocols take a few hundred characters at most.
+const MAX_LENGTH: usize = 4096;
+/// Nesting deeper than this is rejected rather than overflowing the stack.
+const MAX_DEPTH: usize = 32;
+/// Bit fields longer than this don't fit the 64-bit values of expressions.
+const MAX_FIELD_LENGTH: u32 = 64;
+/// Fixed repeat counts of nested streams may multiply up to this.
+const MAX_REPEATS: u64 = 64;
+
+/// Carrier frequency, time unit and bit order.
+#[derive(Debug, PartialEq, Clone)]
+struct GeneralSpec {
+    /// Carrier frequency, in Hz.
+    frequency: u32,
+    /// Time unit, in µs.
+    unit: f64,
+    msb: bool,
+}
+
+/// A duration, in µs.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Duration {
+    Flash(f64),
+    Gap(f64),
+    /// Pause until this long after the start of the enclosing stream.
+    Extent(f64),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    And,
+    Or,
+    Xor,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum Expr {
+    Number(i64),
+    Name(String),
+    Negate(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn eval(&self, env: &BTreeMap<String, i64>) -> Result<i64, IrpError> {
+        Ok(match self {
+            Expr::Number(value) => *value,
+            Expr::Name(name) => *env
+                .get(name)
+                .ok_or_else(|| IrpError::UnknownName(name.clone()))?,
+            Expr::Negate(expr) => expr.eval(env)?.wrapping_neg(),
+            Expr::Not(expr) => !expr.eval(env)?,
+            Expr::Binary(op, left, right) => {
+                let (left, right) = (left.eval(env)?, right.eval(env)?);
+                match op {
+                    Op::Add => left.wrapping_add(right),
+                    Op::Sub => left.wrapping_sub(right),
+                    Op::Mul => left.wrapping_mul(right),
+                    Op::Div => left.checked_div(right).ok_or(IrpError::DivisionByZero)?,
+                    Op::Mod => left.checked_rem(right).ok_or(IrpError::DivisionByZero)?,
+                    Op::And => left & right,
+                    Op::Or => left | right,
+                    Op::Xor => left ^ right,
+                }
+            }
+        })
+    }
+}
+
+/// `length` bits of `value` starting at bit `offset`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct BitField {
+    value: Expr,
+    length: u32,
+    /// Whether the bit order is reversed from the general spec.
+    reverse: bool,
+    offset: u32,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Repeat {
+    Once,
+    Times(u32),
+    ZeroOrMore,
+    OneOrMore,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Item {
+    Duration(Duration),
+    BitField(BitField),
+    Assignment(String, Expr),
+    Stream(Stream),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+struct Stream {
+    items: Vec<Item>,
+    repeat: Repeat,
+}
+
+impl Stream {
+    /// Check the bit fields and the repeat counts of the stream, repeated
+    /// `repeats` times by the enclosing streams.
+    fn check(&self, repeats: u64) -> Result<(), IrpError> {
+        let repeats = match self.repeat {
+            Repeat::Times(times) => repeats.saturating_mul(times.into()),
+            _ => repeats,
+        };
+        if repeats > MAX_REPEATS {
+            return Err(IrpError::TooManyRepeats(repeats));
+        }
+
+        for item in &self.items {
+            match item {
+                Item::BitField(field) if field.length > MAX_FIELD_LENGTH => {
+                    return Err(IrpError::FieldLength(field.length));
+                }
+                Item::Stream(stream) => stream.check(repeats)?,
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct ParameterSpec {
+    name: String,
+    min: i64,
+    max: i64,
+    default: Option<Expr>,
+}
+
+/// A protocol described in IRP notation, to be added to a
+/// [`Registry`](crate::protocol::Registry).
+#[derive(Debug, PartialEq, Clone)]
+pub struct Irp {
+    name: String,
+    general: GeneralSpec,
+    /// Durations coding each symbol, the index of the symbol being its value.
+    bit_spec: Vec<Vec<Duration>>,
+    stream: Stream,
+    parameters: Vec<ParameterSpec>,
+}
+
+/// A frame of an [`Irp`] protocol.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct IrpDecoded {
+    /// Name of the protocol.
+    pub protocol: String,
+    /// Values of the parameters of the protocol.
+    pub parameters: BTreeMap<String, i64>,
+    /// Number of repeats following the frame.
+    pub repeats: usize,
+    /// Bits of the bit fields of the frame, as transmitted.
+    pub(crate) bits: Vec<bool>,
+}
+
+impl IrpDecoded {
+    pub(crate) fn packet(&self) -> Packet {
+        let mut packet = Packet::default();
+        for bit in &self.bits {
+            packet.data.push(*bit);
+        }
+        packet.repeats = self.repeats;
+        packet
+    }
+}
+
+impl Irp {
+    /// Parse a protocol in IRP notation, naming it `name`.
+    ///
+    /// Whitespace in the notation is ignored.
+    pub fn parse(name: impl Into<String>, notation: &str) -> Result<Self, IrpError> {
+        let notation: String = notation.chars().filter(|c| !c.is_whitespace()).collect();
+        if notation.len() > MAX_LENGTH {
+            return Err(IrpError::TooLong(notation.len()));
+        }
+        let depth = notation
+            .chars()
+            .scan(0_usize, |depth, c| {
+                match c {
+                    '(' => *depth += 1,
+                    ')' => *depth = depth.saturating_sub(1),
+                    _ => {}
+                }
+                Some(*depth)
+            })
+            .max()
+            .unwrap_or(0);
+        if depth > MAX_DEPTH {
+            return Err(IrpError::TooDeep(depth));
+        }
+
+        let (_, (general, bit_spec, stream, parameters)) = parser::irp(&notation)
+            .finish()
+            .map_err(|err| IrpError::Syntax {
+                offset: notation.len() - err.input.len(),
+                kind: err.code,
+            })?;
+
+        if !matches!(bit_spec.len(), 2 | 4 | 8 | 16) {
+            return Err(IrpError::BitSpec(bit_spec.len()));
+        }
+        stream.check(1)?;
+
+        Ok(Self {
+            name: name.into(),
+            general,
+            bit_spec,
+            stream,
+            parameters,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Carrier frequency, in Hz.
+    pub fn frequency(&self) -> u32 {
+        self.general.frequency
+    }
+
+    /// Encode a frame with the given parameters, the missing ones taking their
+    /// default values, and `repeats` repeats into timings in the Flipper convention.
+    pub fn encode(
+        &self,
+        parameters: &BTreeMap<String, i64>,
+        repeats: usize,
+    ) -> Result<Vec<u32>, IrpError> {
+        let mut env = parameters.clone();
+        for spec in &self.parameters {
+            if !env.contains_key(&spec.name) {
+                if let Some(default) = &spec.default {
+                    let value = default.eval(&env)?;
+                    env.insert(spec.name.clone(), value);
+                }
+            }
+
+            let value = *env
+                .get(&spec.name)
+                .ok_or_else(|| IrpError::UnknownName(spec.name.clone()))?;
+            if !(spec.min..=spec.max).contains(&value) {
+                return Err(IrpError::OutOfRange {
+                    name: spec.name.clone(),
+                    value,
+                });
+            }
+        }
+
+        engine::encode(self, env, repeats)
+    }
+
+    /// Decode a frame at the start of the timings, along with its repeats.
+    pub fn decode(&self, data: &[u32]) -> Result<IrpDecoded, DecodeError> {
+        let (env, bits, repeats) = engine::decode(self, data)?;
+
+        let mut parameters = BTreeMap::new();
+        for spec in &self.parameters {
+            let Some(&value) = env.get(&spec.name) else {
+                continue;
+            };
+            if !(spec.min..=spec.max).contains(&value) {
+                return Err(DecodeError::Mismatch("parameter out of range"));
+            }
+            parameters.insert(spec.name.clone(), value);
+        }
+
+        Ok(IrpDecoded {
+            protocol: self.name.clone(),
+            parameters,
+            repeats,
+            bits,
+        })
+    }
+}
+
+impl Protocol for Irp {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn matches(&self, data: &[u32], _frequency: u32) -> bool {
+        !data.is_empty()
+    }
+
+    fn decode(&self, data: &[u32], _frequency: u32) -> Result<Decoded, DecodeError> {
+        Irp::decode(self, data).map(Decoded::Irp)
+    }
+
+    fn encode(&self, decoded: &Decoded) -> Option<Vec<u32>> {
+        match decoded {
+            Decoded::Irp(decoded) if decoded.protocol == self.name => {
+                Irp::encode(self, &decoded.parameters, decoded.repeats).ok()
+            }
+            _ => None,
+        }
+    }
+}
+
+impl core::fmt::Display for IrpDecoded {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.protocol)?;
+        for (name, value) in &self.parameters {
+            write!(f, " {}={}", name, value)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::*;
+    use crate::protocol::{
+        nec::{self, Nec},
+        rc5::{self, Rc5},
+        Registry,
+    };
+
+    const NEC: &str = "{38.4k,564}<1,-1|1,-3>(16,-8,D:8,S:8,F:8,~F:8,1,^108m,(16,-4,1,^108m)*) \
+        [D:0..255,S:0..255=255-D,F:0..255]";
+    const RC5: &str = "{36k,msb,889}<1,-1|-1,1>((1,~F:1:6,T:1,D:5,F:6,^114m)*,T=1-T) \
+        [D:0..31,F:0..127,T@:0..1=0]";
+
+    fn parameters(values: &[(&str, i64)]) -> BTreeMap<String, i64> {
+        values
+            .iter()
+            .map(|(name, value)| (name.to_string(), *value))
+            .collect()
+    }
+
+    #[test]
+    fn test_nec() {
+        let irp = Irp::parse("NEC", NEC).unwrap();
+        assert_eq!(irp.frequency(), 38_400);
+
+        let nec = Nec {
+            address: 0x04,
+            inverted_address: !0x04,
+            command: 0x08,
+            repeats: 2,
+        };
+        let decoded = irp.decode(&nec::encode(&nec)).unwrap();
+        assert_eq!(
+            decoded.parameters,
+            parameters(&[("D", 0x04), ("S", 0xFB), ("F", 0x08)])
+        );
+        assert_eq!(decoded.repeats, 2);
+        assert_eq!(decoded.packet(), Decoded::Nec(nec).packet());
+
+        let data = irp
+            .encode(&parameters(&[("D", 0x04), ("F", 0x08)]), 2)
+            .unwrap();
+        assert_eq!(nec::decode(&data), Ok(nec));
+    }
+
+    #[test]
+    fn test_rc5() {
+        let irp = Irp::parse("RC5", RC5).unwrap();
+        let rc5 = Rc5 {
+            address: 0x05,
+            command: 0x35,
+            toggle: true,
+            repeats: 1,
+        };
+
+        let decoded = irp.decode(&rc5::encode(&rc5)).unwrap();
+        assert_eq!(
+            decoded.parameters,
+            parameters(&[("D", 0x05), ("F", 0x35), ("T", 1)])
+        );
+        assert_eq!(decoded.repeats, 1);
+
+        let data = irp
+            .encode(&parameters(&[("D", 0x05), ("F", 0x35), ("T", 1)]), 1)
+            .unwrap();
+        assert_eq!(rc5::decode(&data), Ok(rc5));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(
+            Irp::parse("broken", "{38k}<1,-1|1,-3>(16,-8,D:8"),
+            Err(IrpError::Syntax { .. })
+        ));
+        assert_eq!(
+            Irp::parse("odd", "{38k}<1,-1|1,-2|1,-3>(D:8)[D:0..255]"),
+            Err(IrpError::BitSpec(3))
+        );
+
+        let irp = Irp::parse("NEC", NEC).unwrap();
+        assert_eq!(
+            irp.encode(&parameters(&[("D", 256), ("F", 0)]), 0),
+            Err(IrpError::OutOfRange {
+                name: "D".to_string(),
+                value: 256
+            })
+        );
+        assert_eq!(
+            irp.encode(&parameters(&[("D", 1)]), 0),
+            Err(IrpError::UnknownName("F".to_string()))
+        );
+
+        // corrupted inverted command
+        let mut data = irp.encode(&parameters(&[("D", 1), ("F", 2)]), 0).unwrap();
+        data[51] = 564;
+        assert!(matches!(irp.decode(&data), Err(DecodeError::Integrity(_))));
+    }
+
+    #[test]
+    fn test_limits() {
+        assert_eq!(
+            Irp::parse(
+                "long",
+                "{38k,564}<1,-1|1,-3>(16,-8,D:4000000000,1,-100)[D:0..255]"
+            ),
+            Err(IrpError::FieldLength(4_000_000_000))
+        );
+        assert!(Irp::parse("long", "{38k,564}<1,-1|1,-3>(16,-8,D:64,1,-100)").is_ok());
+
+        assert_eq!(
+            Irp::parse(
+                "repeated",
+                "{38k,564}<1,-1|1,-3>(16,-8,D:8,1,-100)4294967295"
+            ),
+            Err(IrpError::TooManyRepeats(4_294_967_295))
+        );
+        assert_eq!(
+            Irp::parse("repeated", "{36k,msb,889}<1,-1|-1,1>(((1,T:1)*,T=1-T)8)16"),
+            Err(IrpError::TooManyRepeats(128))
+        );
+
+        let nested = alloc::format!(
+            "{{38k}}<1,-1|1,-3>{}1{}",
+            "(".repeat(20_000),
+            ")".repeat(20_000)
+        );
+        assert!(matches!(
+            Irp::parse("nested", &nested),
+            Err(IrpError::TooLong(_))
+        ));
+        let nested = alloc::format!(
+            "{{38k}}<1,-1|1,-3>{}1{}",
+            "(".repeat(1000),
+            ")".repeat(1000)
+        );
+        assert_eq!(Irp::parse("nested", &nested), Err(IrpError::TooDeep(1000)));
+
+        let negated = alloc::format!("{{38k}}<1,-1|1,-3>(1,({}1):8,-1)", "~".repeat(4000));
+        assert!(Irp::parse("negated", &negated).is_ok());
+    }
+
+    #[test]
+    fn test_repeat_without_progress() {
+        let irp = Irp::parse(
+            "toggle",
+            "{36k,msb,889}<1,-1|-1,1>((1,T:1,D:5,^114m)*,T=1-T)64[D:0..31,T@:0..1=0]",
+        )
+        .unwrap();
+        let rc = irp
+            .encode(&parameters(&[("D", 0x05), ("T", 1)]), 0)
+            .unwrap();
+        let decoded = irp.decode(&rc).unwrap();
+        assert_eq!(decoded.parameters["D"], 0x05);
+
+        let irp = Irp::parse(
+            "silent",
+            "{38k,564}<1,-1|1,-3>(16,-8,D:8,1,(^50m)64)[D:0..255]",
+        )
+        .unwrap();
+        let data = irp.encode(&parameters(&[("D", 0x12)]), 0).unwrap();
+        assert_eq!(irp.decode(&data).unwrap().parameters["D"], 0x12);
+    }
+
+    #[test]
+    fn test_registry() {
+        let mut registry = Registry::new();
+        registry.register(Irp::parse("NEC", NEC).unwrap());
+
+        let data = Irp::parse("NEC", NEC)
+            .unwrap()
+            .encode(&parameters(&[("D", 0x10), ("F", 0x20)]), 0)
+            .unwrap();
+        let decoded = registry.decode(&data, 38_000).unwrap();
+        assert_eq!(decoded.protocol(), "NEC");
+        match &decoded {
+            Decoded::Irp(irp) => assert_eq!(irp.to_string(), "NEC D=16 F=32 S=239"),
+            _ => panic!("decoded as {:?}", decoded),
+        }
+        assert_eq!(registry.encode(&decoded), Some(data));
+    }
+}
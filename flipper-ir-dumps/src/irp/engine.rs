@@ -0,0 +1,480 @@
+//! Execution of IRP streams: expanding them into timings, and matching
+//! timings against them.
+
+use alloc::{collections::BTreeMap, string::String, vec, vec::Vec};
+
+use flipper_utils::within_tolerance;
+
+use crate::protocol::{DecodeError, TOLERANCE};
+
+use super::{BitField, Duration, Expr, Irp, IrpError, Item, Repeat, Stream};
+
+type Env = BTreeMap<String, i64>;
+
+/// Encode the stream of `irp` with the parameters in `env`, repeating the
+/// variable part `repeats` times.
+pub(super) fn encode(irp: &Irp, env: Env, repeats: usize) -> Result<Vec<u32>, IrpError> {
+    let mut encoder = Encoder {
+        irp,
+        env,
+        repeats,
+        durations: vec![],
+        elapsed: 0.0,
+    };
+    encoder.stream(&irp.stream)?;
+
+    let mut durations = encoder.durations.as_slice();
+    while let [(false, _), rest @ ..] = durations {
+        durations = rest;
+    }
+    while let [rest @ .., (false, _)] = durations {
+        durations = rest;
+    }
+
+    Ok(durations
+        .iter()
+        .map(|(_, duration)| (duration + 0.5) as u32)
+        .collect())
+}
+
+struct Encoder<'a> {
+    irp: &'a Irp,
+    env: Env,
+    repeats: usize,
+    /// Pulses (`true`) and pauses, in µs, neighbours of the same kind merged.
+    durations: Vec<(bool, f64)>,
+    /// Total of the durations so far.
+    elapsed: f64,
+}
+
+impl Encoder<'_> {
+    fn stream(&mut self, stream: &Stream) -> Result<(), IrpError> {
+        let variable = if self.durations.is_empty() {
+            // the whole signal repeats, the first instance being the frame itself
+            self.repeats + 1
+        } else {
+            self.repeats
+        };
+        let times = match stream.repeat {
+            Repeat::Once => 1,
+            Repeat::Times(times) => times as usize,
+            Repeat::ZeroOrMore => variable,
+            Repeat::OneOrMore => variable.max(1),
+        };
+
+        for _ in 0..times {
+            let start = self.elapsed;
+            for item in &stream.items {
+                match item {
+                    Item::Duration(duration) => self.duration(*duration, start),
+                    Item::BitField(field) => self.bit_field(field, start)?,
+                    Item::Assignment(name, value) => {
+                        let value = value.eval(&self.env)?;
+                        self.env.insert(name.clone(), value);
+                    }
+                    Item::Stream(stream) => self.stream(stream)?,
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn duration(&mut self, duration: Duration, start: f64) {
+        match duration {
+            Duration::Flash(duration) => self.push(true, duration),
+            Duration::Gap(duration) => self.push(false, duration),
+            Duration::Extent(extent) => {
+                let gap = extent - (self.elapsed - start);
+                if gap > 0.0 {
+                    self.push(false, gap);
+                }
+            }
+        }
+    }
+
+    fn push(&mut self, pulse: bool, duration: f64) {
+        self.elapsed += duration;
+        match self.durations.last_mut() {
+            Some((last, total)) if *last == pulse => *total += duration,
+            _ => self.durations.push((pulse, duration)),
+        }
+    }
+
+    fn bit_field(&mut self, field: &BitField, start: f64) -> Result<(), IrpError> {
+        let value = field.value.eval(&self.env)? >> field.offset.min(63);
+        for symbol in symbols(self.irp, field, value) {
+            for duration in &self.irp.bit_spec[symbol] {
+                self.duration(*duration, start);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Number of bits coded by each symbol of the bit spec.
+fn symbol_bits(irp: &Irp) -> u32 {
+    irp.bit_spec.len().trailing_zeros()
+}
+
+fn mask(length: u32) -> i64 {
+    match length {
+        0 => 0,
+        1..=63 => (1 << length) - 1,
+        _ => -1,
+    }
+}
+
+/// Symbols coding the lowest `field.length` bits of `value`, in transmission order.
+fn symbols(irp: &Irp, field: &BitField, value: i64) -> Vec<usize> {
+    let bits = symbol_bits(irp);
+    let count = field.length / bits;
+    let symbol = |i: u32| ((value >> (i * bits).min(63)) & mask(bits)) as usize;
+
+    if irp.general.msb != field.reverse {
+        (0..count).rev().map(symbol).collect()
+    } else {
+        (0..count).map(symbol).collect()
+    }
+}
+
+/// Match `data` against the stream of `irp`, returning the values of the
+/// parameters, the bits of the frame as transmitted and the number of repeats.
+pub(super) fn decode(irp: &Irp, data: &[u32]) -> Result<(Env, Vec<bool>, usize), DecodeError> {
+    let mut decoder = Decoder {
+        irp,
+        cursor: Cursor {
+            data,
+            index: 0,
+            consumed: 0,
+            elapsed: 0.0,
+        },
+        env: Env::new(),
+        known: Env::new(),
+        bits: vec![],
+        recording: true,
+        repeats: 0,
+        failure: None,
+    };
+    decoder.stream(&irp.stream)?;
+
+    let cursor = &decoder.cursor;
+    match cursor.data.get(cursor.index) {
+        None => Ok((decoder.env, decoder.bits, decoder.repeats)),
+        // a trailing pause
+        Some(_) if cursor.index + 1 == data.len() && cursor.index & 1 == 1 => {
+            Ok((decoder.env, decoder.bits, decoder.repeats))
+        }
+        Some(&duration) => Err(decoder.failure.unwrap_or(DecodeError::UnexpectedDuration {
+            index: cursor.index,
+            duration,
+        })),
+    }
+}
+
+/// Position in the timings, which alternate between pulses and pauses.
+#[derive(Clone)]
+struct Cursor<'a> {
+    data: &'a [u32],
+    index: usize,
+    /// Part of the current duration already matched, as pauses in the
+    /// notation may be merged in the timings, e.g. in biphase coding.
+    consumed: u32,
+    /// Total of the matched durations.
+    elapsed: f64,
+}
+
+impl Cursor<'_> {
+    fn is_boundary(&self) -> bool {
+        self.consumed == 0
+    }
+
+    fn advance(&mut self, duration: u32) {
+        self.elapsed += f64::from(duration);
+        self.consumed += duration;
+        if self.consumed >= self.data[self.index] {
+            self.index += 1;
+            self.consumed = 0;
+        }
+    }
+
+    fn take(&mut self, pulse: bool, duration: f64) -> Result<(), DecodeError> {
+        let nominal = (duration + 0.5) as u32;
+        let Some(&current) = self.data.get(self.index) else {
+            // the signal ends with silence
+            self.elapsed += duration;
+            return if pulse {
+                Err(DecodeError::UnexpectedEnd)
+            } else {
+                Ok(())
+            };
+        };
+        if !pulse && self.index == 0 && self.consumed == 0 {
+            // and starts with it as well
+            self.elapsed += duration;
+            return Ok(());
+        }
+        if pulse != (self.index & 1 == 0) {
+            return Err(DecodeError::UnexpectedDuration {
+                index: self.index,
+                duration: current,
+            });
+        }
+
+        let remaining = current - self.consumed;
+        if within_tolerance(remaining, nominal, TOLERANCE) {
+            self.advance(remaining);
+            Ok(())
+        } else if remaining > nominal {
+            self.advance(nominal);
+            Ok(())
+        } else {
+            Err(DecodeError::UnexpectedDuration {
+                index: self.index,
+                duration: current,
+            })
+        }
+    }
+
+    /// Pause until `needed` more µs have passed.
+    fn extent(&mut self, needed: f64) -> Result<(), DecodeError> {
+        let Some(&current) = self.data.get(self.index) else {
+            self.elapsed += needed.max(0.0);
+            return Ok(());
+        };
+        if self.index & 1 == 0 {
+            return if needed <= 0.0 {
+                Ok(())
+            } else {
+                Err(DecodeError::UnexpectedDuration {
+                    index: self.index,
+                    duration: current,
+                })
+            };
+        }
+
+        let remaining = current - self.consumed;
+        if f64::from(remaining) >= needed * f64::from(1.0 - TOLERANCE) {
+            self.advance(remaining);
+            Ok(())
+        } else {
+            Err(DecodeError::UnexpectedDuration {
+                index: self.index,
+                duration: current,
+            })
+        }
+    }
+}
+
+struct Decoder<'a> {
+    irp: &'a Irp,
+    cursor: Cursor<'a>,
+    env: Env,
+    /// Bits of each name bound by the bit fields so far.
+    known: Env,
+    bits: Vec<bool>,
+    /// Whether the bits are recorded, i.e. not in a repeat of the frame.
+    recording: bool,
+    repeats: usize,
+    /// Why the last repetition stopped, to report if the timings aren't
+    /// consumed entirely.
+    failure: Option<DecodeError>,
+}
+
+impl Decoder<'_> {
+    fn stream(&mut self, stream: &Stream) -> Result<(), DecodeError> {
+        match stream.repeat {
+            Repeat::Once => self.instance(stream),
+            Repeat::Times(times) => {
+                for _ in 0..times {
+                    let (index, consumed, env) =
+                        (self.cursor.index, self.cursor.consumed, self.env.clone());
+                    self.instance(stream)?;
+                    // an instance that matched nothing and changed nothing
+                    // leaves the same for the next ones
+                    if (self.cursor.index, self.cursor.consumed) == (index, consumed)
+                        && self.env == env
+                    {
+                        break;
+                    }
+                }
+                Ok(())
+            }
+            Repeat::ZeroOrMore | Repeat::OneOrMore => self.repetition(stream),
+        }
+    }
+
+    /// Match as many instances of `stream` as possible.
+    fn repetition(&mut self, stream: &Stream) -> Result<(), DecodeError> {
+        let from_start = self.cursor.index == 0 && self.cursor.is_boundary();
+        let recording = self.recording;
+
+        let mut count: usize = 0;
+        loop {
+            let (cursor, env, known, bits) = (
+                self.cursor.clone(),
+                self.env.clone(),
+                self.known.clone(),
+                self.bits.len(),
+            );
+            let result = self.instance(stream);
+            let progress =
+                self.cursor.index > cursor.index || self.cursor.consumed > cursor.consumed;
+            match result {
+                Ok(()) if progress => {
+                    count += 1;
+                    self.recording = false;
+                }
+                result => {
+                    (self.cursor, self.env, self.known) = (cursor, env, known);
+                    self.bits.truncate(bits);
+                    if let Err(err) = result {
+                        self.failure = Some(err.clone());
+                        if count == 0 && stream.repeat == Repeat::OneOrMore {
+                            self.recording = recording;
+                            return Err(err);
+                        }
+                    }
+                    break;
+                }
+            }
+        }
+        self.recording = recording;
+
+        self.repeats += if from_start {
+            count.saturating_sub(1)
+        } else {
+            count
+        };
+
+        Ok(())
+    }
+
+    fn instance(&mut self, stream: &Stream) -> Result<(), DecodeError> {
+        let start = self.cursor.elapsed;
+        for item in &stream.items {
+            match item {
+                Item::Duration(duration) => self.duration(*duration, start)?,
+                Item::BitField(field) => self.bit_field(field, start)?,
+                Item::Assignment(name, value) => {
+                    // values decoded from the timings take precedence
+                    if !self.known.contains_key(name) {
+                        if let Ok(value) = value.eval(&self.env) {
+                            self.env.insert(name.clone(), value);
+                        }
+                    }
+                }
+                Item::Stream(stream) => self.stream(stream)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn duration(&mut self, duration: Duration, start: f64) -> Result<(), DecodeError> {
+        match duration {
+            Duration::Flash(duration) => self.cursor.take(true, duration),
+            Duration::Gap(duration) => self.cursor.take(false, duration),
+            Duration::Extent(extent) => self.cursor.extent(extent - (self.cursor.elapsed - start)),
+        }
+    }
+
+    /// Match the durations of a symbol, returning its value.
+    fn symbol(&mut self, start: f64) -> Result<usize, DecodeError> {
+        let mut matched = None;
+        let mut error = None;
+        for (value, durations) in self.irp.bit_spec.iter().enumerate() {
+            let mut cursor = self.cursor.clone();
+            let result = durations.iter().try_for_each(|duration| match duration {
+                Duration::Flash(duration) => cursor.take(true, *duration),
+                Duration::Gap(duration) => cursor.take(false, *duration),
+                Duration::Extent(extent) => cursor.extent(extent - (cursor.elapsed - start)),
+            });
+
+            match result {
+                // prefer symbols ending on a boundary of the timings
+                Ok(()) if cursor.is_boundary() => {
+                    matched = Some((value, cursor));
+                    break;
+                }
+                Ok(()) => {
+                    matched.get_or_insert((value, cursor));
+                }
+                Err(err) => {
+                    error.get_or_insert(err);
+                }
+            }
+        }
+
+        match matched {
+            Some((value, cursor)) => {
+                self.cursor = cursor;
+                Ok(value)
+            }
+            None => Err(error.unwrap_or(DecodeError::UnexpectedEnd)),
+        }
+    }
+
+    fn bit_field(&mut self, field: &BitField, start: f64) -> Result<(), DecodeError> {
+        let bits = symbol_bits(self.irp);
+        let count = field.length / bits;
+        let msb = self.irp.general.msb != field.reverse;
+
+        let mut value = 0;
+        for i in 0..count {
+            let symbol = self.symbol(start)? as i64;
+            let position = if msb { count - 1 - i } else { i };
+            value |= symbol << (position * bits).min(63);
+
+            if self.recording {
+                let symbol_bits = (0..bits).map(|bit| symbol >> bit & 1 == 1);
+                if msb {
+                    self.bits.extend(symbol_bits.rev());
+                } else {
+                    self.bits.extend(symbol_bits);
+                }
+            }
+        }
+
+        self.bind(field, value)
+    }
+
+    /// Bind the name in the bit field to the decoded `value`, or check it
+    /// against the value the field should have.
+    fn bind(&mut self, field: &BitField, value: i64) -> Result<(), DecodeError> {
+        let length_mask = mask(field.length);
+        let (name, value) = match &field.value {
+            Expr::Name(name) => (name, value),
+            Expr::Not(inner) => match inner.as_ref() {
+                Expr::Name(name) => (name, !value & length_mask),
+                _ => return self.check(field, value),
+            },
+            _ => return self.check(field, value),
+        };
+
+        let offset = field.offset.min(63);
+        let mask = length_mask << offset;
+        let bits = (value << offset) & mask;
+
+        let known = self.known.entry(name.clone()).or_default();
+        let current = self.env.entry(name.clone()).or_default();
+        if (*current ^ bits) & *known & mask != 0 {
+            return Err(DecodeError::Integrity("bit field doesn't match"));
+        }
+        *current = (*current & !mask) | bits;
+        *known |= mask;
+
+        Ok(())
+    }
+
+    fn check(&self, field: &BitField, value: i64) -> Result<(), DecodeError> {
+        match field.value.eval(&self.env) {
+            Ok(expected) if (expected >> field.offset.min(63)) & mask(field.length) != value => {
+                Err(DecodeError::Integrity("bit field doesn't match"))
+            }
+            // expressions of unknown names can't be checked
+            _ => Ok(()),
+        }
+    }
+}
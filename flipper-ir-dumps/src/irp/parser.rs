@@ -0,0 +1,363 @@
+//! Parser of IRP notation, expecting the whitespace to be stripped.
+
+use alloc::{boxed::Box, string::ToString, vec::Vec};
+
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{alpha1, alphanumeric0, char, digit1, one_of},
+    combinator::{all_consuming, map_res, opt, recognize},
+    multi::{fold_many0, many0, separated_list0, separated_list1},
+    sequence::{delimited, pair, preceded, tuple},
+    IResult, Parser,
+};
+
+use super::{BitField, Duration, Expr, GeneralSpec, Item, Op, ParameterSpec, Repeat, Stream};
+
+type Irp = (GeneralSpec, Vec<Vec<Duration>>, Stream, Vec<ParameterSpec>);
+
+pub(super) fn irp(input: &str) -> IResult<&str, Irp> {
+    let (input, general) = general_spec(input)?;
+    let (input, bit_spec) = bit_spec(&general)(input)?;
+    let (input, stream) = stream(&general)(input)?;
+    let (input, parameters) = all_consuming(opt(parameter_specs))(input)?;
+
+    Ok((
+        input,
+        (general, bit_spec, stream, parameters.unwrap_or_default()),
+    ))
+}
+
+enum GeneralItem {
+    Frequency(f64),
+    Unit(f64),
+    UnitPeriods(f64),
+    Msb(bool),
+}
+
+/// `{38.4k,564,msb}`: carrier frequency in kHz, time unit in µs or carrier
+/// periods, and bit order, in any order.
+fn general_spec(input: &str) -> IResult<&str, GeneralSpec> {
+    let item = alt((
+        tag("msb").map(|_| GeneralItem::Msb(true)),
+        tag("lsb").map(|_| GeneralItem::Msb(false)),
+        number
+            .and(alt((char('k'), char('p'))))
+            .map(|(value, suffix)| match suffix {
+                'k' => GeneralItem::Frequency(value),
+                _ => GeneralItem::UnitPeriods(value),
+            }),
+        number.map(GeneralItem::Unit),
+    ));
+    let (input, items) = delimited(char('{'), separated_list0(char(','), item), char('}'))(input)?;
+
+    let mut general = GeneralSpec {
+        frequency: 38_000,
+        unit: 1.0,
+        msb: false,
+    };
+    let mut periods = None;
+    for item in items {
+        match item {
+            GeneralItem::Frequency(khz) => general.frequency = (khz * 1000.0 + 0.5) as u32,
+            GeneralItem::Unit(unit) => general.unit = unit,
+            GeneralItem::UnitPeriods(count) => periods = Some(count),
+            GeneralItem::Msb(msb) => general.msb = msb,
+        }
+    }
+    if let Some(count) = periods {
+        general.unit = count * 1_000_000.0 / f64::from(general.frequency.max(1));
+    }
+
+    Ok((input, general))
+}
+
+/// `<1,-1|1,-3>`: durations of each symbol.
+fn bit_spec<'a>(
+    general: &GeneralSpec,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Vec<Vec<Duration>>> + '_ {
+    move |input| {
+        delimited(
+            char('<'),
+            separated_list1(char('|'), separated_list1(char(','), duration(general))),
+            char('>'),
+        )(input)
+    }
+}
+
+/// `16`, `-8`, `^108m`: a pulse, a pause or an extent, in units, or in
+/// milliseconds (`m`), microseconds (`u`) or carrier periods (`p`).
+fn duration<'a>(general: &GeneralSpec) -> impl FnMut(&'a str) -> IResult<&'a str, Duration> + '_ {
+    move |input| {
+        let (input, sign) = opt(alt((char('-'), char('^'))))(input)?;
+        let (input, value) = number(input)?;
+        let (input, suffix) = opt(alt((char('m'), char('u'), char('p'))))(input)?;
+
+        let micros = match suffix {
+            Some('m') => value * 1000.0,
+            Some('u') => value,
+            Some(_) => value * 1_000_000.0 / f64::from(general.frequency.max(1)),
+            None => value * general.unit,
+        };
+
+        Ok((
+            input,
+            match sign {
+                Some('-') => Duration::Gap(micros),
+                Some(_) => Duration::Extent(micros),
+                None => Duration::Flash(micros),
+            },
+        ))
+    }
+}
+
+/// `(16,-8,D:8,(16,-4)*)`: items of a stream and how many times it repeats.
+fn stream<'a>(general: &GeneralSpec) -> impl FnMut(&'a str) -> IResult<&'a str, Stream> + '_ {
+    move |input| {
+        let (input, items) = delimited(
+            char('('),
+            separated_list1(char(','), item(general)),
+            char(')'),
+        )(input)?;
+        let (input, repeat) = opt(alt((
+            char('*').map(|_| Repeat::ZeroOrMore),
+            char('+').map(|_| Repeat::OneOrMore),
+            map_res(digit1, str::parse).map(Repeat::Times),
+        )))(input)?;
+
+        Ok((
+            input,
+            Stream {
+                items,
+                repeat: repeat.unwrap_or(Repeat::Once),
+            },
+        ))
+    }
+}
+
+fn item<'a>(general: &GeneralSpec) -> impl FnMut(&'a str) -> IResult<&'a str, Item> + '_ {
+    move |input| {
+        alt((
+            stream(general).map(Item::Stream),
+            bit_field.map(Item::BitField),
+            pair(name, preceded(char('='), expr))
+                .map(|(name, value)| Item::Assignment(name.to_string(), value)),
+            duration(general).map(Item::Duration),
+        ))(input)
+    }
+}
+
+/// `~F:8:16`: a value, possibly inverted, its length, negative to reverse
+/// the bit order, and the offset of its lowest bit.
+fn bit_field(input: &str) -> IResult<&str, BitField> {
+    let (input, inverted) = opt(char('~'))(input)?;
+    let (input, value) = primary(input)?;
+    let (input, _) = char(':')(input)?;
+    let (input, reverse) = opt(char('-'))(input)?;
+    let (input, length) = uint(input)?;
+    let (input, offset) = opt(preceded(char(':'), uint))(input)?;
+
+    Ok((
+        input,
+        BitField {
+            value: match inverted {
+                Some(_) => Expr::Not(Box::new(value)),
+                None => value,
+            },
+            length,
+            reverse: reverse.is_some(),
+            offset: offset.unwrap_or(0),
+        },
+    ))
+}
+
+/// `[D:0..255,S:0..255=255-D]`: ranges and default values of the parameters.
+fn parameter_specs(input: &str) -> IResult<&str, Vec<ParameterSpec>> {
+    let spec = tuple((
+        name,
+        opt(char('@')),
+        preceded(char(':'), integer),
+        preceded(tag(".."), integer),
+        opt(preceded(char('='), expr)),
+    ))
+    .map(|(name, _, min, max, default)| ParameterSpec {
+        name: name.to_string(),
+        min,
+        max,
+        default,
+    });
+
+    delimited(char('['), separated_list0(char(','), spec), char(']'))(input)
+}
+
+/// Expressions, with the usual precedence of `|`, `^`, `&`, `+ -` and `* / %`.
+fn expr(input: &str) -> IResult<&str, Expr> {
+    binary(xor, &[('|', Op::Or)])(input)
+}
+
+fn xor(input: &str) -> IResult<&str, Expr> {
+    binary(and, &[('^', Op::Xor)])(input)
+}
+
+fn and(input: &str) -> IResult<&str, Expr> {
+    binary(additive, &[('&', Op::And)])(input)
+}
+
+fn additive(input: &str) -> IResult<&str, Expr> {
+    binary(multiplicative, &[('+', Op::Add), ('-', Op::Sub)])(input)
+}
+
+fn multiplicative(input: &str) -> IResult<&str, Expr> {
+    binary(unary, &[('*', Op::Mul), ('/', Op::Div), ('%', Op::Mod)])(input)
+}
+
+/// Left associative chain of `operand`s joined by any of `ops`.
+fn binary<'a>(
+    mut operand: impl FnMut(&'a str) -> IResult<&'a str, Expr> + Copy,
+    ops: &'static [(char, Op)],
+) -> impl FnMut(&'a str) -> IResult<&'a str, Expr> {
+    move |input| {
+        let (input, first) = operand(input)?;
+        let op = |input: &'a str| {
+            let next = input.chars().next();
+            match ops.iter().find(|(c, _)| next == Some(*c)) {
+                Some((c, op)) => Ok((&input[c.len_utf8()..], *op)),
+                None => Err(nom::Err::Error(nom::error::Error::new(
+                    input,
+                    nom::error::ErrorKind::Char,
+                ))),
+            }
+        };
+
+        fold_many0(
+            pair(op, operand),
+            move || first.clone(),
+            |left, (op, right)| Expr::Binary(op, Box::new(left), Box::new(right)),
+        )(input)
+    }
+}
+
+/// `~` and `-` applied to a primary, parsed without recursing per operator.
+fn unary(input: &str) -> IResult<&str, Expr> {
+    let (input, ops) = many0(one_of("~-"))(input)?;
+    let (input, operand) = primary(input)?;
+
+    Ok((
+        input,
+        ops.into_iter().rev().fold(operand, |expr, op| match op {
+            '~' => Expr::Not(Box::new(expr)),
+            _ => Expr::Negate(Box::new(expr)),
+        }),
+    ))
+}
+
+fn primary(input: &str) -> IResult<&str, Expr> {
+    alt((
+        integer.map(Expr::Number),
+        name.map(|name| Expr::Name(name.to_string())),
+        delimited(char('('), expr, char(')')),
+    ))(input)
+}
+
+fn name(input: &str) -> IResult<&str, &str> {
+    recognize(pair(alpha1, alphanumeric0))(input)
+}
+
+fn number(input: &str) -> IResult<&str, f64> {
+    map_res(
+        recognize(pair(digit1, opt(pair(char('.'), digit1)))),
+        str::parse,
+    )(input)
+}
+
+fn uint(input: &str) -> IResult<&str, u32> {
+    map_res(digit1, str::parse)(input)
+}
+
+fn integer(input: &str) -> IResult<&str, i64> {
+    map_res(digit1, str::parse)(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    fn general() -> GeneralSpec {
+        GeneralSpec {
+            frequency: 38_000,
+            unit: 500.0,
+            msb: false,
+        }
+    }
+
+    #[test]
+    fn test_general_spec() {
+        assert_eq!(
+            general_spec("{36k,msb,889}"),
+            Ok((
+                "",
+                GeneralSpec {
+                    frequency: 36_000,
+                    unit: 889.0,
+                    msb: true,
+                }
+            ))
+        );
+        let (_, spec) = general_spec("{40k,20p}").unwrap();
+        assert_eq!(spec.unit, 500.0);
+    }
+
+    #[test]
+    fn test_durations() {
+        let general = general();
+        assert_eq!(duration(&general)("2"), Ok(("", Duration::Flash(1000.0))));
+        assert_eq!(duration(&general)("-3"), Ok(("", Duration::Gap(1500.0))));
+        assert_eq!(
+            duration(&general)("^108m"),
+            Ok(("", Duration::Extent(108_000.0)))
+        );
+        assert_eq!(duration(&general)("-10u"), Ok(("", Duration::Gap(10.0))));
+    }
+
+    #[test]
+    fn test_expr() {
+        let (_, parsed) = expr("255-D^1*2").unwrap();
+        let env = [("D".to_string(), 4)].into_iter().collect();
+        assert_eq!(parsed.eval(&env), Ok((255 - 4) ^ 2));
+
+        let (_, parsed) = expr("~(A|B)&15").unwrap();
+        let env = [("A".to_string(), 1), ("B".to_string(), 4)]
+            .into_iter()
+            .collect();
+        assert_eq!(parsed.eval(&env), Ok(10));
+    }
+
+    #[test]
+    fn test_stream() {
+        let general = general();
+        let (rest, parsed) = stream(&general)("(1,~F:1:6,T=1-T,(2,-1)3)+[").unwrap();
+        assert_eq!(rest, "[");
+        assert_eq!(parsed.repeat, Repeat::OneOrMore);
+        assert_eq!(
+            parsed.items[1],
+            Item::BitField(BitField {
+                value: Expr::Not(Box::new(Expr::Name("F".to_string()))),
+                length: 1,
+                reverse: false,
+                offset: 6,
+            })
+        );
+        assert_eq!(
+            parsed.items[3],
+            Item::Stream(Stream {
+                items: vec![
+                    Item::Duration(Duration::Flash(1000.0)),
+                    Item::Duration(Duration::Gap(500.0)),
+                ],
+                repeat: Repeat::Times(3),
+            })
+        );
+    }
+}
@@ -1,2 +1,58 @@
+//! Parsing and decoding of Flipper Zero IR dumps.
+//!
+//! A dump (`.ir` file) is loaded into a [`DumpFile`](dump::DumpFile) holding
+//! [`RawSignal`](signal::RawSignal)s, the timings as captured. Each of them can
+//! then be decoded into a [`ParsedSignal`](signal::ParsedSignal) made of packets:
+//!
+//! ```rust
+//! use flipper_ir_dumps::{dump::DumpFile, examples::EXAMPLES, signal::ParsedSignal};
+//!
+//! let dump = DumpFile::try_from(EXAMPLES[0].contents).unwrap();
+//! let signal = dump.signal("On_h_16").unwrap();
+//!
+//! let parsed = ParsedSignal::try_from(signal).unwrap();
+//! assert_eq!(parsed.packets().len(), 3);
+//! assert_eq!(
+//!     parsed.packets()[0].to_string(),
+//!     "11110000000000000000000000000000000011111001001000000010"
+//! );
+//! ```
+//!
+//! Dumps can be checked, merged and written back:
+//!
+//! ```rust
+//! use flipper_ir_dumps::{dump::ConflictPolicy, examples::EXAMPLES};
+//!
+//! let mut dump = EXAMPLES[0].dump();
+//! assert!(dump.validate().is_empty());
+//!
+//! dump.merge(EXAMPLES[1].dump(), ConflictPolicy::Skip).unwrap();
+//! assert!(dump.to_string().starts_with("Filetype: IR signals file\nVersion: 1\n"));
+//! ```
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod dedupe;
 pub mod dump;
+pub mod examples;
+pub mod flipper_format;
+pub mod formats;
+pub mod generator;
+pub mod irdb;
+pub mod irp;
+pub mod neighbors;
+pub mod pipeline;
+pub mod protocol;
 pub mod signal;
+pub mod synthesis;
+pub mod validation;
+
+/// Cargo features this crate was compiled with.
+///
+/// Handy to check that an embedded build doesn't pull in more than it needs.
+pub const ENABLED_FEATURES: &[&str] = &[
+    #[cfg(feature = "std")]
+    "std",
+];
@@ -0,0 +1,225 @@
+//! Codes next to a known one, to look for buttons a remote doesn't have.
+//!
+//! Devices often accept more commands than their remotes send, such as
+//! service menus or discrete power on and off. Those usually sit next to
+//! the documented commands: a few values away, or a single bit apart.
+//! The functions here list such neighbors of a decoded frame and write them
+//! into a dump, ready to be tried one by one from the Flipper.
+
+use alloc::{format, vec::Vec};
+
+use crate::{
+    dump::DumpFile,
+    protocol::{Decoded, Registry},
+    signal::{DutyCycle, Frequency, RawSignal, SignalType},
+};
+
+/// Which neighbors of a command to generate.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct NeighborOptions {
+    /// Commands up to this far below and above the original one.
+    pub distance: u32,
+    /// Commands differing from the original one in a single bit among
+    /// this many lowest ones.
+    pub flip_bits: u32,
+}
+
+impl Default for NeighborOptions {
+    fn default() -> Self {
+        Self {
+            distance: 4,
+            flip_bits: 8,
+        }
+    }
+}
+
+/// Commands next to `command`, closest first: the ones within
+/// [`distance`](NeighborOptions::distance), alternating above and below,
+/// and then the ones with a single bit flipped.
+///
+/// Each command is listed once, and `command` itself isn't.
+pub fn neighbor_commands(command: u32, options: &NeighborOptions) -> Vec<u32> {
+    let nearby = (1..=options.distance).flat_map(|offset| {
+        [command.checked_add(offset), command.checked_sub(offset)]
+            .into_iter()
+            .flatten()
+    });
+    let flipped = (0..options.flip_bits.min(u32::BITS)).map(|bit| command ^ 1 << bit);
+
+    let mut commands: Vec<u32> = Vec::new();
+    for neighbor in nearby.chain(flipped) {
+        if neighbor != command && !commands.contains(&neighbor) {
+            commands.push(neighbor);
+        }
+    }
+    commands
+}
+
+/// Frames of the same protocol and address as `decoded`, with the commands
+/// of [`neighbor_commands`].
+///
+/// Commands the protocol can't carry are left out, as are frames of
+/// protocols without commands, see [`Decoded::with_command`].
+pub fn neighbors(decoded: &Decoded, options: &NeighborOptions) -> Vec<Decoded> {
+    let Some(command) = decoded.frame().command else {
+        return Vec::new();
+    };
+
+    neighbor_commands(command, options)
+        .into_iter()
+        .filter_map(|command| decoded.with_command(command))
+        .collect()
+}
+
+/// A dump of the [`neighbors`] of `decoded`, encoded with the protocols of
+/// `registry` and sent on a carrier of `frequency`.
+///
+/// Signals are named after their commands, such as `cmd_0x09`, and commented
+/// with the whole frame. Frames that don't decode back to their command,
+/// such as commands wider than the protocol allows, are left out.
+///
+/// # Examples
+/// ```rust
+/// use flipper_ir_dumps::{
+///     neighbors::{neighbor_dump, NeighborOptions},
+///     protocol::{nec::Nec, Decoded, Registry},
+/// };
+///
+/// let nec = Nec {
+///     address: 0x04,
+///     inverted_address: !0x04,
+///     command: 0x08,
+///     repeats: 0,
+/// };
+/// let options = NeighborOptions {
+///     distance: 1,
+///     flip_bits: 0,
+/// };
+///
+/// let dump = neighbor_dump(&Registry::default(), &Decoded::Nec(nec), 38_000.into(), &options);
+/// let names: Vec<_> = dump.signals().iter().map(|s| s.name()).collect();
+/// assert_eq!(names, ["cmd_0x09", "cmd_0x07"]);
+/// ```
+pub fn neighbor_dump(
+    registry: &Registry,
+    decoded: &Decoded,
+    frequency: Frequency,
+    options: &NeighborOptions,
+) -> DumpFile {
+    let signals = neighbors(decoded, options)
+        .into_iter()
+        .filter_map(|neighbor| {
+            let frame = neighbor.frame();
+            let data = registry.encode(&neighbor)?;
+
+            let roundtrip = registry.decode(&data, frequency.hz())?.frame();
+            if (roundtrip.address, roundtrip.command) != (frame.address, frame.command) {
+                return None;
+            }
+
+            Some(RawSignal {
+                name: format!("cmd_0x{:02X}", frame.command?),
+                r#type: SignalType::Raw,
+                frequency,
+                duty_cycle: DutyCycle::default(),
+                data,
+                comments: Vec::from([format!(" {}", frame)]),
+                unknown_fields: Vec::new(),
+                source: Default::default(),
+                parsed: Default::default(),
+            })
+        })
+        .collect();
+
+    DumpFile::new(1, signals)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::ToString, vec};
+
+    use super::*;
+    use crate::protocol::{dish::Dish, rc5::Rc5};
+
+    #[test]
+    fn test_neighbor_commands() {
+        let options = NeighborOptions {
+            distance: 2,
+            flip_bits: 4,
+        };
+        // 0x11 and 0x13 are both nearby and a bit away
+        assert_eq!(
+            neighbor_commands(0x12, &options),
+            vec![0x13, 0x11, 0x14, 0x10, 0x16, 0x1A]
+        );
+        assert_eq!(neighbor_commands(0, &options), vec![1, 2, 4, 8]);
+        assert!(neighbor_commands(
+            5,
+            &NeighborOptions {
+                distance: 0,
+                flip_bits: 0,
+            }
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let rc5 = Rc5 {
+            address: 0x05,
+            command: 0x35,
+            toggle: false,
+            repeats: 0,
+        };
+        let neighbors = neighbors(&Decoded::Rc5(rc5), &NeighborOptions::default());
+        assert_eq!(neighbors.len(), 13);
+        assert!(neighbors.iter().all(|n| n.frame().address == Some(0x05)));
+        assert_eq!(neighbors[0].frame().command, Some(0x36));
+
+        let custom = crate::protocol::custom::Custom {
+            protocol: "custom".to_string(),
+            value: 1,
+            bits: 8,
+            bit_order: crate::protocol::custom::BitOrder::Lsb,
+            repeats: 0,
+        };
+        assert!(super::neighbors(&Decoded::Custom(custom), &NeighborOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn test_neighbor_dump() {
+        // 6-bit commands: flipping bits 6 and 7 of 0x3E doesn't round trip
+        let dish = Dish {
+            command: 0x3E,
+            address: 0x01,
+            unit: 0x00,
+            repeats: 0,
+        };
+        let registry = Registry::default();
+        let dump = neighbor_dump(
+            &registry,
+            &Decoded::Dish(dish),
+            Frequency::from_hz(57_600),
+            &NeighborOptions::default(),
+        );
+
+        let commands: Vec<_> = dump
+            .signals()
+            .iter()
+            .map(|signal| {
+                let decoded = registry.decode(signal.data(), 57_600).unwrap();
+                assert_eq!(decoded.frame().address, Some(0x01));
+                decoded.frame().command.unwrap()
+            })
+            .collect();
+        assert_eq!(
+            commands,
+            vec![0x3F, 0x3D, 0x3C, 0x3B, 0x3A, 0x36, 0x2E, 0x1E]
+        );
+        assert_eq!(dump.signals()[0].name(), "cmd_0x3F");
+        assert!(dump.signals()[0].comments()[0].contains("command 0x3F"));
+
+        let reparsed = DumpFile::try_from(dump.to_string().as_str()).unwrap();
+        assert_eq!(reparsed.signals().len(), commands.len());
+    }
+}
@@ -0,0 +1,198 @@
+//! Load-and-decode pipeline shared by the frontends.
+//!
+//! Instead of printing to stdout, the pipeline reports what it does through
+//! [`Event`]s passed to a callback, so a CLI, GUI, TUI or HTTP server can each
+//! present progress and results in their own way. To forward events to another
+//! thread, pass a closure sending them over a channel:
+//!
+//! ```rust
+//! use std::sync::mpsc;
+//!
+//! use flipper_ir_dumps::{examples::EXAMPLES, pipeline};
+//!
+//! let (tx, rx) = mpsc::channel();
+//! let example = &EXAMPLES[0];
+//! pipeline::run(example.name, example.contents.as_bytes(), |event| {
+//!     let _ = tx.send(event);
+//! })
+//! .unwrap();
+//!
+//! assert!(rx.try_iter().count() > 0);
+//! ```
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use displaydoc::Display;
+use thiserror::Error;
+
+use crate::{
+    dump::{DumpError, DumpFile},
+    signal::{Packet, ParsedSignal},
+};
+
+/// Something that happened while running the pipeline.
+#[derive(Debug, Display, Clone, PartialEq)]
+pub enum Event {
+    /// loaded {source}: {signals} signals
+    FileLoaded { source: String, signals: usize },
+    /// decoded {name}: {packets} packets
+    SignalDecoded { name: String, packets: usize },
+    /// warning: {context}: {message}
+    Warning {
+        /// The signal or file the warning is about.
+        context: String,
+        message: String,
+    },
+    /// progress: {completed}/{total}
+    Progress { completed: usize, total: usize },
+}
+
+#[derive(Debug, Display, Error)]
+/// Error running the pipeline
+pub enum PipelineError {
+    /// Failed decoding dump {name}: {error}
+    Dump { name: String, error: DumpError },
+}
+
+/// Result of running the pipeline on a single dump.
+#[derive(Debug)]
+pub struct Output {
+    /// The dump as loaded.
+    pub dump: DumpFile,
+    /// Signals decoded successfully, in file order.
+    ///
+    /// Signals that failed to decode are reported via [`Event::Warning`].
+    pub parsed: Vec<ParsedSignal>,
+}
+
+/// Load a dump from `contents` and decode all of its signals, reporting
+/// progress to `on_event`.
+///
+/// Contents don't have to be valid UTF-8, see [`DumpFile::from_bytes`].
+///
+/// `source` names the dump in events and errors, usually its file path.
+pub fn run(
+    source: &str,
+    contents: &[u8],
+    mut on_event: impl FnMut(Event),
+) -> Result<Output, PipelineError> {
+    let dump = DumpFile::from_bytes(contents).map_err(|error| PipelineError::Dump {
+        name: source.to_string(),
+        error,
+    })?;
+
+    let total = dump.signals().len();
+    on_event(Event::FileLoaded {
+        source: source.to_string(),
+        signals: total,
+    });
+    for warning in dump.warnings() {
+        on_event(Event::Warning {
+            context: source.to_string(),
+            message: warning.to_string(),
+        });
+    }
+    for issue in dump.validate() {
+        on_event(Event::Warning {
+            context: source.to_string(),
+            message: issue.to_string(),
+        });
+    }
+
+    let mut parsed = Vec::with_capacity(total);
+    for (i, signal) in dump.signals().iter().enumerate() {
+        match ParsedSignal::try_from(signal) {
+            Ok(signal) => {
+                on_event(Event::SignalDecoded {
+                    name: signal.name().to_string(),
+                    packets: signal.packets().len(),
+                });
+                if let Some(err) = signal.packets().iter().find_map(Packet::integrity_error) {
+                    on_event(Event::Warning {
+                        context: signal.name().to_string(),
+                        message: err.to_string(),
+                    });
+                }
+                if signal.is_ambiguous() {
+                    let protocols: Vec<_> = signal
+                        .alternatives()
+                        .iter()
+                        .map(|candidate| {
+                            format!(
+                                "{} ({})",
+                                candidate.decoded.protocol(),
+                                candidate.confidence
+                            )
+                        })
+                        .collect();
+                    on_event(Event::Warning {
+                        context: signal.name().to_string(),
+                        message: format!("ambiguous, also decodes as {}", protocols.join(", ")),
+                    });
+                }
+                parsed.push(signal);
+            }
+            Err(err) => on_event(Event::Warning {
+                context: signal.name().to_string(),
+                message: err.to_string(),
+            }),
+        }
+
+        on_event(Event::Progress {
+            completed: i + 1,
+            total,
+        });
+    }
+
+    Ok(Output { dump, parsed })
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+
+    #[test]
+    fn test_run_reports_events() {
+        let input = indoc! {"
+            Filetype: IR signals file
+            Version: 1
+            #
+            name: garbage
+            type: raw
+            frequency: 38000
+            duty_cycle: 0.330000
+            data: 1 2 3
+        "};
+
+        let mut events = vec![];
+        let output = run("test.ir", input.as_bytes(), |event| events.push(event)).unwrap();
+
+        assert!(output.parsed.is_empty());
+        assert_eq!(
+            events[0],
+            Event::FileLoaded {
+                source: "test.ir".to_string(),
+                signals: 1
+            }
+        );
+        assert!(matches!(&events[1], Event::Warning { context, .. } if context == "garbage"));
+        assert_eq!(
+            events[2],
+            Event::Progress {
+                completed: 1,
+                total: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_run_rejects_bad_dump() {
+        assert!(run("test.ir", b"not a dump", |_| {}).is_err());
+    }
+}
@@ -0,0 +1,354 @@
+//! Decoders for well-known IR protocols.
+//!
+//! Unlike the generic packet grammar of [`ParsedSignal`](crate::signal::ParsedSignal),
+//! each decoder knows the exact framing of its protocol and extracts
+//! meaningful fields (address, command) from the raw timings.
+
+use alloc::{
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+use displaydoc::Display;
+use flipper_utils::within_tolerance;
+use thiserror::Error;
+
+use crate::signal::{self, slice_half_bits, Packet};
+
+pub mod apple;
+pub mod bang_olufsen;
+mod confidence;
+pub mod custom;
+pub mod daikin;
+pub mod dish;
+mod frame;
+pub mod kaseikyo;
+pub mod mitsubishi;
+pub mod nec;
+pub mod pioneer;
+pub mod rc5;
+pub mod rc6;
+pub mod rca;
+pub mod samsung;
+pub mod sanyo;
+pub mod sharp;
+
+mod registry;
+pub use confidence::{Candidate, Confidence};
+pub use frame::DecodedFrame;
+pub use registry::{Protocol, Registry};
+
+/// Relative tolerance for matching measured durations against nominal protocol timings.
+pub const TOLERANCE: f32 = 0.25;
+
+#[derive(Debug, Display, Error, PartialEq, Eq, Clone)]
+/// Error decoding a signal with a protocol decoder
+pub enum DecodeError {
+    /// Unexpected end of signal
+    UnexpectedEnd,
+    /// Unexpected duration of {duration}µs at index {index}
+    UnexpectedDuration { index: usize, duration: u32 },
+    /// Integrity check failed: {0}
+    Integrity(&'static str),
+    /// Not a frame of this protocol: {0}
+    Mismatch(&'static str),
+}
+
+/// {protocol} frame failed its integrity check: {reason}
+#[derive(Debug, Display, PartialEq, Eq, Clone)]
+pub struct IntegrityError {
+    /// Name of the protocol.
+    pub protocol: String,
+    pub reason: &'static str,
+}
+
+/// A signal decoded by one of the protocol decoders.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Decoded {
+    Nec(nec::Nec),
+    NecExt(nec::NecExt),
+    Samsung32(samsung::Samsung32),
+    Rc5(rc5::Rc5),
+    Rc6(rc6::Rc6),
+    Kaseikyo(kaseikyo::Kaseikyo),
+    Sharp(sharp::Sharp),
+    Mitsubishi(mitsubishi::Mitsubishi),
+    Sanyo(sanyo::Sanyo),
+    BangOlufsen(bang_olufsen::BangOlufsen),
+    Apple(apple::Apple),
+    Pioneer(pioneer::Pioneer),
+    Rca(rca::Rca),
+    Dish(dish::Dish),
+    Daikin(daikin::Daikin),
+    Custom(custom::Custom),
+    Irp(crate::irp::IrpDecoded),
+}
+
+impl Decoded {
+    /// Name of the protocol, as used by the Flipper firmware.
+    pub fn protocol(&self) -> &str {
+        match self {
+            Decoded::Nec(_) => "NEC",
+            Decoded::NecExt(_) => "NECext",
+            Decoded::Samsung32(_) => "Samsung32",
+            Decoded::Rc5(rc5) if rc5.is_extended() => "RC5X",
+            Decoded::Rc5(_) => "RC5",
+            Decoded::Rc6(_) => "RC6",
+            Decoded::Kaseikyo(_) => "Kaseikyo",
+            Decoded::Sharp(_) => "Sharp",
+            Decoded::Mitsubishi(_) => "Mitsubishi",
+            Decoded::Sanyo(_) => "Sanyo",
+            Decoded::BangOlufsen(_) => "B&O",
+            Decoded::Apple(_) => "Apple",
+            Decoded::Pioneer(_) => "Pioneer",
+            Decoded::Rca(_) => "RCA",
+            Decoded::Dish(_) => "Dish",
+            Decoded::Daikin(_) => "Daikin",
+            Decoded::Custom(custom) => &custom.protocol,
+            Decoded::Irp(irp) => &irp.protocol,
+        }
+    }
+
+    /// Payload bits of the frame, as transmitted.
+    pub fn packet(&self) -> Packet {
+        match self {
+            Decoded::Nec(nec) => nec.packet(),
+            Decoded::NecExt(ext) => ext.packet(),
+            Decoded::Samsung32(samsung) => samsung.packet(),
+            Decoded::Rc5(rc5) => rc5.packet(),
+            Decoded::Rc6(rc6) => rc6.packet(),
+            Decoded::Kaseikyo(kaseikyo) => kaseikyo.packet(),
+            Decoded::Sharp(sharp) => sharp.packet(),
+            Decoded::Mitsubishi(mitsubishi) => mitsubishi.packet(),
+            Decoded::Sanyo(sanyo) => sanyo.packet(),
+            Decoded::BangOlufsen(frame) => frame.packet(),
+            Decoded::Apple(apple) => apple.packet(),
+            Decoded::Pioneer(pioneer) => pioneer.packet(),
+            Decoded::Rca(rca) => rca.packet(),
+            Decoded::Dish(dish) => dish.packet(),
+            Decoded::Daikin(daikin) => daikin.packet(),
+            Decoded::Custom(custom) => custom.packet(),
+            Decoded::Irp(irp) => irp.packet(),
+        }
+    }
+
+    /// Payload bits of each section of the frame, as transmitted, flagged
+    /// with the result of [`Decoded::integrity`].
+    ///
+    /// Most protocols send single section frames, while air conditioners
+    /// split their state into several sections separated by gaps.
+    pub fn packets(&self) -> Vec<Packet> {
+        let mut packets = match self {
+            Decoded::Daikin(daikin) => daikin.packets(),
+            _ => vec![self.packet()],
+        };
+
+        if let Err(err) = self.integrity() {
+            for packet in &mut packets {
+                packet.integrity = Some(err.clone());
+            }
+        }
+
+        packets
+    }
+
+    /// Check the integrity of a frame accepted by its decoder despite
+    /// failing it, such as a Sharp frame without its inverted copy.
+    ///
+    /// Decoders of the other protocols reject such frames, see
+    /// [`Registry::integrity_failure`].
+    pub fn integrity(&self) -> Result<(), IntegrityError> {
+        let reason = match self {
+            Decoded::Sharp(sharp) if !sharp.verified => "inverted copy is missing or doesn't match",
+            Decoded::Daikin(daikin) if !daikin.checksums_valid() => "checksum doesn't match",
+            _ => return Ok(()),
+        };
+
+        Err(IntegrityError {
+            protocol: self.protocol().to_string(),
+            reason,
+        })
+    }
+}
+
+/// Try all protocol decoders of the default [`Registry`] on the given timings,
+/// sent on a carrier of `frequency` Hz.
+///
+/// NEC frames whose address doesn't match its inverse are reported as NECext,
+/// the same way the Flipper firmware does.
+pub fn decode(data: &[u32], frequency: u32) -> Option<Decoded> {
+    Registry::with_default(|registry| registry.decode(data, frequency))
+}
+
+/// Encode the lowest `count` bits of `value` with pulse distance coding,
+/// least significant bit first: each bit is a `mark` pulse followed by a
+/// `zero` or `one` pause.
+pub(crate) fn pulse_distance_timings(
+    value: u64,
+    count: usize,
+    mark: u32,
+    zero: u32,
+    one: u32,
+) -> Vec<u32> {
+    (0..count)
+        .flat_map(|bit| [mark, if value >> bit & 1 == 1 { one } else { zero }])
+        .collect()
+}
+
+/// A cursor over timings in the Flipper convention (pulse first),
+/// matching durations against nominal values with [`TOLERANCE`].
+#[derive(Debug, Clone)]
+pub(crate) struct Timings<'a> {
+    data: &'a [u32],
+    position: usize,
+}
+
+impl<'a> Timings<'a> {
+    pub(crate) fn new(data: &'a [u32]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    /// Take the next duration, whatever it is.
+    pub(crate) fn next(&mut self) -> Result<u32, DecodeError> {
+        let duration = *self
+            .data
+            .get(self.position)
+            .ok_or(DecodeError::UnexpectedEnd)?;
+        self.position += 1;
+
+        Ok(duration)
+    }
+
+    /// Take the next duration, checking that it matches `nominal`.
+    pub(crate) fn expect(&mut self, nominal: u32) -> Result<(), DecodeError> {
+        let index = self.position;
+        let duration = self.next()?;
+
+        if within_tolerance(duration, nominal, TOLERANCE) {
+            Ok(())
+        } else {
+            Err(DecodeError::UnexpectedDuration { index, duration })
+        }
+    }
+
+    /// Read Manchester coded half-bits of `unit` µs, see [`slice_half_bits`].
+    pub(crate) fn half_bits(&mut self, unit: u32, max_units: u32) -> Vec<bool> {
+        let rest = self.data.get(self.position..).unwrap_or_default();
+        let (levels, consumed) =
+            slice_half_bits(rest, self.position & 1 == 0, unit, max_units, TOLERANCE);
+        self.position += consumed;
+
+        levels
+    }
+
+    /// Decode Manchester coded bits from half-bit levels, see [`biphase_bits`].
+    pub(crate) fn biphase_bits(levels: &[bool], one: bool) -> Result<u64, DecodeError> {
+        signal::biphase_bits(levels, one).ok_or(DecodeError::Integrity("invalid Manchester coding"))
+    }
+
+    /// Check that a frame ends here: at the end of the timings or at a pause
+    /// of at least `min_gap`.
+    pub(crate) fn expect_end(&self, min_gap: u32) -> Result<(), DecodeError> {
+        match self.data.get(self.position) {
+            Some(&duration) if duration < min_gap => Err(DecodeError::UnexpectedDuration {
+                index: self.position,
+                duration,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Run `parse`, only advancing the cursor if it succeeds.
+    pub(crate) fn attempt<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, DecodeError>,
+    ) -> Result<T, DecodeError> {
+        let mut lookahead = self.clone();
+        let result = parse(&mut lookahead)?;
+        *self = lookahead;
+
+        Ok(result)
+    }
+
+    /// Count the repeats following a frame: each one is a pause of at least
+    /// `min_gap` and a frame accepted by `is_repeat`.
+    pub(crate) fn repeats(
+        &mut self,
+        min_gap: u32,
+        mut is_repeat: impl FnMut(&mut Self) -> bool,
+    ) -> usize {
+        let mut repeats = 0;
+        while self
+            .attempt(|timings| {
+                let index = timings.position;
+                let gap = timings.next()?;
+                if gap < min_gap || !is_repeat(timings) {
+                    return Err(DecodeError::UnexpectedDuration {
+                        index,
+                        duration: gap,
+                    });
+                }
+
+                Ok(())
+            })
+            .is_ok()
+        {
+            repeats += 1;
+        }
+
+        repeats
+    }
+
+    /// Read `count` pulse distance coded bits, least significant bit first:
+    /// each bit is a `mark` pulse followed by a `zero` or `one` pause.
+    pub(crate) fn pulse_distance_bits(
+        &mut self,
+        count: usize,
+        mark: u32,
+        zero: u32,
+        one: u32,
+    ) -> Result<u64, DecodeError> {
+        let mut value = 0;
+
+        for bit in 0..count {
+            self.expect(mark)?;
+
+            let index = self.position;
+            let space = self.next()?;
+            if within_tolerance(space, one, TOLERANCE) {
+                value |= 1 << bit;
+            } else if !within_tolerance(space, zero, TOLERANCE) {
+                return Err(DecodeError::UnexpectedDuration {
+                    index,
+                    duration: space,
+                });
+            }
+        }
+
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pulse_distance_bits() {
+        let data = [560, 560, 560, 1690, 560, 1690, 560];
+        let mut timings = Timings::new(&data);
+        assert_eq!(timings.pulse_distance_bits(3, 560, 560, 1690), Ok(0b110));
+        assert_eq!(timings.expect(560), Ok(()));
+        assert_eq!(timings.next(), Err(DecodeError::UnexpectedEnd));
+
+        let mut timings = Timings::new(&[560, 1100]);
+        assert_eq!(
+            timings.pulse_distance_bits(1, 560, 560, 1690),
+            Err(DecodeError::UnexpectedDuration {
+                index: 1,
+                duration: 1100
+            })
+        );
+    }
+}
@@ -0,0 +1,117 @@
+//! Apple Remote protocol: the NEC framing with the NECext address `0x87EE`,
+//! followed by a command byte and the pairing ID of the remote instead of the
+//! inverted command.
+//!
+//! A remote can be paired with a device, which then ignores remotes with
+//! other IDs; comparing IDs tells two physical remotes apart.
+
+use alloc::vec::Vec;
+
+use crate::signal::Packet;
+
+use super::{nec, DecodeError, Decoded, Protocol};
+
+/// NECext address shared by all Apple remotes.
+pub const ADDRESS: u16 = 0x87EE;
+const BITS: usize = 32;
+
+/// A decoded Apple Remote frame.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Apple {
+    pub command: u8,
+    pub pairing_id: u8,
+    /// Number of repeat frames following the frame.
+    pub repeats: usize,
+}
+
+impl Apple {
+    /// All 32 bits of the frame, as transmitted.
+    pub fn raw(&self) -> u32 {
+        u32::from(ADDRESS) | u32::from(self.command) << 16 | u32::from(self.pairing_id) << 24
+    }
+
+    pub(crate) fn packet(&self) -> Packet {
+        let mut packet = Packet::from_value(u64::from(self.raw()), BITS);
+        packet.repeats = self.repeats;
+        packet
+    }
+}
+
+/// Decode an Apple Remote frame at the start of the timings, along with the
+/// repeat frames following it.
+pub fn decode(data: &[u32]) -> Result<Apple, DecodeError> {
+    let (mut apple, repeats) = nec::decode_framed(data, |raw| {
+        if raw as u16 != ADDRESS {
+            return Err(DecodeError::Mismatch("not an Apple address"));
+        }
+
+        let [_, _, command, pairing_id] = raw.to_le_bytes();
+        Ok(Apple {
+            command,
+            pairing_id,
+            repeats: 0,
+        })
+    })?;
+    apple.repeats = repeats;
+
+    Ok(apple)
+}
+
+/// Encode an Apple Remote frame and its repeat frames into timings in the Flipper convention.
+pub fn encode(apple: &Apple) -> Vec<u32> {
+    nec::encode_framed(apple.raw(), apple.repeats)
+}
+
+/// Apple Remote decoder for the protocol [`Registry`](super::Registry).
+pub struct AppleProtocol;
+
+impl Protocol for AppleProtocol {
+    fn name(&self) -> &'static str {
+        "Apple"
+    }
+
+    fn matches(&self, data: &[u32], frequency: u32) -> bool {
+        nec::NecProtocol.matches(data, frequency)
+    }
+
+    fn decode(&self, data: &[u32], _frequency: u32) -> Result<Decoded, DecodeError> {
+        decode(data).map(Decoded::Apple)
+    }
+
+    fn encode(&self, decoded: &Decoded) -> Option<Vec<u32>> {
+        match decoded {
+            Decoded::Apple(apple) => Some(encode(apple)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{self, Decoded};
+
+    #[test]
+    fn test_decode() {
+        let apple = Apple {
+            command: 0x0B,
+            pairing_id: 0x5A,
+            repeats: 1,
+        };
+        let data = encode(&apple);
+
+        assert_eq!(decode(&data), Ok(apple));
+        assert_eq!(protocol::decode(&data, 38_000), Some(Decoded::Apple(apple)));
+    }
+
+    #[test]
+    fn test_decode_rejects_other_addresses() {
+        let nec = nec::Nec {
+            address: 0x04,
+            inverted_address: !0x04,
+            command: 0x08,
+            repeats: 0,
+        };
+        assert!(decode(&nec::encode(&nec)).is_err());
+    }
+}
@@ -0,0 +1,175 @@
+//! Bang & Olufsen Datalink 80 protocol, sent on a 455kHz carrier: every
+//! duration is a 200µs pulse followed by a pause, and the pause length
+//! carries the information.
+//!
+//! A frame is a preamble of two zero bits and a start bit, 16 data bits,
+//! most significant bit first, and a trailer. Data bits are coded relative to
+//! the previous bit: a 3.125ms period is a zero, a 9.375ms period is a one and
+//! a 6.25ms period repeats the previous bit. The bits are an 8-bit address
+//! and an 8-bit command.
+
+use alloc::{vec, vec::Vec};
+
+use crate::{signal::Packet, synthesis::GapPolicy};
+
+use super::{DecodeError, Decoded, Protocol, Timings};
+
+/// Carrier frequency of Bang & Olufsen remotes, in Hz.
+pub const FREQUENCY: u32 = 455_000;
+
+const MARK: u32 = 200;
+const ZERO_SPACE: u32 = 3125 - MARK;
+const SAME_SPACE: u32 = 6250 - MARK;
+const ONE_SPACE: u32 = 9375 - MARK;
+const START_SPACE: u32 = 15625 - MARK;
+const TRAILER_SPACE: u32 = 12500 - MARK;
+const BITS: usize = 16;
+/// Frames are separated by at least this much silence.
+const MIN_GAP: u32 = 20_000;
+
+/// A decoded Bang & Olufsen frame.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct BangOlufsen {
+    pub address: u8,
+    pub command: u8,
+    /// Number of repeat frames following the frame.
+    pub repeats: usize,
+}
+
+impl BangOlufsen {
+    /// All 16 data bits of the frame.
+    pub fn raw(&self) -> u16 {
+        u16::from_be_bytes([self.address, self.command])
+    }
+
+    pub(crate) fn packet(&self) -> Packet {
+        let mut packet = Packet::from_value(u64::from(self.raw()), BITS);
+        packet.repeats = self.repeats;
+        packet
+    }
+}
+
+/// Decode a Bang & Olufsen frame at the start of the timings, along with the
+/// identical frames following it.
+pub fn decode(data: &[u32]) -> Result<BangOlufsen, DecodeError> {
+    let mut timings = Timings::new(data);
+    let mut frame = decode_frame(&mut timings)?;
+
+    let raw = frame.raw();
+    frame.repeats = timings.repeats(MIN_GAP, |timings| {
+        timings
+            .attempt(decode_frame)
+            .is_ok_and(|frame| frame.raw() == raw)
+    });
+
+    Ok(frame)
+}
+
+fn decode_frame(timings: &mut Timings) -> Result<BangOlufsen, DecodeError> {
+    for space in [ZERO_SPACE, ZERO_SPACE, START_SPACE] {
+        timings.expect(MARK)?;
+        timings.expect(space)?;
+    }
+
+    let mut raw = 0u16;
+    let mut previous = false;
+    for _ in 0..BITS {
+        timings.expect(MARK)?;
+        let bit = if timings.attempt(|t| t.expect(ZERO_SPACE)).is_ok() {
+            false
+        } else if timings.attempt(|t| t.expect(ONE_SPACE)).is_ok() {
+            true
+        } else {
+            timings.expect(SAME_SPACE)?;
+            previous
+        };
+
+        raw = raw << 1 | u16::from(bit);
+        previous = bit;
+    }
+
+    timings.expect(MARK)?;
+    timings.expect(TRAILER_SPACE)?;
+    timings.expect(MARK)?;
+    timings.expect_end(MIN_GAP)?;
+
+    let [address, command] = raw.to_be_bytes();
+
+    Ok(BangOlufsen {
+        address,
+        command,
+        repeats: 0,
+    })
+}
+
+/// Encode a Bang & Olufsen frame and its repeats into timings in the Flipper convention.
+pub fn encode(frame: &BangOlufsen) -> Vec<u32> {
+    let mut timings = vec![MARK, ZERO_SPACE, MARK, ZERO_SPACE, MARK, START_SPACE];
+
+    let mut previous = false;
+    for bit in (0..BITS).rev().map(|bit| frame.raw() >> bit & 1 == 1) {
+        timings.push(MARK);
+        timings.push(match (bit, previous) {
+            (bit, previous) if bit == previous => SAME_SPACE,
+            (true, _) => ONE_SPACE,
+            (false, _) => ZERO_SPACE,
+        });
+        previous = bit;
+    }
+    timings.extend([MARK, TRAILER_SPACE, MARK]);
+
+    let gap = GapPolicy::BANG_OLUFSEN.required_gap(timings.iter().sum());
+    let mut data = timings.clone();
+    for _ in 0..frame.repeats {
+        data.push(gap);
+        data.extend(&timings);
+    }
+
+    data
+}
+
+/// Bang & Olufsen decoder for the protocol [`Registry`](super::Registry).
+pub struct BangOlufsenProtocol;
+
+impl Protocol for BangOlufsenProtocol {
+    fn name(&self) -> &'static str {
+        "B&O"
+    }
+
+    fn matches(&self, data: &[u32], _frequency: u32) -> bool {
+        Timings::new(data).expect(MARK).is_ok()
+    }
+
+    fn decode(&self, data: &[u32], _frequency: u32) -> Result<Decoded, DecodeError> {
+        decode(data).map(Decoded::BangOlufsen)
+    }
+
+    fn encode(&self, decoded: &Decoded) -> Option<Vec<u32>> {
+        match decoded {
+            Decoded::BangOlufsen(frame) => Some(encode(frame)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let frame = BangOlufsen {
+            address: 0x00,
+            command: 0x35,
+            repeats: 1,
+        };
+        let mut data = encode(&frame);
+        // some jitter
+        data[0] = 220;
+        data[5] = 15000;
+
+        assert_eq!(decode(&data), Ok(frame));
+        // 0x0035: the leading zeroes repeat the preamble bit
+        assert_eq!(data[7], SAME_SPACE);
+    }
+}
@@ -0,0 +1,160 @@
+//! How well decoded signals fit the timings they were decoded from.
+//!
+//! Decoders accept any duration within [`TOLERANCE`](super::TOLERANCE) of the
+//! nominal one, so a clean capture and a barely recognizable one decode
+//! alike, and timings of one protocol can be accepted by another with a
+//! similar framing. Encoding the decoded signal back and comparing it with
+//! the capture tells them apart.
+
+use core::fmt;
+
+use crate::signal::SEGMENT_GAP;
+
+use super::{Decoded, TOLERANCE};
+
+/// Confidence in a decoded signal, see [`Confidence::score`].
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Confidence {
+    /// Mean relative deviation of the captured durations from the ones the
+    /// protocol sends, pauses between frames excluded: 0 for a perfect fit.
+    ///
+    /// `None` if the protocol can't encode signals.
+    pub residual: Option<f32>,
+    /// Whether the protocol sends as many durations as were captured,
+    /// i.e. the decoder didn't skip or ignore any.
+    pub complete: bool,
+    /// Whether the signal passed the integrity checks of its protocol,
+    /// see [`Decoded::integrity`].
+    pub intact: bool,
+}
+
+impl Confidence {
+    /// Compare the `captured` timings with the ones `encoded` back from
+    /// the `decoded` signal.
+    pub fn measure(decoded: &Decoded, captured: &[u32], encoded: Option<&[u32]>) -> Self {
+        Self {
+            residual: encoded.map(|encoded| residual(captured, encoded)),
+            complete: encoded.is_some_and(|encoded| {
+                // trailing pauses aren't always captured
+                let trim = |data: &[u32]| data.len() - (data.len() & 1 == 0) as usize;
+                trim(captured) == trim(encoded)
+            }),
+            intact: decoded.integrity().is_ok(),
+        }
+    }
+
+    /// Score from 0 to 1: 1 for a complete, intact signal with exact timings,
+    /// halved by deviations of twice the [`TOLERANCE`], by each failed
+    /// structural check, and for unknown timings.
+    pub fn score(&self) -> f32 {
+        let fit = match self.residual {
+            Some(residual) => 1.0 - (residual / (4.0 * TOLERANCE)).min(1.0),
+            None => 0.5,
+        };
+        let complete = if self.complete { 1.0 } else { 0.5 };
+        let intact = if self.intact { 1.0 } else { 0.5 };
+
+        fit * complete * intact
+    }
+}
+
+impl fmt::Display for Confidence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.0}%", self.score() * 100.0)
+    }
+}
+
+/// Mean relative deviation of the `captured` durations from the `nominal` ones.
+fn residual(captured: &[u32], nominal: &[u32]) -> f32 {
+    let deviations = captured
+        .iter()
+        .zip(nominal)
+        // pauses between frames depend on the remote rather than the protocol
+        .filter(|(captured, nominal)| **captured < SEGMENT_GAP || **nominal < SEGMENT_GAP)
+        .map(|(&captured, &nominal)| {
+            let delta = (captured as f32 - nominal as f32).abs();
+            (delta / nominal.max(1) as f32).min(1.0)
+        });
+
+    let (sum, count) = deviations.fold((0.0, 0), |(sum, count), deviation| {
+        (sum + deviation, count + 1)
+    });
+    if count == 0 {
+        0.0
+    } else {
+        sum / count as f32
+    }
+}
+
+/// A signal decoded by one of the protocols of a [`Registry`](super::Registry).
+#[derive(Debug, PartialEq, Clone)]
+pub struct Candidate {
+    pub decoded: Decoded,
+    pub confidence: Confidence,
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::ToString, vec::Vec};
+
+    use super::*;
+    use crate::protocol::{
+        nec::{self, Nec},
+        sharp::{self, Sharp},
+    };
+
+    const NEC: Nec = Nec {
+        address: 0x04,
+        inverted_address: !0x04,
+        command: 0x08,
+        repeats: 0,
+    };
+
+    #[test]
+    fn test_residual() {
+        assert_eq!(residual(&[100, 200], &[100, 200]), 0.0);
+        assert_eq!(residual(&[110, 180], &[100, 200]), 0.1);
+        // pauses between frames are ignored
+        assert_eq!(residual(&[100, 40_000, 100], &[100, 60_000, 80]), 0.125);
+        assert_eq!(residual(&[], &[]), 0.0);
+    }
+
+    #[test]
+    fn test_score() {
+        let decoded = Decoded::Nec(NEC);
+        let data = nec::encode(&NEC);
+
+        let exact = Confidence::measure(&decoded, &data, Some(&data));
+        assert_eq!(exact.residual, Some(0.0));
+        assert!(exact.complete && exact.intact);
+        assert_eq!(exact.score(), 1.0);
+        assert_eq!(exact.to_string(), "100%");
+
+        let jittery: Vec<u32> = data.iter().map(|duration| duration * 6 / 5).collect();
+        let confidence = Confidence::measure(&decoded, &jittery, Some(&data));
+        assert!(confidence.score() < 0.9 && confidence.score() > 0.7);
+
+        // truncated captures and unknown timings
+        let confidence = Confidence::measure(&decoded, &data[..data.len() - 2], Some(&data));
+        assert!(!confidence.complete);
+        assert_eq!(confidence.score(), 0.5);
+        assert_eq!(Confidence::measure(&decoded, &data, None).score(), 0.25);
+    }
+
+    #[test]
+    fn test_integrity() {
+        let sharp = Sharp {
+            address: 0x01,
+            command: 0x02,
+            extension: 0b01,
+            verified: false,
+            repeats: 0,
+        };
+        let decoded = Decoded::Sharp(sharp);
+        let data = sharp::encode(&sharp);
+
+        let confidence = Confidence::measure(&decoded, &data, Some(&data));
+        assert!(!confidence.intact);
+        assert_eq!(confidence.score(), 0.5);
+    }
+}
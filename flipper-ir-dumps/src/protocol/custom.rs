@@ -0,0 +1,580 @@
+//! Protocols defined at runtime, from a protocol definition file in the
+//! Flipper "key: value" format:
+//!
+//! ```text
+//! Filetype: IR protocol definition
+//! Version: 1
+//! # pulse and pause of each part, in µs
+//! name: ACME TV
+//! header: 9000 4500
+//! zero: 560 560
+//! one: 560 1690
+//! bits: 32
+//! bit_order: LSB
+//! stop: 560
+//! gap: 40000
+//! checksum: sum8
+//! ```
+//!
+//! Each frame is an optional header, `bits` bits made of a pulse and a pause
+//! each, in the given order, and an optional stop pulse, followed by a pause
+//! of at least `gap`. Identical frames following it are counted as repeats.
+//!
+//! With a checksum, the last byte sent is the sum (`sum8`) or the xor
+//! (`xor8`) of the other bytes, computed by the encoder.
+
+use alloc::{string::String, vec, vec::Vec};
+
+use displaydoc::Display;
+use flipper_utils::within_tolerance;
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    combinator::{all_consuming, map_res, opt},
+    multi::many0,
+    Finish, Parser,
+};
+use thiserror::Error;
+
+use crate::{
+    flipper_format::{comment, field, header, string, uint, uint_list},
+    signal::Packet,
+};
+
+pub use crate::signal::BitOrder;
+
+use super::{DecodeError, Decoded, Protocol, Timings, TOLERANCE};
+
+/// Integrity check carried in the last byte of a frame.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum Checksum {
+    #[default]
+    None,
+    /// Sum of the other bytes.
+    Sum8,
+    /// Xor of the other bytes.
+    Xor8,
+}
+
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+/// Error loading a protocol definition
+pub enum DefinitionError {
+    /// Failed parsing protocol definition at line {line}: {kind:?}
+    Syntax {
+        line: usize,
+        kind: nom::error::ErrorKind,
+    },
+    /// Invalid protocol definition: {0}
+    Invalid(&'static str),
+}
+
+/// A protocol loaded from a definition file, to be added to a
+/// [`Registry`](super::Registry).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CustomProtocol {
+    pub name: String,
+    /// Pulse and pause starting each frame, if any.
+    pub header: Option<(u32, u32)>,
+    /// Pulse and pause of a zero bit.
+    pub zero: (u32, u32),
+    /// Pulse and pause of a one bit.
+    pub one: (u32, u32),
+    /// Number of bits of a frame, at most 64.
+    pub bits: usize,
+    pub bit_order: BitOrder,
+    /// Pulse ending each frame, if any.
+    pub stop: Option<u32>,
+    /// Minimum pause between frames.
+    pub gap: u32,
+    pub checksum: Checksum,
+}
+
+/// A frame of a [`CustomProtocol`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Custom {
+    /// Name of the protocol.
+    pub protocol: String,
+    /// Bits of the frame, the first one sent being the least significant
+    /// bit with [`BitOrder::Lsb`] and the most significant one with [`BitOrder::Msb`].
+    pub value: u64,
+    pub bits: usize,
+    pub bit_order: BitOrder,
+    /// Number of repeat frames following the frame.
+    pub repeats: usize,
+}
+
+impl Custom {
+    /// The frame as a packet, of at most 64 bits as `value` holds no more.
+    pub(crate) fn packet(&self) -> Packet {
+        let bits = self.bits.min(64);
+        let value = match self.bit_order {
+            BitOrder::Lsb => self.value,
+            BitOrder::Msb => self
+                .value
+                .reverse_bits()
+                .checked_shr(64 - bits as u32)
+                .unwrap_or(0),
+        };
+
+        let mut packet = Packet::from_value(value, bits).with_bit_order(self.bit_order);
+        packet.repeats = self.repeats;
+        packet
+    }
+}
+
+impl CustomProtocol {
+    /// Load a protocol from a definition file.
+    pub fn parse(input: &str) -> Result<Self, DefinitionError> {
+        let (_, protocol) = definition(input).finish().map_err(|err| {
+            let offset = input.len() - err.input.len();
+            DefinitionError::Syntax {
+                line: input[..offset].matches('\n').count() + 1,
+                kind: err.code,
+            }
+        })?;
+
+        protocol.check().map_err(DefinitionError::Invalid)?;
+        Ok(protocol)
+    }
+
+    /// Why the protocol is invalid, if it is.
+    fn check(&self) -> Result<(), &'static str> {
+        if !(1..=64).contains(&self.bits) {
+            return Err("frames must have 1 to 64 bits");
+        }
+        if self.zero == self.one {
+            return Err("zero and one bits are the same");
+        }
+        if self.stop.is_none() && self.zero.0 == self.one.0 {
+            return Err("bits differing by their pause only need a stop pulse");
+        }
+        if self.checksum != Checksum::None && (self.bits & 7 != 0 || self.bits < 16) {
+            return Err("checksums need frames of two or more whole bytes");
+        }
+
+        Ok(())
+    }
+
+    /// Bytes of `value`, in the order they are sent.
+    fn bytes(&self, value: u64) -> Vec<u8> {
+        let count = self.bits / 8;
+        let byte = |i: usize| (value >> (8 * i)) as u8;
+
+        match self.bit_order {
+            BitOrder::Lsb => (0..count).map(byte).collect(),
+            BitOrder::Msb => (0..count).rev().map(byte).collect(),
+        }
+    }
+
+    /// Checksum of the bytes of `value` but the last one sent.
+    fn checksum_of(&self, value: u64) -> Option<u8> {
+        let bytes = self.bytes(value);
+        let (_, bytes) = bytes.split_last()?;
+
+        match self.checksum {
+            Checksum::None => None,
+            Checksum::Sum8 => Some(bytes.iter().fold(0, |sum, b| sum.wrapping_add(*b))),
+            Checksum::Xor8 => Some(bytes.iter().fold(0, |sum, b| sum ^ b)),
+        }
+    }
+
+    /// `value` with its last byte sent replaced by the checksum.
+    fn with_checksum(&self, value: u64) -> u64 {
+        let Some(checksum) = self.checksum_of(value) else {
+            return value;
+        };
+        let shift = match self.bit_order {
+            BitOrder::Lsb => self.bits - 8,
+            BitOrder::Msb => 0,
+        };
+
+        value & !(0xFF << shift) | u64::from(checksum) << shift
+    }
+
+    /// Decode a frame at the start of the timings, along with the identical
+    /// frames following it.
+    ///
+    /// Protocols built in code rather than [parsed](CustomProtocol::parse)
+    /// are checked the same way first, and match no frames if invalid.
+    pub fn decode(&self, data: &[u32]) -> Result<Custom, DecodeError> {
+        self.check().map_err(DecodeError::Mismatch)?;
+
+        let mut timings = Timings::new(data);
+        let value = self.decode_frame(&mut timings)?;
+
+        if self.with_checksum(value) != value {
+            return Err(DecodeError::Integrity("checksum mismatch"));
+        }
+
+        let repeats = timings.repeats(self.gap, |timings| {
+            timings
+                .attempt(|timings| self.decode_frame(timings))
+                .is_ok_and(|repeat| repeat == value)
+        });
+
+        Ok(Custom {
+            protocol: self.name.clone(),
+            value,
+            bits: self.bits,
+            bit_order: self.bit_order,
+            repeats,
+        })
+    }
+
+    fn decode_frame(&self, timings: &mut Timings) -> Result<u64, DecodeError> {
+        if let Some((pulse, pause)) = self.header {
+            timings.expect(pulse)?;
+            timings.expect(pause)?;
+        }
+
+        let mut value = 0;
+        for i in 0..self.bits {
+            let bit = u64::from(self.decode_bit(timings, i + 1 == self.bits)?);
+            value = match self.bit_order {
+                BitOrder::Lsb => value | bit << i,
+                BitOrder::Msb => value << 1 | bit,
+            };
+        }
+
+        if let Some(stop) = self.stop {
+            timings.expect(stop)?;
+        }
+        timings.expect_end(self.gap)?;
+
+        Ok(value)
+    }
+
+    fn decode_bit(&self, timings: &mut Timings, last: bool) -> Result<bool, DecodeError> {
+        let matches = |duration, nominal| within_tolerance(duration, nominal, TOLERANCE);
+
+        let index = timings.position;
+        let pulse = timings.next()?;
+        let unexpected = |index, duration| DecodeError::UnexpectedDuration { index, duration };
+
+        // without a stop pulse, the pause of the last bit merges into the gap
+        if last && self.stop.is_none() {
+            return match (matches(pulse, self.zero.0), matches(pulse, self.one.0)) {
+                (true, false) => Ok(false),
+                (false, true) => Ok(true),
+                _ => Err(unexpected(index, pulse)),
+            };
+        }
+
+        let pause = timings.next()?;
+        if matches(pulse, self.zero.0) && matches(pause, self.zero.1) {
+            Ok(false)
+        } else if matches(pulse, self.one.0) && matches(pause, self.one.1) {
+            Ok(true)
+        } else {
+            Err(unexpected(index, pulse))
+        }
+    }
+
+    /// Encode a frame and its repeats into timings in the Flipper
+    /// convention, filling in the checksum.
+    ///
+    /// Fails if the protocol is invalid, as it can be if built in code
+    /// rather than [parsed](CustomProtocol::parse).
+    pub fn encode(&self, custom: &Custom) -> Result<Vec<u32>, DefinitionError> {
+        self.check().map_err(DefinitionError::Invalid)?;
+        let value = self.with_checksum(custom.value);
+
+        let mut frame = vec![];
+        if let Some((pulse, pause)) = self.header {
+            frame.extend([pulse, pause]);
+        }
+        for i in 0..self.bits {
+            let bit = match self.bit_order {
+                BitOrder::Lsb => value >> i & 1 == 1,
+                BitOrder::Msb => value >> (self.bits - 1 - i) & 1 == 1,
+            };
+            let (pulse, pause) = if bit { self.one } else { self.zero };
+            frame.extend([pulse, pause]);
+        }
+        match self.stop {
+            Some(stop) => frame.push(stop),
+            None => {
+                frame.pop();
+            }
+        }
+
+        let mut data = vec![];
+        for i in 0..=custom.repeats {
+            if i > 0 {
+                data.push(self.gap);
+            }
+            data.extend(&frame);
+        }
+
+        Ok(data)
+    }
+}
+
+impl Protocol for CustomProtocol {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn matches(&self, data: &[u32], _frequency: u32) -> bool {
+        let mut timings = Timings::new(data);
+        match self.header {
+            Some((pulse, pause)) => timings.expect(pulse).is_ok() && timings.expect(pause).is_ok(),
+            None => true,
+        }
+    }
+
+    fn decode(&self, data: &[u32], _frequency: u32) -> Result<Decoded, DecodeError> {
+        CustomProtocol::decode(self, data).map(Decoded::Custom)
+    }
+
+    fn encode(&self, decoded: &Decoded) -> Option<Vec<u32>> {
+        match decoded {
+            Decoded::Custom(custom) if custom.protocol == self.name => {
+                CustomProtocol::encode(self, custom).ok()
+            }
+            _ => None,
+        }
+    }
+}
+
+fn definition(input: &str) -> nom::IResult<&str, CustomProtocol> {
+    let (input, _) = header("IR protocol definition")(input)?;
+    let (input, name) = documented(field("name", string))(input)?;
+    let (input, header) = opt(documented(field("header", pair)))(input)?;
+    let (input, zero) = documented(field("zero", pair))(input)?;
+    let (input, one) = documented(field("one", pair))(input)?;
+    let (input, bits) = documented(field("bits", uint))(input)?;
+    let (input, bit_order) = documented(field("bit_order", bit_order))(input)?;
+    let (input, stop) = opt(documented(field("stop", uint)))(input)?;
+    let (input, gap) = documented(field("gap", uint))(input)?;
+    let (input, checksum) = opt(documented(field("checksum", checksum)))(input)?;
+    let (input, _) = all_consuming(many0(comment))(input)?;
+
+    Ok((
+        input,
+        CustomProtocol {
+            name,
+            header,
+            zero,
+            one,
+            bits: bits as usize,
+            bit_order,
+            stop,
+            gap,
+            checksum: checksum.unwrap_or_default(),
+        },
+    ))
+}
+
+/// A field preceded by any number of comments.
+fn documented<'a, O>(
+    mut field: impl FnMut(&'a str) -> nom::IResult<&'a str, O>,
+) -> impl FnMut(&'a str) -> nom::IResult<&'a str, O> {
+    move |input| {
+        let (input, _) = many0(comment)(input)?;
+        field(input)
+    }
+}
+
+/// A pulse and a pause.
+fn pair(input: &str) -> nom::IResult<&str, (u32, u32)> {
+    map_res(uint_list, |list| match list[..] {
+        [pulse, pause] => Ok((pulse, pause)),
+        _ => Err("expected a pulse and a pause"),
+    })(input)
+}
+
+fn bit_order(input: &str) -> nom::IResult<&str, BitOrder> {
+    alt((
+        tag("LSB").map(|_| BitOrder::Lsb),
+        tag("MSB").map(|_| BitOrder::Msb),
+    ))(input)
+}
+
+fn checksum(input: &str) -> nom::IResult<&str, Checksum> {
+    alt((
+        tag("none").map(|_| Checksum::None),
+        tag("sum8").map(|_| Checksum::Sum8),
+        tag("xor8").map(|_| Checksum::Xor8),
+    ))(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use indoc::indoc;
+
+    use super::*;
+    use crate::protocol::{
+        nec::{self, Nec},
+        Registry,
+    };
+
+    const ACME: &str = indoc! {"
+        Filetype: IR protocol definition
+        Version: 1
+        # NEC timings, but with a checksum instead of inverted bytes
+        name: ACME TV
+        header: 9000 4500
+        zero: 560 560
+        one: 560 1690
+        bits: 32
+        bit_order: LSB
+        stop: 560
+        gap: 40000
+        checksum: sum8
+    "};
+
+    fn acme() -> CustomProtocol {
+        CustomProtocol::parse(ACME).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let acme = acme();
+        assert_eq!(acme.name, "ACME TV");
+        assert_eq!(acme.header, Some((9000, 4500)));
+        assert_eq!(acme.stop, Some(560));
+        assert_eq!(acme.checksum, Checksum::Sum8);
+
+        let invalid = ACME.replace("bits: 32", "bits: 12");
+        assert_eq!(
+            CustomProtocol::parse(&invalid),
+            Err(DefinitionError::Invalid(
+                "checksums need frames of two or more whole bytes"
+            ))
+        );
+        assert_eq!(
+            CustomProtocol::parse(&ACME.replace("LSB", "lsb")),
+            Err(DefinitionError::Syntax {
+                line: 9,
+                kind: nom::error::ErrorKind::Tag
+            })
+        );
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let acme = acme();
+        let custom = Custom {
+            protocol: "ACME TV".to_string(),
+            value: 0x0030_2010,
+            bits: 32,
+            bit_order: BitOrder::Lsb,
+            repeats: 1,
+        };
+        let data = acme.encode(&custom).unwrap();
+
+        let decoded = acme.decode(&data).unwrap();
+        assert_eq!(decoded.value, 0x6030_2010);
+        assert_eq!(decoded.repeats, 1);
+
+        // a NEC frame has the right timings, but not the checksum
+        let nec = nec::encode(&Nec {
+            address: 0x10,
+            inverted_address: !0x10,
+            command: 0x20,
+            repeats: 0,
+        });
+        assert_eq!(
+            acme.decode(&nec),
+            Err(DecodeError::Integrity("checksum mismatch"))
+        );
+    }
+
+    #[test]
+    fn test_pulse_width() {
+        let sirc = CustomProtocol {
+            name: "SIRC".to_string(),
+            header: Some((2400, 600)),
+            zero: (600, 600),
+            one: (1200, 600),
+            bits: 12,
+            bit_order: BitOrder::Lsb,
+            stop: None,
+            gap: 10_000,
+            checksum: Checksum::None,
+        };
+        let custom = Custom {
+            protocol: "SIRC".to_string(),
+            value: 0x095,
+            bits: 12,
+            bit_order: BitOrder::Lsb,
+            repeats: 2,
+        };
+
+        assert_eq!(sirc.decode(&sirc.encode(&custom).unwrap()), Ok(custom));
+    }
+
+    #[test]
+    fn test_registry() {
+        let mut registry = Registry::new();
+        registry.register(acme());
+
+        let custom = Custom {
+            protocol: "ACME VCR".to_string(),
+            value: 0xA2_0201,
+            bits: 32,
+            bit_order: BitOrder::Msb,
+            repeats: 0,
+        };
+        let msb = CustomProtocol {
+            name: "ACME VCR".to_string(),
+            bit_order: BitOrder::Msb,
+            ..acme()
+        };
+        let data = msb.encode(&custom).unwrap();
+        assert!(registry.decode(&data, 38_000).is_none());
+
+        registry.register(msb);
+        let decoded = registry.decode(&data, 38_000).unwrap();
+        assert_eq!(decoded.protocol(), "ACME VCR");
+        // packets read in the bit order of the protocol, checksum included
+        let packet = decoded.packet();
+        assert_eq!(packet.as_u64(), Ok(0xA2_02A4));
+        assert_eq!(
+            packet.with_bit_order(BitOrder::Lsb).to_string(),
+            "00100101010000000100010100000000"
+        );
+        assert_eq!(registry.encode(&decoded), Some(data));
+    }
+
+    #[test]
+    fn test_invalid_protocols_built_in_code() {
+        for (bits, bit_order) in [(0, BitOrder::Msb), (65, BitOrder::Lsb)] {
+            let protocol = CustomProtocol {
+                bits,
+                bit_order,
+                ..acme()
+            };
+            let custom = Custom {
+                protocol: protocol.name.clone(),
+                value: u64::MAX,
+                bits,
+                bit_order,
+                repeats: 0,
+            };
+
+            let data = acme()
+                .encode(&Custom {
+                    bits: 32,
+                    ..custom.clone()
+                })
+                .unwrap();
+            assert_eq!(
+                protocol.decode(&data),
+                Err(DecodeError::Mismatch("frames must have 1 to 64 bits"))
+            );
+            assert_eq!(
+                protocol.encode(&custom),
+                Err(DefinitionError::Invalid("frames must have 1 to 64 bits"))
+            );
+            assert_eq!(
+                Protocol::encode(&protocol, &Decoded::Custom(custom.clone())),
+                None
+            );
+            assert_eq!(custom.packet().bit_len(), bits.min(64));
+        }
+    }
+}
@@ -0,0 +1,219 @@
+//! Daikin air conditioner protocol (216 bits, ARC433 and similar remotes):
+//! instead of key presses, each frame carries the whole state of the unit.
+//!
+//! A frame is two sections separated by a gap, each a 3440µs/1750µs header,
+//! pulse distance coded bytes with 420µs marks, least significant bit first,
+//! and a stop mark. The first section is 8 bytes, the second one 19 bytes
+//! with the actual settings. Both start with the `11 DA 27` signature and
+//! end with a checksum: the sum of the other bytes of the section.
+
+use alloc::{vec, vec::Vec};
+
+use crate::signal::Packet;
+
+use super::{pulse_distance_timings, DecodeError, Decoded, Protocol, Timings};
+
+const HEADER_MARK: u32 = 3440;
+const HEADER_SPACE: u32 = 1750;
+const BIT_MARK: u32 = 420;
+const ZERO_SPACE: u32 = 450;
+const ONE_SPACE: u32 = 1300;
+/// Gap between the sections of a frame.
+const SECTION_GAP: u32 = 29_650;
+/// Frames end with at least this much silence.
+const MIN_GAP: u32 = 20_000;
+/// Lengths of the sections, in bytes.
+const SECTIONS: [usize; 2] = [8, 19];
+/// Total length of the state, in bytes.
+pub const STATE_BYTES: usize = SECTIONS[0] + SECTIONS[1];
+/// First bytes of each section.
+const SIGNATURE: [u8; 3] = [0x11, 0xDA, 0x27];
+
+/// A decoded Daikin frame.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Daikin {
+    /// Bytes of both sections, as transmitted, checksums included.
+    pub state: [u8; STATE_BYTES],
+}
+
+impl Daikin {
+    /// Bytes of each section of the frame.
+    pub fn sections(&self) -> impl Iterator<Item = &[u8]> {
+        let (first, second) = self.state.split_at(SECTIONS[0]);
+        [first, second].into_iter()
+    }
+
+    /// Whether the checksums of all sections match their contents.
+    ///
+    /// Frames with bad checksums are still decoded, as they may come from a
+    /// remote computing them differently, or from a noisy capture.
+    pub fn checksums_valid(&self) -> bool {
+        self.sections().all(|section| {
+            let (checksum, bytes) = section.split_last().expect("sections aren't empty");
+            *checksum == checksum_of(bytes)
+        })
+    }
+
+    /// Recompute the checksums of all sections, e.g. after changing the state.
+    pub fn update_checksums(&mut self) {
+        let mut start = 0;
+        for len in SECTIONS {
+            let end = start + len;
+            self.state[end - 1] = checksum_of(&self.state[start..end - 1]);
+            start = end;
+        }
+    }
+
+    /// All bits of the frame, as transmitted.
+    pub(crate) fn packet(&self) -> Packet {
+        Packet::from_bytes(&self.state)
+    }
+
+    /// Bits of each section, as transmitted.
+    pub(crate) fn packets(&self) -> Vec<Packet> {
+        self.sections().map(Packet::from_bytes).collect()
+    }
+}
+
+fn checksum_of(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0, |sum, byte| sum.wrapping_add(*byte))
+}
+
+/// Decode a Daikin frame at the start of the timings.
+pub fn decode(data: &[u32]) -> Result<Daikin, DecodeError> {
+    let mut timings = Timings::new(data);
+    let mut state = [0; STATE_BYTES];
+
+    let mut start = 0;
+    for (i, len) in SECTIONS.into_iter().enumerate() {
+        if i > 0 {
+            timings.expect(SECTION_GAP)?;
+        }
+        let end = start + len;
+        decode_section(&mut timings, &mut state[start..end])?;
+        start = end;
+    }
+    timings.expect_end(MIN_GAP)?;
+
+    Ok(Daikin { state })
+}
+
+fn decode_section(timings: &mut Timings, bytes: &mut [u8]) -> Result<(), DecodeError> {
+    timings.expect(HEADER_MARK)?;
+    timings.expect(HEADER_SPACE)?;
+    for byte in bytes.iter_mut() {
+        *byte = timings.pulse_distance_bits(8, BIT_MARK, ZERO_SPACE, ONE_SPACE)? as u8;
+    }
+    timings.expect(BIT_MARK)?;
+
+    if bytes.starts_with(&SIGNATURE) {
+        Ok(())
+    } else {
+        Err(DecodeError::Mismatch("unknown signature"))
+    }
+}
+
+/// Encode a Daikin frame into timings in the Flipper convention.
+pub fn encode(daikin: &Daikin) -> Vec<u32> {
+    let mut data = vec![];
+
+    for (i, section) in daikin.sections().enumerate() {
+        if i > 0 {
+            data.push(SECTION_GAP);
+        }
+        data.extend([HEADER_MARK, HEADER_SPACE]);
+        for byte in section {
+            data.extend(pulse_distance_timings(
+                u64::from(*byte),
+                8,
+                BIT_MARK,
+                ZERO_SPACE,
+                ONE_SPACE,
+            ));
+        }
+        data.push(BIT_MARK);
+    }
+
+    data
+}
+
+/// Daikin decoder for the protocol [`Registry`](super::Registry).
+pub struct DaikinProtocol;
+
+impl Protocol for DaikinProtocol {
+    fn name(&self) -> &'static str {
+        "Daikin"
+    }
+
+    fn matches(&self, data: &[u32], _frequency: u32) -> bool {
+        let mut timings = Timings::new(data);
+        timings.expect(HEADER_MARK).is_ok() && timings.expect(HEADER_SPACE).is_ok()
+    }
+
+    fn decode(&self, data: &[u32], _frequency: u32) -> Result<Decoded, DecodeError> {
+        decode(data).map(Decoded::Daikin)
+    }
+
+    fn encode(&self, decoded: &Decoded) -> Option<Vec<u32>> {
+        match decoded {
+            Decoded::Daikin(daikin) => Some(encode(daikin)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::*;
+    use crate::protocol;
+
+    /// Powered on, cooling at 24°C.
+    const COOL_24: [u8; STATE_BYTES] = [
+        0x11, 0xDA, 0x27, 0xF0, 0x00, 0x00, 0x00, 0x02, // first section
+        0x11, 0xDA, 0x27, 0x00, 0x00, 0x39, 0x30, 0x00, 0xA0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00,
+    ];
+
+    fn cool_24() -> Daikin {
+        let mut daikin = Daikin { state: COOL_24 };
+        daikin.update_checksums();
+        daikin
+    }
+
+    #[test]
+    fn test_decode() {
+        let daikin = cool_24();
+        assert!(daikin.checksums_valid());
+
+        let mut data = encode(&daikin);
+        // some jitter
+        data[0] = 3500;
+        data[3] = 1210;
+
+        assert_eq!(decode(&data), Ok(daikin));
+        assert_eq!(daikin.packets().len(), 2);
+        assert_eq!(daikin.packets()[1].to_string().len(), 19 * 8);
+    }
+
+    #[test]
+    fn test_invalid_checksum() {
+        let mut daikin = cool_24();
+        daikin.state[13] ^= 0x01;
+
+        let decoded = decode(&encode(&daikin)).unwrap();
+        assert_eq!(decoded, daikin);
+        assert!(!decoded.checksums_valid());
+    }
+
+    #[test]
+    fn test_decoded() {
+        let daikin = cool_24();
+        let decoded = protocol::decode(&encode(&daikin), 38_000).unwrap();
+
+        assert_eq!(decoded, Decoded::Daikin(daikin));
+        assert_eq!(decoded.protocol(), "Daikin");
+        assert_eq!(decoded.packets().len(), 2);
+    }
+}
@@ -0,0 +1,152 @@
+//! Dish Network (EchoStar) protocol: a 400µs/6.1ms header, 16 pulse distance
+//! coded bits with 400µs marks, most significant bit first, and a final stop
+//! mark, on a 57.6kHz carrier. Unusually, a one has the shorter pause.
+//!
+//! The bits are a 6-bit command, a 5-bit address and a 5-bit unit code.
+//!
+//! Repeats follow without a gap: the stop mark of a frame doubles as the
+//! header mark of the next one.
+
+use alloc::{vec, vec::Vec};
+
+use crate::signal::Packet;
+
+use super::{pulse_distance_timings, DecodeError, Decoded, Protocol, Timings};
+
+/// Carrier frequency of Dish Network remotes, in Hz.
+pub const FREQUENCY: u32 = 57_600;
+
+const BIT_MARK: u32 = 400;
+const HEADER_SPACE: u32 = 6100;
+const ZERO_SPACE: u32 = 2800;
+const ONE_SPACE: u32 = 1700;
+const BITS: usize = 16;
+/// Messages are separated by at least this much silence.
+const MIN_GAP: u32 = 10_000;
+
+/// A decoded Dish Network frame.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Dish {
+    /// 6-bit command.
+    pub command: u8,
+    /// 5-bit address.
+    pub address: u8,
+    /// 5-bit unit code.
+    pub unit: u8,
+    /// Number of repeat frames following the frame.
+    pub repeats: usize,
+}
+
+impl Dish {
+    /// All 16 bits of the frame, as transmitted.
+    pub fn raw(&self) -> u16 {
+        u16::from(self.command & 0x3F) << 10
+            | u16::from(self.address & 0x1F) << 5
+            | u16::from(self.unit & 0x1F)
+    }
+
+    pub(crate) fn packet(&self) -> Packet {
+        let mut packet = Packet::from_value(u64::from(self.raw()), BITS);
+        packet.repeats = self.repeats;
+        packet
+    }
+}
+
+/// Decode a Dish Network frame at the start of the timings, along with the
+/// identical frames following it.
+pub fn decode(data: &[u32]) -> Result<Dish, DecodeError> {
+    let mut timings = Timings::new(data);
+    timings.expect(BIT_MARK)?;
+    let raw = decode_frame(&mut timings)?;
+
+    let mut repeats = 0;
+    while timings
+        .attempt(|timings| match decode_frame(timings)? {
+            frame if frame == raw => Ok(()),
+            _ => Err(DecodeError::Integrity("repeat doesn't match")),
+        })
+        .is_ok()
+    {
+        repeats += 1;
+    }
+    timings.expect_end(MIN_GAP)?;
+
+    Ok(Dish {
+        command: (raw >> 10) as u8,
+        address: (raw >> 5 & 0x1F) as u8,
+        unit: (raw & 0x1F) as u8,
+        repeats,
+    })
+}
+
+/// Decode a frame following a mark: the header pause, the bits and the stop mark.
+fn decode_frame(timings: &mut Timings) -> Result<u16, DecodeError> {
+    timings.expect(HEADER_SPACE)?;
+    let lsb_first = timings.pulse_distance_bits(BITS, BIT_MARK, ZERO_SPACE, ONE_SPACE)?;
+    timings.expect(BIT_MARK)?;
+
+    Ok((lsb_first.reverse_bits() >> (64 - BITS)) as u16)
+}
+
+/// Encode a Dish Network frame and its repeats into timings in the Flipper convention.
+pub fn encode(dish: &Dish) -> Vec<u32> {
+    let lsb_first = u64::from(dish.raw()).reverse_bits() >> (64 - BITS);
+
+    let mut data = vec![BIT_MARK];
+    for _ in 0..=dish.repeats {
+        data.push(HEADER_SPACE);
+        data.extend(pulse_distance_timings(
+            lsb_first, BITS, BIT_MARK, ZERO_SPACE, ONE_SPACE,
+        ));
+        data.push(BIT_MARK);
+    }
+
+    data
+}
+
+/// Dish Network decoder for the protocol [`Registry`](super::Registry).
+pub struct DishProtocol;
+
+impl Protocol for DishProtocol {
+    fn name(&self) -> &'static str {
+        "Dish"
+    }
+
+    fn matches(&self, data: &[u32], _frequency: u32) -> bool {
+        let mut timings = Timings::new(data);
+        timings.expect(BIT_MARK).is_ok() && timings.expect(HEADER_SPACE).is_ok()
+    }
+
+    fn decode(&self, data: &[u32], _frequency: u32) -> Result<Decoded, DecodeError> {
+        decode(data).map(Decoded::Dish)
+    }
+
+    fn encode(&self, decoded: &Decoded) -> Option<Vec<u32>> {
+        match decoded {
+            Decoded::Dish(dish) => Some(encode(dish)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let dish = Dish {
+            command: 0x11,
+            address: 0x00,
+            unit: 0x01,
+            repeats: 3,
+        };
+        let mut data = encode(&dish);
+        // some jitter
+        data[1] = 6000;
+        data[3] = 2750;
+
+        assert_eq!(decode(&data), Ok(dish));
+        assert_eq!(dish.raw(), 0b0100_0100_0000_0001);
+    }
+}
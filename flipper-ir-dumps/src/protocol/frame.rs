@@ -0,0 +1,260 @@
+//! The fields of decoded signals, alike for all protocols.
+
+use alloc::{
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::fmt;
+
+use super::{rc6::Rc6Mode, Decoded, IntegrityError};
+
+/// A frame decoded by a protocol, with its fields under common names:
+/// the main consumer-facing result of decoding, see [`Packet::frame`](crate::signal::Packet::frame).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DecodedFrame {
+    /// Name of the protocol, see [`Decoded::protocol`].
+    pub protocol: String,
+    /// Device the frame is meant for, if the protocol addresses devices.
+    pub address: Option<u32>,
+    /// Button or function of the device, if the protocol has commands.
+    pub command: Option<u32>,
+    /// Other fields of the protocol by name, such as toggle bits.
+    pub extra: Vec<(String, u64)>,
+    /// The integrity check the frame failed, if any, see [`Decoded::integrity`].
+    pub integrity: Option<IntegrityError>,
+}
+
+impl DecodedFrame {
+    /// Whether the frame passed the integrity checks of its protocol.
+    pub fn is_valid(&self) -> bool {
+        self.integrity.is_none()
+    }
+
+    /// Value of the extra field `name`.
+    pub fn extra(&self, name: &str) -> Option<u64> {
+        self.extra
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| *value)
+    }
+}
+
+impl fmt::Display for DecodedFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.protocol)?;
+        if let Some(address) = self.address {
+            write!(f, " address 0x{:02X}", address)?;
+        }
+        if let Some(command) = self.command {
+            write!(f, " command 0x{:02X}", command)?;
+        }
+        for (name, value) in &self.extra {
+            write!(f, " {}={}", name, value)?;
+        }
+        if !self.is_valid() {
+            write!(f, " (invalid)")?;
+        }
+        Ok(())
+    }
+}
+
+impl Decoded {
+    /// The fields of the frame under the names common to all protocols.
+    pub fn frame(&self) -> DecodedFrame {
+        let fields = |address: Option<u32>, command: Option<u32>, extra: &[(&str, u64)]| {
+            let extra = extra
+                .iter()
+                .map(|(name, value)| (name.to_string(), *value))
+                .collect();
+            (address, command, extra)
+        };
+
+        let (address, command, extra) = match self {
+            Decoded::Nec(nec) => fields(
+                Some(nec.address.into()),
+                Some(nec.command.into()),
+                &[("inverted_address", nec.inverted_address.into())],
+            ),
+            Decoded::NecExt(ext) => fields(Some(ext.address.into()), Some(ext.command.into()), &[]),
+            Decoded::Samsung32(samsung) => fields(
+                Some(samsung.address.into()),
+                Some(samsung.command.into()),
+                &[],
+            ),
+            Decoded::Rc5(rc5) => fields(
+                Some(rc5.address.into()),
+                Some(rc5.command.into()),
+                &[("toggle", rc5.toggle.into())],
+            ),
+            Decoded::Rc6(rc6) => {
+                let mut extra = vec![("toggle", rc6.toggle.into())];
+                match rc6.mode {
+                    Rc6Mode::Mode0 => extra.push(("mode", 0)),
+                    Rc6Mode::Mode6A { customer } => {
+                        extra.extend([("mode", 6), ("customer", customer.into())]);
+                    }
+                }
+                fields(Some(rc6.address.into()), Some(rc6.command.into()), &extra)
+            }
+            Decoded::Kaseikyo(kaseikyo) => fields(
+                None,
+                Some(kaseikyo.data.into()),
+                &[
+                    ("vendor", kaseikyo.vendor.into()),
+                    ("genre1", kaseikyo.genre1.into()),
+                    ("genre2", kaseikyo.genre2.into()),
+                    ("id", kaseikyo.id.into()),
+                ],
+            ),
+            Decoded::Sharp(sharp) => fields(
+                Some(sharp.address.into()),
+                Some(sharp.command.into()),
+                &[("extension", sharp.extension.into())],
+            ),
+            Decoded::Mitsubishi(mitsubishi) => fields(
+                Some(mitsubishi.address.into()),
+                Some(mitsubishi.command.into()),
+                &[],
+            ),
+            Decoded::Sanyo(sanyo) => {
+                fields(Some(sanyo.address.into()), Some(sanyo.command.into()), &[])
+            }
+            Decoded::BangOlufsen(frame) => {
+                fields(Some(frame.address.into()), Some(frame.command.into()), &[])
+            }
+            Decoded::Apple(apple) => fields(
+                None,
+                Some(apple.command.into()),
+                &[("pairing_id", apple.pairing_id.into())],
+            ),
+            Decoded::Pioneer(pioneer) => {
+                let mut extra = vec![];
+                if let Some((address, command)) = pioneer.second {
+                    extra.extend([
+                        ("second_address", address.into()),
+                        ("second_command", command.into()),
+                    ]);
+                }
+                fields(
+                    Some(pioneer.address.into()),
+                    Some(pioneer.command.into()),
+                    &extra,
+                )
+            }
+            Decoded::Rca(rca) => fields(Some(rca.address.into()), Some(rca.command.into()), &[]),
+            Decoded::Dish(dish) => fields(
+                Some(dish.address.into()),
+                Some(dish.command.into()),
+                &[("unit", dish.unit.into())],
+            ),
+            Decoded::Daikin(_) => fields(None, None, &[]),
+            Decoded::Custom(custom) => fields(None, None, &[("value", custom.value)]),
+            Decoded::Irp(irp) => {
+                // IRP names the device D and the function F
+                let parameter = |name: &str| irp.parameters.get(name).map(|&value| value as u32);
+                let extra = irp
+                    .parameters
+                    .iter()
+                    .filter(|(name, _)| !matches!(name.as_str(), "D" | "F"))
+                    .map(|(name, &value)| (name.clone(), value as u64))
+                    .collect();
+                (parameter("D"), parameter("F"), extra)
+            }
+        };
+
+        DecodedFrame {
+            protocol: self.protocol().to_string(),
+            address,
+            command,
+            extra,
+            integrity: self.integrity().err(),
+        }
+    }
+
+    /// The same frame with another command, see [`DecodedFrame::command`].
+    ///
+    /// Returns `None` for protocols without commands and for commands that
+    /// don't fit the field of the protocol. Commands that fit the field but
+    /// exceed the width of the protocol are truncated when encoded.
+    pub fn with_command(&self, command: u32) -> Option<Decoded> {
+        let byte = u8::try_from(command).ok();
+        let mut decoded = self.clone();
+
+        match &mut decoded {
+            Decoded::Nec(nec) => nec.command = byte?,
+            Decoded::NecExt(ext) => ext.command = byte?,
+            Decoded::Samsung32(samsung) => samsung.command = byte?,
+            Decoded::Rc5(rc5) => rc5.command = byte?,
+            Decoded::Rc6(rc6) => rc6.command = byte?,
+            Decoded::Kaseikyo(kaseikyo) => kaseikyo.data = u16::try_from(command).ok()?,
+            Decoded::Sharp(sharp) => sharp.command = byte?,
+            Decoded::Mitsubishi(mitsubishi) => mitsubishi.command = byte?,
+            Decoded::Sanyo(sanyo) => sanyo.command = byte?,
+            Decoded::BangOlufsen(frame) => frame.command = byte?,
+            Decoded::Apple(apple) => apple.command = byte?,
+            Decoded::Pioneer(pioneer) => pioneer.command = byte?,
+            Decoded::Rca(rca) => rca.command = byte?,
+            Decoded::Dish(dish) => dish.command = byte?,
+            Decoded::Irp(irp) => {
+                *irp.parameters.get_mut("F")? = command.into();
+            }
+            Decoded::Daikin(_) | Decoded::Custom(_) => return None,
+        }
+
+        Some(decoded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::*;
+    use crate::protocol::{
+        nec::{self, Nec},
+        rc5::Rc5,
+        sharp::Sharp,
+    };
+
+    #[test]
+    fn test_frame() {
+        let nec = Nec {
+            address: 0x04,
+            inverted_address: !0x04,
+            command: 0x08,
+            repeats: 0,
+        };
+        let frame = Decoded::Nec(nec).frame();
+        assert_eq!(frame.address, Some(0x04));
+        assert_eq!(frame.command, Some(0x08));
+        assert_eq!(frame.extra("inverted_address"), Some(0xFB));
+        assert!(frame.is_valid());
+        assert_eq!(
+            frame.to_string(),
+            "NEC address 0x04 command 0x08 inverted_address=251"
+        );
+
+        let rc5 = Rc5 {
+            address: 0x05,
+            command: 0x35,
+            toggle: true,
+            repeats: 0,
+        };
+        assert_eq!(Decoded::Rc5(rc5).frame().extra("toggle"), Some(1));
+
+        let sharp = Sharp {
+            address: 0x01,
+            command: 0x02,
+            extension: 0b01,
+            verified: false,
+            repeats: 0,
+        };
+        let frame = Decoded::Sharp(sharp).frame();
+        assert!(!frame.is_valid());
+        assert!(frame.to_string().ends_with("(invalid)"));
+
+        let decoded = crate::protocol::decode(&nec::encode(&nec), 38_000).unwrap();
+        assert_eq!(decoded.frame(), Decoded::Nec(nec).frame());
+    }
+}
@@ -0,0 +1,214 @@
+//! Kaseikyo protocol family (Panasonic, Denon, JVC, Mitsubishi, Sharp):
+//! 3.456ms/1.728ms header, 48 pulse distance coded bits with 432µs marks,
+//! least significant bit first, and a final stop mark.
+//!
+//! The bits are a 16-bit vendor ID, a 4-bit vendor parity, two 4-bit genre
+//! codes, 10 data bits, a 2-bit ID and an 8-bit checksum: the XOR of the
+//! three bytes before it.
+
+use alloc::{vec, vec::Vec};
+
+use crate::{signal::Packet, synthesis::GapPolicy};
+
+use super::{pulse_distance_timings, DecodeError, Decoded, Protocol, Timings};
+
+const UNIT: u32 = 432;
+const HEADER_MARK: u32 = 8 * UNIT;
+const HEADER_SPACE: u32 = 4 * UNIT;
+const BIT_MARK: u32 = UNIT;
+const ZERO_SPACE: u32 = UNIT;
+const ONE_SPACE: u32 = 3 * UNIT;
+const BITS: usize = 48;
+/// Frames are separated by at least this much silence.
+const MIN_GAP: u32 = 20_000;
+
+/// A decoded Kaseikyo frame.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Kaseikyo {
+    pub vendor: u16,
+    /// 4-bit genre code.
+    pub genre1: u8,
+    /// 4-bit genre code.
+    pub genre2: u8,
+    /// 10-bit command.
+    pub data: u16,
+    /// 2-bit ID.
+    pub id: u8,
+    /// Number of repeat frames following the frame.
+    pub repeats: usize,
+}
+
+impl Kaseikyo {
+    /// Name of the manufacturer the vendor ID is assigned to, if known.
+    pub fn vendor_name(&self) -> Option<&'static str> {
+        match self.vendor {
+            0x2002 => Some("Panasonic"),
+            0x3254 => Some("Denon"),
+            0x0103 => Some("JVC"),
+            0xCB23 => Some("Mitsubishi"),
+            0x5AAA => Some("Sharp"),
+            _ => None,
+        }
+    }
+
+    /// 4-bit XOR of the vendor ID nibbles.
+    fn vendor_parity(&self) -> u8 {
+        let [vendor_low, vendor_high] = self.vendor.to_le_bytes();
+        let parity = vendor_low ^ vendor_high;
+        (parity ^ parity >> 4) & 0xF
+    }
+
+    /// All 48 bits of the frame, as transmitted.
+    pub fn raw(&self) -> u64 {
+        let bytes = [
+            self.vendor_parity() | (self.genre1 & 0xF) << 4,
+            (self.genre2 & 0xF) | (self.data as u8 & 0xF) << 4,
+            (self.data >> 4) as u8 & 0x3F | (self.id & 0x3) << 6,
+        ];
+        let checksum = bytes[0] ^ bytes[1] ^ bytes[2];
+
+        u64::from(self.vendor)
+            | u64::from(bytes[0]) << 16
+            | u64::from(bytes[1]) << 24
+            | u64::from(bytes[2]) << 32
+            | u64::from(checksum) << 40
+    }
+
+    pub(crate) fn packet(&self) -> Packet {
+        let mut packet = Packet::from_value(self.raw(), BITS);
+        packet.repeats = self.repeats;
+        packet
+    }
+}
+
+/// Decode a Kaseikyo frame at the start of the timings, along with the
+/// identical frames following it.
+///
+/// Fails if the vendor parity or the checksum doesn't match.
+pub fn decode(data: &[u32]) -> Result<Kaseikyo, DecodeError> {
+    let mut timings = Timings::new(data);
+    let mut kaseikyo = decode_frame(&mut timings)?;
+
+    let raw = kaseikyo.raw();
+    kaseikyo.repeats = timings.repeats(MIN_GAP, |timings| {
+        timings
+            .attempt(decode_frame)
+            .is_ok_and(|frame| frame.raw() == raw)
+    });
+
+    Ok(kaseikyo)
+}
+
+fn decode_frame(timings: &mut Timings) -> Result<Kaseikyo, DecodeError> {
+    timings.expect(HEADER_MARK)?;
+    timings.expect(HEADER_SPACE)?;
+    let raw = timings.pulse_distance_bits(BITS, BIT_MARK, ZERO_SPACE, ONE_SPACE)?;
+    timings.expect(BIT_MARK)?;
+    timings.expect_end(MIN_GAP)?;
+
+    let [vendor_low, vendor_high, b2, b3, b4, checksum, ..] = raw.to_le_bytes();
+    let kaseikyo = Kaseikyo {
+        vendor: u16::from_le_bytes([vendor_low, vendor_high]),
+        genre1: b2 >> 4,
+        genre2: b3 & 0xF,
+        data: u16::from(b3 >> 4) | u16::from(b4 & 0x3F) << 4,
+        id: b4 >> 6,
+        repeats: 0,
+    };
+
+    if b2 & 0xF != kaseikyo.vendor_parity() {
+        return Err(DecodeError::Integrity("vendor parity doesn't match"));
+    }
+    if checksum != b2 ^ b3 ^ b4 {
+        return Err(DecodeError::Integrity("checksum doesn't match"));
+    }
+
+    Ok(kaseikyo)
+}
+
+/// Encode a Kaseikyo frame and its repeats into timings in the Flipper convention.
+pub fn encode(kaseikyo: &Kaseikyo) -> Vec<u32> {
+    let mut frame = vec![HEADER_MARK, HEADER_SPACE];
+    frame.extend(pulse_distance_timings(
+        kaseikyo.raw(),
+        BITS,
+        BIT_MARK,
+        ZERO_SPACE,
+        ONE_SPACE,
+    ));
+    frame.push(BIT_MARK);
+
+    let gap = GapPolicy::KASEIKYO.required_gap(frame.iter().sum());
+    let mut data = frame.clone();
+    for _ in 0..kaseikyo.repeats {
+        data.push(gap);
+        data.extend(&frame);
+    }
+
+    data
+}
+
+/// Kaseikyo decoder for the protocol [`Registry`](super::Registry).
+pub struct KaseikyoProtocol;
+
+impl Protocol for KaseikyoProtocol {
+    fn name(&self) -> &'static str {
+        "Kaseikyo"
+    }
+
+    fn matches(&self, data: &[u32], _frequency: u32) -> bool {
+        Timings::new(data).expect(HEADER_MARK).is_ok()
+    }
+
+    fn decode(&self, data: &[u32], _frequency: u32) -> Result<Decoded, DecodeError> {
+        decode(data).map(Decoded::Kaseikyo)
+    }
+
+    fn encode(&self, decoded: &Decoded) -> Option<Vec<u32>> {
+        match decoded {
+            Decoded::Kaseikyo(kaseikyo) => Some(encode(kaseikyo)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let kaseikyo = Kaseikyo {
+            vendor: 0x2002,
+            genre1: 0x0,
+            genre2: 0x8,
+            data: 0x3D,
+            id: 0,
+            repeats: 1,
+        };
+        let mut data = encode(&kaseikyo);
+        // some jitter
+        data[0] = 3500;
+        data[5] = 1250;
+
+        assert_eq!(decode(&data), Ok(kaseikyo));
+        assert_eq!(kaseikyo.vendor_name(), Some("Panasonic"));
+        assert_eq!(kaseikyo.raw(), 0xDB03_D800_2002);
+    }
+
+    #[test]
+    fn test_decode_rejects_bad_checksum() {
+        let mut data = encode(&Kaseikyo {
+            vendor: 0x2002,
+            genre1: 0x0,
+            genre2: 0x8,
+            data: 0x3D,
+            id: 0,
+            repeats: 0,
+        });
+        // clear the lowest bit of the checksum
+        data[2 + 2 * 40 + 1] = ZERO_SPACE;
+
+        assert!(matches!(decode(&data), Err(DecodeError::Integrity(_))));
+    }
+}
@@ -0,0 +1,153 @@
+//! Mitsubishi protocol, used by older TVs and VCRs: 16 pulse distance coded
+//! bits with 300µs marks, least significant bit first, and a final stop mark,
+//! with no header.
+//!
+//! The bits are an 8-bit address and an 8-bit command, with no integrity check.
+
+use alloc::{vec, vec::Vec};
+
+use crate::{signal::Packet, synthesis::GapPolicy};
+
+use super::{pulse_distance_timings, DecodeError, Decoded, Protocol, Timings};
+
+const BIT_MARK: u32 = 300;
+const ZERO_SPACE: u32 = 700;
+const ONE_SPACE: u32 = 1700;
+const BITS: usize = 16;
+/// Frames are separated by at least this much silence.
+const MIN_GAP: u32 = 10_000;
+
+/// A decoded Mitsubishi frame.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Mitsubishi {
+    pub address: u8,
+    pub command: u8,
+    /// Number of repeat frames following the frame.
+    pub repeats: usize,
+}
+
+impl Mitsubishi {
+    /// All 16 bits of the frame, as transmitted.
+    pub fn raw(&self) -> u16 {
+        u16::from_le_bytes([self.address, self.command])
+    }
+
+    pub(crate) fn packet(&self) -> Packet {
+        let mut packet = Packet::from_value(u64::from(self.raw()), BITS);
+        packet.repeats = self.repeats;
+        packet
+    }
+}
+
+/// Decode a Mitsubishi frame at the start of the timings, along with the
+/// identical frames following it.
+pub fn decode(data: &[u32]) -> Result<Mitsubishi, DecodeError> {
+    let mut timings = Timings::new(data);
+    let mut mitsubishi = decode_frame(&mut timings)?;
+
+    let raw = mitsubishi.raw();
+    mitsubishi.repeats = timings.repeats(MIN_GAP, |timings| {
+        timings
+            .attempt(decode_frame)
+            .is_ok_and(|frame| frame.raw() == raw)
+    });
+
+    Ok(mitsubishi)
+}
+
+fn decode_frame(timings: &mut Timings) -> Result<Mitsubishi, DecodeError> {
+    let raw = timings.pulse_distance_bits(BITS, BIT_MARK, ZERO_SPACE, ONE_SPACE)?;
+    timings.expect(BIT_MARK)?;
+    timings.expect_end(MIN_GAP)?;
+
+    let [address, command, ..] = raw.to_le_bytes();
+
+    Ok(Mitsubishi {
+        address,
+        command,
+        repeats: 0,
+    })
+}
+
+/// Encode a Mitsubishi frame and its repeats into timings in the Flipper convention.
+pub fn encode(mitsubishi: &Mitsubishi) -> Vec<u32> {
+    let mut frame = pulse_distance_timings(
+        u64::from(mitsubishi.raw()),
+        BITS,
+        BIT_MARK,
+        ZERO_SPACE,
+        ONE_SPACE,
+    );
+    frame.push(BIT_MARK);
+
+    let gap = GapPolicy::MITSUBISHI.required_gap(frame.iter().sum());
+    let mut data = vec![];
+    for i in 0..=mitsubishi.repeats {
+        if i > 0 {
+            data.push(gap);
+        }
+        data.extend(&frame);
+    }
+
+    data
+}
+
+/// Mitsubishi decoder for the protocol [`Registry`](super::Registry).
+pub struct MitsubishiProtocol;
+
+impl Protocol for MitsubishiProtocol {
+    fn name(&self) -> &'static str {
+        "Mitsubishi"
+    }
+
+    fn matches(&self, data: &[u32], _frequency: u32) -> bool {
+        Timings::new(data).expect(BIT_MARK).is_ok()
+    }
+
+    fn decode(&self, data: &[u32], _frequency: u32) -> Result<Decoded, DecodeError> {
+        decode(data).map(Decoded::Mitsubishi)
+    }
+
+    fn encode(&self, decoded: &Decoded) -> Option<Vec<u32>> {
+        match decoded {
+            Decoded::Mitsubishi(mitsubishi) => Some(encode(mitsubishi)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::sharp::{self, Sharp};
+
+    #[test]
+    fn test_decode() {
+        let mitsubishi = Mitsubishi {
+            address: 0x47,
+            command: 0x11,
+            repeats: 1,
+        };
+        let mut data = encode(&mitsubishi);
+        // some jitter
+        data[0] = 320;
+        data[1] = 1650;
+
+        assert_eq!(decode(&data), Ok(mitsubishi));
+    }
+
+    #[test]
+    fn test_sharp_is_not_mitsubishi() {
+        let sharp = Sharp {
+            address: 0x01,
+            command: 0x16,
+            extension: 0b01,
+            verified: true,
+            repeats: 0,
+        };
+        let data = sharp::encode(&sharp);
+
+        assert!(decode(&data).is_err());
+        assert_eq!(sharp::decode(&data), Ok(sharp));
+    }
+}
@@ -0,0 +1,315 @@
+//! NEC protocol: 9ms/4.5ms header, 32 pulse distance coded bits with
+//! 560µs marks, least significant bit first, and a final stop mark.
+//!
+//! The 32 bits are the address, the inverted address, the command
+//! and the inverted command, 8 bits each.
+//!
+//! Extended NEC (NECext) uses the same framing, but both address bytes form
+//! a single 16-bit address with no integrity check.
+//!
+//! While a button is held, the frame is followed by repeat frames every 108ms:
+//! a 9ms mark, a 2.25ms space and a stop mark.
+
+use alloc::{vec, vec::Vec};
+
+use crate::{signal::Packet, synthesis::GapPolicy};
+
+use super::{pulse_distance_timings, DecodeError, Decoded, Protocol, Timings};
+
+const HEADER_MARK: u32 = 9000;
+const HEADER_SPACE: u32 = 4500;
+const BIT_MARK: u32 = 560;
+const ZERO_SPACE: u32 = 560;
+const ONE_SPACE: u32 = 1690;
+const BITS: usize = 32;
+const REPEAT_SPACE: u32 = 2250;
+/// Frames are separated by at least this much silence.
+pub(crate) const MIN_GAP: u32 = 20_000;
+
+/// A decoded NEC frame.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Nec {
+    pub address: u8,
+    /// Should be the bitwise inverse of `address`, see [`Nec::address_check`].
+    pub inverted_address: u8,
+    pub command: u8,
+    /// Number of repeat frames following the frame.
+    pub repeats: usize,
+}
+
+impl Nec {
+    /// Whether the inverted address byte matches the address.
+    pub fn address_check(&self) -> bool {
+        self.address == !self.inverted_address
+    }
+
+    /// All 32 bits of the frame, as transmitted.
+    pub fn raw(&self) -> u32 {
+        u32::from(self.address)
+            | u32::from(self.inverted_address) << 8
+            | u32::from(self.command) << 16
+            | u32::from(!self.command) << 24
+    }
+
+    pub(crate) fn packet(&self) -> Packet {
+        let mut packet = Packet::from_value(u64::from(self.raw()), BITS);
+        packet.repeats = self.repeats;
+        packet
+    }
+}
+
+/// A decoded extended NEC frame.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct NecExt {
+    pub address: u16,
+    pub command: u8,
+    /// Number of repeat frames following the frame.
+    pub repeats: usize,
+}
+
+impl NecExt {
+    /// All 32 bits of the frame, as transmitted.
+    pub fn raw(&self) -> u32 {
+        Nec::from(*self).raw()
+    }
+
+    pub(crate) fn packet(&self) -> Packet {
+        Nec::from(*self).packet()
+    }
+}
+
+impl From<Nec> for NecExt {
+    fn from(nec: Nec) -> Self {
+        Self {
+            address: u16::from_le_bytes([nec.address, nec.inverted_address]),
+            command: nec.command,
+            repeats: nec.repeats,
+        }
+    }
+}
+
+impl From<NecExt> for Nec {
+    fn from(ext: NecExt) -> Self {
+        let [address, inverted_address] = ext.address.to_le_bytes();
+        Self {
+            address,
+            inverted_address,
+            command: ext.command,
+            repeats: ext.repeats,
+        }
+    }
+}
+
+/// Decode an NEC frame at the start of the timings, along with the repeat
+/// frames following it.
+///
+/// Fails if the command byte doesn't match its inverse; a mismatching
+/// address inverse is reported via [`Nec::address_check`] instead.
+pub fn decode(data: &[u32]) -> Result<Nec, DecodeError> {
+    let (mut nec, repeats) = decode_framed(data, parse)?;
+    nec.repeats = repeats;
+
+    Ok(nec)
+}
+
+/// Interpret the 32 bits of an NEC frame, checking the command inverse.
+pub(crate) fn parse(raw: u32) -> Result<Nec, DecodeError> {
+    let [address, inverted_address, command, inverted_command] = raw.to_le_bytes();
+    if command != !inverted_command {
+        return Err(DecodeError::Integrity(
+            "inverted command doesn't match command",
+        ));
+    }
+
+    Ok(Nec {
+        address,
+        inverted_address,
+        command,
+        repeats: 0,
+    })
+}
+
+/// Decode an extended NEC frame at the start of the timings, along with the
+/// repeat frames following it.
+pub fn decode_ext(data: &[u32]) -> Result<NecExt, DecodeError> {
+    decode(data).map(NecExt::from)
+}
+
+/// Decode a frame in the NEC framing at the start of the timings, interpreting
+/// its 32 bits with `parse`, and count the repeat frames following it.
+///
+/// Shared by the protocols built on top of NEC.
+pub(crate) fn decode_framed<T>(
+    data: &[u32],
+    parse: impl FnOnce(u32) -> Result<T, DecodeError>,
+) -> Result<(T, usize), DecodeError> {
+    let mut timings = Timings::new(data);
+    let raw = decode_raw(&mut timings)?;
+    let frame = parse(raw)?;
+
+    let repeats = timings.repeats(MIN_GAP, |timings| {
+        timings.attempt(decode_repeat).is_ok()
+            || timings.attempt(decode_raw).is_ok_and(|frame| frame == raw)
+    });
+
+    Ok((frame, repeats))
+}
+
+pub(crate) fn decode_raw(timings: &mut Timings) -> Result<u32, DecodeError> {
+    timings.expect(HEADER_MARK)?;
+    timings.expect(HEADER_SPACE)?;
+    let raw = timings.pulse_distance_bits(BITS, BIT_MARK, ZERO_SPACE, ONE_SPACE)?;
+    timings.expect(BIT_MARK)?;
+    timings.expect_end(MIN_GAP)?;
+
+    Ok(raw as u32)
+}
+
+fn decode_repeat(timings: &mut Timings) -> Result<(), DecodeError> {
+    timings.expect(HEADER_MARK)?;
+    timings.expect(REPEAT_SPACE)?;
+    timings.expect(BIT_MARK)
+}
+
+/// Encode an NEC frame and its repeat frames into timings in the Flipper convention.
+pub fn encode(nec: &Nec) -> Vec<u32> {
+    encode_framed(nec.raw(), nec.repeats)
+}
+
+/// Encode 32 bits in the NEC framing, followed by `repeats` repeat frames.
+pub(crate) fn encode_framed(raw: u32, repeats: usize) -> Vec<u32> {
+    let mut data = encode_raw(raw);
+
+    let mut frame_duration = data.iter().sum();
+    for _ in 0..repeats {
+        data.push(GapPolicy::NEC.required_gap(frame_duration));
+        data.extend([HEADER_MARK, REPEAT_SPACE, BIT_MARK]);
+        frame_duration = HEADER_MARK + REPEAT_SPACE + BIT_MARK;
+    }
+
+    data
+}
+
+/// Encode a single frame of 32 bits in the NEC framing.
+pub(crate) fn encode_raw(raw: u32) -> Vec<u32> {
+    let mut data = vec![HEADER_MARK, HEADER_SPACE];
+    data.extend(pulse_distance_timings(
+        u64::from(raw),
+        BITS,
+        BIT_MARK,
+        ZERO_SPACE,
+        ONE_SPACE,
+    ));
+    data.push(BIT_MARK);
+    data
+}
+
+/// Encode an extended NEC frame and its repeat frames into timings in the Flipper convention.
+pub fn encode_ext(ext: &NecExt) -> Vec<u32> {
+    encode(&Nec::from(*ext))
+}
+
+/// NEC and NECext decoder for the protocol [`Registry`](super::Registry).
+pub struct NecProtocol;
+
+impl Protocol for NecProtocol {
+    fn name(&self) -> &'static str {
+        "NEC"
+    }
+
+    fn matches(&self, data: &[u32], _frequency: u32) -> bool {
+        Timings::new(data).expect(HEADER_MARK).is_ok()
+    }
+
+    fn decode(&self, data: &[u32], _frequency: u32) -> Result<Decoded, DecodeError> {
+        let nec = decode(data)?;
+        Ok(if nec.address_check() {
+            Decoded::Nec(nec)
+        } else {
+            Decoded::NecExt(nec.into())
+        })
+    }
+
+    fn encode(&self, decoded: &Decoded) -> Option<Vec<u32>> {
+        match decoded {
+            Decoded::Nec(nec) => Some(encode(nec)),
+            Decoded::NecExt(ext) => Some(encode_ext(ext)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let nec = Nec {
+            address: 0x04,
+            inverted_address: !0x04,
+            command: 0x08,
+            repeats: 0,
+        };
+        let mut data = encode(&nec);
+        // some jitter
+        data[0] = 8950;
+        data[3] = 600;
+
+        assert_eq!(decode(&data), Ok(nec));
+        assert!(nec.address_check());
+        assert_eq!(nec.raw(), 0xF708_FB04);
+    }
+
+    #[test]
+    fn test_decode_repeats() {
+        let nec = Nec {
+            address: 0x04,
+            inverted_address: !0x04,
+            command: 0x08,
+            repeats: 3,
+        };
+        let data = encode(&nec);
+        assert_eq!(decode(&data), Ok(nec));
+
+        // a full frame sent again counts as a repeat too
+        let single = Nec { repeats: 0, ..nec };
+        let mut data = encode(&single);
+        data.push(40_000);
+        data.extend(encode(&single));
+        assert_eq!(decode(&data), Ok(Nec { repeats: 1, ..nec }));
+    }
+
+    #[test]
+    fn test_decode_ext() {
+        let ext = NecExt {
+            address: 0xEE87,
+            command: 0x5D,
+            repeats: 1,
+        };
+        let data = encode_ext(&ext);
+
+        assert_eq!(decode_ext(&data), Ok(ext));
+        assert!(!decode(&data).unwrap().address_check());
+        assert_eq!(ext.raw(), 0xA25D_EE87);
+    }
+
+    #[test]
+    fn test_decode_rejects_bad_command() {
+        let mut data = encode(&Nec {
+            address: 0x04,
+            inverted_address: !0x04,
+            command: 0x08,
+            repeats: 0,
+        });
+        // flip the lowest bit of the inverted command
+        data[2 + 2 * 24 + 1] = ZERO_SPACE;
+
+        assert!(matches!(decode(&data), Err(DecodeError::Integrity(_))));
+    }
+
+    #[test]
+    fn test_decode_rejects_other_protocols() {
+        assert!(decode(&[550, 17700, 2972, 8930, 555, 434, 557]).is_err());
+    }
+}
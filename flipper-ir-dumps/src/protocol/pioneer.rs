@@ -0,0 +1,199 @@
+//! Pioneer protocol: NEC frames on a 40kHz carrier, always sent twice.
+//!
+//! Some commands consist of two different codes, sent one after another
+//! instead of two copies of the same code.
+
+use alloc::{vec, vec::Vec};
+
+use flipper_utils::within_tolerance;
+
+use crate::{signal::Packet, synthesis::GapPolicy};
+
+use super::{
+    nec::{self, MIN_GAP},
+    DecodeError, Decoded, Protocol, Timings,
+};
+
+/// Carrier frequency of Pioneer remotes, in Hz.
+pub const FREQUENCY: u32 = 40_000;
+/// Relative tolerance for the carrier frequency, tight enough to exclude 38kHz.
+const FREQUENCY_TOLERANCE: f32 = 0.02;
+const BITS: usize = 32;
+
+/// A decoded Pioneer frame pair.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Pioneer {
+    pub address: u8,
+    pub command: u8,
+    /// Address and command of the second code of two-part commands.
+    pub second: Option<(u8, u8)>,
+    /// Number of repeated frame pairs following the first one.
+    pub repeats: usize,
+}
+
+impl Pioneer {
+    fn raw_first(&self) -> u32 {
+        nec_raw(self.address, self.command)
+    }
+
+    fn raw_second(&self) -> u32 {
+        match self.second {
+            Some((address, command)) => nec_raw(address, command),
+            None => self.raw_first(),
+        }
+    }
+
+    /// All 64 bits of the frame pair, as transmitted.
+    pub fn raw(&self) -> u64 {
+        u64::from(self.raw_first()) | u64::from(self.raw_second()) << BITS
+    }
+
+    pub(crate) fn packet(&self) -> Packet {
+        let mut packet = Packet::from_value(self.raw(), 2 * BITS);
+        packet.repeats = self.repeats;
+        packet
+    }
+}
+
+fn nec_raw(address: u8, command: u8) -> u32 {
+    nec::Nec {
+        address,
+        inverted_address: !address,
+        command,
+        repeats: 0,
+    }
+    .raw()
+}
+
+/// Decode a Pioneer frame pair at the start of the timings, along with the
+/// repeated pairs following it.
+///
+/// As the framing is the same as NEC, only signals with a 40kHz carrier are
+/// considered Pioneer.
+pub fn decode(data: &[u32], frequency: u32) -> Result<Pioneer, DecodeError> {
+    if !within_tolerance(frequency, FREQUENCY, FREQUENCY_TOLERANCE) {
+        return Err(DecodeError::Mismatch("carrier isn't 40kHz"));
+    }
+
+    let mut timings = Timings::new(data);
+    let first = decode_frame(&mut timings)?;
+    let second = timings.attempt(|timings| {
+        expect_gap(timings)?;
+        decode_frame(timings)
+    })?;
+
+    let repeats = timings.repeats(MIN_GAP, |timings| {
+        timings
+            .attempt(|timings| {
+                let repeat = decode_frame(timings)?;
+                expect_gap(timings)?;
+                if repeat != first || decode_frame(timings)? != second {
+                    return Err(DecodeError::Integrity("repeat doesn't match"));
+                }
+
+                Ok(())
+            })
+            .is_ok()
+    });
+
+    Ok(Pioneer {
+        address: first.address,
+        command: first.command,
+        second: (second != first).then_some((second.address, second.command)),
+        repeats,
+    })
+}
+
+fn decode_frame(timings: &mut Timings) -> Result<nec::Nec, DecodeError> {
+    let nec = nec::parse(nec::decode_raw(timings)?)?;
+    if !nec.address_check() {
+        return Err(DecodeError::Integrity(
+            "inverted address doesn't match address",
+        ));
+    }
+
+    Ok(nec)
+}
+
+fn expect_gap(timings: &mut Timings) -> Result<(), DecodeError> {
+    if timings.next()? < MIN_GAP {
+        return Err(DecodeError::Integrity("frames aren't separated"));
+    }
+
+    Ok(())
+}
+
+/// Encode a Pioneer frame pair and its repeats into timings in the Flipper convention.
+pub fn encode(pioneer: &Pioneer) -> Vec<u32> {
+    let first = nec::encode_raw(pioneer.raw_first());
+    let second = nec::encode_raw(pioneer.raw_second());
+    let first_gap = GapPolicy::NEC.required_gap(first.iter().sum());
+    let second_gap = GapPolicy::NEC.required_gap(second.iter().sum());
+
+    let mut data = vec![];
+    for i in 0..=pioneer.repeats {
+        if i > 0 {
+            data.push(second_gap);
+        }
+        data.extend(&first);
+        data.push(first_gap);
+        data.extend(&second);
+    }
+
+    data
+}
+
+/// Pioneer decoder for the protocol [`Registry`](super::Registry).
+pub struct PioneerProtocol;
+
+impl Protocol for PioneerProtocol {
+    fn name(&self) -> &'static str {
+        "Pioneer"
+    }
+
+    fn matches(&self, data: &[u32], frequency: u32) -> bool {
+        within_tolerance(frequency, FREQUENCY, FREQUENCY_TOLERANCE)
+            && nec::NecProtocol.matches(data, frequency)
+    }
+
+    fn decode(&self, data: &[u32], frequency: u32) -> Result<Decoded, DecodeError> {
+        decode(data, frequency).map(Decoded::Pioneer)
+    }
+
+    fn encode(&self, decoded: &Decoded) -> Option<Vec<u32>> {
+        match decoded {
+            Decoded::Pioneer(pioneer) => Some(encode(pioneer)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let pioneer = Pioneer {
+            address: 0xA5,
+            command: 0x1C,
+            second: None,
+            repeats: 1,
+        };
+        let data = encode(&pioneer);
+
+        assert_eq!(decode(&data, 40_000), Ok(pioneer));
+        assert!(decode(&data, 38_000).is_err());
+    }
+
+    #[test]
+    fn test_decode_two_part() {
+        let pioneer = Pioneer {
+            address: 0xA5,
+            command: 0x1C,
+            second: Some((0xAA, 0x20)),
+            repeats: 0,
+        };
+        assert_eq!(decode(&encode(&pioneer), 40_000), Ok(pioneer));
+    }
+}
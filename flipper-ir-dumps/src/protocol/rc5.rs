@@ -0,0 +1,174 @@
+//! Philips RC5 protocol: 14 Manchester coded bits of 1.778ms, most
+//! significant bit first, with a pause followed by a pulse for a one.
+//!
+//! The bits are two start bits, a toggle bit, a 5-bit address and a 6-bit
+//! command. RC5X reuses the second start bit as an inverted seventh command bit.
+//!
+//! The toggle bit flips on every key press, so repeats of a held button are
+//! identical frames.
+
+use alloc::{vec, vec::Vec};
+
+use crate::{
+    signal::{biphase_levels, levels_to_timings, Packet},
+    synthesis::GapPolicy,
+};
+
+use super::{DecodeError, Decoded, Protocol, Timings};
+
+/// Duration of a half-bit.
+const UNIT: u32 = 889;
+const BITS: usize = 14;
+/// Frames are separated by at least this much silence.
+const MIN_GAP: u32 = 20_000;
+
+/// A decoded RC5 or RC5X frame.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Rc5 {
+    /// 5-bit address.
+    pub address: u8,
+    /// 7-bit command, commands above 63 are only available in RC5X.
+    pub command: u8,
+    pub toggle: bool,
+    /// Number of repeat frames following the frame.
+    pub repeats: usize,
+}
+
+impl Rc5 {
+    /// Whether the command needs the RC5X extension.
+    pub fn is_extended(&self) -> bool {
+        self.command & 0x40 != 0
+    }
+
+    /// All 14 bits of the frame, as transmitted.
+    pub fn raw(&self) -> u16 {
+        1 << 13
+            | u16::from(!self.is_extended()) << 12
+            | u16::from(self.toggle) << 11
+            | u16::from(self.address & 0x1F) << 6
+            | u16::from(self.command & 0x3F)
+    }
+
+    pub(crate) fn packet(&self) -> Packet {
+        let mut packet = Packet::from_value(u64::from(self.raw()), BITS);
+        packet.repeats = self.repeats;
+        packet
+    }
+}
+
+/// Decode an RC5 frame at the start of the timings, along with the identical
+/// frames following it.
+pub fn decode(data: &[u32]) -> Result<Rc5, DecodeError> {
+    let mut timings = Timings::new(data);
+    let mut rc5 = decode_frame(&mut timings)?;
+
+    let raw = rc5.raw();
+    rc5.repeats = timings.repeats(MIN_GAP, |timings| {
+        timings
+            .attempt(decode_frame)
+            .is_ok_and(|frame| frame.raw() == raw)
+    });
+
+    Ok(rc5)
+}
+
+fn decode_frame(timings: &mut Timings) -> Result<Rc5, DecodeError> {
+    // the pause before the first start bit is indistinguishable from silence
+    let mut levels = vec![false];
+    levels.extend(timings.half_bits(UNIT, 2));
+    if levels.len() == 2 * BITS - 1 {
+        levels.push(false);
+    }
+    if levels.len() != 2 * BITS {
+        return Err(DecodeError::UnexpectedEnd);
+    }
+
+    let raw = Timings::biphase_bits(&levels, false)?;
+    let field = raw >> 12 & 1 == 1;
+
+    Ok(Rc5 {
+        address: (raw >> 6 & 0x1F) as u8,
+        command: (raw & 0x3F) as u8 | u8::from(!field) << 6,
+        toggle: raw >> 11 & 1 == 1,
+        repeats: 0,
+    })
+}
+
+/// Encode an RC5 frame and its repeats into timings in the Flipper convention.
+pub fn encode(rc5: &Rc5) -> Vec<u32> {
+    let frame = levels_to_timings(&biphase_levels(u64::from(rc5.raw()), BITS, false), UNIT);
+    let gap = GapPolicy::RC5.required_gap(frame.iter().sum());
+
+    let mut data = frame.clone();
+    for _ in 0..rc5.repeats {
+        // the leading pause of the first start bit is part of the gap
+        data.push(gap + UNIT);
+        data.extend(&frame);
+    }
+
+    data
+}
+
+/// RC5 and RC5X decoder for the protocol [`Registry`](super::Registry).
+pub struct Rc5Protocol;
+
+impl Protocol for Rc5Protocol {
+    fn name(&self) -> &'static str {
+        "RC5"
+    }
+
+    fn matches(&self, data: &[u32], _frequency: u32) -> bool {
+        Timings::new(data).expect(UNIT).is_ok()
+    }
+
+    fn decode(&self, data: &[u32], _frequency: u32) -> Result<Decoded, DecodeError> {
+        decode(data).map(Decoded::Rc5)
+    }
+
+    fn encode(&self, decoded: &Decoded) -> Option<Vec<u32>> {
+        match decoded {
+            Decoded::Rc5(rc5) => Some(encode(rc5)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let rc5 = Rc5 {
+            address: 0x05,
+            command: 0x35,
+            toggle: true,
+            repeats: 1,
+        };
+        let mut data = encode(&rc5);
+        // some jitter
+        data[0] = 850;
+        data[1] = 930;
+
+        assert_eq!(decode(&data), Ok(rc5));
+        assert!(!rc5.is_extended());
+        assert_eq!(rc5.raw(), 0b11_1001_0111_0101);
+    }
+
+    #[test]
+    fn test_decode_extended() {
+        let rc5 = Rc5 {
+            address: 0x10,
+            command: 0x4C,
+            toggle: false,
+            repeats: 0,
+        };
+        assert_eq!(decode(&encode(&rc5)), Ok(rc5));
+        assert!(rc5.is_extended());
+    }
+
+    #[test]
+    fn test_decode_rejects_pulse_distance() {
+        assert!(decode(&[550, 17700, 2972, 8930, 555, 434, 557]).is_err());
+    }
+}
@@ -0,0 +1,262 @@
+//! Philips RC6 protocol: a 2.666ms/0.889ms leader, then Manchester coded
+//! bits of 0.889ms, most significant bit first, with a pulse followed by a
+//! pause for a one.
+//!
+//! The bits are a start bit, a 3-bit mode, a double-width toggle (trailer)
+//! bit and the mode-specific payload:
+//!
+//! - mode 0: 8-bit address and 8-bit command;
+//! - mode 6A: 16-bit customer code, 8-bit address and 8-bit command. Windows
+//!   Media Center (MCE) remotes use customer code `0x800F` and flip its top
+//!   bit on every key press instead of the toggle bit.
+
+use alloc::{vec, vec::Vec};
+
+use crate::{
+    signal::{biphase_levels, levels_to_timings, Packet},
+    synthesis::GapPolicy,
+};
+
+use super::{DecodeError, Decoded, Protocol, Timings};
+
+/// Duration of a half-bit.
+const UNIT: u32 = 444;
+const LEADER_MARK: u32 = 6 * UNIT;
+const LEADER_SPACE: u32 = 2 * UNIT;
+/// Half-bits of the start bit and the mode.
+const HEADER_HALVES: usize = 8;
+/// Half-bits of the double-width toggle bit.
+const TOGGLE_HALVES: usize = 4;
+/// Frames are separated by at least this much silence.
+const MIN_GAP: u32 = 20_000;
+
+/// RC6 mode along with its mode-specific fields.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Rc6Mode {
+    /// Mode 0, used by most consumer devices.
+    Mode0,
+    /// Mode 6A with a 16-bit customer code, e.g. MCE remotes.
+    Mode6A { customer: u16 },
+}
+
+impl Rc6Mode {
+    fn number(&self) -> u64 {
+        match self {
+            Rc6Mode::Mode0 => 0,
+            Rc6Mode::Mode6A { .. } => 6,
+        }
+    }
+
+    fn payload_bits(&self) -> usize {
+        match self {
+            Rc6Mode::Mode0 => 16,
+            Rc6Mode::Mode6A { .. } => 32,
+        }
+    }
+}
+
+/// A decoded RC6 frame.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Rc6 {
+    pub mode: Rc6Mode,
+    pub address: u8,
+    pub command: u8,
+    pub toggle: bool,
+    /// Number of repeat frames following the frame.
+    pub repeats: usize,
+}
+
+impl Rc6 {
+    /// Payload following the toggle bit, as transmitted.
+    pub fn payload(&self) -> u64 {
+        let customer = match self.mode {
+            Rc6Mode::Mode0 => 0,
+            Rc6Mode::Mode6A { customer } => u64::from(customer),
+        };
+
+        customer << 16 | u64::from(self.address) << 8 | u64::from(self.command)
+    }
+
+    /// Number of bits in the frame, excluding the leader.
+    pub fn bits(&self) -> usize {
+        1 + 3 + 1 + self.mode.payload_bits()
+    }
+
+    /// All bits of the frame, excluding the leader, as transmitted.
+    pub fn raw(&self) -> u64 {
+        let payload_bits = self.mode.payload_bits();
+
+        1 << (payload_bits + 4)
+            | self.mode.number() << (payload_bits + 1)
+            | u64::from(self.toggle) << payload_bits
+            | self.payload()
+    }
+
+    pub(crate) fn packet(&self) -> Packet {
+        let mut packet = Packet::from_value(self.raw(), self.bits());
+        packet.repeats = self.repeats;
+        packet
+    }
+}
+
+/// Decode an RC6 frame at the start of the timings, along with the identical
+/// frames following it.
+///
+/// Only modes 0 and 6A are supported.
+pub fn decode(data: &[u32]) -> Result<Rc6, DecodeError> {
+    let mut timings = Timings::new(data);
+    let mut rc6 = decode_frame(&mut timings)?;
+
+    let raw = rc6.raw();
+    rc6.repeats = timings.repeats(MIN_GAP, |timings| {
+        timings
+            .attempt(decode_frame)
+            .is_ok_and(|frame| frame.raw() == raw)
+    });
+
+    Ok(rc6)
+}
+
+fn decode_frame(timings: &mut Timings) -> Result<Rc6, DecodeError> {
+    timings.expect(LEADER_MARK)?;
+    timings.expect(LEADER_SPACE)?;
+
+    // the double-width toggle bit can merge with its neighbours into three units
+    let mut levels = timings.half_bits(UNIT, 3);
+    if levels.len() < HEADER_HALVES + TOGGLE_HALVES {
+        return Err(DecodeError::UnexpectedEnd);
+    }
+
+    let header = Timings::biphase_bits(&levels[..HEADER_HALVES], true)?;
+    if header >> 3 != 1 {
+        return Err(DecodeError::Integrity("missing start bit"));
+    }
+
+    let toggle = &levels[HEADER_HALVES..HEADER_HALVES + TOGGLE_HALVES];
+    if toggle[0] != toggle[1] || toggle[2] != toggle[3] || toggle[0] == toggle[2] {
+        return Err(DecodeError::Integrity("invalid toggle bit"));
+    }
+    let toggle = toggle[0];
+
+    let payload_bits = match header & 0b111 {
+        0 => Rc6Mode::Mode0.payload_bits(),
+        6 => Rc6Mode::Mode6A { customer: 0 }.payload_bits(),
+        _ => return Err(DecodeError::Mismatch("unsupported RC6 mode")),
+    };
+    let halves = HEADER_HALVES + TOGGLE_HALVES + 2 * payload_bits;
+    if levels.len() == halves - 1 {
+        levels.push(false);
+    }
+    if levels.len() != halves {
+        return Err(DecodeError::UnexpectedEnd);
+    }
+
+    let payload = Timings::biphase_bits(&levels[HEADER_HALVES + TOGGLE_HALVES..], true)?;
+    let mode = match header & 0b111 {
+        0 => Rc6Mode::Mode0,
+        _ => Rc6Mode::Mode6A {
+            customer: (payload >> 16) as u16,
+        },
+    };
+
+    Ok(Rc6 {
+        mode,
+        address: (payload >> 8) as u8,
+        command: payload as u8,
+        toggle,
+        repeats: 0,
+    })
+}
+
+/// Encode an RC6 frame and its repeats into timings in the Flipper convention.
+pub fn encode(rc6: &Rc6) -> Vec<u32> {
+    let mut levels = vec![true; 6];
+    levels.extend([false; 2]);
+    levels.extend(biphase_levels(1 << 3 | rc6.mode.number(), 4, true));
+    levels.extend([rc6.toggle, rc6.toggle, !rc6.toggle, !rc6.toggle]);
+    levels.extend(biphase_levels(rc6.payload(), rc6.mode.payload_bits(), true));
+
+    let frame = levels_to_timings(&levels, UNIT);
+    let gap = GapPolicy::RC6.required_gap(frame.iter().sum());
+
+    let mut data = frame.clone();
+    for _ in 0..rc6.repeats {
+        data.push(gap);
+        data.extend(&frame);
+    }
+
+    data
+}
+
+/// RC6 decoder for the protocol [`Registry`](super::Registry).
+pub struct Rc6Protocol;
+
+impl Protocol for Rc6Protocol {
+    fn name(&self) -> &'static str {
+        "RC6"
+    }
+
+    fn matches(&self, data: &[u32], _frequency: u32) -> bool {
+        Timings::new(data).expect(LEADER_MARK).is_ok()
+    }
+
+    fn decode(&self, data: &[u32], _frequency: u32) -> Result<Decoded, DecodeError> {
+        decode(data).map(Decoded::Rc6)
+    }
+
+    fn encode(&self, decoded: &Decoded) -> Option<Vec<u32>> {
+        match decoded {
+            Decoded::Rc6(rc6) => Some(encode(rc6)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_mode0() {
+        let rc6 = Rc6 {
+            mode: Rc6Mode::Mode0,
+            address: 0x00,
+            command: 0x0C,
+            toggle: true,
+            repeats: 2,
+        };
+        let mut data = encode(&rc6);
+        // some jitter
+        data[0] = 2600;
+        data[2] = 480;
+
+        assert_eq!(decode(&data), Ok(rc6));
+        assert_eq!(rc6.raw(), 0b1_0001_0000_0000_0000_1100);
+    }
+
+    #[test]
+    fn test_decode_mce() {
+        let rc6 = Rc6 {
+            mode: Rc6Mode::Mode6A { customer: 0x800F },
+            address: 0x04,
+            command: 0x0D,
+            toggle: false,
+            repeats: 0,
+        };
+        assert_eq!(decode(&encode(&rc6)), Ok(rc6));
+        assert_eq!(rc6.bits(), 37);
+    }
+
+    #[test]
+    fn test_decode_rejects_rc5() {
+        use crate::protocol::rc5::{self, Rc5};
+
+        let rc5 = Rc5 {
+            address: 0x05,
+            command: 0x35,
+            toggle: true,
+            repeats: 0,
+        };
+        assert!(decode(&rc5::encode(&rc5)).is_err());
+    }
+}
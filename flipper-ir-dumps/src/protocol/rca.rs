@@ -0,0 +1,147 @@
+//! RCA protocol: 4ms/4ms header, 24 pulse distance coded bits with 500µs
+//! marks, most significant bit first, and a final stop mark, on a 56kHz
+//! carrier.
+//!
+//! The bits are a 4-bit address and an 8-bit command, followed by their
+//! inverted copies.
+
+use alloc::{vec, vec::Vec};
+
+use crate::{signal::Packet, synthesis::GapPolicy};
+
+use super::{pulse_distance_timings, DecodeError, Decoded, Protocol, Timings};
+
+const HEADER_MARK: u32 = 4000;
+const HEADER_SPACE: u32 = 4000;
+const BIT_MARK: u32 = 500;
+const ZERO_SPACE: u32 = 1000;
+const ONE_SPACE: u32 = 2000;
+const BITS: usize = 24;
+/// Frames are separated by at least this much silence.
+const MIN_GAP: u32 = 5_000;
+
+/// A decoded RCA frame.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Rca {
+    /// 4-bit address.
+    pub address: u8,
+    pub command: u8,
+    /// Number of repeat frames following the frame.
+    pub repeats: usize,
+}
+
+impl Rca {
+    /// All 24 bits of the frame, as transmitted.
+    pub fn raw(&self) -> u32 {
+        let code = u32::from(self.address & 0xF) << 8 | u32::from(self.command);
+        code << 12 | !code & 0xFFF
+    }
+
+    pub(crate) fn packet(&self) -> Packet {
+        let mut packet = Packet::from_value(u64::from(self.raw()), BITS);
+        packet.repeats = self.repeats;
+        packet
+    }
+}
+
+/// Decode an RCA frame at the start of the timings, along with the identical
+/// frames following it.
+///
+/// Fails if the inverted copy doesn't match.
+pub fn decode(data: &[u32]) -> Result<Rca, DecodeError> {
+    let mut timings = Timings::new(data);
+    let mut rca = decode_frame(&mut timings)?;
+
+    let raw = rca.raw();
+    rca.repeats = timings.repeats(MIN_GAP, |timings| {
+        timings
+            .attempt(decode_frame)
+            .is_ok_and(|frame| frame.raw() == raw)
+    });
+
+    Ok(rca)
+}
+
+fn decode_frame(timings: &mut Timings) -> Result<Rca, DecodeError> {
+    timings.expect(HEADER_MARK)?;
+    timings.expect(HEADER_SPACE)?;
+    let lsb_first = timings.pulse_distance_bits(BITS, BIT_MARK, ZERO_SPACE, ONE_SPACE)?;
+    timings.expect(BIT_MARK)?;
+    timings.expect_end(MIN_GAP)?;
+
+    let raw = (lsb_first.reverse_bits() >> (64 - BITS)) as u32;
+    let code = raw >> 12;
+    if raw & 0xFFF != !code & 0xFFF {
+        return Err(DecodeError::Integrity("inverted copy doesn't match"));
+    }
+
+    Ok(Rca {
+        address: (code >> 8) as u8,
+        command: code as u8,
+        repeats: 0,
+    })
+}
+
+/// Encode an RCA frame and its repeats into timings in the Flipper convention.
+pub fn encode(rca: &Rca) -> Vec<u32> {
+    let lsb_first = u64::from(rca.raw()).reverse_bits() >> (64 - BITS);
+    let mut frame = vec![HEADER_MARK, HEADER_SPACE];
+    frame.extend(pulse_distance_timings(
+        lsb_first, BITS, BIT_MARK, ZERO_SPACE, ONE_SPACE,
+    ));
+    frame.push(BIT_MARK);
+
+    let gap = GapPolicy::RCA.required_gap(frame.iter().sum());
+    let mut data = frame.clone();
+    for _ in 0..rca.repeats {
+        data.push(gap);
+        data.extend(&frame);
+    }
+
+    data
+}
+
+/// RCA decoder for the protocol [`Registry`](super::Registry).
+pub struct RcaProtocol;
+
+impl Protocol for RcaProtocol {
+    fn name(&self) -> &'static str {
+        "RCA"
+    }
+
+    fn matches(&self, data: &[u32], _frequency: u32) -> bool {
+        Timings::new(data).expect(HEADER_MARK).is_ok()
+    }
+
+    fn decode(&self, data: &[u32], _frequency: u32) -> Result<Decoded, DecodeError> {
+        decode(data).map(Decoded::Rca)
+    }
+
+    fn encode(&self, decoded: &Decoded) -> Option<Vec<u32>> {
+        match decoded {
+            Decoded::Rca(rca) => Some(encode(rca)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let rca = Rca {
+            address: 0xF,
+            command: 0x2A,
+            repeats: 1,
+        };
+        let mut data = encode(&rca);
+        // some jitter
+        data[1] = 3900;
+        data[3] = 1950;
+
+        assert_eq!(decode(&data), Ok(rca));
+        assert_eq!(rca.raw(), 0xF2A_0D5);
+    }
+}
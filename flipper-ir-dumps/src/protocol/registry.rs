@@ -0,0 +1,237 @@
+use alloc::{boxed::Box, string::ToString, vec, vec::Vec};
+
+use super::{
+    apple::AppleProtocol, bang_olufsen::BangOlufsenProtocol, daikin::DaikinProtocol,
+    dish::DishProtocol, kaseikyo::KaseikyoProtocol, mitsubishi::MitsubishiProtocol,
+    nec::NecProtocol, pioneer::PioneerProtocol, rc5::Rc5Protocol, rc6::Rc6Protocol,
+    rca::RcaProtocol, samsung::Samsung32Protocol, sanyo::SanyoProtocol, sharp::SharpProtocol,
+    Candidate, Confidence, DecodeError, Decoded, IntegrityError,
+};
+
+/// An IR protocol decoder that can be plugged into a [`Registry`].
+///
+/// Protocols are `Send + Sync` so that registries can be shared across threads.
+pub trait Protocol: Send + Sync {
+    /// Name of the protocol family.
+    fn name(&self) -> &str;
+
+    /// Cheap check whether the timings could be of this protocol,
+    /// e.g. by the header, before trying to decode them.
+    fn matches(&self, data: &[u32], frequency: u32) -> bool;
+
+    /// Decode the timings, sent on a carrier of `frequency` Hz.
+    fn decode(&self, data: &[u32], frequency: u32) -> Result<Decoded, DecodeError>;
+
+    /// Encode a signal decoded by this protocol back into timings in the
+    /// Flipper convention, or `None` if it's of another protocol.
+    fn encode(&self, decoded: &Decoded) -> Option<Vec<u32>>;
+}
+
+/// Protocol decoders, tried in priority order.
+///
+/// Protocols sharing their framing with a more generic one (e.g. Apple and
+/// NEC) have to come before it.
+pub struct Registry {
+    protocols: Vec<Box<dyn Protocol>>,
+}
+
+impl Registry {
+    /// A registry without any protocols.
+    pub fn new() -> Self {
+        Self { protocols: vec![] }
+    }
+
+    /// Add a protocol with a lower priority than the ones already registered.
+    pub fn register(&mut self, protocol: impl Protocol + 'static) {
+        self.protocols.push(Box::new(protocol));
+    }
+
+    /// The [default](Registry::default) registry, built on first use and
+    /// shared afterwards.
+    #[cfg(feature = "std")]
+    pub fn shared() -> &'static Registry {
+        static SHARED: std::sync::OnceLock<Registry> = std::sync::OnceLock::new();
+        SHARED.get_or_init(Registry::default)
+    }
+
+    /// Call `f` with the default registry: the [shared](Registry::shared) one
+    /// with `std`, or one built for the call without.
+    pub(crate) fn with_default<R>(f: impl FnOnce(&Registry) -> R) -> R {
+        #[cfg(feature = "std")]
+        return f(Registry::shared());
+        #[cfg(not(feature = "std"))]
+        return f(&Registry::default());
+    }
+
+    /// Registered protocols, in priority order.
+    pub fn protocols(&self) -> impl Iterator<Item = &dyn Protocol> {
+        self.protocols.iter().map(Box::as_ref)
+    }
+
+    /// Decode the timings with the first matching protocol that accepts them.
+    pub fn decode(&self, data: &[u32], frequency: u32) -> Option<Decoded> {
+        self.protocols()
+            .filter(|protocol| protocol.matches(data, frequency))
+            .find_map(|protocol| protocol.decode(data, frequency).ok())
+    }
+
+    /// Decode the timings with all matching protocols that accept them, in
+    /// priority order, scoring how well each decoded signal fits them.
+    ///
+    /// More than one candidate means the timings are ambiguous: tools may
+    /// prefer the one with the best [`Confidence::score`] over the first one.
+    pub fn candidates(&self, data: &[u32], frequency: u32) -> Vec<Candidate> {
+        self.protocols()
+            .filter(|protocol| protocol.matches(data, frequency))
+            .filter_map(|protocol| {
+                let decoded = protocol.decode(data, frequency).ok()?;
+                let encoded = protocol.encode(&decoded);
+                let confidence = Confidence::measure(&decoded, data, encoded.as_deref());
+                Some(Candidate {
+                    decoded,
+                    confidence,
+                })
+            })
+            .collect()
+    }
+
+    /// Find the first matching protocol whose timings the signal follows,
+    /// but whose integrity check it fails, e.g. a NEC frame whose command
+    /// doesn't match its inverse. Such signals are likely corrupted captures.
+    pub fn integrity_failure(&self, data: &[u32], frequency: u32) -> Option<IntegrityError> {
+        self.protocols()
+            .filter(|protocol| protocol.matches(data, frequency))
+            .find_map(|protocol| match protocol.decode(data, frequency) {
+                Err(DecodeError::Integrity(reason)) => Some(IntegrityError {
+                    protocol: protocol.name().to_string(),
+                    reason,
+                }),
+                _ => None,
+            })
+    }
+
+    /// Encode a decoded signal with the protocol that produced it.
+    pub fn encode(&self, decoded: &Decoded) -> Option<Vec<u32>> {
+        self.protocols()
+            .find_map(|protocol| protocol.encode(decoded))
+    }
+}
+
+impl Default for Registry {
+    /// All the protocols of this crate.
+    fn default() -> Self {
+        let mut registry = Self::new();
+        registry.register(PioneerProtocol);
+        registry.register(AppleProtocol);
+        registry.register(NecProtocol);
+        registry.register(Samsung32Protocol);
+        registry.register(Rc5Protocol);
+        registry.register(Rc6Protocol);
+        registry.register(KaseikyoProtocol);
+        registry.register(DaikinProtocol);
+        registry.register(SanyoProtocol);
+        registry.register(MitsubishiProtocol);
+        registry.register(SharpProtocol);
+        registry.register(RcaProtocol);
+        registry.register(DishProtocol);
+        registry.register(BangOlufsenProtocol);
+        registry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::nec::{self, Nec};
+
+    struct Stub;
+
+    impl Protocol for Stub {
+        fn name(&self) -> &'static str {
+            "stub"
+        }
+
+        fn matches(&self, data: &[u32], _frequency: u32) -> bool {
+            data.len() == 1
+        }
+
+        fn decode(&self, _data: &[u32], _frequency: u32) -> Result<Decoded, DecodeError> {
+            Ok(Decoded::Nec(Nec {
+                address: 0,
+                inverted_address: 0,
+                command: 0,
+                repeats: 0,
+            }))
+        }
+
+        fn encode(&self, _decoded: &Decoded) -> Option<Vec<u32>> {
+            None
+        }
+    }
+
+    #[test]
+    fn test_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Registry>();
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_shared() {
+        assert!(core::ptr::eq(Registry::shared(), Registry::shared()));
+        assert_eq!(
+            Registry::shared().protocols().count(),
+            Registry::default().protocols().count()
+        );
+    }
+
+    #[test]
+    fn test_priority() {
+        let nec = Nec {
+            address: 0x04,
+            inverted_address: !0x04,
+            command: 0x08,
+            repeats: 0,
+        };
+        let data = nec::encode(&nec);
+
+        let registry = Registry::default();
+        assert_eq!(registry.decode(&data, 38_000), Some(Decoded::Nec(nec)));
+        assert_eq!(registry.encode(&Decoded::Nec(nec)), Some(data.clone()));
+        assert_eq!(registry.decode(&[1], 38_000), None);
+
+        let mut registry = Registry::new();
+        registry.register(Stub);
+        assert_eq!(registry.decode(&data, 38_000), None);
+        assert!(registry.decode(&[1], 38_000).is_some());
+    }
+
+    #[test]
+    fn test_candidates() {
+        let nec = Nec {
+            address: 0x04,
+            inverted_address: !0x04,
+            command: 0x08,
+            repeats: 0,
+        };
+        let mut data = nec::encode(&nec);
+
+        let registry = Registry::default();
+        let candidates = registry.candidates(&data, 38_000);
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].decoded, Decoded::Nec(nec));
+        assert_eq!(candidates[0].confidence.score(), 1.0);
+
+        // a sloppy capture still decodes, with less confidence
+        for duration in data.iter_mut().step_by(2) {
+            *duration += 100;
+        }
+        let candidates = registry.candidates(&data, 38_000);
+        assert_eq!(candidates[0].decoded, Decoded::Nec(nec));
+        assert!(candidates[0].confidence.score() < 0.95);
+
+        let mut registry = Registry::new();
+        registry.register(Stub);
+        assert!(registry.candidates(&data, 38_000).is_empty());
+    }
+}
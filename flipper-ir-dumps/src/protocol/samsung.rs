@@ -0,0 +1,192 @@
+//! Samsung32 protocol: 4.5ms/4.5ms header, 32 pulse distance coded bits with
+//! 550µs marks, least significant bit first, and a final stop mark.
+//!
+//! The 32 bits are the address sent twice, the command and the inverted
+//! command, 8 bits each.
+//!
+//! While a button is held, the frame is followed by repeat frames: the header
+//! with a single one bit and a stop mark.
+
+use alloc::{vec, vec::Vec};
+
+use crate::{signal::Packet, synthesis::GapPolicy};
+
+use super::{pulse_distance_timings, DecodeError, Decoded, Protocol, Timings};
+
+const HEADER_MARK: u32 = 4500;
+const HEADER_SPACE: u32 = 4500;
+const BIT_MARK: u32 = 550;
+const ZERO_SPACE: u32 = 550;
+const ONE_SPACE: u32 = 1650;
+const BITS: usize = 32;
+/// Frames are separated by at least this much silence.
+const MIN_GAP: u32 = 20_000;
+
+/// A decoded Samsung32 frame.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Samsung32 {
+    pub address: u8,
+    pub command: u8,
+    /// Number of repeat frames following the frame.
+    pub repeats: usize,
+}
+
+impl Samsung32 {
+    /// All 32 bits of the frame, as transmitted.
+    pub fn raw(&self) -> u32 {
+        u32::from(self.address)
+            | u32::from(self.address) << 8
+            | u32::from(self.command) << 16
+            | u32::from(!self.command) << 24
+    }
+
+    pub(crate) fn packet(&self) -> Packet {
+        let mut packet = Packet::from_value(u64::from(self.raw()), BITS);
+        packet.repeats = self.repeats;
+        packet
+    }
+}
+
+/// Decode a Samsung32 frame at the start of the timings, along with the
+/// repeat frames following it.
+///
+/// Fails if the address isn't sent twice or the command byte doesn't match
+/// its inverse.
+pub fn decode(data: &[u32]) -> Result<Samsung32, DecodeError> {
+    let mut timings = Timings::new(data);
+    let mut samsung = decode_frame(&mut timings)?;
+
+    let raw = samsung.raw();
+    samsung.repeats = timings.repeats(MIN_GAP, |timings| {
+        timings.attempt(decode_repeat).is_ok()
+            || timings
+                .attempt(decode_frame)
+                .is_ok_and(|frame| frame.raw() == raw)
+    });
+
+    Ok(samsung)
+}
+
+fn decode_frame(timings: &mut Timings) -> Result<Samsung32, DecodeError> {
+    timings.expect(HEADER_MARK)?;
+    timings.expect(HEADER_SPACE)?;
+    let raw = timings.pulse_distance_bits(BITS, BIT_MARK, ZERO_SPACE, ONE_SPACE)?;
+    timings.expect(BIT_MARK)?;
+    timings.expect_end(MIN_GAP)?;
+
+    let [address, repeated_address, command, inverted_command, ..] = raw.to_le_bytes();
+    if address != repeated_address {
+        return Err(DecodeError::Integrity("address isn't repeated"));
+    }
+    if command != !inverted_command {
+        return Err(DecodeError::Integrity(
+            "inverted command doesn't match command",
+        ));
+    }
+
+    Ok(Samsung32 {
+        address,
+        command,
+        repeats: 0,
+    })
+}
+
+fn decode_repeat(timings: &mut Timings) -> Result<(), DecodeError> {
+    timings.expect(HEADER_MARK)?;
+    timings.expect(HEADER_SPACE)?;
+    timings.expect(BIT_MARK)?;
+    timings.expect(ONE_SPACE)?;
+    timings.expect(BIT_MARK)
+}
+
+/// Encode a Samsung32 frame and its repeat frames into timings in the Flipper convention.
+pub fn encode(samsung: &Samsung32) -> Vec<u32> {
+    let mut data = vec![HEADER_MARK, HEADER_SPACE];
+    data.extend(pulse_distance_timings(
+        u64::from(samsung.raw()),
+        BITS,
+        BIT_MARK,
+        ZERO_SPACE,
+        ONE_SPACE,
+    ));
+    data.push(BIT_MARK);
+
+    let mut frame_duration = data.iter().sum();
+    for _ in 0..samsung.repeats {
+        data.push(GapPolicy::SAMSUNG32.required_gap(frame_duration));
+        data.extend([HEADER_MARK, HEADER_SPACE, BIT_MARK, ONE_SPACE, BIT_MARK]);
+        frame_duration = HEADER_MARK + HEADER_SPACE + BIT_MARK + ONE_SPACE + BIT_MARK;
+    }
+
+    data
+}
+
+/// Samsung32 decoder for the protocol [`Registry`](super::Registry).
+pub struct Samsung32Protocol;
+
+impl Protocol for Samsung32Protocol {
+    fn name(&self) -> &'static str {
+        "Samsung32"
+    }
+
+    fn matches(&self, data: &[u32], _frequency: u32) -> bool {
+        Timings::new(data).expect(HEADER_MARK).is_ok()
+    }
+
+    fn decode(&self, data: &[u32], _frequency: u32) -> Result<Decoded, DecodeError> {
+        decode(data).map(Decoded::Samsung32)
+    }
+
+    fn encode(&self, decoded: &Decoded) -> Option<Vec<u32>> {
+        match decoded {
+            Decoded::Samsung32(samsung) => Some(encode(samsung)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::nec::{self, Nec};
+
+    #[test]
+    fn test_decode() {
+        let samsung = Samsung32 {
+            address: 0x07,
+            command: 0x02,
+            repeats: 2,
+        };
+        let mut data = encode(&samsung);
+        // some jitter
+        data[0] = 4420;
+        data[5] = 1700;
+
+        assert_eq!(decode(&data), Ok(samsung));
+        assert_eq!(samsung.raw(), 0xFD02_0707);
+    }
+
+    #[test]
+    fn test_decode_rejects_bad_address() {
+        let mut data = encode(&Samsung32 {
+            address: 0x07,
+            command: 0x02,
+            repeats: 0,
+        });
+        // clear the lowest bit of the repeated address
+        data[2 + 2 * 8 + 1] = ZERO_SPACE;
+
+        assert!(matches!(decode(&data), Err(DecodeError::Integrity(_))));
+    }
+
+    #[test]
+    fn test_decode_rejects_nec() {
+        let nec = Nec {
+            address: 0x04,
+            inverted_address: !0x04,
+            command: 0x08,
+            repeats: 0,
+        };
+        assert!(decode(&nec::encode(&nec)).is_err());
+    }
+}
@@ -0,0 +1,180 @@
+//! Sanyo (LC7461) protocol: the NEC framing with 42 bits instead of 32.
+//!
+//! The bits are a 13-bit address, its inverse, an 8-bit command and its
+//! inverse. Like NEC, a held button sends repeat frames: a 9ms mark, a 2.25ms
+//! space and a stop mark.
+
+use alloc::{vec, vec::Vec};
+
+use crate::{signal::Packet, synthesis::GapPolicy};
+
+use super::{pulse_distance_timings, DecodeError, Decoded, Protocol, Timings};
+
+const HEADER_MARK: u32 = 9000;
+const HEADER_SPACE: u32 = 4500;
+const BIT_MARK: u32 = 560;
+const ZERO_SPACE: u32 = 560;
+const ONE_SPACE: u32 = 1690;
+const REPEAT_SPACE: u32 = 2250;
+const ADDRESS_BITS: usize = 13;
+const ADDRESS_MASK: u64 = (1 << ADDRESS_BITS) - 1;
+const BITS: usize = 2 * ADDRESS_BITS + 16;
+/// Frames are separated by at least this much silence.
+const MIN_GAP: u32 = 20_000;
+
+/// A decoded Sanyo frame.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Sanyo {
+    /// 13-bit address.
+    pub address: u16,
+    pub command: u8,
+    /// Number of repeat frames following the frame.
+    pub repeats: usize,
+}
+
+impl Sanyo {
+    /// All 42 bits of the frame, as transmitted.
+    pub fn raw(&self) -> u64 {
+        let address = u64::from(self.address) & ADDRESS_MASK;
+
+        address
+            | (!address & ADDRESS_MASK) << ADDRESS_BITS
+            | u64::from(self.command) << (2 * ADDRESS_BITS)
+            | u64::from(!self.command) << (2 * ADDRESS_BITS + 8)
+    }
+
+    pub(crate) fn packet(&self) -> Packet {
+        let mut packet = Packet::from_value(self.raw(), BITS);
+        packet.repeats = self.repeats;
+        packet
+    }
+}
+
+/// Decode a Sanyo frame at the start of the timings, along with the repeat
+/// frames following it.
+///
+/// Fails if the address or the command doesn't match its inverse.
+pub fn decode(data: &[u32]) -> Result<Sanyo, DecodeError> {
+    let mut timings = Timings::new(data);
+    let mut sanyo = decode_frame(&mut timings)?;
+
+    let raw = sanyo.raw();
+    sanyo.repeats = timings.repeats(MIN_GAP, |timings| {
+        timings.attempt(decode_repeat).is_ok()
+            || timings
+                .attempt(decode_frame)
+                .is_ok_and(|frame| frame.raw() == raw)
+    });
+
+    Ok(sanyo)
+}
+
+fn decode_frame(timings: &mut Timings) -> Result<Sanyo, DecodeError> {
+    timings.expect(HEADER_MARK)?;
+    timings.expect(HEADER_SPACE)?;
+    let raw = timings.pulse_distance_bits(BITS, BIT_MARK, ZERO_SPACE, ONE_SPACE)?;
+    timings.expect(BIT_MARK)?;
+    timings.expect_end(MIN_GAP)?;
+
+    let address = raw & ADDRESS_MASK;
+    if raw >> ADDRESS_BITS & ADDRESS_MASK != !address & ADDRESS_MASK {
+        return Err(DecodeError::Integrity(
+            "inverted address doesn't match address",
+        ));
+    }
+    let command = (raw >> (2 * ADDRESS_BITS)) as u8;
+    if (raw >> (2 * ADDRESS_BITS + 8)) as u8 != !command {
+        return Err(DecodeError::Integrity(
+            "inverted command doesn't match command",
+        ));
+    }
+
+    Ok(Sanyo {
+        address: address as u16,
+        command,
+        repeats: 0,
+    })
+}
+
+fn decode_repeat(timings: &mut Timings) -> Result<(), DecodeError> {
+    timings.expect(HEADER_MARK)?;
+    timings.expect(REPEAT_SPACE)?;
+    timings.expect(BIT_MARK)
+}
+
+/// Encode a Sanyo frame and its repeat frames into timings in the Flipper convention.
+pub fn encode(sanyo: &Sanyo) -> Vec<u32> {
+    let mut data = vec![HEADER_MARK, HEADER_SPACE];
+    data.extend(pulse_distance_timings(
+        sanyo.raw(),
+        BITS,
+        BIT_MARK,
+        ZERO_SPACE,
+        ONE_SPACE,
+    ));
+    data.push(BIT_MARK);
+
+    let mut frame_duration = data.iter().sum();
+    for _ in 0..sanyo.repeats {
+        data.push(GapPolicy::NEC.required_gap(frame_duration));
+        data.extend([HEADER_MARK, REPEAT_SPACE, BIT_MARK]);
+        frame_duration = HEADER_MARK + REPEAT_SPACE + BIT_MARK;
+    }
+
+    data
+}
+
+/// Sanyo decoder for the protocol [`Registry`](super::Registry).
+pub struct SanyoProtocol;
+
+impl Protocol for SanyoProtocol {
+    fn name(&self) -> &'static str {
+        "Sanyo"
+    }
+
+    fn matches(&self, data: &[u32], _frequency: u32) -> bool {
+        Timings::new(data).expect(HEADER_MARK).is_ok()
+    }
+
+    fn decode(&self, data: &[u32], _frequency: u32) -> Result<Decoded, DecodeError> {
+        decode(data).map(Decoded::Sanyo)
+    }
+
+    fn encode(&self, decoded: &Decoded) -> Option<Vec<u32>> {
+        match decoded {
+            Decoded::Sanyo(sanyo) => Some(encode(sanyo)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::nec;
+
+    #[test]
+    fn test_decode() {
+        let sanyo = Sanyo {
+            address: 0x1ABC,
+            command: 0x42,
+            repeats: 2,
+        };
+        let mut data = encode(&sanyo);
+        // some jitter
+        data[0] = 9100;
+        data[2] = 600;
+
+        assert_eq!(decode(&data), Ok(sanyo));
+    }
+
+    #[test]
+    fn test_nec_is_not_sanyo() {
+        let data = encode(&Sanyo {
+            address: 0x1ABC,
+            command: 0x42,
+            repeats: 0,
+        });
+        assert!(nec::decode(&data).is_err());
+    }
+}
@@ -0,0 +1,208 @@
+//! Denon/Sharp protocol: 15 pulse distance coded bits with 320µs marks,
+//! least significant bit first, and a final stop mark, with no header.
+//!
+//! The bits are a 5-bit address, an 8-bit command and two extension bits.
+//! Each frame is sent twice, with everything but the address inverted in the
+//! second copy, which serves as the integrity check.
+
+use alloc::{vec, vec::Vec};
+
+use crate::{signal::Packet, synthesis::GapPolicy};
+
+use super::{pulse_distance_timings, DecodeError, Decoded, Protocol, Timings};
+
+const BIT_MARK: u32 = 320;
+const ZERO_SPACE: u32 = 680;
+const ONE_SPACE: u32 = 1680;
+const BITS: usize = 15;
+/// Bits inverted in the second copy of a frame.
+const INVERTED_BITS: u16 = 0x7FE0;
+/// The two copies of a frame are separated by at least this much silence.
+const MIN_GAP: u32 = 10_000;
+
+/// A decoded Denon/Sharp frame pair.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Sharp {
+    /// 5-bit address.
+    pub address: u8,
+    pub command: u8,
+    /// 2-bit extension, `0b01` for Sharp and `0b00` for Denon.
+    pub extension: u8,
+    /// Whether the inverted copy of the frame was received and matched.
+    pub verified: bool,
+    /// Number of repeated frame pairs following the first one.
+    pub repeats: usize,
+}
+
+impl Sharp {
+    /// All 15 bits of the first frame, as transmitted.
+    pub fn raw(&self) -> u16 {
+        u16::from(self.address & 0x1F)
+            | u16::from(self.command) << 5
+            | u16::from(self.extension & 0x3) << 13
+    }
+
+    pub(crate) fn packet(&self) -> Packet {
+        let mut packet = Packet::from_value(u64::from(self.raw()), BITS);
+        packet.repeats = self.repeats;
+        packet
+    }
+}
+
+/// Decode a Denon/Sharp frame at the start of the timings, verifying it
+/// against the inverted copy following it, along with the repeated pairs.
+///
+/// A missing or mismatching inverted copy is reported via [`Sharp::verified`].
+pub fn decode(data: &[u32]) -> Result<Sharp, DecodeError> {
+    let mut timings = Timings::new(data);
+    let raw = decode_frame(&mut timings)?;
+    let inverted = timings.attempt(|timings| decode_copy(timings, raw ^ INVERTED_BITS));
+
+    let repeats = match inverted {
+        Ok(()) => timings.repeats(MIN_GAP, |timings| {
+            timings
+                .attempt(|timings| {
+                    if decode_frame(timings)? != raw {
+                        return Err(DecodeError::Integrity("repeat doesn't match"));
+                    }
+                    decode_copy(timings, raw ^ INVERTED_BITS)
+                })
+                .is_ok()
+        }),
+        Err(_) => 0,
+    };
+
+    Ok(Sharp {
+        address: (raw & 0x1F) as u8,
+        command: (raw >> 5) as u8,
+        extension: (raw >> 13) as u8,
+        verified: inverted.is_ok(),
+        repeats,
+    })
+}
+
+fn decode_frame(timings: &mut Timings) -> Result<u16, DecodeError> {
+    let raw = timings.pulse_distance_bits(BITS, BIT_MARK, ZERO_SPACE, ONE_SPACE)?;
+    timings.expect(BIT_MARK)?;
+    timings.expect_end(MIN_GAP)?;
+
+    Ok(raw as u16)
+}
+
+/// Decode a gap followed by a frame that must equal `expected`.
+fn decode_copy(timings: &mut Timings, expected: u16) -> Result<(), DecodeError> {
+    if timings.next()? < MIN_GAP {
+        return Err(DecodeError::Integrity("frames aren't separated"));
+    }
+    if decode_frame(timings)? != expected {
+        return Err(DecodeError::Integrity("inverted frame doesn't match"));
+    }
+
+    Ok(())
+}
+
+fn frame_timings(raw: u16) -> Vec<u32> {
+    let mut frame = pulse_distance_timings(u64::from(raw), BITS, BIT_MARK, ZERO_SPACE, ONE_SPACE);
+    frame.push(BIT_MARK);
+    frame
+}
+
+/// Encode a Denon/Sharp frame pair and its repeats into timings in the
+/// Flipper convention.
+///
+/// The inverted copy is only sent for verified frames.
+pub fn encode(sharp: &Sharp) -> Vec<u32> {
+    let frame = frame_timings(sharp.raw());
+    if !sharp.verified {
+        return frame;
+    }
+
+    let inverted = frame_timings(sharp.raw() ^ INVERTED_BITS);
+    let gap = GapPolicy::SHARP.required_gap(frame.iter().sum());
+    let inverted_gap = GapPolicy::SHARP.required_gap(inverted.iter().sum());
+
+    let mut data = vec![];
+    for i in 0..=sharp.repeats {
+        if i > 0 {
+            data.push(inverted_gap);
+        }
+        data.extend(&frame);
+        data.push(gap);
+        data.extend(&inverted);
+    }
+
+    data
+}
+
+/// Denon/Sharp decoder for the protocol [`Registry`](super::Registry).
+pub struct SharpProtocol;
+
+impl Protocol for SharpProtocol {
+    fn name(&self) -> &'static str {
+        "Sharp"
+    }
+
+    fn matches(&self, data: &[u32], _frequency: u32) -> bool {
+        Timings::new(data).expect(BIT_MARK).is_ok()
+    }
+
+    fn decode(&self, data: &[u32], _frequency: u32) -> Result<Decoded, DecodeError> {
+        decode(data).map(Decoded::Sharp)
+    }
+
+    fn encode(&self, decoded: &Decoded) -> Option<Vec<u32>> {
+        match decoded {
+            Decoded::Sharp(sharp) => Some(encode(sharp)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let sharp = Sharp {
+            address: 0x01,
+            command: 0x16,
+            extension: 0b01,
+            verified: true,
+            repeats: 1,
+        };
+        let mut data = encode(&sharp);
+        // some jitter
+        data[0] = 300;
+        data[1] = 1720;
+
+        assert_eq!(decode(&data), Ok(sharp));
+        assert_eq!(sharp.raw(), 0x22C1);
+    }
+
+    #[test]
+    fn test_decode_unverified() {
+        let sharp = Sharp {
+            address: 0x01,
+            command: 0x16,
+            extension: 0b01,
+            verified: true,
+            repeats: 0,
+        };
+        let mut data = encode(&sharp);
+        // corrupt the lowest command bit of the inverted copy
+        data[BITS * 2 + 2 + 5 * 2 + 1] = ZERO_SPACE;
+
+        assert_eq!(
+            decode(&data),
+            Ok(Sharp {
+                verified: false,
+                ..sharp
+            })
+        );
+
+        let decoded = Decoded::Sharp(decode(&data).unwrap());
+        assert!(decoded.integrity().is_err());
+        assert!(!decoded.packets()[0].is_valid());
+    }
+}
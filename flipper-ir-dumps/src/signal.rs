@@ -1,10 +1,22 @@
+mod carrier;
 mod parsed;
 mod raw;
+mod stats;
+mod timing;
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum SignalType {
     Raw,
 }
 
-pub use parsed::{Packet, ParsedSignal};
-pub use raw::RawSignal;
+pub use carrier::{DutyCycle, DutyCycleError, Frequency};
+pub use parsed::{
+    biphase_bits, biphase_levels, classify_timings, levels_to_timings, recover_half_bit,
+    slice_half_bits, BitOrder, Coding, Diagnostics, DurationClass, DurationClassifier, Header,
+    NearestClass, Packet, PacketError, ParseError, ParsedSignal, SignalComponent, SkippedRegion,
+    TimeSlot, TimingProfile, UnusualDuration, SEGMENT_GAP,
+};
+pub(crate) use raw::SourceText;
+pub use raw::{ConcatError, RawSignal, UnknownField};
+pub use stats::{Bucket, ClassStats, TimingStats, UnitEstimate};
+pub use timing::{Polarity, PolarityError, TimingSequence};
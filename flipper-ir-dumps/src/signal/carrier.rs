@@ -0,0 +1,126 @@
+//! Properties of the carrier the pulses of a signal are modulated on.
+
+use core::fmt;
+
+use displaydoc::Display;
+use thiserror::Error;
+
+/// Carrier frequency of a signal, in Hz.
+///
+/// Any frequency is accepted, see [`RawSignal::validate`](super::RawSignal::validate)
+/// for the plausible ones.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct Frequency(u32);
+
+impl Frequency {
+    pub const fn from_hz(hz: u32) -> Self {
+        Self(hz)
+    }
+
+    pub const fn hz(self) -> u32 {
+        self.0
+    }
+}
+
+impl From<u32> for Frequency {
+    fn from(hz: u32) -> Self {
+        Self(hz)
+    }
+}
+
+impl From<Frequency> for u32 {
+    fn from(frequency: Frequency) -> Self {
+        frequency.0
+    }
+}
+
+impl fmt::Display for Frequency {
+    /// In kHz, with as many decimals as needed, e.g. `38kHz` or `36.7kHz`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (khz, mut hz) = (self.0 / 1000, self.0 % 1000);
+        write!(f, "{}", khz)?;
+        if hz > 0 {
+            write!(f, ".")?;
+            let mut digit = 100;
+            while hz > 0 {
+                write!(f, "{}", hz / digit)?;
+                hz %= digit;
+                digit /= 10;
+            }
+        }
+        write!(f, "kHz")
+    }
+}
+
+#[derive(Debug, Display, Error, PartialEq, Clone, Copy)]
+/// Duty cycle {0} is outside of (0, 1]
+pub struct DutyCycleError(pub f32);
+
+/// Share of each carrier period the IR LED is on, over 0 and up to 1.
+#[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
+pub struct DutyCycle(f32);
+
+impl DutyCycle {
+    pub fn new(duty_cycle: f32) -> Result<Self, DutyCycleError> {
+        if duty_cycle > 0.0 && duty_cycle <= 1.0 {
+            Ok(Self(duty_cycle))
+        } else {
+            Err(DutyCycleError(duty_cycle))
+        }
+    }
+
+    pub fn get(self) -> f32 {
+        self.0
+    }
+}
+
+impl Default for DutyCycle {
+    /// A third, what the Flipper sends with.
+    fn default() -> Self {
+        Self(0.33)
+    }
+}
+
+impl TryFrom<f32> for DutyCycle {
+    type Error = DutyCycleError;
+
+    fn try_from(duty_cycle: f32) -> Result<Self, Self::Error> {
+        Self::new(duty_cycle)
+    }
+}
+
+impl fmt::Display for DutyCycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.0}%", self.0 * 100.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::*;
+
+    #[test]
+    fn test_frequency() {
+        assert_eq!(Frequency::from_hz(38_000).to_string(), "38kHz");
+        assert_eq!(Frequency::from_hz(36_700).to_string(), "36.7kHz");
+        assert_eq!(Frequency::from_hz(455_000).to_string(), "455kHz");
+        assert_eq!(Frequency::from_hz(38_005).to_string(), "38.005kHz");
+        assert_eq!(Frequency::from(40_000).hz(), 40_000);
+    }
+
+    #[test]
+    fn test_duty_cycle() {
+        assert_eq!(DutyCycle::new(0.5).unwrap().get(), 0.5);
+        assert_eq!(DutyCycle::new(1.0).unwrap().to_string(), "100%");
+        assert_eq!(DutyCycle::default().to_string(), "33%");
+        assert_eq!(DutyCycle::new(33.0), Err(DutyCycleError(33.0)));
+        assert!(DutyCycle::new(0.0).is_err());
+        assert!(DutyCycle::new(f32::NAN).is_err());
+        assert_eq!(
+            DutyCycleError(33.0).to_string(),
+            "Duty cycle 33 is outside of (0, 1]"
+        );
+    }
+}
@@ -1,23 +1,55 @@
-use std::fmt::Debug;
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::fmt::{self, Debug};
 
 use bitvec::{order::Lsb0, vec::BitVec};
+use displaydoc::Display;
+use thiserror::Error;
 
-use crate::signal::SignalType;
+use crate::{
+    protocol::{Candidate, Confidence, Decoded, DecodedFrame, IntegrityError, Registry},
+    signal::{DutyCycle, Frequency, SignalType},
+};
 
 use super::RawSignal;
 
 mod parsing;
-use parsing::{stream_to_packets, ParseError};
+pub(crate) use parsing::duration_groups;
+pub use parsing::{
+    biphase_bits, biphase_levels, classify_timings, levels_to_timings, recover_half_bit,
+    slice_half_bits, Coding, Diagnostics, DurationClass, DurationClassifier, Header, NearestClass,
+    ParseError, SignalComponent, SkippedRegion, TimeSlot, TimingProfile, UnusualDuration,
+};
+use parsing::{segment_to_packets, slots_to_packets, stream_to_packets_lenient};
 
 type DataVec = BitVec<usize, Lsb0>;
 
+/// Pauses at least this long separate frames when segmenting signals
+/// matching neither a protocol nor the packet grammar.
+pub const SEGMENT_GAP: u32 = 10_000;
+
 #[derive(Debug)]
 pub struct ParsedSignal {
     pub(crate) name: String,
     pub(crate) r#type: SignalType,
-    pub(crate) frequency: u32,
-    pub(crate) duty_cycle: f32,
+    pub(crate) frequency: Frequency,
+    pub(crate) duty_cycle: DutyCycle,
+    /// Total duration of the timings, in µs.
+    pub(crate) duration: u64,
     pub(crate) packets: Vec<Packet>,
+    pub(crate) protocol: Option<Decoded>,
+    pub(crate) confidence: Option<Confidence>,
+    /// Other protocols decoding the signal into different packets.
+    pub(crate) alternatives: Vec<Candidate>,
+    pub(crate) skipped: Vec<SkippedRegion>,
+    /// Timings the packets were parsed with.
+    pub(crate) profile: TimingProfile,
+    /// Whether the packets are bit guesses of gap separated frames.
+    pub(crate) guessed: bool,
 }
 
 impl ParsedSignal {
@@ -29,55 +61,925 @@ impl ParsedSignal {
         self.r#type
     }
 
-    pub fn frequency(&self) -> u32 {
+    pub fn frequency(&self) -> Frequency {
         self.frequency
     }
 
-    pub fn duty_cycle(&self) -> f32 {
+    pub fn duty_cycle(&self) -> DutyCycle {
         self.duty_cycle
     }
 
+    /// Total duration of the timings the signal was parsed from, in µs.
+    pub fn duration(&self) -> u64 {
+        self.duration
+    }
+
     pub fn packets(&self) -> &[Packet] {
         &self.packets
     }
+
+    /// Replace the packets, e.g. to change a setting of an air conditioner
+    /// before [encoding](RawSignal::from) the signal again.
+    ///
+    /// The signal is no longer considered decoded by its protocol.
+    pub fn set_packets(&mut self, mut packets: Vec<Packet>) {
+        for packet in &mut packets {
+            packet.frame = None;
+        }
+        self.packets = packets;
+        self.protocol = None;
+        self.confidence = None;
+        self.alternatives.clear();
+        self.guessed = false;
+    }
+
+    /// Merge each packet into the previous one as a repeat if they differ
+    /// in at most `max_distance` bits, see [`Packet::hamming`], counting
+    /// the repeats of both.
+    ///
+    /// Captures of a held button contain the same frame several times, and
+    /// a misread bit in one of them shouldn't make it look like another.
+    pub fn merge_repeats(&mut self, max_distance: usize) {
+        let mut merged: Vec<Packet> = Vec::with_capacity(self.packets.len());
+        for packet in self.packets.drain(..) {
+            match merged.last_mut() {
+                Some(last) if last.hamming(&packet) <= max_distance => {
+                    last.repeats += 1 + packet.repeats;
+                }
+                _ => merged.push(packet),
+            }
+        }
+        self.packets = merged;
+    }
+
+    /// Timings of the generic packet grammar the signal was parsed with,
+    /// also used to encode it back.
+    pub fn profile(&self) -> &TimingProfile {
+        &self.profile
+    }
+
+    /// The signal as decoded by a protocol decoder, if any of them recognized it.
+    pub fn protocol(&self) -> Option<&Decoded> {
+        self.protocol.as_ref()
+    }
+
+    /// How well the [protocol](ParsedSignal::protocol) fits the timings.
+    pub fn confidence(&self) -> Option<Confidence> {
+        self.confidence
+    }
+
+    /// Other protocols decoding the signal into different packets, in
+    /// priority order, see [`Registry::candidates`].
+    pub fn alternatives(&self) -> &[Candidate] {
+        &self.alternatives
+    }
+
+    /// Whether several protocols decode the signal into different packets.
+    pub fn is_ambiguous(&self) -> bool {
+        !self.alternatives.is_empty()
+    }
+
+    /// Regions of the signal skipped as unparseable, see [`ParsedSignal::parse_lenient`].
+    pub fn skipped(&self) -> &[SkippedRegion] {
+        &self.skipped
+    }
+
+    /// Whether all packets passed the integrity checks of their protocol,
+    /// see [`Packet::is_valid`].
+    pub fn is_valid(&self) -> bool {
+        self.packets.iter().all(Packet::is_valid)
+    }
+
+    /// Whether the packets are mere guesses, see [`ParsedSignal::parse_segmented`].
+    pub fn is_guessed(&self) -> bool {
+        self.guessed
+    }
 }
 
-#[derive(Default, PartialEq, Eq)]
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+/// Error converting a packet
+pub enum PacketError {
+    /// Packet of {0} bits doesn't fit into 64 bits
+    TooLong(usize),
+    /// Packet has no digits
+    Empty,
+    /// Invalid digit `{0}` in packet
+    InvalidDigit(char),
+}
+
+/// Order in which the bits of a frame are sent.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum BitOrder {
+    /// Least significant bit first.
+    #[default]
+    Lsb,
+    /// Most significant bit first.
+    Msb,
+}
+
+/// Payload bits of a frame.
+///
+/// The packet reads as a number according to its [`BitOrder`]: with
+/// [`BitOrder::Lsb`] the last transmitted bit is the most significant one,
+/// with [`BitOrder::Msb`] the first one is. That's how [`Display`](core::fmt::Display)
+/// and the numeric accessors show it, see [`Packet::with_bit_order`] for
+/// the other view.
+#[derive(Default, PartialEq, Eq, Clone)]
 pub struct Packet {
+    /// Bits in transmission order, whatever the bit order.
     pub(crate) data: DataVec,
+    pub(crate) bit_order: BitOrder,
+    /// Number of times the packet was repeated right after being sent,
+    /// e.g. NEC repeat frames while a button is held.
+    pub(crate) repeats: usize,
+    /// The integrity check the packet failed, if any.
+    pub(crate) integrity: Option<IntegrityError>,
+    /// The frame the packet was decoded into by a protocol, if any.
+    pub(crate) frame: Option<DecodedFrame>,
+}
+
+impl Packet {
+    /// Number of repeats following the packet.
+    pub fn repeats(&self) -> usize {
+        self.repeats
+    }
+
+    /// Whether the packet passed the integrity checks of its protocol, if any.
+    pub fn is_valid(&self) -> bool {
+        self.integrity.is_none()
+    }
+
+    /// The integrity check the packet failed, if any.
+    pub fn integrity_error(&self) -> Option<&IntegrityError> {
+        self.integrity.as_ref()
+    }
+
+    /// The frame the packet was decoded into, with the address, command
+    /// and other fields of its protocol, if a protocol decoded it.
+    pub fn frame(&self) -> Option<&DecodedFrame> {
+        self.frame.as_ref()
+    }
+
+    /// Number of bits in the packet.
+    pub fn bit_len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Order in which the bits were sent, deciding how the packet reads as a number.
+    pub fn bit_order(&self) -> BitOrder {
+        self.bit_order
+    }
+
+    /// The same bits read in another order, e.g. to compare with codes of
+    /// a database using it.
+    pub fn with_bit_order(self, bit_order: BitOrder) -> Self {
+        Self { bit_order, ..self }
+    }
+
+    /// Bits in transmission order, as seen on the wire or by a logic analyzer.
+    pub fn transmitted_bits(&self) -> impl DoubleEndedIterator<Item = bool> + '_ {
+        self.data.iter().map(|bit| *bit)
+    }
+
+    /// Bits from the most significant one according to the [`BitOrder`],
+    /// as shown by [`Display`](core::fmt::Display).
+    pub fn logical_bits(&self) -> impl Iterator<Item = bool> + '_ {
+        let len = self.data.len();
+        let lsb = self.bit_order == BitOrder::Lsb;
+        (0..len).map(move |i| self.data[if lsb { len - 1 - i } else { i }])
+    }
+
+    /// The packet as a number, see [`Packet`].
+    pub fn as_u64(&self) -> Result<u64, PacketError> {
+        if self.data.len() > 64 {
+            return Err(PacketError::TooLong(self.data.len()));
+        }
+
+        Ok(self
+            .logical_bits()
+            .fold(0, |value, bit| value << 1 | u64::from(bit)))
+    }
+
+    /// Bytes of the packet in transmission order, the bits of each one
+    /// sent in the [`BitOrder`] of the packet. The last byte is padded with zeros.
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0; self.data.len().div_ceil(8)];
+        for (i, bit) in self.transmitted_bits().enumerate() {
+            let shift = match self.bit_order {
+                BitOrder::Lsb => i % 8,
+                BitOrder::Msb => 7 - i % 8,
+            };
+            bytes[i / 8] |= u8::from(bit) << shift;
+        }
+        bytes
+    }
+
+    /// Number of bits differing from `other` in transmission order, bits
+    /// missing from the shorter packet counting as different.
+    ///
+    /// Small distances between packets of two captures hint at the same
+    /// button with a bit misread, or at a setting of an air conditioner.
+    pub fn hamming(&self, other: &Packet) -> usize {
+        let differing = self
+            .transmitted_bits()
+            .zip(other.transmitted_bits())
+            .filter(|(ours, theirs)| ours != theirs)
+            .count();
+        differing + self.bit_len().abs_diff(other.bit_len())
+    }
+
+    /// The packet as a hexadecimal number, see [`Packet`].
+    ///
+    /// Same as formatting it with `{:X}`.
+    pub fn to_hex(&self) -> String {
+        format!("{:X}", self)
+    }
+
+    /// Build a packet from the `bits` lowest bits of `value`, transmitted
+    /// least significant bit first.
+    pub(crate) fn from_value(value: u64, bits: usize) -> Self {
+        let mut packet = Packet::default();
+        for bit in 0..bits {
+            packet.data.push(value >> bit & 1 == 1);
+        }
+        packet
+    }
+
+    /// Build a packet from bytes, each transmitted least significant bit first.
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Self {
+        let mut packet = Packet::default();
+        for byte in bytes {
+            for bit in 0..8 {
+                packet.data.push(byte >> bit & 1 == 1);
+            }
+        }
+        packet
+    }
 }
 
-impl std::fmt::Display for Packet {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for Packet {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         // prints out the data as a series of 0s and 1s
-        for bit in self.data.iter() {
-            write!(f, "{}", if *bit { '1' } else { '0' })?;
+        for bit in self.logical_bits() {
+            write!(f, "{}", if bit { '1' } else { '0' })?;
         }
         Ok(())
     }
 }
 
+impl core::str::FromStr for Packet {
+    type Err = PacketError;
+
+    /// Parse a packet from a hexadecimal number prefixed by `0x`, taking 4
+    /// bits per digit, or from a binary one optionally prefixed by `0b`, as
+    /// formatted by [`Display`](core::fmt::Display). Spaces and underscores
+    /// between the digits are ignored, leading zeros are kept.
+    ///
+    /// The packet reads in [`BitOrder::Lsb`], see [`Packet::with_bit_order`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (digits, radix): (_, u32) =
+            if let Some(hex) = s.strip_prefix("0x").or(s.strip_prefix("0X")) {
+                (hex, 16)
+            } else {
+                (s.strip_prefix("0b").unwrap_or(s), 2)
+            };
+        let bits_per_digit = radix.trailing_zeros();
+
+        let mut bits = vec![];
+        for c in digits.chars().filter(|c| !matches!(c, ' ' | '_')) {
+            let digit = c.to_digit(radix).ok_or(PacketError::InvalidDigit(c))?;
+            bits.extend((0..bits_per_digit).rev().map(|bit| digit >> bit & 1 == 1));
+        }
+        if bits.is_empty() {
+            return Err(PacketError::Empty);
+        }
+
+        // the last digit is sent first
+        let mut packet = Packet::default();
+        for bit in bits.into_iter().rev() {
+            packet.data.push(bit);
+        }
+        Ok(packet)
+    }
+}
+
+impl Packet {
+    /// Format the packet as a hexadecimal number, one digit per 4 bits,
+    /// the first digit holding the remaining ones.
+    fn fmt_hex(&self, f: &mut fmt::Formatter<'_>, digits: &[u8; 16]) -> fmt::Result {
+        let len = self.data.len();
+        let mut digit = 0;
+        for (i, bit) in self.logical_bits().enumerate() {
+            digit = digit << 1 | usize::from(bit);
+            if (len - i - 1) & 3 == 0 {
+                write!(f, "{}", char::from(digits[digit]))?;
+                digit = 0;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::LowerHex for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_hex(f, b"0123456789abcdef")
+    }
+}
+
+impl fmt::UpperHex for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_hex(f, b"0123456789ABCDEF")
+    }
+}
+
 impl Debug for Packet {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         // prints out the data as a series of 0s and 1s
         f.debug_struct("Packet")
             .field("data", &format!("{}", self))
+            .field("bit_order", &self.bit_order)
+            .field("repeats", &self.repeats)
+            .field("integrity", &self.integrity)
+            .field("frame", &self.frame)
             .finish()
     }
 }
 
-impl TryFrom<&RawSignal> for ParsedSignal {
-    type Error = ParseError;
+impl ParsedSignal {
+    /// Decode the signal with the protocols of `registry`, falling back to
+    /// the generic packet grammar with the given timings if none of them
+    /// recognizes it.
+    pub fn parse_with(
+        raw: &RawSignal,
+        registry: &Registry,
+        profile: &TimingProfile,
+    ) -> Result<Self, ParseError> {
+        Self::parse_classified(raw, registry, profile, profile)
+    }
 
-    fn try_from(raw: &RawSignal) -> Result<Self, Self::Error> {
-        let packets = stream_to_packets(&raw.data)?;
+    /// Like [`ParsedSignal::parse_with`], but with durations told apart by a
+    /// custom `classifier` for the packet grammar. The `profile` still bounds
+    /// headers and trailers, and encodes the signal back.
+    pub fn parse_classified(
+        raw: &RawSignal,
+        registry: &Registry,
+        profile: &TimingProfile,
+        classifier: &impl DurationClassifier,
+    ) -> Result<Self, ParseError> {
+        let (protocol, confidence, alternatives) = decode(raw, registry);
+        let packets = match &protocol {
+            Some(decoded) => framed_packets(decoded),
+            None => slots_to_packets(&classify_timings(&raw.data, classifier), profile)?,
+        };
+
+        let mut signal = ParsedSignal {
+            name: raw.name.clone(),
+            r#type: raw.r#type,
+            frequency: raw.frequency,
+            duty_cycle: raw.duty_cycle,
+            duration: raw.duration(),
+            packets,
+            protocol,
+            confidence,
+            alternatives,
+            skipped: vec![],
+            profile: profile.clone(),
+            guessed: false,
+        };
+        signal.flag_integrity(raw, registry);
+
+        Ok(signal)
+    }
+
+    /// Like [`ParsedSignal::parse_with`], but instead of failing the whole
+    /// signal on a malformed packet, skips to the next packet header.
+    /// The skipped regions are available through [`ParsedSignal::skipped`].
+    pub fn parse_lenient(
+        raw: &RawSignal,
+        registry: &Registry,
+        profile: &TimingProfile,
+    ) -> Result<Self, ParseError> {
+        let (protocol, confidence, alternatives) = decode(raw, registry);
+        let (packets, skipped) = match &protocol {
+            Some(decoded) => (framed_packets(decoded), vec![]),
+            None => stream_to_packets_lenient(&raw.data, profile)?,
+        };
+
+        let mut signal = ParsedSignal {
+            name: raw.name.clone(),
+            r#type: raw.r#type,
+            frequency: raw.frequency,
+            duty_cycle: raw.duty_cycle,
+            duration: raw.duration(),
+            packets,
+            protocol,
+            confidence,
+            alternatives,
+            skipped,
+            profile: profile.clone(),
+            guessed: false,
+        };
+        signal.flag_integrity(raw, registry);
+
+        Ok(signal)
+    }
+
+    /// Split the signal into frames at pauses of at least `min_gap` and
+    /// guess the bits of each frame, telling them apart by the durations
+    /// that vary. Meant as a last resort for exotic remotes, giving partial
+    /// results instead of an error.
+    pub fn parse_segmented(raw: &RawSignal, min_gap: u32) -> Result<Self, ParseError> {
+        let packets = segment_to_packets(&raw.data, min_gap);
+        if packets.is_empty() {
+            return Err(ParseError::NoPackets);
+        }
 
         Ok(ParsedSignal {
             name: raw.name.clone(),
             r#type: raw.r#type,
             frequency: raw.frequency,
             duty_cycle: raw.duty_cycle,
+            duration: raw.duration(),
             packets,
+            protocol: None,
+            confidence: None,
+            alternatives: vec![],
+            skipped: vec![],
+            profile: TimingProfile::infer(&raw.data).unwrap_or_default(),
+            guessed: true,
         })
     }
+
+    /// Flag the packets of a signal no protocol decoded, but one of them
+    /// rejected for failing its integrity check: likely a corrupted capture.
+    fn flag_integrity(&mut self, raw: &RawSignal, registry: &Registry) {
+        if self.protocol.is_some() {
+            return;
+        }
+
+        if let Some(err) = registry.integrity_failure(&raw.data, raw.frequency.hz()) {
+            for packet in &mut self.packets {
+                packet.integrity = Some(err.clone());
+            }
+        }
+    }
+}
+
+/// Decode the signal with the first protocol of `registry` accepting it,
+/// along with the candidates of the other protocols interpreting it differently.
+fn decode(
+    raw: &RawSignal,
+    registry: &Registry,
+) -> (Option<Decoded>, Option<Confidence>, Vec<Candidate>) {
+    let mut candidates = registry
+        .candidates(&raw.data, raw.frequency.hz())
+        .into_iter();
+    let Some(first) = candidates.next() else {
+        return (None, None, vec![]);
+    };
+
+    let packets = first.decoded.packets();
+    let alternatives = candidates
+        .filter(|candidate| candidate.decoded.packets() != packets)
+        .collect();
+
+    (Some(first.decoded), Some(first.confidence), alternatives)
+}
+
+/// Packets of a decoded signal, each with the frame they were decoded into.
+fn framed_packets(decoded: &Decoded) -> Vec<Packet> {
+    let frame = decoded.frame();
+    let mut packets = decoded.packets();
+    for packet in &mut packets {
+        packet.frame = Some(frame.clone());
+    }
+    packets
+}
+
+impl ParsedSignal {
+    /// Synthesize timings for the signal, encoded with its protocol from
+    /// `registry` if it has one, or with the generic packet grammar.
+    ///
+    /// The result is transmittable, but not necessarily the original capture.
+    pub fn to_raw(&self, registry: &Registry, profile: &TimingProfile) -> RawSignal {
+        let data = self
+            .protocol
+            .as_ref()
+            .and_then(|decoded| registry.encode(decoded))
+            .unwrap_or_else(|| profile.encode(&self.packets));
+
+        RawSignal {
+            name: self.name.clone(),
+            r#type: self.r#type,
+            frequency: self.frequency,
+            duty_cycle: self.duty_cycle,
+            data,
+            comments: Vec::new(),
+            unknown_fields: Vec::new(),
+            source: Default::default(),
+            parsed: Default::default(),
+        }
+    }
+}
+
+impl From<&ParsedSignal> for RawSignal {
+    /// Encode the signal with its protocol from the default [`Registry`],
+    /// or with the generic packet grammar and the [profile](ParsedSignal::profile)
+    /// it was parsed with, see [`ParsedSignal::to_raw`].
+    fn from(parsed: &ParsedSignal) -> Self {
+        Registry::with_default(|registry| parsed.to_raw(registry, &parsed.profile))
+    }
+}
+
+impl ParsedSignal {
+    /// One line summary of the signal, same as its [`Display`](fmt::Display):
+    /// the fields of its protocol if decoded, or the number of packets.
+    pub fn summary(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for ParsedSignal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.name)?;
+        let frames = match &self.protocol {
+            Some(decoded) => {
+                write!(f, "{}", decoded.frame())?;
+                // sections of a frame share its repeats
+                1 + self.packets.first().map_or(0, Packet::repeats)
+            }
+            None => {
+                let plural = if self.packets.len() == 1 { "" } else { "s" };
+                write!(f, "{} packet{}", self.packets.len(), plural)?;
+                if self.guessed {
+                    write!(f, " (guessed)")?;
+                }
+                self.packets.iter().map(|packet| 1 + packet.repeats).sum()
+            }
+        };
+        let plural = if frames == 1 { "" } else { "s" };
+
+        write!(
+            f,
+            ", {} frame{}, {}ms",
+            frames,
+            plural,
+            (self.duration + 500) / 1000
+        )
+    }
+}
+
+impl TryFrom<&RawSignal> for ParsedSignal {
+    type Error = ParseError;
+
+    /// Parses with the default [`TimingProfile`], falling back to one inferred
+    /// from the signal itself, and then to [segmenting](ParsedSignal::parse_segmented)
+    /// it at gaps of [`SEGMENT_GAP`].
+    fn try_from(raw: &RawSignal) -> Result<Self, Self::Error> {
+        Registry::with_default(|registry| {
+            Self::parse_with(raw, registry, &TimingProfile::default())
+                .or_else(|err| match TimingProfile::infer(&raw.data) {
+                    Some(profile) => Self::parse_with(raw, registry, &profile),
+                    None => Err(err),
+                })
+                .or_else(|err| {
+                    let mut signal = Self::parse_segmented(raw, SEGMENT_GAP).map_err(|_| err)?;
+                    signal.flag_integrity(raw, registry);
+                    Ok(signal)
+                })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{format, string::ToString};
+
+    use crate::{
+        protocol::nec::{self, Nec},
+        signal::{Polarity, TimingSequence},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_nec_signal() {
+        let nec = Nec {
+            address: 0x04,
+            inverted_address: !0x04,
+            command: 0x08,
+            repeats: 2,
+        };
+        let timings = TimingSequence::new(Polarity::StartsWithPulse, nec::encode(&nec));
+        let raw = RawSignal::new("power", 38000, DutyCycle::default(), timings).unwrap();
+
+        let parsed = ParsedSignal::try_from(&raw).unwrap();
+        assert_eq!(parsed.protocol(), Some(&Decoded::Nec(nec)));
+        assert_eq!(
+            parsed.packets()[0].to_string(),
+            "11110111000010001111101100000100"
+        );
+        assert_eq!(parsed.packets()[0].repeats(), 2);
+        let frame = parsed.packets()[0].frame().unwrap();
+        assert_eq!((frame.address, frame.command), (Some(0x04), Some(0x08)));
+        assert_eq!(
+            parsed
+                .to_raw(&Registry::default(), &TimingProfile::default())
+                .data(),
+            raw.data()
+        );
+    }
+
+    #[test]
+    fn test_integrity() {
+        let nec = Nec {
+            address: 0x04,
+            inverted_address: !0x04,
+            command: 0x08,
+            repeats: 0,
+        };
+        let mut data = nec::encode(&nec);
+        // flip the lowest bit of the inverted command from 1 to 0
+        data[51] = 560;
+        let timings = TimingSequence::new(Polarity::StartsWithPulse, data);
+        let raw = RawSignal::new("corrupted", 38000, DutyCycle::default(), timings).unwrap();
+
+        let parsed = ParsedSignal::try_from(&raw).unwrap();
+        assert_eq!(parsed.protocol(), None);
+        assert!(!parsed.is_valid());
+        assert_eq!(
+            parsed.packets()[0].integrity_error().unwrap().protocol,
+            "NEC"
+        );
+    }
+
+    #[test]
+    fn test_packet_accessors() {
+        let packet = Packet::from_value(0xE0E0_40BF, 32);
+        assert_eq!(packet.bit_len(), 32);
+        assert_eq!(packet.as_u64(), Ok(0xE0E0_40BF));
+        assert_eq!(packet.as_bytes(), [0xBF, 0x40, 0xE0, 0xE0]);
+        assert_eq!(packet.to_hex(), "E0E040BF");
+        assert_eq!(format!("{:x}", packet), "e0e040bf");
+
+        // lengths not divisible by 4 or 8
+        let packet = Packet::from_value(0b1_0110_0101, 9);
+        assert_eq!(packet.to_hex(), "165");
+        assert_eq!(packet.as_bytes(), [0x65, 0x01]);
+
+        let packet = Packet::from_bytes(&[0x11; 9]);
+        assert_eq!(packet.as_u64(), Err(PacketError::TooLong(72)));
+        assert_eq!(packet.as_bytes(), [0x11; 9]);
+        assert_eq!(packet.to_hex(), "111111111111111111");
+
+        // the other bit order
+        let packet = Packet::from_value(0b1_0110_0101, 9).with_bit_order(BitOrder::Msb);
+        assert_eq!(packet.to_string(), "101001101");
+        assert_eq!(packet.as_u64(), Ok(0b1_0100_1101));
+        assert_eq!(packet.as_bytes(), [0xA6, 0x80]);
+        assert!(packet
+            .transmitted_bits()
+            .eq([1, 0, 1, 0, 0, 1, 1, 0, 1].map(|bit| bit == 1)));
+
+        assert_eq!(Packet::default().as_u64(), Ok(0));
+        assert_eq!(Packet::default().to_hex(), "");
+    }
+
+    #[test]
+    fn test_hamming() {
+        let packet = Packet::from_value(0b1011, 4);
+        assert_eq!(packet.hamming(&packet), 0);
+        assert_eq!(packet.hamming(&Packet::from_value(0b0011, 4)), 1);
+        assert_eq!(packet.hamming(&Packet::from_value(0b0100, 4)), 4);
+        // 0b1011 followed by two more bits
+        assert_eq!(packet.hamming(&Packet::from_value(0b01_1011, 6)), 2);
+        assert_eq!(packet.hamming(&Packet::default()), 4);
+    }
+
+    #[test]
+    fn test_packet_views() {
+        // 0b110 sent least significant bit first
+        let packet = Packet::from_value(0b110, 3);
+        assert!(packet.transmitted_bits().eq([false, true, true]));
+        assert!(packet.logical_bits().eq([true, true, false]));
+
+        let packet = packet.with_bit_order(BitOrder::Msb);
+        assert!(packet.transmitted_bits().eq([false, true, true]));
+        assert!(packet.logical_bits().eq([false, true, true]));
+    }
+
+    #[test]
+    fn test_packet_from_str() {
+        let packet: Packet = "0xE0E040BF".parse().unwrap();
+        assert_eq!(packet, Packet::from_value(0xE0E0_40BF, 32));
+        assert_eq!("0xe0 e0 40 bf".parse(), Ok(packet));
+
+        let packet: Packet = "0b0001_1011".parse().unwrap();
+        assert_eq!(packet.bit_len(), 8);
+        assert_eq!(packet.to_string(), "00011011");
+        assert_eq!("00011011".parse(), Ok(packet));
+
+        // round trips, leading zeros included
+        let packet = Packet::from_bytes(&[0x01, 0x80, 0x00]);
+        assert_eq!(packet.to_string().parse(), Ok(packet.clone()));
+        assert_eq!(format!("0x{:x}", packet).parse(), Ok(packet));
+
+        assert_eq!("0x".parse::<Packet>(), Err(PacketError::Empty));
+        assert_eq!(
+            "0xE0G0".parse::<Packet>(),
+            Err(PacketError::InvalidDigit('G'))
+        );
+        assert_eq!(
+            "0120".parse::<Packet>(),
+            Err(PacketError::InvalidDigit('2'))
+        );
+    }
+
+    #[test]
+    fn test_ambiguity() {
+        let nec = Nec {
+            address: 0x04,
+            inverted_address: !0x04,
+            command: 0x08,
+            repeats: 0,
+        };
+        let timings = TimingSequence::new(Polarity::StartsWithPulse, nec::encode(&nec));
+        let raw = RawSignal::new("power", 38000, DutyCycle::default(), timings).unwrap();
+
+        let parsed = ParsedSignal::try_from(&raw).unwrap();
+        assert_eq!(parsed.confidence().unwrap().score(), 1.0);
+        assert!(!parsed.is_ambiguous());
+
+        // the same framing, but with the bit values swapped
+        let mut registry = Registry::default();
+        registry.register(
+            crate::irp::Irp::parse(
+                "NEC inverted",
+                "{38.4k,564}<1,-3|1,-1>(16,-8,D:8,S:8,F:8,~F:8,1,^108m,(16,-4,1,^108m)*) \
+                 [D:0..255,S:0..255,F:0..255]",
+            )
+            .unwrap(),
+        );
+        let parsed = ParsedSignal::parse_with(&raw, &registry, &TimingProfile::default()).unwrap();
+        assert_eq!(parsed.protocol().unwrap().protocol(), "NEC");
+        assert!(parsed.is_ambiguous());
+        assert_eq!(parsed.alternatives()[0].decoded.protocol(), "NEC inverted");
+    }
+
+    #[test]
+    fn test_segmented_fallback() {
+        // pulse width coded frames with an unusual header, twice
+        let frame = [3000, 1000, 1200, 600, 600, 600, 1200, 600, 600];
+        let mut data = frame.to_vec();
+        data.push(25_000);
+        data.extend(frame);
+        let timings = TimingSequence::new(Polarity::StartsWithPulse, data);
+        let raw = RawSignal::new("exotic", 38000, DutyCycle::default(), timings).unwrap();
+
+        let parsed = ParsedSignal::try_from(&raw).unwrap();
+        assert!(parsed.is_guessed());
+        assert_eq!(parsed.packets().len(), 2);
+        assert_eq!(parsed.packets()[0].to_string(), "0101");
+    }
+
+    #[test]
+    fn test_to_raw() {
+        let dump = crate::examples::EXAMPLES[0].dump();
+        let registry = Registry::default();
+        let profile = TimingProfile::default();
+
+        for signal in dump.signals() {
+            let parsed = ParsedSignal::try_from(signal).unwrap();
+            let raw = parsed.to_raw(&registry, &profile);
+            assert_eq!(raw.name(), signal.name());
+
+            let reparsed = ParsedSignal::try_from(&raw).unwrap();
+            assert_eq!(reparsed.packets(), parsed.packets());
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let dump = crate::examples::EXAMPLES[0].dump();
+        let mut parsed = ParsedSignal::try_from(&dump.signals()[0]).unwrap();
+
+        // flip the last bit of the first packet
+        let mut bits = parsed.packets()[0].to_string();
+        let last = if bits.pop() == Some('0') { '1' } else { '0' };
+        bits.push(last);
+        let edited: Packet = bits.parse().unwrap();
+
+        let mut packets = parsed.packets().to_vec();
+        packets[0] = edited.clone();
+        parsed.set_packets(packets);
+
+        let raw = RawSignal::from(&parsed);
+        let reparsed = ParsedSignal::try_from(&raw).unwrap();
+        assert_eq!(reparsed.packets()[0], edited);
+        assert_eq!(reparsed.packets()[1..], parsed.packets()[1..]);
+    }
+
+    #[test]
+    fn test_merge_repeats() {
+        let dump = crate::examples::EXAMPLES[0].dump();
+        let mut parsed = ParsedSignal::try_from(&dump.signals()[0]).unwrap();
+        let packets: Vec<Packet> = ["10100101", "10100101", "10100111", "00001111"]
+            .iter()
+            .map(|bits| bits.parse().unwrap())
+            .collect();
+
+        parsed.set_packets(packets.clone());
+        parsed.merge_repeats(0);
+        let repeats: Vec<usize> = parsed.packets().iter().map(Packet::repeats).collect();
+        assert_eq!(repeats, [1, 0, 0]);
+
+        // a misread bit is still a repeat, and repeats add up
+        parsed.set_packets(packets);
+        parsed.merge_repeats(1);
+        assert_eq!(parsed.packets().len(), 2);
+        assert_eq!(parsed.packets()[0].to_string(), "10100101");
+        assert_eq!(parsed.packets()[0].repeats(), 2);
+        parsed.merge_repeats(4);
+        assert_eq!(parsed.packets().len(), 1);
+        assert_eq!(parsed.packets()[0].repeats(), 3);
+    }
+
+    #[test]
+    fn test_custom_classifier() {
+        /// A receiver stretching pauses by half.
+        struct Stretched(TimingProfile);
+
+        impl DurationClassifier for Stretched {
+            fn classify(&self, duration: u32, component: SignalComponent) -> DurationClass {
+                match component {
+                    SignalComponent::Pulse => self.0.classify(duration, component),
+                    SignalComponent::Pause => self.0.classify(duration * 2 / 3, component),
+                }
+            }
+        }
+
+        let dump = crate::examples::EXAMPLES[0].dump();
+        let signal = &dump.signals()[0];
+        let expected = ParsedSignal::try_from(signal).unwrap();
+
+        let data = signal
+            .data()
+            .iter()
+            .enumerate()
+            .map(|(i, &duration)| {
+                if i & 1 == 1 {
+                    duration * 3 / 2
+                } else {
+                    duration
+                }
+            })
+            .collect();
+        let stretched = RawSignal {
+            data,
+            ..signal.clone()
+        };
+        let registry = Registry::default();
+        let profile = TimingProfile::default();
+        assert!(ParsedSignal::parse_with(&stretched, &registry, &profile).is_err());
+
+        let parsed = ParsedSignal::parse_classified(
+            &stretched,
+            &registry,
+            &profile,
+            &Stretched(profile.clone()),
+        )
+        .unwrap();
+        assert_eq!(parsed.packets(), expected.packets());
+    }
+
+    #[test]
+    fn test_summary() {
+        let nec = Nec {
+            address: 0x04,
+            inverted_address: !0x04,
+            command: 0x08,
+            repeats: 1,
+        };
+        let timings = TimingSequence::new(Polarity::StartsWithPulse, nec::encode(&nec));
+        let raw = RawSignal::new("power", 38000, DutyCycle::default(), timings).unwrap();
+        let parsed = ParsedSignal::try_from(&raw).unwrap();
+        assert_eq!(
+            parsed.summary(),
+            format!(
+                "power: NEC address 0x04 command 0x08 inverted_address=251, 2 frames, {}ms",
+                (raw.duration() + 500) / 1000
+            )
+        );
+
+        let dump = crate::examples::EXAMPLES[0].dump();
+        let parsed = ParsedSignal::try_from(&dump.signals()[0]).unwrap();
+        let summary = parsed.to_string();
+        assert!(summary.starts_with(&format!(
+            "{}: {} packets, ",
+            parsed.name(),
+            parsed.packets().len()
+        )));
+        assert!(summary.ends_with("ms"));
+    }
 }
@@ -1,82 +1,716 @@
+use alloc::{format, string::String, vec, vec::Vec};
+use core::ops::Range;
+
 use displaydoc::Display;
-use flipper_utils::round_to;
-use nom::{combinator::all_consuming, multi::many1, Finish, IResult};
+use flipper_utils::within_tolerance;
+use nom::{
+    combinator::all_consuming,
+    multi::{many0, many1},
+    Finish, IResult,
+};
 use thiserror::Error;
 
-use super::Packet;
+use super::{BitOrder, Packet};
 
-#[derive(Debug, PartialEq, Eq)]
-enum DurationClass {
-    /// A short duration (~550ms in case of my Samsung devices).
+/// Class of a duration, as told by a [`DurationClassifier`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DurationClass {
+    /// A short duration, one base unit.
     Short,
-    /// A long duration (typically ~3x the short duration).
+    /// A long duration, `long_ratio` base units.
     Long,
-    /// Unusual duration.
+    /// Unusual duration, such as a header or a trailer, in µs.
     Unusual(u32),
 }
 
-#[derive(Debug, PartialEq, Eq)]
-enum SignalComponent {
+/// Whether a duration is a pulse (carrier on) or a pause.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SignalComponent {
     Pulse,
     Pause,
 }
 
-#[derive(Debug, PartialEq, Eq)]
-struct TimeSlot {
-    duration: DurationClass,
-    component: SignalComponent,
+impl SignalComponent {
+    /// Component of the duration at `index` of Flipper timings, which start with a pulse.
+    pub(crate) fn at(index: usize) -> Self {
+        if index & 1 == 0 {
+            SignalComponent::Pulse
+        } else {
+            SignalComponent::Pause
+        }
+    }
+}
+
+/// A classified duration, what the packet grammar works on.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct TimeSlot {
+    pub duration: DurationClass,
+    pub component: SignalComponent,
+}
+
+/// Tells short durations from long and unusual ones for the packet grammar.
+///
+/// [`TimingProfile`] is the default one, comparing durations with its base
+/// unit within its tolerance. Custom classifiers allow experimenting with
+/// protocols whose durations don't fit that, e.g. with pulses and pauses of
+/// different lengths, see [`ParsedSignal::parse_classified`](super::ParsedSignal::parse_classified).
+pub trait DurationClassifier {
+    /// Classify a `duration` in µs of the given `component`.
+    fn classify(&self, duration: u32, component: SignalComponent) -> DurationClass;
+}
+
+impl DurationClassifier for TimingProfile {
+    fn classify(&self, duration: u32, _component: SignalComponent) -> DurationClass {
+        TimingProfile::classify(self, duration)
+    }
+}
+
+/// Short or long duration of a [`TimingProfile`].
+#[derive(Debug, Display, PartialEq, Eq, Clone, Copy)]
+pub enum NearestClass {
+    /// short
+    Short,
+    /// long
+    Long,
+}
+
+/// A duration that is neither short nor long in a [`TimingProfile`].
+#[derive(Debug, PartialEq, Clone)]
+pub struct UnusualDuration {
+    /// Index of the duration in the timings.
+    pub index: usize,
+    pub duration: u32,
+    /// Whether the duration is a pulse rather than a pause.
+    pub pulse: bool,
+    /// The closest class, even if way out of tolerance.
+    pub nearest: NearestClass,
+    /// Nominal duration of the closest class, in µs.
+    pub nominal: u32,
+}
+
+impl UnusualDuration {
+    /// Deviation from the nominal duration, as a fraction of it.
+    ///
+    /// Deviations just over the profile tolerance hint at a noisy capture,
+    /// while large ones are headers, trailers or a different protocol.
+    pub fn deviation(&self) -> f32 {
+        (self.duration as f32 - self.nominal as f32).abs() / self.nominal.max(1) as f32
+    }
+}
+
+impl core::fmt::Display for UnusualDuration {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "timing {}: {}µs, nearest is {} ({}µs)",
+            self.index, self.duration, self.nearest, self.nominal
+        )
+    }
+}
+
+/// Durations of a capture that didn't fit a [`TimingProfile`], see
+/// [`TimingProfile::diagnose`].
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Diagnostics {
+    pub unusual: Vec<UnusualDuration>,
+}
+
+/// Dumps start with a short pulse followed by a pause longer than this many base units.
+const MIN_DUMP_START_UNITS: u32 = 26;
+/// Frames with durations shorter than this are taken for noise rather than
+/// guessed, IR receivers can't resolve them anyway.
+const MIN_GUESS_DURATION: u32 = 100;
+/// Relative spread of durations grouped into a single cluster by [`TimingProfile::infer`].
+const CLUSTER_TOLERANCE: f32 = 0.25;
+
+/// Bounds of the packet header, in base units.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Header {
+    pub pulse: Range<u32>,
+    pub pause: Range<u32>,
+}
+
+/// How bits are coded in the durations of a packet.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum Coding {
+    /// A short pulse followed by a short (0) or long (1) pause, and a final
+    /// stop pulse, like NEC or Samsung.
+    #[default]
+    PulseDistance,
+    /// A short (0) or long (1) pulse followed by a short pause, like Sony SIRC.
+    /// The pause of the last bit merges into the trailer, so there's no stop pulse.
+    PulseWidth,
+}
+
+/// Timings of the generic packet grammar: packets with a header and coded bits.
+///
+/// Ranges are in whole base units, rounded down.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TimingProfile {
+    /// Duration of a short pulse or pause, in µs.
+    pub base_unit: u32,
+    /// Long durations are this many base units.
+    pub long_ratio: u32,
+    /// How bits are coded.
+    pub coding: Coding,
+    /// Header starting each packet.
+    pub header: Header,
+    /// Pause after the stop pulse of a packet followed by another one.
+    pub trailer: Range<u32>,
+    /// Largest deviation from a short or long duration, as a fraction of
+    /// the nominal duration.
+    pub tolerance: f32,
+    /// Order in which the bits of the packets are sent.
+    pub bit_order: BitOrder,
+}
+
+impl Default for TimingProfile {
+    /// Timings of Samsung air conditioners.
+    fn default() -> Self {
+        Self {
+            base_unit: 550,
+            long_ratio: 3,
+            coding: Coding::PulseDistance,
+            header: Header {
+                pulse: 4..7,
+                pause: 15..20,
+            },
+            trailer: 4..7,
+            // captured pauses of these remotes are up to 25% shorter than nominal
+            tolerance: 0.3,
+            bit_order: BitOrder::Lsb,
+        }
+    }
 }
 
-const SHORT_DURATION: u32 = 550;
-const LONG_BIT_DURATION: u32 = 3 * SHORT_DURATION;
+impl TimingProfile {
+    /// Use a different tolerance for short and long durations.
+    pub fn with_tolerance(self, tolerance: f32) -> Self {
+        Self { tolerance, ..self }
+    }
+
+    /// Use a different bit order for the packets.
+    pub fn with_bit_order(self, bit_order: BitOrder) -> Self {
+        Self { bit_order, ..self }
+    }
+
+    fn classify(&self, duration: u32) -> DurationClass {
+        let matches = |units: u32| {
+            within_tolerance(
+                duration,
+                units.saturating_mul(self.base_unit),
+                self.tolerance,
+            )
+        };
+
+        if matches(1) {
+            DurationClass::Short
+        } else if matches(self.long_ratio) {
+            DurationClass::Long
+        } else {
+            DurationClass::Unusual(duration)
+        }
+    }
+
+    /// Ideal value of `duration`: short or long, or else the nearest whole
+    /// number of base units.
+    pub(crate) fn nominal(&self, duration: u32) -> u32 {
+        match self.classify(duration) {
+            DurationClass::Short => self.base_unit,
+            DurationClass::Long => self.long_ratio.saturating_mul(self.base_unit),
+            DurationClass::Unusual(duration) => {
+                let unit = self.base_unit.max(1);
+                (duration.saturating_add(unit / 2) / unit).saturating_mul(unit)
+            }
+        }
+    }
+
+    /// List the durations of `data` that are neither short nor long, to tell
+    /// why it fails to parse.
+    pub fn diagnose(&self, data: &[u32]) -> Diagnostics {
+        let short = self.base_unit;
+        let long = self.long_ratio.saturating_mul(self.base_unit);
+
+        let unusual = data
+            .iter()
+            .enumerate()
+            .filter(|&(_, &duration)| matches!(self.classify(duration), DurationClass::Unusual(_)))
+            .map(|(index, &duration)| {
+                let (nearest, nominal) = if duration.abs_diff(short) <= duration.abs_diff(long) {
+                    (NearestClass::Short, short)
+                } else {
+                    (NearestClass::Long, long)
+                };
+
+                UnusualDuration {
+                    index,
+                    duration,
+                    pulse: index & 1 == 0,
+                    nearest,
+                    nominal,
+                }
+            })
+            .collect();
+
+        Diagnostics { unusual }
+    }
+
+    /// Derive the timings from a capture itself by clustering its durations.
+    ///
+    /// The most common pulse and the most common pause shorter than two such
+    /// pulses make a short bit, their average being the base unit: real
+    /// captures tend to have longer pulses and shorter pauses than nominal.
+    /// The most common longer pause is a long one, and the most common longer
+    /// pulse starts packet headers.
+    ///
+    /// Returns `None` if the capture doesn't look pulse distance coded.
+    pub fn infer(data: &[u32]) -> Option<Self> {
+        let pulses = clusters(data.iter().step_by(2).copied());
+        let pauses = clusters(data.iter().skip(1).step_by(2).copied());
+
+        let short_pulse = most_common(&pulses, |_| true)?;
+        let short_pause = most_common(&pauses, |mean| mean < short_pulse.saturating_mul(2))?;
+        let base_unit = ((u64::from(short_pulse) + u64::from(short_pause)) / 2) as u32;
+        if base_unit == 0 {
+            return None;
+        }
+        let is_short = |duration: u32| u64::from(duration) < u64::from(base_unit) * 3 / 2;
+
+        let long = most_common(&pauses, |mean| {
+            !is_short(mean) && mean / base_unit <= MIN_DUMP_START_UNITS
+        })?;
+        let long_ratio = long.saturating_add(base_unit / 2) / base_unit;
+        if long_ratio < 2 {
+            return None;
+        }
+
+        let header_pulse =
+            most_common(&pulses, |mean| mean > long_ratio.saturating_mul(base_unit))?;
+        let is_header_pulse =
+            |duration: u32| within_tolerance(duration, header_pulse, CLUSTER_TOLERANCE);
+        let after_header = data
+            .chunks(2)
+            .filter(|pair| is_header_pulse(pair[0]))
+            .filter_map(|pair| pair.get(1).copied());
+        let header_pause = most_common(&clusters(after_header), |_| true)?;
+        // pauses between two packets: right before a header, but not the dump start
+        let before_header = data
+            .windows(2)
+            .enumerate()
+            .skip(2)
+            .filter(|(i, window)| i & 1 == 1 && is_header_pulse(window[1]))
+            .map(|(_, window)| window[0]);
+        let trailer = most_common(&clusters(before_header), |_| true);
+
+        // pulses and pauses deviate from the base unit in opposite directions
+        let asymmetry = short_pulse.abs_diff(base_unit) as f32 / base_unit as f32;
+        let default = Self::default();
+        let profile = Self {
+            base_unit,
+            long_ratio,
+            tolerance: default.tolerance + asymmetry,
+            ..default
+        };
+        let band = |duration: u32| {
+            let units = profile.units(duration);
+            units - units / 4..(units + units / 4).saturating_add(2)
+        };
+
+        Some(Self {
+            header: Header {
+                pulse: band(header_pulse),
+                pause: band(header_pause),
+            },
+            trailer: trailer.map_or(profile.trailer.clone(), band),
+            ..profile
+        })
+    }
+
+    /// Length of a time slot in whole base units, nominal for short and long ones.
+    /// Synthesize the timings of `packets`, the inverse of parsing: a dump
+    /// preamble, then each packet with a header and coded bits, sent
+    /// `1 + repeats` times and separated by trailers.
+    ///
+    /// Headers and trailers get durations in the middle of their ranges.
+    pub fn encode(&self, packets: &[Packet]) -> Vec<u32> {
+        let short = self.base_unit;
+        let long = self.long_ratio.saturating_mul(self.base_unit);
+        let nominal = |units: &Range<u32>| {
+            let middle = units.start + units.end.saturating_sub(units.start + 1) / 2;
+            // ranges are in whole units rounded down, so aim at the middle of one
+            middle.saturating_mul(self.base_unit) + self.base_unit / 2
+        };
+
+        let mut data = vec![
+            short,
+            (MIN_DUMP_START_UNITS + 6).saturating_mul(self.base_unit),
+        ];
+        let frames = packets
+            .iter()
+            .flat_map(|packet| core::iter::repeat_n(packet, 1 + packet.repeats));
+        for (i, packet) in frames.enumerate() {
+            if i > 0 {
+                data.push(nominal(&self.trailer));
+            }
+            data.push(nominal(&self.header.pulse));
+            data.push(nominal(&self.header.pause));
+
+            let bits = packet.transmitted_bits();
+            match self.coding {
+                Coding::PulseDistance => {
+                    for bit in bits {
+                        data.extend([short, if bit { long } else { short }]);
+                    }
+                    data.push(short);
+                }
+                Coding::PulseWidth => {
+                    for bit in bits {
+                        data.extend([if bit { long } else { short }, short]);
+                    }
+                    // the last pause is replaced by the trailer, if any
+                    data.pop();
+                }
+            }
+        }
+
+        data
+    }
+
+    fn slot_units(&self, slot: &TimeSlot) -> u32 {
+        match slot.duration {
+            DurationClass::Short => 1,
+            DurationClass::Long => self.long_ratio,
+            DurationClass::Unusual(duration) => self.units(duration),
+        }
+    }
 
-const ROUND_TO: u32 = SHORT_DURATION;
+    fn units(&self, duration: u32) -> u32 {
+        duration / self.base_unit.max(1)
+    }
+}
 
 #[derive(Debug, Display, Error)]
 /// Error parsing IR signals
 pub enum ParseError {
     /// Nom error: {0}
     Nom(String),
+    /// No packets could be parsed
+    NoPackets,
 }
 
-pub(super) fn stream_to_packets(signal_timings: &[u32]) -> Result<Vec<Packet>, ParseError> {
-    let signals = stream_to_signals(signal_timings);
-    let (_, packets) = ir_dump_to_packets(&signals)
+/// A region of the timings that couldn't be parsed as a packet and was
+/// skipped by [`ParsedSignal::parse_lenient`](super::ParsedSignal::parse_lenient).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SkippedRegion {
+    /// Indices of the skipped timings.
+    pub range: Range<usize>,
+}
+
+/// Group durations within [`CLUSTER_TOLERANCE`] of the mean of their group,
+/// shortest first, each group sorted.
+pub(crate) fn duration_groups(durations: impl Iterator<Item = u32>) -> Vec<Vec<u32>> {
+    let mut durations: Vec<u32> = durations.collect();
+    durations.sort_unstable();
+
+    let mut groups: Vec<(u64, Vec<u32>)> = vec![];
+    for duration in durations {
+        match groups.last_mut() {
+            Some((sum, group))
+                if within_tolerance(
+                    duration,
+                    (*sum / group.len() as u64) as u32,
+                    CLUSTER_TOLERANCE,
+                ) =>
+            {
+                *sum += u64::from(duration);
+                group.push(duration);
+            }
+            _ => groups.push((u64::from(duration), vec![duration])),
+        }
+    }
+
+    groups.into_iter().map(|(_, group)| group).collect()
+}
+
+/// Group durations like [`duration_groups`], as pairs of the mean duration
+/// and the number of durations.
+fn clusters(durations: impl Iterator<Item = u32>) -> Vec<(u32, usize)> {
+    duration_groups(durations)
+        .into_iter()
+        .map(|group| {
+            let sum: u64 = group.iter().map(|&duration| u64::from(duration)).sum();
+            ((sum / group.len() as u64) as u32, group.len())
+        })
+        .collect()
+}
+
+/// Mean of the most populous cluster satisfying `filter`, the shortest one on ties.
+fn most_common(clusters: &[(u32, usize)], filter: impl Fn(u32) -> bool) -> Option<u32> {
+    clusters
+        .iter()
+        .filter(|(mean, _)| filter(*mean))
+        .rev()
+        .max_by_key(|(_, count)| *count)
+        .map(|(mean, _)| *mean)
+}
+
+/// Mean of the shortest cluster of durations that isn't just a few outliers.
+fn shortest_common(durations: &[u32]) -> Option<u32> {
+    let significant = durations.len() / 10;
+
+    clusters(durations.iter().copied())
+        .into_iter()
+        .find(|(_, count)| *count > significant)
+        .map(|(mean, _)| mean)
+}
+
+/// Slice Manchester coded durations into half-bits of `unit` µs as levels
+/// (`true` for a pulse), starting at a pulse if `first_is_pulse`.
+///
+/// Stops at the end of the durations or at one that isn't one to `max_units`
+/// units long within `tolerance`, usually the gap after the frame. A trailing
+/// half-bit pause merges into that gap, so it's left for the caller to add.
+///
+/// Returns the levels and the number of durations consumed.
+pub fn slice_half_bits(
+    durations: &[u32],
+    first_is_pulse: bool,
+    unit: u32,
+    max_units: u32,
+    tolerance: f32,
+) -> (Vec<bool>, usize) {
+    let mut levels = vec![];
+    let mut consumed = 0;
+
+    for (i, &duration) in durations.iter().enumerate() {
+        let Some(units) = (1..=max_units)
+            .find(|units| within_tolerance(duration, units.saturating_mul(unit), tolerance))
+        else {
+            break;
+        };
+
+        let level = first_is_pulse == (i & 1 == 0);
+        levels.extend((0..units).map(|_| level));
+        consumed += 1;
+    }
+
+    (levels, consumed)
+}
+
+/// Recover the half-bit duration of Manchester coded durations, which are
+/// all either one or two half-bits long: the mean of the shortest common
+/// cluster of durations.
+///
+/// Returns `None` if the durations don't fit the one-or-two pattern.
+pub fn recover_half_bit(durations: &[u32]) -> Option<u32> {
+    let unit = shortest_common(durations)?;
+
+    let fits = durations.iter().all(|&duration| {
+        within_tolerance(duration, unit, CLUSTER_TOLERANCE)
+            || within_tolerance(duration, unit.saturating_mul(2), CLUSTER_TOLERANCE)
+    });
+
+    fits.then_some(unit)
+}
+
+/// Decode Manchester coded bits from pairs of half-bit levels, most
+/// significant bit first, recovering each bit from the transition in its
+/// middle. `one` is the level of the first half of a one bit.
+///
+/// Returns `None` for a pair without a transition.
+pub fn biphase_bits(levels: &[bool], one: bool) -> Option<u64> {
+    let mut value = 0;
+
+    for pair in levels.chunks(2) {
+        let bit = match pair {
+            [first, second] if first != second => *first == one,
+            _ => return None,
+        };
+        value = value << 1 | u64::from(bit);
+    }
+
+    Some(value)
+}
+
+/// Manchester code the lowest `count` bits of `value`, most significant bit
+/// first, into half-bit levels. `one` is the level of the first half of a one bit.
+pub fn biphase_levels(value: u64, count: usize, one: bool) -> Vec<bool> {
+    (0..count)
+        .rev()
+        .flat_map(|bit| {
+            let first = (value >> bit & 1 == 1) == one;
+            [first, !first]
+        })
+        .collect()
+}
+
+/// Turn half-bit levels of `unit` µs into timings in the Flipper convention,
+/// dropping leading and trailing pauses.
+pub fn levels_to_timings(levels: &[bool], unit: u32) -> Vec<u32> {
+    let mut data = vec![];
+    let mut level = true;
+
+    for &half in levels.iter().skip_while(|&&half| !half) {
+        if half == level {
+            if let Some(last) = data.last_mut() {
+                *last += unit;
+                continue;
+            }
+        } else {
+            level = half;
+        }
+        data.push(unit);
+    }
+
+    if data.len() & 1 == 0 {
+        data.pop();
+    }
+
+    data
+}
+
+/// Parse time slots classified by any [`DurationClassifier`] with the
+/// packet grammar, headers and trailers being bounded by `profile`.
+pub(super) fn slots_to_packets(
+    slots: &[TimeSlot],
+    profile: &TimingProfile,
+) -> Result<Vec<Packet>, ParseError> {
+    let (_, packets) = ir_dump_to_packets(slots, profile)
         .finish()
         .map_err(|e| ParseError::Nom(format!("{:?}", e)))?;
 
     Ok(packets)
 }
 
-fn ir_dump_to_packets(stream: &[TimeSlot]) -> IResult<&[TimeSlot], Vec<Packet>> {
-    let (signals, _) = ir_dump_start(stream)?;
-    let (signals, packets) = all_consuming(many1(single_packet))(signals)?;
+/// Split timings into frames at pauses of at least `min_gap` and guess the
+/// bits of each one, for signals matching neither a protocol nor the packet
+/// grammar. Frames without any bits, such as the dump preamble, and noise
+/// are dropped.
+pub(super) fn segment_to_packets(signal_timings: &[u32], min_gap: u32) -> Vec<Packet> {
+    let mut frames = vec![];
+    let mut start = 0;
+    for (i, &duration) in signal_timings.iter().enumerate() {
+        if i & 1 == 1 && duration >= min_gap {
+            frames.push(&signal_timings[start..i]);
+            start = i + 1;
+        }
+    }
+    frames.push(&signal_timings[start..]);
+
+    frames.into_iter().filter_map(guess_packet).collect()
+}
+
+/// Guess the bits of a frame: leading pulses much longer than the shortest
+/// common one are taken for a header, and bits are told apart by their pauses
+/// if they vary, or by their pulses otherwise.
+fn guess_packet(frame: &[u32]) -> Option<Packet> {
+    if frame.iter().any(|&duration| duration < MIN_GUESS_DURATION) {
+        return None;
+    }
+
+    let short_pulse = shortest_common(&frame.iter().step_by(2).copied().collect::<Vec<_>>())?;
+    let header = frame
+        .chunks(2)
+        .take_while(|pair| pair[0] > short_pulse.saturating_mul(5) / 2)
+        .count();
+    let frame = &frame[(2 * header).min(frame.len())..];
+    if frame.len() < 2 {
+        return None;
+    }
+
+    let pulses: Vec<u32> = frame.iter().step_by(2).copied().collect();
+    let pauses: Vec<u32> = frame.iter().skip(1).step_by(2).copied().collect();
+    let significant = pauses.len() / 10;
+    let pulse_distance = clusters(pauses.iter().copied())
+        .iter()
+        .filter(|(_, count)| *count > significant)
+        .count()
+        > 1;
+
+    let (durations, short) = if pulse_distance {
+        let short = shortest_common(&pauses)?;
+        (pauses, short)
+    } else {
+        (pulses, short_pulse)
+    };
+    if durations.is_empty() {
+        return None;
+    }
+
+    let mut packet = Packet::default();
+    for &duration in &durations {
+        packet.data.push(duration > short.saturating_mul(3) / 2);
+    }
+
+    Some(packet)
+}
+
+/// Like [`stream_to_packets`], but instead of failing on a malformed packet,
+/// skips ahead to the next packet header and records the skipped region.
+///
+/// Fails only if no packet could be parsed at all.
+pub(super) fn stream_to_packets_lenient(
+    signal_timings: &[u32],
+    profile: &TimingProfile,
+) -> Result<(Vec<Packet>, Vec<SkippedRegion>), ParseError> {
+    let signals = classify_timings(signal_timings, profile);
+    let mut stream = match ir_dump_start(&signals, profile) {
+        Ok((rest, _)) => rest,
+        Err(_) => &signals[..],
+    };
+
+    let mut packets = vec![];
+    let mut skipped = vec![];
+    while !stream.is_empty() {
+        match single_packet(stream, profile) {
+            Ok((rest, packet)) => {
+                packets.push(packet);
+                stream = rest;
+            }
+            Err(_) => {
+                let next = (1..stream.len())
+                    .find(|&i| packet_start(&stream[i..], profile).is_ok())
+                    .unwrap_or(stream.len());
+                let start = signals.len() - stream.len();
+
+                skipped.push(SkippedRegion {
+                    range: start..start + next,
+                });
+                stream = &stream[next..];
+            }
+        }
+    }
+
+    if packets.is_empty() {
+        return Err(ParseError::NoPackets);
+    }
+
+    Ok((packets, skipped))
+}
+
+fn ir_dump_to_packets<'a>(
+    stream: &'a [TimeSlot],
+    profile: &TimingProfile,
+) -> IResult<&'a [TimeSlot], Vec<Packet>> {
+    let (signals, _) = ir_dump_start(stream, profile)?;
+    let (signals, packets) =
+        all_consuming(many1(|stream| single_packet(stream, profile)))(signals)?;
 
     Ok((signals, packets))
 }
 
-fn stream_to_signals(signal_timings: &[u32]) -> Vec<TimeSlot> {
+/// Classify Flipper timings, starting with a pulse, into time slots.
+pub fn classify_timings(
+    signal_timings: &[u32],
+    classifier: &impl DurationClassifier,
+) -> Vec<TimeSlot> {
     signal_timings
         .iter()
         .enumerate()
         .map(|(i, &duration)| {
-            (
-                if i & 1 == 0 {
-                    SignalComponent::Pulse
-                } else {
-                    SignalComponent::Pause
-                },
-                duration,
-            )
-        })
-        .map(|(component, duration)| {
-            let duration = match round_to(duration, ROUND_TO) {
-                SHORT_DURATION => DurationClass::Short,
-                LONG_BIT_DURATION => DurationClass::Long,
-                _ => DurationClass::Unusual(duration),
-            };
+            let component = SignalComponent::at(i);
             TimeSlot {
-                duration,
+                duration: classifier.classify(duration, component),
                 component,
             }
         })
@@ -136,13 +770,71 @@ macro_rules! ts {
 
 /// Dump starts with a single short pulse, followed by a "super-long"
 /// (something like 17700ns) pause.
-fn ir_dump_start(stream: &[TimeSlot]) -> IResult<&[TimeSlot], ()> {
+fn ir_dump_start<'a>(
+    stream: &'a [TimeSlot],
+    profile: &TimingProfile,
+) -> IResult<&'a [TimeSlot], ()> {
     match stream {
         [] => Err(nom::Err::Error(nom::error::Error::new(
             stream,
             nom::error::ErrorKind::Eof,
         ))),
-        [ts!(+short), ts!(-x), rest @ ..] if x / SHORT_DURATION > 26 => Ok((rest, ())),
+        [ts!(+short), ts!(-x), rest @ ..] if profile.units(*x) > MIN_DUMP_START_UNITS => {
+            Ok((rest, ()))
+        }
+        _ => Err(nom::Err::Error(nom::error::Error::new(
+            stream,
+            nom::error::ErrorKind::Tag,
+        ))),
+    }
+}
+
+/// A single pulse width coded bit: a short (0) or a long (1) pulse followed
+/// by a short pause.
+fn pulse_width_bit(stream: &[TimeSlot]) -> IResult<&[TimeSlot], bool> {
+    match stream {
+        [] => Err(nom::Err::Error(nom::error::Error::new(
+            stream,
+            nom::error::ErrorKind::Eof,
+        ))),
+        [ts!(+short), ts!(-short), rest @ ..] => Ok((rest, false)),
+        [ts!(+long), ts!(-short), rest @ ..] => Ok((rest, true)),
+        _ => Err(nom::Err::Error(nom::error::Error::new(
+            stream,
+            nom::error::ErrorKind::Tag,
+        ))),
+    }
+}
+
+/// The last pulse width coded bit of a packet: a short (0) or a long (1)
+/// pulse followed by either a trailer pause, or nothing (if this is the
+/// last packet).
+fn pulse_width_last_bit<'a>(
+    stream: &'a [TimeSlot],
+    profile: &TimingProfile,
+) -> IResult<&'a [TimeSlot], bool> {
+    let (bit, rest) = match stream {
+        [] => {
+            return Err(nom::Err::Error(nom::error::Error::new(
+                stream,
+                nom::error::ErrorKind::Eof,
+            )))
+        }
+        [ts!(+short), rest @ ..] => (false, rest),
+        [ts!(+long), rest @ ..] => (true, rest),
+        _ => {
+            return Err(nom::Err::Error(nom::error::Error::new(
+                stream,
+                nom::error::ErrorKind::Tag,
+            )))
+        }
+    };
+
+    match rest {
+        [] => Ok((rest, bit)),
+        [ts!(-pause), rest @ ..] if profile.trailer.contains(&profile.units(*pause)) => {
+            Ok((rest, bit))
+        }
         _ => Err(nom::Err::Error(nom::error::Error::new(
             stream,
             nom::error::ErrorKind::Tag,
@@ -169,15 +861,24 @@ fn packet_bit(stream: &[TimeSlot]) -> IResult<&[TimeSlot], bool> {
 
 /// Each signal starts as an unusually long (~3000ns) pulse, followed by
 /// an unusually long (~9000ns) pause.
-fn packet_start(stream: &[TimeSlot]) -> IResult<&[TimeSlot], ()> {
+fn packet_start<'a>(
+    stream: &'a [TimeSlot],
+    profile: &TimingProfile,
+) -> IResult<&'a [TimeSlot], ()> {
     match stream {
         [] => Err(nom::Err::Error(nom::error::Error::new(
             stream,
             nom::error::ErrorKind::Eof,
         ))),
-        [ts!(+pulse), ts!(-pause), rest @ ..]
-            if (4..7).contains(&(pulse / SHORT_DURATION))
-                && (15..20).contains(&(pause / SHORT_DURATION)) =>
+        [pulse @ TimeSlot {
+            component: SignalComponent::Pulse,
+            ..
+        }, pause @ TimeSlot {
+            component: SignalComponent::Pause,
+            ..
+        }, rest @ ..]
+            if profile.header.pulse.contains(&profile.slot_units(pulse))
+                && profile.header.pause.contains(&profile.slot_units(pause)) =>
         {
             Ok((rest, ()))
         }
@@ -190,7 +891,7 @@ fn packet_start(stream: &[TimeSlot]) -> IResult<&[TimeSlot], ()> {
 
 /// Packet ends with a single short pulse and either a ~3000ns pause,
 /// or nothing (if this is the last packet).
-fn packet_end(stream: &[TimeSlot]) -> IResult<&[TimeSlot], ()> {
+fn packet_end<'a>(stream: &'a [TimeSlot], profile: &TimingProfile) -> IResult<&'a [TimeSlot], ()> {
     match stream {
         [] => Err(nom::Err::Error(nom::error::Error::new(
             stream,
@@ -199,7 +900,9 @@ fn packet_end(stream: &[TimeSlot]) -> IResult<&[TimeSlot], ()> {
         // either a short pulse followed by the end of the stream
         [ts!(+short)] => Ok((&stream[1..], ())),
         // or a short pulse followed by long (~3000ns) pause
-        [ts!(+short), ts!(-pause), rest @ ..] if (4..7).contains(&(pause / SHORT_DURATION)) => {
+        [ts!(+short), ts!(-pause), rest @ ..]
+            if profile.trailer.contains(&profile.units(*pause)) =>
+        {
             Ok((rest, ()))
         }
         _ => Err(nom::Err::Error(nom::error::Error::new(
@@ -210,14 +913,28 @@ fn packet_end(stream: &[TimeSlot]) -> IResult<&[TimeSlot], ()> {
 }
 
 /// A single packet is encoded as a start signal, followed by a stream of bits.
-fn single_packet(stream: &[TimeSlot]) -> IResult<&[TimeSlot], Packet> {
-    let (stream, _) = packet_start(stream)?;
-    let (stream, bits) = many1(packet_bit)(stream)?;
-    let (stream, _) = packet_end(stream)?;
+fn single_packet<'a>(
+    stream: &'a [TimeSlot],
+    profile: &TimingProfile,
+) -> IResult<&'a [TimeSlot], Packet> {
+    let (stream, _) = packet_start(stream, profile)?;
+    let (stream, bits) = match profile.coding {
+        Coding::PulseDistance => {
+            let (stream, bits) = many1(packet_bit)(stream)?;
+            let (stream, _) = packet_end(stream, profile)?;
+            (stream, bits)
+        }
+        Coding::PulseWidth => {
+            let (stream, mut bits) = many0(pulse_width_bit)(stream)?;
+            let (stream, last) = pulse_width_last_bit(stream, profile)?;
+            bits.push(last);
+            (stream, bits)
+        }
+    };
 
-    let mut packet = Packet::default();
-    for bit in bits.iter().rev() {
-        packet.data.push(*bit);
+    let mut packet = Packet::default().with_bit_order(profile.bit_order);
+    for bit in bits {
+        packet.data.push(bit);
     }
 
     Ok((stream, packet))
@@ -225,32 +942,47 @@ fn single_packet(stream: &[TimeSlot]) -> IResult<&[TimeSlot], Packet> {
 
 #[cfg(test)]
 mod tests {
+    use alloc::string::ToString;
+
     use bitvec::{bits, order::Lsb0, vec::BitVec};
 
     use super::*;
 
+    fn stream_to_packets(
+        signal_timings: &[u32],
+        profile: &TimingProfile,
+    ) -> Result<Vec<Packet>, ParseError> {
+        slots_to_packets(&classify_timings(signal_timings, profile), profile)
+    }
+
     #[test]
     fn test_ir_dump_start() {
         let stream = vec![ts!(+short), ts!(-short)];
-        assert!(ir_dump_start(&stream).is_err());
+        assert!(ir_dump_start(&stream, &TimingProfile::default()).is_err());
 
         let stream = vec![ts!(+short), ts!(-17700)];
-        assert_eq!(ir_dump_start(&stream), Ok((&[][..], ())));
+        assert_eq!(
+            ir_dump_start(&stream, &TimingProfile::default()),
+            Ok((&[][..], ()))
+        );
 
         let stream = vec![ts!(+short), ts!(+17700)];
-        assert!(ir_dump_start(&stream).is_err());
+        assert!(ir_dump_start(&stream, &TimingProfile::default()).is_err());
     }
 
     #[test]
     fn test_packet_start() {
         let stream = vec![ts!(+short), ts!(-short)];
-        assert!(packet_start(&stream).is_err());
+        assert!(packet_start(&stream, &TimingProfile::default()).is_err());
 
         let stream = vec![ts!(+short), ts!(-short), ts!(+short), ts!(-short)];
-        assert!(packet_start(&stream).is_err());
+        assert!(packet_start(&stream, &TimingProfile::default()).is_err());
 
         let stream = vec![ts!(+2972), ts!(-8930)];
-        assert_eq!(packet_start(&stream), Ok((&[][..], ())));
+        assert_eq!(
+            packet_start(&stream, &TimingProfile::default()),
+            Ok((&[][..], ()))
+        );
     }
 
     #[test]
@@ -281,17 +1013,264 @@ mod tests {
         ];
 
         assert_eq!(
-            single_packet(&stream),
+            single_packet(&stream, &TimingProfile::default()),
             Ok((
                 &[][..],
-                // packet bits transmitted in LSB order, so 01 in the stream is 10 in the packet
                 Packet {
-                    data: BitVec::from_bitslice(bits![1, 0])
+                    data: BitVec::from_bitslice(bits![0, 1]),
+                    bit_order: BitOrder::Lsb,
+                    repeats: 0,
+                    integrity: None,
+                    frame: None,
                 }
             ))
         );
     }
 
+    #[test]
+    fn test_biphase() {
+        let levels = biphase_levels(0b1101, 4, false);
+        assert_eq!(biphase_bits(&levels, false), Some(0b1101));
+        assert!(biphase_bits(&[true, true], false).is_none());
+
+        // leading pause of the first one bit is dropped
+        let data = levels_to_timings(&levels, 100);
+        assert_eq!(data, [100, 100, 200, 200, 100]);
+
+        let (half_bits, consumed) = slice_half_bits(&data, true, 100, 2, 0.25);
+        assert_eq!(consumed, data.len());
+        let mut levels = vec![false];
+        levels.extend(half_bits);
+        assert_eq!(biphase_bits(&levels, false), Some(0b1101));
+    }
+
+    #[test]
+    fn test_recover_half_bit() {
+        let data = [880, 900, 1790, 870, 890, 1760, 905, 1800, 880];
+        assert_eq!(recover_half_bit(&data), Some(887));
+        assert_eq!(
+            recover_half_bit(&[550, 1650, 550, 550, 550, 1650, 550]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_classify() {
+        let profile = TimingProfile::default();
+        assert_eq!(profile.classify(410), DurationClass::Short);
+        assert_eq!(profile.classify(880), DurationClass::Unusual(880));
+        // rounding to 550µs would make it 1100µs
+        assert_eq!(profile.classify(1200), DurationClass::Long);
+
+        let strict = profile.with_tolerance(0.1);
+        assert_eq!(strict.classify(410), DurationClass::Unusual(410));
+        assert_eq!(strict.classify(1600), DurationClass::Long);
+    }
+
+    #[test]
+    fn test_pulse_width_packets() {
+        // Sony SIRC: 2.4ms header, 1.2ms ones and 0.6ms zeroes, 0.6ms pauses
+        let profile = TimingProfile {
+            base_unit: 600,
+            long_ratio: 2,
+            coding: Coding::PulseWidth,
+            header: Header {
+                pulse: 3..5,
+                pause: 1..2,
+            },
+            trailer: 10..80,
+            tolerance: 0.25,
+            bit_order: BitOrder::Lsb,
+        };
+        let stream = classify_timings(
+            &[
+                2400, 600, 1200, 600, 600, 600, 1200, 25_000, // first packet
+                2400, 600, 1200, 600, 600, 600, 1200, // repeat
+            ],
+            &profile,
+        );
+
+        let (rest, first) = single_packet(&stream, &profile).unwrap();
+        let (rest, second) = single_packet(rest, &profile).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(first, second);
+        assert_eq!(
+            first.data,
+            BitVec::<usize, Lsb0>::from_bitslice(bits![1, 0, 1])
+        );
+    }
+
+    #[test]
+    fn test_custom_profile() {
+        let profile = TimingProfile {
+            base_unit: 400,
+            long_ratio: 2,
+            coding: Coding::PulseDistance,
+            header: Header {
+                pulse: 8..10,
+                pause: 4..6,
+            },
+            trailer: 10..20,
+            tolerance: 0.3,
+            bit_order: BitOrder::Lsb,
+        };
+        let timings = [400, 20_000, 3400, 1900, 390, 820, 410, 380, 400];
+
+        let packets = stream_to_packets(&timings, &profile).unwrap();
+        assert_eq!(packets.len(), 1);
+        assert_eq!(
+            packets[0].data,
+            BitVec::<usize, Lsb0>::from_bitslice(bits![1, 0])
+        );
+        assert_eq!(packets[0].to_string(), "01");
+
+        // the same bits, sent most significant bit first
+        let msb = profile.clone().with_bit_order(BitOrder::Msb);
+        let packets = stream_to_packets(&timings, &msb).unwrap();
+        assert_eq!(packets[0].to_string(), "10");
+        assert_eq!(msb.encode(&packets), profile.encode(&packets));
+
+        assert!(stream_to_packets(&timings, &TimingProfile::default()).is_err());
+    }
+
+    #[test]
+    fn test_infer_profile() {
+        let dump = crate::examples::EXAMPLES[0].dump();
+        for signal in dump.signals() {
+            let expected = stream_to_packets(signal.data(), &TimingProfile::default()).unwrap();
+
+            // same remote, but 30% faster
+            let faster: Vec<u32> = signal.data().iter().map(|d| d * 7 / 10).collect();
+            assert!(stream_to_packets(&faster, &TimingProfile::default()).is_err());
+
+            let profile = TimingProfile::infer(&faster).unwrap();
+            assert_eq!(stream_to_packets(&faster, &profile).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_encode() {
+        let dump = crate::examples::EXAMPLES[0].dump();
+        let profile = TimingProfile::default();
+        for signal in dump.signals() {
+            let packets = stream_to_packets(signal.data(), &profile).unwrap();
+            let data = profile.encode(&packets);
+            assert_eq!(stream_to_packets(&data, &profile).unwrap(), packets);
+        }
+
+        let sirc = TimingProfile {
+            base_unit: 600,
+            long_ratio: 2,
+            coding: Coding::PulseWidth,
+            header: Header {
+                pulse: 4..5,
+                pause: 1..2,
+            },
+            trailer: 10..80,
+            tolerance: 0.25,
+            bit_order: BitOrder::Lsb,
+        };
+        let packet = Packet {
+            data: BitVec::from_bitslice(bits![1, 1, 0, 1]),
+            bit_order: BitOrder::Lsb,
+            repeats: 1,
+            integrity: None,
+            frame: None,
+        };
+        let data = sirc.encode(core::slice::from_ref(&packet));
+        assert_eq!(
+            data[2..],
+            [
+                2700, 900, 1200, 600, 1200, 600, 600, 600, 1200, 26_700, // first
+                2700, 900, 1200, 600, 1200, 600, 600, 600, 1200, // repeat
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diagnose() {
+        let profile = TimingProfile::default();
+        let diagnostics = profile.diagnose(&[550, 17_700, 540, 1650, 800, 2300]);
+        assert_eq!(
+            diagnostics.unusual,
+            [
+                UnusualDuration {
+                    index: 1,
+                    duration: 17_700,
+                    pulse: false,
+                    nearest: NearestClass::Long,
+                    nominal: 1650,
+                },
+                UnusualDuration {
+                    index: 4,
+                    duration: 800,
+                    pulse: true,
+                    nearest: NearestClass::Short,
+                    nominal: 550,
+                },
+                UnusualDuration {
+                    index: 5,
+                    duration: 2300,
+                    pulse: false,
+                    nearest: NearestClass::Long,
+                    nominal: 1650,
+                },
+            ]
+        );
+        assert!(diagnostics.unusual[1].deviation() < 0.5);
+        assert_eq!(
+            diagnostics.unusual[1].to_string(),
+            "timing 4: 800µs, nearest is short (550µs)"
+        );
+    }
+
+    #[test]
+    fn test_segment() {
+        let timings = [
+            // a short pulse and a gap, dropped
+            400, 30_000, // pulse distance coded frame with a header, 011
+            4000, 4000, 500, 500, 500, 1500, 480, 1560, 500, 40_000,
+            // pulse width coded frame, 1101
+            1200, 600, 1200, 600, 600, 600, 1200,
+        ];
+
+        let packets = segment_to_packets(&timings, 10_000);
+        assert_eq!(
+            packets.iter().map(|p| p.data.clone()).collect::<Vec<_>>(),
+            [
+                BitVec::<usize, Lsb0>::from_bitslice(bits![0, 1, 1]),
+                BitVec::from_bitslice(bits![1, 1, 0, 1]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lenient() {
+        let timings = [
+            550, 17_700, // dump header
+            2972, 8930, 550, 550, 550, 1650, 550, 2920, // packet
+            2972, 8930, 550, 5000, 550, 2920, // glitched packet
+            2972, 8930, 550, 1650, 550, 550, 550, // packet
+        ];
+        let profile = TimingProfile::default();
+        assert!(stream_to_packets(&timings, &profile).is_err());
+
+        let (packets, skipped) = stream_to_packets_lenient(&timings, &profile).unwrap();
+        assert_eq!(
+            packets.iter().map(|p| p.data.clone()).collect::<Vec<_>>(),
+            [
+                BitVec::<usize, Lsb0>::from_bitslice(bits![0, 1]),
+                BitVec::from_bitslice(bits![1, 0]),
+            ]
+        );
+        assert_eq!(skipped, [SkippedRegion { range: 10..16 }]);
+
+        assert!(matches!(
+            stream_to_packets_lenient(&[550, 17_700, 2972, 8930, 550, 5000, 550], &profile),
+            Err(ParseError::NoPackets)
+        ));
+    }
+
     #[test]
     fn test_ir_dump() {
         let stream = vec![
@@ -325,16 +1304,23 @@ mod tests {
         ];
 
         assert_eq!(
-            ir_dump_to_packets(&stream),
+            ir_dump_to_packets(&stream, &TimingProfile::default()),
             Ok((
                 &[][..],
                 vec![
-                    // packet bits transmitted in LSB order, so 01 in the stream is 10 in the packet
                     Packet {
-                        data: BitVec::from_bitslice(bits![1, 0])
+                        data: BitVec::from_bitslice(bits![0, 1]),
+                        bit_order: BitOrder::Lsb,
+                        repeats: 0,
+                        integrity: None,
+                        frame: None,
                     },
                     Packet {
-                        data: BitVec::from_bitslice(bits![0, 1])
+                        data: BitVec::from_bitslice(bits![1, 0]),
+                        bit_order: BitOrder::Lsb,
+                        repeats: 0,
+                        integrity: None,
+                        frame: None,
                     }
                 ]
             ))
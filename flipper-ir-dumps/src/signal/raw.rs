@@ -1,22 +1,120 @@
-use std::fmt::Debug;
+use alloc::{string::String, vec, vec::Vec};
+use core::{fmt::Debug, ops::Range, time::Duration};
 
-use crate::signal::SignalType;
+use displaydoc::Display;
+use flipper_utils::within_tolerance;
+use thiserror::Error;
 
-#[derive(PartialEq)]
+use crate::{
+    signal::{
+        DutyCycle, Frequency, ParseError, ParsedSignal, PolarityError, SignalComponent, SignalType,
+        TimingProfile, TimingSequence,
+    },
+    validation::{
+        ValidationIssue, MAX_DURATION, MAX_FREQUENCY, MIN_FREQUENCY, SPECIAL_FREQUENCIES,
+    },
+};
+
+#[derive(PartialEq, Clone)]
 pub struct RawSignal {
     pub(crate) name: String,
     pub(crate) r#type: SignalType,
-    pub(crate) frequency: u32,
-    pub(crate) duty_cycle: f32,
+    pub(crate) frequency: Frequency,
+    pub(crate) duty_cycle: DutyCycle,
     /// Data is a list of durations in microseconds.
     ///
     /// The first value is the duration of the first pulse, the second value is the duration of the
     /// pause after that, the third value is the duration of the second pulse, and so on.
     pub(crate) data: Vec<u32>,
+    /// Comment lines preceding the signal, verbatim text after the `#`.
+    pub(crate) comments: Vec<String>,
+    /// Keys the parser doesn't recognize, kept so the file can be written back losslessly.
+    pub(crate) unknown_fields: Vec<UnknownField>,
+    pub(crate) source: SourceText,
+    pub(crate) parsed: ParsedCache,
+}
+
+/// Values as they were written in the file the signal was read from, when
+/// not as [`DumpFile`](crate::dump::DumpFile) writes them, e.g. `0.33` for a
+/// duty cycle, so they can be written back unchanged.
+///
+/// Not part of the signal: texts compare equal.
+#[derive(Default, Clone)]
+pub(crate) struct SourceText {
+    pub(crate) frequency: Option<(Frequency, String)>,
+    pub(crate) duty_cycle: Option<(DutyCycle, String)>,
+    pub(crate) data: Option<(Vec<u32>, String)>,
+}
+
+impl PartialEq for SourceText {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+/// The signal parsed on first use, see [`RawSignal::parsed`].
+///
+/// Not part of the signal: clones start empty, and caches compare equal.
+/// With `std`, the cache is thread-safe, so that signals and dumps are `Sync`.
+#[derive(Default)]
+pub(crate) struct ParsedCache(Once<Result<ParsedSignal, ParseError>>);
+
+#[cfg(feature = "std")]
+type Once<T> = std::sync::OnceLock<T>;
+#[cfg(not(feature = "std"))]
+type Once<T> = core::cell::OnceCell<T>;
+
+impl Clone for ParsedCache {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl PartialEq for ParsedCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+/// Error concatenating raw signals
+pub enum ConcatError {
+    /// No signals to concatenate
+    Empty,
+    /// Carrier of signal {index} is {frequency}Hz rather than {expected}Hz
+    FrequencyMismatch {
+        index: usize,
+        frequency: u32,
+        expected: u32,
+    },
+}
+
+/// A `key: value` line of a signal block that the parser doesn't recognize.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct UnknownField {
+    pub(crate) key: String,
+    pub(crate) value: String,
+    /// Number of known fields preceding this one in the signal block.
+    pub(crate) position: usize,
+}
+
+impl UnknownField {
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Number of known fields (`name`, `type`, ...) preceding this one in the signal block.
+    pub fn position(&self) -> usize {
+        self.position
+    }
 }
 
 impl Debug for RawSignal {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("SavedSignal")
             .field("name", &self.name)
             .field("type", &self.r#type)
@@ -27,11 +125,473 @@ impl Debug for RawSignal {
 }
 
 impl RawSignal {
+    /// Create a raw signal from timings of any polarity, converting them to
+    /// the Flipper pulse-first convention.
+    pub fn new(
+        name: impl Into<String>,
+        frequency: impl Into<Frequency>,
+        duty_cycle: DutyCycle,
+        timings: TimingSequence,
+    ) -> Result<Self, PolarityError> {
+        Ok(Self {
+            name: name.into(),
+            r#type: SignalType::Raw,
+            frequency: frequency.into(),
+            duty_cycle,
+            data: timings.into_flipper()?,
+            comments: Vec::new(),
+            unknown_fields: Vec::new(),
+            source: Default::default(),
+            parsed: ParsedCache::default(),
+        })
+    }
+
+    /// The signal parsed into packets as by [`ParsedSignal::try_from`],
+    /// on first use only: later calls return the same result.
+    pub fn parsed(&self) -> Result<&ParsedSignal, &ParseError> {
+        self.parsed
+            .0
+            .get_or_init(|| ParsedSignal::try_from(self))
+            .as_ref()
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
 
+    pub fn frequency(&self) -> Frequency {
+        self.frequency
+    }
+
+    pub fn duty_cycle(&self) -> DutyCycle {
+        self.duty_cycle
+    }
+
     pub fn data(&self) -> &[u32] {
         &self.data
     }
+
+    /// The timings as pulses and pauses, alternating from a pulse.
+    pub fn slots(&self) -> impl ExactSizeIterator<Item = (SignalComponent, Duration)> + '_ {
+        self.data.iter().enumerate().map(|(i, &duration)| {
+            (
+                SignalComponent::at(i),
+                Duration::from_micros(duration.into()),
+            )
+        })
+    }
+
+    /// Total duration of the timings, in µs.
+    pub fn duration(&self) -> u64 {
+        self.data.iter().map(|&duration| u64::from(duration)).sum()
+    }
+
+    pub fn comments(&self) -> &[String] {
+        &self.comments
+    }
+
+    pub fn unknown_fields(&self) -> &[UnknownField] {
+        &self.unknown_fields
+    }
+
+    /// Check the signal for problems that would make it unusable, like zero durations
+    /// or a carrier frequency no IR receiver uses.
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let signal = || self.name.clone();
+        let mut issues = Vec::new();
+
+        if self.data.is_empty() {
+            issues.push(ValidationIssue::EmptyData { signal: signal() });
+        } else if self.data.len() & 1 == 0 {
+            issues.push(ValidationIssue::TrailingPause { signal: signal() });
+        }
+
+        for (index, &duration) in self.data.iter().enumerate() {
+            if duration == 0 {
+                issues.push(ValidationIssue::ZeroDuration {
+                    signal: signal(),
+                    index,
+                });
+            } else if duration > MAX_DURATION {
+                issues.push(ValidationIssue::AbsurdDuration {
+                    signal: signal(),
+                    index,
+                    duration,
+                });
+            }
+        }
+
+        let frequency = self.frequency.hz();
+        if !(MIN_FREQUENCY..=MAX_FREQUENCY).contains(&frequency)
+            && !SPECIAL_FREQUENCIES.contains(&frequency)
+        {
+            issues.push(ValidationIssue::FrequencyOutOfRange {
+                signal: signal(),
+                frequency,
+            });
+        }
+
+        issues
+    }
+
+    /// Check whether `other` is a capture of the same signal: same type and carrier,
+    /// and every timing within `tolerance` (a fraction of the duration) of ours.
+    ///
+    /// Names are not compared.
+    pub fn is_duplicate_of(&self, other: &RawSignal, tolerance: f32) -> bool {
+        self.r#type == other.r#type
+            && self.frequency == other.frequency
+            && self.data.len() == other.data.len()
+            && self
+                .data
+                .iter()
+                .zip(&other.data)
+                .all(|(&ours, &theirs)| within_tolerance(theirs, ours, tolerance))
+    }
+
+    /// Chain `signals` into a single one, separated by pauses of `gap` µs,
+    /// e.g. to make a macro button turning a TV on and switching its input.
+    ///
+    /// The result is named after all the signals and takes the duty cycle
+    /// of the first one. All signals must share the carrier frequency.
+    pub fn concat(signals: &[RawSignal], gap: u32) -> Result<RawSignal, ConcatError> {
+        let first = signals.first().ok_or(ConcatError::Empty)?;
+        if let Some((index, signal)) = signals
+            .iter()
+            .enumerate()
+            .find(|(_, signal)| signal.frequency != first.frequency)
+        {
+            return Err(ConcatError::FrequencyMismatch {
+                index,
+                frequency: signal.frequency.hz(),
+                expected: first.frequency.hz(),
+            });
+        }
+
+        let mut data: Vec<u32> = vec![];
+        for signal in signals.iter().filter(|signal| !signal.data.is_empty()) {
+            if !data.is_empty() {
+                // the gap extends a trailing pause rather than following it
+                if data.len() & 1 == 0 {
+                    let pause = data.pop().unwrap_or_default();
+                    data.push(pause.saturating_add(gap));
+                } else {
+                    data.push(gap);
+                }
+            }
+            data.extend(&signal.data);
+        }
+
+        let names: Vec<&str> = signals.iter().map(|signal| signal.name.as_str()).collect();
+        Ok(RawSignal {
+            name: names.join(" + "),
+            r#type: first.r#type,
+            frequency: first.frequency,
+            duty_cycle: first.duty_cycle,
+            data,
+            comments: Vec::new(),
+            unknown_fields: Vec::new(),
+            source: Default::default(),
+            parsed: ParsedCache::default(),
+        })
+    }
+
+    /// Ranges of the timings of each frame, frames being separated by
+    /// pauses of at least `min_gap` µs, see [`SEGMENT_GAP`](crate::signal::SEGMENT_GAP).
+    ///
+    /// The separating pauses belong to no frame.
+    pub fn frames(&self, min_gap: u32) -> Vec<Range<usize>> {
+        let mut frames = vec![];
+        let mut start = 0;
+        for (i, &duration) in self.data.iter().enumerate() {
+            if i & 1 == 1 && duration >= min_gap {
+                frames.push(start..i);
+                start = i + 1;
+            }
+        }
+        if start < self.data.len() {
+            frames.push(start..self.data.len());
+        }
+        frames
+    }
+
+    /// Keep only the frames in `frames` (see [`RawSignal::frames`]) and
+    /// the pauses between them, e.g. `0..1` to drop the repeats following
+    /// the first frame.
+    pub fn slice_frames(&self, frames: Range<usize>, min_gap: u32) -> RawSignal {
+        let all = self.frames(min_gap);
+        let frames = &all[frames.start.min(all.len())..frames.end.min(all.len())];
+        let data = match (frames.first(), frames.last()) {
+            (Some(first), Some(last)) => self.data[first.start..last.end].to_vec(),
+            _ => vec![],
+        };
+
+        RawSignal {
+            data,
+            ..self.clone()
+        }
+    }
+
+    /// Keep only the part of the signal from `range.start` to `range.end`
+    /// µs after its start, cutting the durations at the bounds.
+    ///
+    /// The result starts and ends with a pulse, like a capture, so pauses
+    /// at the bounds are dropped.
+    pub fn slice_time(&self, range: Range<u64>) -> RawSignal {
+        let mut data = vec![];
+        let mut start = 0;
+        for (i, &duration) in self.data.iter().enumerate() {
+            let end = start + u64::from(duration);
+            let clipped = end.min(range.end).saturating_sub(start.max(range.start));
+            if clipped > 0 && (i & 1 == 0 || !data.is_empty()) {
+                data.push(clipped as u32);
+            }
+            start = end;
+        }
+        if data.len() & 1 == 0 {
+            data.pop();
+        }
+
+        RawSignal {
+            data,
+            ..self.clone()
+        }
+    }
+
+    /// Snap every duration to its ideal value in `profile`: short and long
+    /// durations to their nominal ones, and the others, like headers and
+    /// gaps, to whole base units.
+    ///
+    /// Captures of the same button normalize to (almost) the same timings,
+    /// which also transmit cleaner than the jittery originals.
+    pub fn normalized(&self, profile: &TimingProfile) -> RawSignal {
+        RawSignal {
+            data: self
+                .data
+                .iter()
+                .map(|&duration| profile.nominal(duration))
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    /// How similar the timings of `other` are to ours, from 0 for nothing
+    /// in common to 1 for identical ones.
+    ///
+    /// Unlike [`RawSignal::is_duplicate_of`], tolerates captures with extra
+    /// or missing durations, e.g. a repeat frame more or a glitch, by
+    /// aligning the timings with dynamic time warping: the result is one
+    /// minus the mean relative difference of the aligned durations, pulses
+    /// aligned with pauses counting as completely different.
+    ///
+    /// Names and carriers are not compared.
+    pub fn similarity(&self, other: &RawSignal) -> f32 {
+        let (ours, theirs) = (&self.data, &other.data);
+        if ours.is_empty() || theirs.is_empty() {
+            return if ours.len() == theirs.len() { 1.0 } else { 0.0 };
+        }
+
+        let cost = |i: usize, j: usize| {
+            if i & 1 != j & 1 {
+                return 1.0;
+            }
+            let (a, b) = (ours[i], theirs[j]);
+            a.abs_diff(b) as f32 / a.max(b).max(1) as f32
+        };
+
+        // total cost and length of the cheapest alignment ending at each
+        // pair of durations, row by row
+        let mut previous: Vec<(f32, u32)> = vec![(0.0, 0); theirs.len()];
+        let mut current = previous.clone();
+        for i in 0..ours.len() {
+            for j in 0..theirs.len() {
+                let best = match (i, j) {
+                    (0, 0) => (0.0, 0),
+                    (0, _) => current[j - 1],
+                    (_, 0) => previous[j],
+                    _ => [previous[j - 1], previous[j], current[j - 1]]
+                        .into_iter()
+                        .min_by(|a, b| a.0.total_cmp(&b.0))
+                        .expect("three candidates"),
+                };
+                current[j] = (best.0 + cost(i, j), best.1 + 1);
+            }
+            core::mem::swap(&mut previous, &mut current);
+        }
+
+        let (total, len) = previous[theirs.len() - 1];
+        1.0 - total / len as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::*;
+
+    fn raw(data: &[u32]) -> RawSignal {
+        RawSignal {
+            name: "signal".to_string(),
+            r#type: SignalType::Raw,
+            frequency: Frequency::from_hz(38_000),
+            duty_cycle: DutyCycle::default(),
+            data: data.to_vec(),
+            comments: Vec::new(),
+            unknown_fields: Vec::new(),
+            source: Default::default(),
+            parsed: ParsedCache::default(),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<RawSignal>();
+        assert_send_sync::<ParsedSignal>();
+        assert_send_sync::<crate::dump::DumpFile>();
+
+        let signal = raw(&[9000, 4500, 560, 560, 560, 1690, 560]);
+        let parsed = std::thread::scope(|scope| {
+            let threads: Vec<_> = (0..2)
+                .map(|_| scope.spawn(|| signal.parsed().unwrap() as *const _ as usize))
+                .collect();
+            threads
+                .into_iter()
+                .map(|thread| thread.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+        // both threads see the one cached result
+        assert_eq!(parsed[0], parsed[1]);
+    }
+
+    #[test]
+    fn test_similarity() {
+        let signal = raw(&[9000, 4500, 560, 560, 560, 1690, 560]);
+        assert_eq!(signal.similarity(&signal), 1.0);
+
+        // jitter
+        let jittery = raw(&[9100, 4400, 600, 520, 540, 1700, 580]);
+        assert!(signal.similarity(&jittery) > 0.95);
+        assert!(signal.is_duplicate_of(&jittery, 0.1));
+
+        // a glitch splitting a pause: no longer a duplicate, but still similar
+        let glitched = raw(&[9000, 4500, 560, 200, 50, 300, 560, 1690, 560]);
+        assert!(!signal.is_duplicate_of(&glitched, 0.1));
+        assert!(signal.similarity(&glitched) > 0.7);
+        assert_eq!(signal.similarity(&glitched), glitched.similarity(&signal));
+
+        // another button
+        let other = raw(&[9000, 4500, 560, 1690, 560, 560, 560]);
+        assert!(signal.similarity(&other) < 0.9);
+
+        assert_eq!(raw(&[]).similarity(&raw(&[])), 1.0);
+        assert_eq!(signal.similarity(&raw(&[])), 0.0);
+    }
+
+    #[test]
+    fn test_normalized() {
+        let profile = TimingProfile {
+            base_unit: 560,
+            ..TimingProfile::default()
+        };
+        let signal = raw(&[3110, 9100, 520, 1620, 600, 560, 40_050, 0]);
+        let normalized = signal.normalized(&profile);
+        assert_eq!(
+            normalized.data(),
+            [3360, 8960, 560, 1680, 560, 560, 40_320, 0]
+        );
+        assert_eq!(normalized.name(), signal.name());
+
+        // captures of the same button become identical
+        let other = raw(&[3400, 8990, 580, 1700, 530, 590, 40_100, 0]);
+        assert_eq!(other.normalized(&profile), normalized);
+        assert_eq!(normalized.normalized(&profile), normalized);
+    }
+
+    #[test]
+    fn test_slicing() {
+        let signal = raw(&[
+            9000, 4500, 560, 1690, 560, 40_000, 9000, 2250, 560, 96_000, 9000, 2250, 560,
+        ]);
+        assert_eq!(signal.frames(10_000), [0..5, 6..9, 10..13]);
+        assert_eq!(raw(&[560, 20_000]).frames(10_000), vec![0..1]);
+
+        assert_eq!(
+            signal.slice_frames(0..1, 10_000).data(),
+            [9000, 4500, 560, 1690, 560]
+        );
+        assert_eq!(
+            signal.slice_frames(1..5, 10_000).data(),
+            [9000, 2250, 560, 96_000, 9000, 2250, 560]
+        );
+        assert!(signal.slice_frames(3..4, 10_000).data().is_empty());
+
+        // cut in the middle of durations, dropping pauses at the bounds
+        assert_eq!(signal.slice_time(1000..14_000).data(), [8000, 4500, 500]);
+        assert_eq!(signal.slice_time(10_000..16_000).data(), [560, 1690, 250]);
+        assert_eq!(signal.slice_time(0..u64::MAX), signal);
+        assert!(signal.slice_time(5000..5000).data().is_empty());
+    }
+
+    #[test]
+    fn test_concat() {
+        let mut on = raw(&[9000, 4500, 560]);
+        on.name = "TV on".to_string();
+        let mut hdmi = raw(&[9000, 2250, 560, 1000]);
+        hdmi.name = "HDMI2".to_string();
+
+        let chained = RawSignal::concat(&[on.clone(), hdmi.clone(), on.clone()], 50_000).unwrap();
+        assert_eq!(chained.name(), "TV on + HDMI2 + TV on");
+        assert_eq!(
+            chained.data(),
+            [9000, 4500, 560, 50_000, 9000, 2250, 560, 51_000, 9000, 4500, 560]
+        );
+        assert_eq!(chained.frames(10_000).len(), 3);
+        assert_eq!(RawSignal::concat(&[on.clone()], 50_000), Ok(on.clone()));
+
+        assert_eq!(RawSignal::concat(&[], 50_000), Err(ConcatError::Empty));
+        hdmi.frequency = Frequency::from_hz(36_000);
+        assert_eq!(
+            RawSignal::concat(&[on, hdmi], 50_000),
+            Err(ConcatError::FrequencyMismatch {
+                index: 1,
+                frequency: 36_000,
+                expected: 38_000,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parsed() {
+        let dump = crate::examples::EXAMPLES[0].dump();
+        let signal = &dump.signals()[0];
+
+        let parsed = signal.parsed().unwrap();
+        assert!(core::ptr::eq(parsed, signal.parsed().unwrap()));
+        assert_eq!(
+            parsed.packets(),
+            ParsedSignal::try_from(signal).unwrap().packets()
+        );
+
+        // caches don't make signals differ
+        assert_eq!(signal.clone(), *signal);
+        assert!(raw(&[560]).parsed().is_err());
+    }
+
+    #[test]
+    fn test_slots() {
+        let signal = raw(&[9000, 4500, 560]);
+        let slots: Vec<_> = signal.slots().collect();
+        assert_eq!(
+            slots,
+            [
+                (SignalComponent::Pulse, Duration::from_micros(9000)),
+                (SignalComponent::Pause, Duration::from_micros(4500)),
+                (SignalComponent::Pulse, Duration::from_micros(560)),
+            ]
+        );
+        assert_eq!(signal.slots().len(), 3);
+    }
 }
@@ -0,0 +1,350 @@
+//! Statistics of the durations of a signal, to tell clean captures from
+//! noisy ones at a glance.
+
+use alloc::{vec, vec::Vec};
+use core::ops::Range;
+
+use super::{parsed::duration_groups, RawSignal, SignalComponent};
+
+/// Lower bound of the first [`Bucket`] of the histogram, in µs.
+const FIRST_BUCKET: u32 = 64;
+/// Durations longer than this many base units are headers or gaps rather
+/// than multiples of the base unit, and are left out of [`UnitEstimate`]s.
+const MAX_UNITS: f32 = 16.0;
+
+/// Statistics of the durations of a signal, see [`RawSignal::timing_stats`].
+#[derive(Debug, PartialEq, Clone)]
+pub struct TimingStats {
+    pub pulses: usize,
+    pub pauses: usize,
+    /// Sum of all durations, in µs.
+    pub total_duration: u64,
+    /// Durations by order of magnitude, shortest first.
+    pub histogram: Vec<Bucket>,
+    /// Durations grouped by their nominal values, pulses first, shortest first.
+    pub classes: Vec<ClassStats>,
+}
+
+/// Durations falling into a range of the histogram of [`TimingStats`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Bucket {
+    /// Durations in µs: below 64µs for the first bucket, and then doubling.
+    pub range: Range<u32>,
+    pub pulses: usize,
+    pub pauses: usize,
+}
+
+/// Durations close enough to each other to stand for the same nominal one,
+/// e.g. all the short pulses of a NEC frame.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ClassStats {
+    /// Whether the durations are pulses.
+    pub pulse: bool,
+    pub count: usize,
+    pub min: u32,
+    pub max: u32,
+    /// Mean duration, in µs.
+    pub mean: f32,
+    /// Standard deviation of the durations, in µs.
+    pub stddev: f32,
+    /// Largest deviation of a duration from the mean, in µs.
+    pub jitter: f32,
+}
+
+impl ClassStats {
+    fn new(pulse: bool, durations: &[u32]) -> Self {
+        let count = durations.len();
+        let mean = durations.iter().map(|&d| d as f32).sum::<f32>() / count as f32;
+        let deviations = durations.iter().map(|&d| d as f32 - mean);
+        let variance = deviations.clone().map(|d| d * d).sum::<f32>() / count as f32;
+        let jitter = deviations.map(f32::abs).fold(0.0, f32::max);
+
+        Self {
+            pulse,
+            count,
+            min: durations.iter().copied().min().unwrap_or_default(),
+            max: durations.iter().copied().max().unwrap_or_default(),
+            mean,
+            stddev: sqrt(variance),
+            jitter,
+        }
+    }
+}
+
+/// Base unit of the protocol of a signal and how precisely the capture
+/// follows it, see [`TimingStats::estimate_unit`].
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct UnitEstimate {
+    /// Duration of the shortest nominal pulse or pause, in µs.
+    pub base_unit: f32,
+    /// Standard deviation of the durations from the mean of their class,
+    /// in µs: the noise of the receiver.
+    pub jitter: f32,
+    /// Mean distance of the classes from the nearest multiple of the base
+    /// unit, as a fraction of it: 0 if all durations are whole units.
+    pub grid_error: f32,
+}
+
+impl UnitEstimate {
+    /// Jitter as a fraction of the base unit.
+    pub fn relative_jitter(&self) -> f32 {
+        self.jitter / self.base_unit
+    }
+
+    /// Relative difference of the base unit from the `nominal` one of a
+    /// protocol: within the [`relative_jitter`](Self::relative_jitter),
+    /// the capture is likely a noisy one of that protocol.
+    pub fn deviation_from(&self, nominal: u32) -> f32 {
+        (self.base_unit - nominal as f32).abs() / nominal.max(1) as f32
+    }
+}
+
+impl TimingStats {
+    /// Estimate the base unit as the one best fitting all the classes as
+    /// whole multiples, starting from the shortest class that isn't just a
+    /// few outliers.
+    ///
+    /// Returns `None` for signals without durations.
+    pub fn estimate_unit(&self) -> Option<UnitEstimate> {
+        let total: usize = self.classes.iter().map(|class| class.count).sum();
+        let shortest = self
+            .classes
+            .iter()
+            .filter(|class| class.count > total / 10)
+            .map(|class| class.mean)
+            .reduce(f32::min)?;
+        if shortest < 1.0 {
+            return None;
+        }
+
+        let units = |class: &ClassStats, unit: f32| ((class.mean / unit + 0.5) as u32).max(1);
+        let gridded = |unit: f32| {
+            self.classes
+                .iter()
+                .filter(move |class| class.mean / unit <= MAX_UNITS + 0.5)
+        };
+
+        let (durations, multiples) =
+            gridded(shortest).fold((0.0, 0.0), |(durations, multiples), class| {
+                let weight = class.count as f32;
+                (
+                    durations + class.mean * weight,
+                    multiples + units(class, shortest) as f32 * weight,
+                )
+            });
+        let base_unit = durations / multiples;
+
+        let (count, variance, error) =
+            gridded(base_unit).fold((0.0, 0.0, 0.0), |(count, variance, error), class| {
+                let offset = class.mean / base_unit - units(class, base_unit) as f32;
+                let weight = class.count as f32;
+                (
+                    count + weight,
+                    variance + class.stddev * class.stddev * weight,
+                    error + offset.abs() * weight,
+                )
+            });
+
+        Some(UnitEstimate {
+            base_unit,
+            jitter: sqrt(variance / count),
+            grid_error: error / count,
+        })
+    }
+}
+
+impl RawSignal {
+    /// Statistics of the durations, such as a histogram and the spread of
+    /// durations standing for the same nominal one.
+    pub fn timing_stats(&self) -> TimingStats {
+        let pulses = self.data.iter().step_by(2).copied();
+        let pauses = self.data.iter().skip(1).step_by(2).copied();
+
+        let mut histogram = vec![];
+        for (component, duration) in self.slots() {
+            let index = bucket_index(duration.as_micros() as u32);
+            if histogram.len() <= index {
+                histogram.resize_with(index + 1, || None);
+            }
+            let bucket = histogram[index].get_or_insert_with(|| Bucket {
+                range: bucket_range(index),
+                pulses: 0,
+                pauses: 0,
+            });
+            match component {
+                SignalComponent::Pulse => bucket.pulses += 1,
+                SignalComponent::Pause => bucket.pauses += 1,
+            }
+        }
+
+        let classes = duration_groups(pulses.clone())
+            .iter()
+            .map(|group| ClassStats::new(true, group))
+            .chain(
+                duration_groups(pauses.clone())
+                    .iter()
+                    .map(|group| ClassStats::new(false, group)),
+            )
+            .collect();
+
+        TimingStats {
+            pulses: pulses.count(),
+            pauses: pauses.count(),
+            total_duration: self.duration(),
+            histogram: histogram.into_iter().flatten().collect(),
+            classes,
+        }
+    }
+
+    /// Estimate the base unit of the protocol and the jitter of the capture,
+    /// see [`TimingStats::estimate_unit`].
+    pub fn estimate_unit(&self) -> Option<UnitEstimate> {
+        self.timing_stats().estimate_unit()
+    }
+}
+
+fn bucket_index(duration: u32) -> usize {
+    (u32::BITS - (duration / FIRST_BUCKET).leading_zeros()) as usize
+}
+
+fn bucket_range(index: usize) -> Range<u32> {
+    let bound = |index: usize| (u64::from(FIRST_BUCKET) << index).min(u32::MAX.into()) as u32;
+    match index {
+        0 => 0..FIRST_BUCKET,
+        _ => bound(index - 1)..bound(index),
+    }
+}
+
+/// Square root by Newton's method, as `f32::sqrt` needs `std`.
+fn sqrt(x: f32) -> f32 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+
+    let mut root = x.max(1.0);
+    for _ in 0..64 {
+        let next = (root + x / root) / 2.0;
+        if next >= root {
+            break;
+        }
+        root = next;
+    }
+    root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signal::{DutyCycle, Polarity, TimingSequence};
+
+    #[test]
+    fn test_buckets() {
+        assert_eq!(bucket_index(0), 0);
+        assert_eq!(bucket_index(63), 0);
+        assert_eq!(bucket_index(64), 1);
+        assert_eq!(bucket_index(560), 4);
+        assert_eq!(bucket_range(0), 0..64);
+        assert_eq!(bucket_range(4), 512..1024);
+        assert_eq!(bucket_range(bucket_index(u32::MAX)).end, u32::MAX);
+    }
+
+    #[test]
+    fn test_sqrt() {
+        assert_eq!(sqrt(0.0), 0.0);
+        assert_eq!(sqrt(16.0), 4.0);
+        assert!((sqrt(2.0) - core::f32::consts::SQRT_2).abs() < 1e-6);
+        assert!((sqrt(0.25) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_timing_stats() {
+        let timings = TimingSequence::new(
+            Polarity::StartsWithPulse,
+            vec![9000, 4500, 550, 570, 560, 1700, 570, 1680, 560],
+        );
+        let raw = RawSignal::new("signal", 38_000, DutyCycle::default(), timings).unwrap();
+        let stats = raw.timing_stats();
+
+        assert_eq!((stats.pulses, stats.pauses), (5, 4));
+        assert_eq!(stats.total_duration, 19_690);
+        assert_eq!(
+            stats.histogram,
+            [
+                Bucket {
+                    range: 512..1024,
+                    pulses: 4,
+                    pauses: 1,
+                },
+                Bucket {
+                    range: 1024..2048,
+                    pulses: 0,
+                    pauses: 2,
+                },
+                Bucket {
+                    range: 4096..8192,
+                    pulses: 0,
+                    pauses: 1,
+                },
+                Bucket {
+                    range: 8192..16_384,
+                    pulses: 1,
+                    pauses: 0,
+                },
+            ]
+        );
+
+        // short pulses, header pulse, short pauses, long pauses, header pause
+        assert_eq!(stats.classes.len(), 5);
+        let short = &stats.classes[0];
+        assert!(short.pulse);
+        assert_eq!((short.count, short.min, short.max), (4, 550, 570));
+        assert_eq!(short.mean, 560.0);
+        assert_eq!(short.jitter, 10.0);
+        assert!((short.stddev - 7.071).abs() < 0.01);
+
+        let long = &stats.classes[3];
+        assert!(!long.pulse);
+        assert_eq!((long.count, long.mean, long.jitter), (2, 1690.0, 10.0));
+    }
+
+    #[test]
+    fn test_estimate_unit() {
+        let estimate = |data: Vec<u32>| {
+            let timings = TimingSequence::new(Polarity::StartsWithPulse, data);
+            RawSignal::new("signal", 38_000, DutyCycle::default(), timings)
+                .unwrap()
+                .estimate_unit()
+        };
+
+        // NEC, 562µs, with pulses a bit long and pauses a bit short
+        let mut nec = vec![9000, 4500];
+        for bit in [0, 1, 1, 0, 1, 0, 0, 1] {
+            nec.extend([600, if bit == 1 { 1650 } else { 530 }]);
+        }
+        nec.push(600);
+        let unit = estimate(nec).unwrap();
+        assert!(unit.deviation_from(562) < 0.02, "{unit:?}");
+        assert!(unit.deviation_from(600) > 0.05, "{unit:?}");
+        assert!(unit.grid_error < 0.1, "{unit:?}");
+
+        // noisy capture of the same grid
+        let noisy = vec![560, 500, 620, 1700, 540, 1620, 590, 560, 530, 1690, 570];
+        let unit = estimate(noisy).unwrap();
+        assert!(unit.deviation_from(562) < 0.05, "{unit:?}");
+        assert!(unit.relative_jitter() > 0.02, "{unit:?}");
+
+        // durations that aren't multiples of a common unit
+        let unit = estimate(vec![500, 800, 500, 800, 500, 800, 500]).unwrap();
+        assert!(unit.grid_error > 0.1, "{unit:?}");
+        assert_eq!(unit.jitter, 0.0);
+
+        let empty = TimingStats {
+            pulses: 0,
+            pauses: 0,
+            total_duration: 0,
+            histogram: vec![],
+            classes: vec![],
+        };
+        assert_eq!(empty.estimate_unit(), None);
+    }
+}
@@ -0,0 +1,165 @@
+use alloc::vec::Vec;
+
+use displaydoc::Display;
+use thiserror::Error;
+
+/// Pauses at least this long at the start of a sequence of unknown polarity
+/// are assumed to be receiver idle time rather than part of the signal.
+const IDLE_PAUSE: u32 = 20_000;
+
+/// Which level a [`TimingSequence`] starts with.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Polarity {
+    /// The first duration is a pulse (carrier on), as in Flipper dumps.
+    StartsWithPulse,
+    /// The first duration is a pause (carrier off).
+    StartsWithPause,
+    /// The source didn't say.
+    Unknown,
+}
+
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+/// Error converting timings to the Flipper convention
+pub enum PolarityError {
+    /// Can't determine whether the timings start with a pulse or a pause
+    Unknown,
+    /// Timings contain no pulses
+    NoPulses,
+}
+
+/// A sequence of alternating pulse and pause durations in microseconds,
+/// with explicit tracking of which one comes first.
+///
+/// Flipper dumps always start with a pulse, but other sources (logic analyzers,
+/// LIRC `mode2`) may start with a pause; importers should go through this type
+/// instead of assuming that even indices are pulses.
+///
+/// # Examples
+/// ```rust
+/// use flipper_ir_dumps::signal::{DutyCycle, RawSignal, TimingSequence};
+///
+/// // as reported by `mode2`: idle space, then the signal
+/// let timings = TimingSequence::from_levels([(false, 100_000), (true, 9000), (false, 4500), (true, 560)]);
+///
+/// let signal = RawSignal::new("power", 38000, DutyCycle::default(), timings).unwrap();
+/// assert_eq!(signal.data(), [9000, 4500, 560]);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TimingSequence {
+    polarity: Polarity,
+    durations: Vec<u32>,
+}
+
+impl TimingSequence {
+    pub fn new(polarity: Polarity, durations: Vec<u32>) -> Self {
+        Self {
+            polarity,
+            durations,
+        }
+    }
+
+    /// Build a sequence from durations tagged with their level (`true` for a pulse).
+    ///
+    /// Adjacent durations of the same level are merged, and the polarity is
+    /// taken from the first one.
+    pub fn from_levels(levels: impl IntoIterator<Item = (bool, u32)>) -> Self {
+        let mut polarity = Polarity::Unknown;
+        let mut durations: Vec<u32> = Vec::new();
+        let mut last_level = None;
+
+        for (is_pulse, duration) in levels {
+            if last_level == Some(is_pulse) {
+                if let Some(last) = durations.last_mut() {
+                    *last = last.saturating_add(duration);
+                }
+                continue;
+            }
+
+            if last_level.is_none() {
+                polarity = if is_pulse {
+                    Polarity::StartsWithPulse
+                } else {
+                    Polarity::StartsWithPause
+                };
+            }
+            last_level = Some(is_pulse);
+            durations.push(duration);
+        }
+
+        Self {
+            polarity,
+            durations,
+        }
+    }
+
+    pub fn polarity(&self) -> Polarity {
+        self.polarity
+    }
+
+    pub fn durations(&self) -> &[u32] {
+        &self.durations
+    }
+
+    /// Convert to the Flipper convention: the first duration is a pulse
+    /// and the last one is a pulse too.
+    ///
+    /// Leading and trailing pauses carry no information and are dropped.
+    /// If the polarity is unknown, a leading pause is recognized only
+    /// if it is long enough to be receiver idle time.
+    pub fn into_flipper(self) -> Result<Vec<u32>, PolarityError> {
+        let mut durations = self.durations;
+
+        let starts_with_pause = match self.polarity {
+            Polarity::StartsWithPulse => false,
+            Polarity::StartsWithPause => true,
+            Polarity::Unknown => match durations.first() {
+                Some(&first) if first >= IDLE_PAUSE => true,
+                _ => return Err(PolarityError::Unknown),
+            },
+        };
+
+        if starts_with_pause {
+            durations.remove(0);
+        }
+        if durations.is_empty() {
+            return Err(PolarityError::NoPulses);
+        }
+        if durations.len() & 1 == 0 {
+            durations.pop();
+        }
+
+        Ok(durations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_levels() {
+        let sequence =
+            TimingSequence::from_levels([(false, 100), (true, 500), (true, 50), (false, 500)]);
+        assert_eq!(sequence.polarity(), Polarity::StartsWithPause);
+        assert_eq!(sequence.durations(), [100, 550, 500]);
+    }
+
+    #[test]
+    fn test_into_flipper() {
+        let sequence = TimingSequence::new(Polarity::StartsWithPulse, vec![550, 550, 550]);
+        assert_eq!(sequence.into_flipper(), Ok(vec![550, 550, 550]));
+
+        let sequence =
+            TimingSequence::new(Polarity::StartsWithPause, vec![100, 550, 550, 550, 900]);
+        assert_eq!(sequence.into_flipper(), Ok(vec![550, 550, 550]));
+
+        let sequence = TimingSequence::new(Polarity::Unknown, vec![30_000, 550, 550, 550]);
+        assert_eq!(sequence.into_flipper(), Ok(vec![550, 550, 550]));
+
+        let sequence = TimingSequence::new(Polarity::Unknown, vec![550, 550, 550]);
+        assert_eq!(sequence.into_flipper(), Err(PolarityError::Unknown));
+
+        let sequence = TimingSequence::new(Polarity::StartsWithPause, vec![100]);
+        assert_eq!(sequence.into_flipper(), Err(PolarityError::NoPulses));
+    }
+}
@@ -0,0 +1,254 @@
+//! Building new timing sequences out of existing frames: macros and repeats.
+//!
+//! Receivers need some silence between two messages to tell them apart, and
+//! most protocols specify a minimum gap or a minimum start-to-start period.
+//! The functions here insert gaps automatically and extend user-specified
+//! gaps that are too short for real hardware, reporting each extension.
+
+use alloc::vec::Vec;
+
+use displaydoc::Display;
+
+/// Silence after which the Flipper Zero receiver considers a capture finished.
+///
+/// Frames separated by less than this end up in a single signal when
+/// captured by a Flipper.
+pub const FLIPPER_RX_TIMEOUT: u32 = 150_000;
+
+/// Timing constraints between consecutive messages, in microseconds.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct GapPolicy {
+    /// Minimum silence between the end of a frame and the start of the next one.
+    pub min_gap: u32,
+    /// Minimum time between the starts of two consecutive frames.
+    pub min_period: u32,
+}
+
+impl GapPolicy {
+    /// NEC and its variants: frames repeat every 108 ms.
+    pub const NEC: Self = Self {
+        min_gap: 0,
+        min_period: 108_000,
+    };
+    /// Samsung32: same framing as NEC.
+    pub const SAMSUNG32: Self = Self::NEC;
+    /// Philips RC5: frames repeat every 114 ms (64 × 1.778 ms).
+    pub const RC5: Self = Self {
+        min_gap: 0,
+        min_period: 113_778,
+    };
+    /// Philips RC6: frames repeat every 107 ms (240 × 444 µs).
+    pub const RC6: Self = Self {
+        min_gap: 0,
+        min_period: 106_667,
+    };
+    /// Kaseikyo (Panasonic and others): frames repeat every 130 ms.
+    pub const KASEIKYO: Self = Self {
+        min_gap: 0,
+        min_period: 130_000,
+    };
+    /// Denon/Sharp: frames, including the inverted copies, repeat every 40 ms.
+    pub const SHARP: Self = Self {
+        min_gap: 0,
+        min_period: 40_000,
+    };
+    /// Mitsubishi: frames repeat every 53 ms.
+    pub const MITSUBISHI: Self = Self {
+        min_gap: 0,
+        min_period: 53_000,
+    };
+    /// Bang & Olufsen: frames are at least 20 ms apart.
+    pub const BANG_OLUFSEN: Self = Self {
+        min_gap: 20_000,
+        min_period: 0,
+    };
+    /// RCA: frames repeat every 64 ms.
+    pub const RCA: Self = Self {
+        min_gap: 0,
+        min_period: 64_000,
+    };
+    /// Sony SIRC: frames repeat every 45 ms.
+    pub const SIRC: Self = Self {
+        min_gap: 0,
+        min_period: 45_000,
+    };
+    /// Gaps long enough for a Flipper Zero to capture each frame as a separate signal.
+    pub const FLIPPER: Self = Self {
+        min_gap: FLIPPER_RX_TIMEOUT,
+        min_period: 0,
+    };
+
+    /// Combine two policies, satisfying both.
+    pub fn and(self, other: Self) -> Self {
+        Self {
+            min_gap: self.min_gap.max(other.min_gap),
+            min_period: self.min_period.max(other.min_period),
+        }
+    }
+
+    /// Shortest gap allowed after a frame of the given total duration.
+    pub fn required_gap(&self, frame_duration: u32) -> u32 {
+        self.min_gap
+            .max(self.min_period.saturating_sub(frame_duration))
+    }
+}
+
+/// A gap that had to be changed to satisfy a [`GapPolicy`].
+#[derive(Debug, Display, PartialEq, Eq, Clone)]
+pub enum GapWarning {
+    /// gap of {requested}µs before frame {frame} is too short to be received, extended to {applied}µs
+    TooShort {
+        frame: usize,
+        requested: u32,
+        applied: u32,
+    },
+}
+
+/// Timings produced by joining frames, plus the gap adjustments made.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct Synthesized {
+    /// Durations in the Flipper convention: pulse first, alternating with pauses.
+    pub data: Vec<u32>,
+    pub warnings: Vec<GapWarning>,
+}
+
+/// Join frames into a single timing sequence, separated by gaps.
+///
+/// With `gap` of `None`, the shortest gap allowed by `policy` is inserted.
+/// A requested gap shorter than that is extended and reported.
+///
+/// Frames are expected in the Flipper convention; a trailing pause of a frame
+/// is replaced by the gap following it. Empty frames are skipped, not to
+/// leave two gaps in a row.
+///
+/// # Examples
+/// ```rust
+/// use flipper_ir_dumps::{
+///     examples::EXAMPLES,
+///     synthesis::{concat_frames, GapPolicy},
+/// };
+///
+/// let dump = EXAMPLES[1].dump();
+/// let first = dump.signal("C_24_f_1").unwrap().data();
+/// let second = dump.signal("C_24_f_2").unwrap().data();
+///
+/// let synthesized = concat_frames([first, second], Some(1_000), GapPolicy::FLIPPER);
+/// assert_eq!(synthesized.data.len(), first.len() + 1 + second.len());
+/// // the requested gap is way too short for a Flipper to tell the signals apart
+/// assert_eq!(synthesized.warnings.len(), 1);
+/// ```
+pub fn concat_frames<'a>(
+    frames: impl IntoIterator<Item = &'a [u32]>,
+    gap: Option<u32>,
+    policy: GapPolicy,
+) -> Synthesized {
+    let mut synthesized = Synthesized::default();
+    let mut last_frame_duration = None;
+
+    for (i, frame) in frames.into_iter().enumerate() {
+        let frame = match frame.len() & 1 {
+            0 => &frame[..frame.len().saturating_sub(1)],
+            _ => frame,
+        };
+        if frame.is_empty() {
+            continue;
+        }
+
+        if let Some(previous) = last_frame_duration {
+            let required = policy.required_gap(previous);
+            let applied = match gap {
+                Some(requested) if requested < required => {
+                    synthesized.warnings.push(GapWarning::TooShort {
+                        frame: i,
+                        requested,
+                        applied: required,
+                    });
+                    required
+                }
+                Some(requested) => requested,
+                None => required,
+            };
+            synthesized.data.push(applied);
+        }
+
+        synthesized.data.extend_from_slice(frame);
+        let duration = frame
+            .iter()
+            .map(|&duration| u64::from(duration))
+            .sum::<u64>();
+        last_frame_duration = Some(u32::try_from(duration).unwrap_or(u32::MAX));
+    }
+
+    synthesized
+}
+
+/// Repeat a frame `count` times, separated by gaps as in [`concat_frames`].
+pub fn repeat_frame(
+    frame: &[u32],
+    count: usize,
+    gap: Option<u32>,
+    policy: GapPolicy,
+) -> Synthesized {
+    concat_frames(core::iter::repeat_n(frame, count), gap, policy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_required_gap() {
+        assert_eq!(GapPolicy::NEC.required_gap(68_000), 40_000);
+        assert_eq!(GapPolicy::NEC.required_gap(120_000), 0);
+        assert_eq!(
+            GapPolicy::NEC.and(GapPolicy::FLIPPER).required_gap(68_000),
+            FLIPPER_RX_TIMEOUT
+        );
+    }
+
+    #[test]
+    fn test_concat_frames() {
+        let frame: &[u32] = &[1000, 500, 1000];
+        let policy = GapPolicy {
+            min_gap: 3000,
+            min_period: 0,
+        };
+
+        let synthesized = concat_frames([frame, frame], None, policy);
+        assert_eq!(synthesized.data, [1000, 500, 1000, 3000, 1000, 500, 1000]);
+        assert!(synthesized.warnings.is_empty());
+
+        // trailing pause gets replaced by the gap
+        let synthesized = concat_frames([&[1000, 500, 1000, 200][..], frame], Some(5000), policy);
+        assert_eq!(synthesized.data, [1000, 500, 1000, 5000, 1000, 500, 1000]);
+        assert!(synthesized.warnings.is_empty());
+
+        // empty frames add no gap
+        let synthesized = concat_frames([&[][..], frame, &[], frame, &[]], None, policy);
+        assert_eq!(synthesized.data, [1000, 500, 1000, 3000, 1000, 500, 1000]);
+
+        // frames lasting longer than a u32 of µs
+        let long: &[u32] = &[u32::MAX, u32::MAX, 1];
+        let nec = concat_frames([long, frame], Some(10), GapPolicy::NEC);
+        assert_eq!(nec.data, [u32::MAX, u32::MAX, 1, 10, 1000, 500, 1000]);
+        assert!(nec.warnings.is_empty());
+
+        let synthesized = repeat_frame(frame, 3, Some(100), policy);
+        assert_eq!(synthesized.data.len(), 11);
+        assert_eq!(
+            synthesized.warnings,
+            [
+                GapWarning::TooShort {
+                    frame: 1,
+                    requested: 100,
+                    applied: 3000
+                },
+                GapWarning::TooShort {
+                    frame: 2,
+                    requested: 100,
+                    applied: 3000
+                },
+            ]
+        );
+    }
+}
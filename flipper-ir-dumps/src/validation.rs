@@ -0,0 +1,35 @@
+use alloc::string::String;
+
+use displaydoc::Display;
+
+/// Lowest carrier frequency considered plausible for consumer IR, in Hz.
+pub const MIN_FREQUENCY: u32 = 30_000;
+/// Highest carrier frequency considered plausible for consumer IR, in Hz.
+pub const MAX_FREQUENCY: u32 = 60_000;
+/// Carrier frequencies outside of the range that are still used by real remotes,
+/// like the 455kHz of Bang & Olufsen.
+pub const SPECIAL_FREQUENCIES: &[u32] = &[crate::protocol::bang_olufsen::FREQUENCY];
+/// Durations longer than this (one second) are certainly capture artifacts.
+pub const MAX_DURATION: u32 = 1_000_000;
+
+/// A problem with a dump that doesn't prevent parsing it,
+/// but likely makes its signals unusable.
+#[derive(Debug, Display, PartialEq, Eq, Clone)]
+pub enum ValidationIssue {
+    /// signal `{signal}` has no timing data
+    EmptyData { signal: String },
+    /// signal `{signal}` ends with a pause, timing data should have odd length
+    TrailingPause { signal: String },
+    /// signal `{signal}` has a zero duration at index {index}
+    ZeroDuration { signal: String, index: usize },
+    /// signal `{signal}` has an absurd duration of {duration}µs at index {index}
+    AbsurdDuration {
+        signal: String,
+        index: usize,
+        duration: u32,
+    },
+    /// signal `{signal}` has frequency {frequency}Hz outside of 30-60kHz and not used by any known remote
+    FrequencyOutOfRange { signal: String, frequency: u32 },
+    /// signal name `{signal}` is used more than once
+    DuplicateName { signal: String },
+}
@@ -0,0 +1,83 @@
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+
+use flipper_ir_dumps::dedupe::DEFAULT_THRESHOLD;
+
+/// Clap based CLI argument parsing.
+#[derive(Debug, Parser)]
+#[command(version, author)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Decode, plot and report on the bundled example dumps.
+    Demo {
+        /// Where to put the results; defaults to a directory in the system temp dir.
+        #[clap(short, long)]
+        output_dir: Option<PathBuf>,
+    },
+    /// Print the cargo features the decoder library was built with.
+    Features,
+    /// Check whether two dumps are captures of the same remote and propose how to merge them.
+    DedupeRemotes {
+        /// The dump to merge into.
+        first: PathBuf,
+        /// The dump to compare with the first one.
+        second: PathBuf,
+        /// Fraction of matching commands above which the dumps are considered the same remote.
+        #[clap(short, long, default_value_t = DEFAULT_THRESHOLD)]
+        threshold: f32,
+    },
+    /// Generate a synthetic dump with random packets and timing jitter.
+    Generate {
+        /// The file to write the dump to.
+        #[clap(short, long)]
+        output_file: PathBuf,
+        /// Seed of the random number generator; picked at random and printed if omitted.
+        #[clap(short, long)]
+        seed: Option<u64>,
+        /// Number of signals to generate.
+        #[clap(long, default_value_t = 4)]
+        signals: usize,
+        /// Maximum deviation of each duration from its nominal value, in microseconds.
+        #[clap(long, default_value_t = 50)]
+        jitter: u32,
+    },
+    /// Write the codes next to a decoded signal into a dump, to look for undocumented buttons.
+    Neighbors {
+        /// The dump holding the signal.
+        input: PathBuf,
+        /// Name of the signal in the dump.
+        signal: String,
+        /// The file to write the neighboring codes to.
+        #[clap(short, long)]
+        output_file: PathBuf,
+        /// Commands up to this far below and above the original one.
+        #[clap(long, default_value_t = 4)]
+        distance: u32,
+        /// Commands differing from the original one in a single bit among this many lowest ones.
+        #[clap(long, default_value_t = 8)]
+        flip_bits: u32,
+    },
+    /// Look the signals of a dump up in a local clone of Flipper-IRDB to identify the remote.
+    Identify {
+        /// The dump to identify.
+        capture: PathBuf,
+        /// Root of the Flipper-IRDB clone.
+        #[clap(long)]
+        irdb: PathBuf,
+        /// Where to keep the index of the database; defaults to a file in its root.
+        #[clap(long)]
+        cache: Option<PathBuf>,
+        /// Index the database again even if the cached index is up to date.
+        #[clap(long)]
+        reindex: bool,
+        /// Number of best matching remotes to report.
+        #[clap(short, long, default_value_t = 5)]
+        limit: usize,
+    },
+}
@@ -0,0 +1,47 @@
+use std::path::Path;
+
+use color_eyre::eyre::{Result, WrapErr};
+
+use flipper_ir_dumps::{dedupe::compare_dumps, dump::DumpFile};
+
+/// Compares two dumps and prints a verdict along with a merge proposal.
+pub fn run(first: &Path, second: &Path, threshold: f32) -> Result<()> {
+    let first_dump = load_dump(first)?;
+    let second_dump = load_dump(second)?;
+
+    let comparison = compare_dumps(&first_dump, &second_dump);
+
+    println!(
+        "similarity: {:.0}% ({} matching commands)",
+        comparison.similarity() * 100.0,
+        comparison.matches.len()
+    );
+
+    if !comparison.is_same_remote(threshold) {
+        println!("verdict: different remotes");
+        return Ok(());
+    }
+
+    println!("verdict: same remote");
+    println!("merge proposal for {}:", first.display());
+    for (first_name, second_name) in comparison.proposed_renames() {
+        println!(
+            "  keep {} (captured as {} in second)",
+            first_name, second_name
+        );
+    }
+    for name in comparison.proposed_additions() {
+        println!("  add {}", name);
+    }
+    for name in &comparison.only_in_first {
+        println!("  keep {} (missing from second)", name);
+    }
+
+    Ok(())
+}
+
+fn load_dump(path: &Path) -> Result<DumpFile> {
+    let contents = std::fs::read(path).wrap_err("Failed to read file")?;
+
+    DumpFile::from_bytes(&contents).wrap_err_with(|| format!("Failed decoding {}", path.display()))
+}
@@ -0,0 +1,54 @@
+use std::{fmt::Write as _, path::Path};
+
+use color_eyre::eyre::{Result, WrapErr};
+
+use flipper_ir_dumps::{
+    examples::EXAMPLES,
+    pipeline::{self, Event},
+};
+use flipper_ir_dumps_vis::plotting;
+
+/// Runs the whole toolchain on the bundled example dumps.
+///
+/// For every example a subdirectory of `output_dir` is created, containing
+/// a plot for each signal and a `report.txt` with the decoded packets.
+pub fn run(output_dir: &Path) -> Result<()> {
+    for example in EXAMPLES {
+        let stem = example.name.trim_end_matches(".ir");
+        let example_dir = output_dir.join(stem);
+        std::fs::create_dir_all(&example_dir).wrap_err("Failed to create output directory")?;
+
+        let output = pipeline::run(example.name, example.contents.as_bytes(), |event| {
+            if let Event::Warning { .. } = event {
+                eprintln!("{}", event);
+            }
+        })?;
+        let mut report = String::new();
+
+        for signal in output.dump.signals() {
+            plotting::plot_signal(signal, &example_dir)?;
+        }
+
+        for parsed in &output.parsed {
+            match parsed.confidence() {
+                Some(confidence) => writeln!(report, "{} (confidence {})", parsed, confidence)?,
+                None => writeln!(report, "{}", parsed)?,
+            }
+            for packet in parsed.packets() {
+                writeln!(report, "  {}", packet)?;
+            }
+        }
+
+        std::fs::write(example_dir.join("report.txt"), report)
+            .wrap_err("Failed to write report")?;
+
+        println!(
+            "{}: {} signals -> {}",
+            example.name,
+            output.dump.signals().len(),
+            example_dir.display()
+        );
+    }
+
+    Ok(())
+}
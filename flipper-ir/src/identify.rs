@@ -0,0 +1,124 @@
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use color_eyre::eyre::{Result, WrapErr};
+
+use flipper_ir_dumps::{dump::DumpFile, irdb::IrdbIndex, protocol::Registry};
+
+/// Default name of the index, kept in the root of the database.
+pub const CACHE_FILE: &str = ".flipper-ir-index.tsv";
+
+/// Matches the signals of a capture against a Flipper-IRDB clone and prints
+/// the files sharing the most codes with it.
+pub fn run(capture: &Path, irdb: &Path, cache: &Path, reindex: bool, limit: usize) -> Result<()> {
+    let contents = std::fs::read(capture).wrap_err("Failed to read file")?;
+    let dump = DumpFile::from_bytes(&contents)
+        .wrap_err_with(|| format!("Failed decoding {}", capture.display()))?;
+
+    let registry = Registry::default();
+    let index = load_index(irdb, cache, reindex, &registry)?;
+    let matches = index.identify(&dump, &registry);
+
+    if matches.is_empty() {
+        println!(
+            "no remote in {} shares codes with {}",
+            irdb.display(),
+            capture.display()
+        );
+        return Ok(());
+    }
+
+    for found in matches.iter().take(limit) {
+        println!(
+            "{}: {} {} ({}), {} of {} signals ({:.0}%)",
+            found.file,
+            found.brand().unwrap_or("unknown brand"),
+            found.model(),
+            found.category().unwrap_or("uncategorized"),
+            found.buttons.len(),
+            found.decoded,
+            found.score() * 100.0
+        );
+        for (signal, button) in &found.buttons {
+            println!("    {} = {}", signal, button);
+        }
+    }
+
+    Ok(())
+}
+
+/// The cached index, or a new one if it's missing or older than any file of
+/// the database.
+fn load_index(irdb: &Path, cache: &Path, reindex: bool, registry: &Registry) -> Result<IrdbIndex> {
+    let files = ir_files(irdb)?;
+
+    let cached_at = std::fs::metadata(cache)
+        .and_then(|meta| meta.modified())
+        .ok();
+    let up_to_date = cached_at.is_some_and(|cached_at| {
+        files
+            .iter()
+            .all(|file| modified(file).is_some_and(|at| at <= cached_at))
+    });
+    if !reindex && up_to_date {
+        let saved = std::fs::read_to_string(cache).wrap_err("Failed to read index")?;
+        if let Ok(index) = saved.parse() {
+            return Ok(index);
+        }
+    }
+
+    let mut index = IrdbIndex::new();
+    for file in &files {
+        // skip files that aren't text rather than failing the whole database
+        let Ok(contents) = std::fs::read_to_string(file) else {
+            continue;
+        };
+        let relative = file.strip_prefix(irdb).unwrap_or(file);
+        index.add_file(&relative.to_string_lossy(), &contents, registry);
+    }
+    println!(
+        "indexed {} buttons of {} files in {}",
+        index.len(),
+        files.len(),
+        irdb.display()
+    );
+
+    std::fs::write(cache, index.to_string()).wrap_err("Failed to write index")?;
+    Ok(index)
+}
+
+/// All `.ir` files under `dir`, skipping hidden directories such as `.git`.
+fn ir_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        let entries = std::fs::read_dir(&dir)
+            .wrap_err_with(|| format!("Failed to list {}", dir.display()))?;
+        for entry in entries {
+            let path = entry.wrap_err("Failed to list directory")?.path();
+            let hidden = path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+            if hidden {
+                continue;
+            }
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path.extension().is_some_and(|extension| extension == "ir") {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
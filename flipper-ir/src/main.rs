@@ -0,0 +1,78 @@
+use clap::Parser;
+use color_eyre::eyre::WrapErr;
+
+use flipper_ir_dumps::generator::{self, GeneratorOptions};
+
+mod cli;
+use cli::{Cli, Command};
+
+mod dedupe;
+mod demo;
+mod identify;
+mod neighbors;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    color_eyre::install()?;
+
+    let cli = Cli::parse();
+
+    match cli.command {
+        Command::Demo { output_dir } => {
+            let output_dir =
+                output_dir.unwrap_or_else(|| std::env::temp_dir().join("flipper-ir-demo"));
+            demo::run(&output_dir)?;
+        }
+        Command::Features => {
+            for feature in flipper_ir_dumps::ENABLED_FEATURES {
+                println!("{}", feature);
+            }
+        }
+        Command::DedupeRemotes {
+            first,
+            second,
+            threshold,
+        } => dedupe::run(&first, &second, threshold)?,
+        Command::Generate {
+            output_file,
+            seed,
+            signals,
+            jitter,
+        } => {
+            let seed = seed.unwrap_or_else(|| {
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|elapsed| elapsed.as_nanos() as u64)
+                    .unwrap_or_default()
+            });
+            let options = GeneratorOptions {
+                seed,
+                signals,
+                jitter,
+                ..GeneratorOptions::default()
+            };
+
+            std::fs::write(&output_file, generator::generate(&options).to_string())
+                .wrap_err("Failed to write dump")?;
+            println!("generated {} with seed {}", output_file.display(), seed);
+        }
+        Command::Neighbors {
+            input,
+            signal,
+            output_file,
+            distance,
+            flip_bits,
+        } => neighbors::run(&input, &signal, &output_file, distance, flip_bits)?,
+        Command::Identify {
+            capture,
+            irdb,
+            cache,
+            reindex,
+            limit,
+        } => {
+            let cache = cache.unwrap_or_else(|| irdb.join(identify::CACHE_FILE));
+            identify::run(&capture, &irdb, &cache, reindex, limit)?;
+        }
+    }
+
+    Ok(())
+}
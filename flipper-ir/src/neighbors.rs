@@ -0,0 +1,46 @@
+use std::path::Path;
+
+use color_eyre::eyre::{eyre, Result, WrapErr};
+
+use flipper_ir_dumps::{
+    dump::DumpFile,
+    neighbors::{neighbor_dump, NeighborOptions},
+    protocol::Registry,
+};
+
+/// Decodes a signal of a dump and writes the codes next to it into a new dump.
+pub fn run(
+    input: &Path,
+    signal: &str,
+    output_file: &Path,
+    distance: u32,
+    flip_bits: u32,
+) -> Result<()> {
+    let contents = std::fs::read(input).wrap_err("Failed to read file")?;
+    let dump = DumpFile::from_bytes(&contents)
+        .wrap_err_with(|| format!("Failed decoding {}", input.display()))?;
+
+    let raw = dump
+        .signal(signal)
+        .ok_or_else(|| eyre!("No signal named {} in {}", signal, input.display()))?;
+    let registry = Registry::default();
+    let decoded = registry
+        .decode(raw.data(), raw.frequency().hz())
+        .ok_or_else(|| eyre!("{} isn't a frame of any known protocol", signal))?;
+
+    let options = NeighborOptions {
+        distance,
+        flip_bits,
+    };
+    let neighbors = neighbor_dump(&registry, &decoded, raw.frequency(), &options);
+
+    std::fs::write(output_file, neighbors.to_string()).wrap_err("Failed to write dump")?;
+    println!(
+        "wrote {} codes next to {} to {}",
+        neighbors.signals().len(),
+        decoded.frame(),
+        output_file.display()
+    );
+
+    Ok(())
+}
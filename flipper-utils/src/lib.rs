@@ -1,3 +1,5 @@
+#![no_std]
+
 /// Round a number to the nearest multiple of another number.
 ///
 /// # Examples
@@ -13,9 +15,38 @@
 /// assert_eq!(round_to(125, 50), 150);
 ///
 /// assert_eq!(round_to(2972, 550), 2750);
+///
+/// // doesn't overflow, saturating if the result doesn't fit
+/// assert_eq!(round_to(u32::MAX, 550), 4_294_967_050);
+/// assert_eq!(round_to(u32::MAX, 1 << 31), u32::MAX);
 /// ```
+///
+/// # Panics
+/// Panics if `round_to` is zero.
 pub fn round_to(x: u32, round_to: u32) -> u32 {
-    (x + round_to / 2) / round_to * round_to
+    let (x, round_to) = (u64::from(x), u64::from(round_to));
+    let rounded = (x + round_to / 2) / round_to * round_to;
+
+    u32::try_from(rounded).unwrap_or(u32::MAX)
+}
+
+/// Check whether a measured value lies within a relative tolerance of the nominal one.
+///
+/// `tolerance` is a fraction of `nominal`, e.g. `0.25` means ±25%.
+///
+/// # Examples
+/// ```rust
+/// use flipper_utils::within_tolerance;
+///
+/// assert!(within_tolerance(560, 560, 0.0));
+/// assert!(within_tolerance(690, 560, 0.25));
+/// assert!(within_tolerance(430, 560, 0.25));
+/// assert!(!within_tolerance(710, 560, 0.25));
+/// assert!(!within_tolerance(1, 0, 0.25));
+/// ```
+pub fn within_tolerance(actual: u32, nominal: u32, tolerance: f32) -> bool {
+    let delta = (actual as f32 - nominal as f32).abs();
+    delta <= nominal as f32 * tolerance
 }
 
 #[cfg(test)]
@@ -34,5 +65,17 @@ mod tests {
         assert_eq!(round_to(125, 50), 150);
 
         assert_eq!(round_to(2972, 550), 2750);
+
+        assert_eq!(round_to(u32::MAX, 550), 4_294_967_050);
+        assert_eq!(round_to(u32::MAX, 1 << 31), u32::MAX);
+    }
+
+    #[test]
+    fn test_within_tolerance() {
+        assert!(within_tolerance(560, 560, 0.0));
+        assert!(within_tolerance(690, 560, 0.25));
+        assert!(within_tolerance(430, 560, 0.25));
+        assert!(!within_tolerance(710, 560, 0.25));
+        assert!(!within_tolerance(1, 0, 0.25));
     }
 }